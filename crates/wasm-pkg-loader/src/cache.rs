@@ -0,0 +1,597 @@
+//! A local cache of package metadata and content.
+//!
+//! Version lists are cached per registry and package, and are considered
+//! fresh for the [`CachePolicy::metadata_ttl`] of the package's namespace.
+//! Content is cached by digest, so a cached entry never goes stale.
+
+use std::{
+    collections::HashMap,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, OnceLock, Weak},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use bytes::Bytes;
+use futures_util::{
+    stream::{self, BoxStream},
+    StreamExt, TryStreamExt,
+};
+use serde::{Deserialize, Serialize};
+use tempfile::NamedTempFile;
+use tokio::sync::OwnedMutexGuard;
+use tokio_util::io::ReaderStream;
+
+use semver::Version;
+
+use crate::{source::VersionInfo, ContentDigest, Deprecation, Error, PackageRef, Release};
+
+/// Whether package content may be cached.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ContentCachePolicy {
+    /// Read content from and write content to the cache.
+    #[default]
+    Store,
+    /// Never cache content.
+    NoStore,
+}
+
+impl std::str::FromStr for ContentCachePolicy {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "store" => Ok(Self::Store),
+            "no-store" => Ok(Self::NoStore),
+            _ => Err(Error::InvalidConfig(anyhow::anyhow!(
+                "invalid content cache policy {s:?}; expected \"store\" or \"no-store\""
+            ))),
+        }
+    }
+}
+
+/// Policy for caching a package's metadata and content.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CachePolicy {
+    /// How long a cached version list may be used before it is refetched.
+    /// The default of zero always refetches.
+    pub metadata_ttl: Duration,
+    /// Whether content may be cached.
+    pub content: ContentCachePolicy,
+}
+
+/// Per-scope [`CachePolicy`] settings; unset fields fall back to a broader
+/// scope.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CachePolicyConfig {
+    pub metadata_ttl: Option<Duration>,
+    pub content: Option<ContentCachePolicy>,
+}
+
+impl CachePolicyConfig {
+    /// Overrides fields of this config with any set in `other`.
+    pub fn merge(&mut self, other: CachePolicyConfig) {
+        self.metadata_ttl = other.metadata_ttl.or(self.metadata_ttl);
+        self.content = other.content.or(self.content);
+    }
+
+    /// Applies the fields set in this config to the given policy.
+    pub fn apply(&self, policy: CachePolicy) -> CachePolicy {
+        CachePolicy {
+            metadata_ttl: self.metadata_ttl.unwrap_or(policy.metadata_ttl),
+            content: self.content.unwrap_or(policy.content),
+        }
+    }
+}
+
+/// Parses a duration like `"30s"`, `"5m"`, `"12h"`, or `"7d"`.
+pub(crate) fn parse_duration(s: &str) -> anyhow::Result<Duration> {
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (value, unit) = s.split_at(split);
+    let value: u64 = value
+        .parse()
+        .map_err(|_| anyhow::anyhow!("invalid duration {s:?}; expected e.g. \"30s\" or \"5m\""))?;
+    let secs = match unit {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => anyhow::bail!("invalid duration unit {unit:?}; expected one of s, m, h, d"),
+    };
+    let secs = value
+        .checked_mul(secs)
+        .ok_or_else(|| anyhow::anyhow!("duration {s:?} is too large"))?;
+    Ok(Duration::from_secs(secs))
+}
+
+/// Formats a duration in the largest whole unit accepted by
+/// [`parse_duration`], e.g. `"5m"`.
+pub(crate) fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    match secs {
+        0 => "0s".to_string(),
+        _ if secs.is_multiple_of(24 * 60 * 60) => format!("{}d", secs / (24 * 60 * 60)),
+        _ if secs.is_multiple_of(60 * 60) => format!("{}h", secs / (60 * 60)),
+        _ if secs.is_multiple_of(60) => format!("{}m", secs / 60),
+        _ => format!("{secs}s"),
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Cache {
+    root: PathBuf,
+}
+
+impl Cache {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Returns the default cache directory, e.g. `~/.cache/wasm-pkg`.
+    pub fn default_dir() -> Option<PathBuf> {
+        crate::paths::cache_dir()
+    }
+
+    fn metadata_path(&self, registry: &str, package: &PackageRef) -> PathBuf {
+        // Registry names may include a port, which isn't valid in Windows paths
+        self.root
+            .join("metadata")
+            .join(registry.replace(':', "_"))
+            .join(package.namespace().as_ref())
+            .join(format!("{}.json", package.name()))
+    }
+
+    fn release_path(&self, registry: &str, package: &PackageRef, version: &Version) -> PathBuf {
+        self.root
+            .join("metadata")
+            .join(registry.replace(':', "_"))
+            .join(package.namespace().as_ref())
+            .join(package.name().as_ref())
+            .join(format!("{version}.json"))
+    }
+
+    fn content_path(&self, digest: &ContentDigest) -> PathBuf {
+        match digest {
+            ContentDigest::Sha256 { hex } => self.root.join("content").join("sha256").join(hex),
+        }
+    }
+
+    /// Returns the cached version list for the given package if it was
+    /// fetched within `ttl`.
+    pub fn versions(
+        &self,
+        registry: &str,
+        package: &PackageRef,
+        ttl: Duration,
+    ) -> Option<Vec<VersionInfo>> {
+        if ttl.is_zero() {
+            return None;
+        }
+        let path = self.metadata_path(registry, package);
+        let contents = std::fs::read(&path).ok()?;
+        let cached: CachedVersions = match serde_json::from_slice(&contents) {
+            Ok(cached) => cached,
+            Err(err) => {
+                tracing::warn!("Ignoring invalid cache entry {path:?}: {err}");
+                return None;
+            }
+        };
+        let age = now_secs().saturating_sub(cached.fetched_at);
+        if age > ttl.as_secs() {
+            tracing::debug!("Cached versions of {package} are stale ({age}s old)");
+            return None;
+        }
+        tracing::debug!("Using cached versions of {package} ({age}s old)");
+        cached
+            .versions
+            .into_iter()
+            .map(|cached| {
+                Some(VersionInfo {
+                    version: cached.version.parse().ok()?,
+                    yanked: cached.yanked,
+                    deprecation: cached.deprecation,
+                })
+            })
+            .collect()
+    }
+
+    /// Caches the version list for the given package.
+    pub fn store_versions(&self, registry: &str, package: &PackageRef, versions: &[VersionInfo]) {
+        let cached = CachedVersions {
+            fetched_at: now_secs(),
+            versions: versions
+                .iter()
+                .map(|info| CachedVersion {
+                    version: info.version.to_string(),
+                    yanked: info.yanked,
+                    deprecation: info.deprecation.clone(),
+                })
+                .collect(),
+        };
+        let path = self.metadata_path(registry, package);
+        let res = serde_json::to_vec(&cached)
+            .map_err(std::io::Error::from)
+            .and_then(|contents| write_atomic(&path, &contents));
+        if let Err(err) = res {
+            tracing::warn!("Failed to cache versions of {package} at {path:?}: {err}");
+        }
+    }
+
+    /// Returns the cached release details for the given package version, if
+    /// present. Cached releases are only used as-is in offline mode, as a
+    /// version's content may change in some registries; see
+    /// [`Cache::revalidated_release`].
+    pub fn release(
+        &self,
+        registry: &str,
+        package: &PackageRef,
+        version: &Version,
+    ) -> Option<Release> {
+        self.cached_release(registry, package, version)
+            .map(|(release, _)| release)
+    }
+
+    /// Returns cached release details for the given package whose content
+    /// has the given digest, searching the versions in its cached version
+    /// list regardless of age.
+    pub fn release_by_digest(
+        &self,
+        registry: &str,
+        package: &PackageRef,
+        digest: &ContentDigest,
+    ) -> Option<Release> {
+        self.versions(registry, package, Duration::MAX)?
+            .into_iter()
+            .rev()
+            .filter_map(|info| self.release(registry, package, &info.version))
+            .find(|release| &release.content_digest == digest)
+    }
+
+    /// Returns the cached release details for the given package version if
+    /// they were stored with the given revision, i.e. they haven't changed
+    /// in the registry since.
+    pub fn revalidated_release(
+        &self,
+        registry: &str,
+        package: &PackageRef,
+        version: &Version,
+        revision: &str,
+    ) -> Option<Release> {
+        match self.cached_release(registry, package, version)? {
+            (release, Some(cached)) if cached == revision => Some(release),
+            _ => None,
+        }
+    }
+
+    fn cached_release(
+        &self,
+        registry: &str,
+        package: &PackageRef,
+        version: &Version,
+    ) -> Option<(Release, Option<String>)> {
+        let path = self.release_path(registry, package, version);
+        let contents = std::fs::read(&path).ok()?;
+        let (content_digest, revision) = match serde_json::from_slice::<CachedRelease>(&contents)
+            .map_err(anyhow::Error::from)
+            .and_then(|cached| Ok((cached.content_digest.parse()?, cached.revision)))
+        {
+            Ok(cached) => cached,
+            Err(err) => {
+                tracing::warn!("Ignoring invalid cache entry {path:?}: {err}");
+                return None;
+            }
+        };
+        let release = Release {
+            version: version.clone(),
+            content_digest,
+        };
+        Some((release, revision))
+    }
+
+    /// Caches the release details for the given package, with the revision
+    /// they were fetched at if known.
+    pub fn store_release(
+        &self,
+        registry: &str,
+        package: &PackageRef,
+        release: &Release,
+        revision: Option<&str>,
+    ) {
+        let cached = CachedRelease {
+            content_digest: release.content_digest.to_string(),
+            revision: revision.map(Into::into),
+        };
+        let path = self.release_path(registry, package, &release.version);
+        let res = serde_json::to_vec(&cached)
+            .map_err(std::io::Error::from)
+            .and_then(|contents| write_atomic(&path, &contents));
+        if let Err(err) = res {
+            tracing::warn!(
+                "Failed to cache release {package}@{} at {path:?}: {err}",
+                release.version
+            );
+        }
+    }
+
+    /// Returns a stream of the cached content with the given digest, if
+    /// present. The content is validated against its digest as it's read;
+    /// if it doesn't match, the stream ends with an error and the entry is
+    /// evicted.
+    pub async fn content(
+        &self,
+        digest: &ContentDigest,
+    ) -> Option<BoxStream<'static, Result<Bytes, Error>>> {
+        let path = self.content_path(digest);
+        let file = tokio::fs::File::open(&path).await.ok()?;
+        tracing::debug!("Using cached content {digest}");
+        let stream = digest
+            .validating_stream(ReaderStream::new(file).map_err(Error::from))
+            .inspect_err(move |err| {
+                if matches!(err, Error::InvalidContent(_)) {
+                    tracing::warn!("Evicting corrupt cache entry {path:?}");
+                    let _ = std::fs::remove_file(&path);
+                }
+            });
+        Some(stream.boxed())
+    }
+
+    /// Returns true if content with the given digest is cached. Unlike
+    /// [`Cache::content_file`], the content isn't verified.
+    pub fn has_content(&self, digest: &ContentDigest) -> bool {
+        self.content_path(digest).is_file()
+    }
+
+    /// Returns the path of the cached content with the given digest, if
+    /// present, so it can be copied without reading it into memory. Content
+    /// that doesn't match its digest is evicted.
+    pub async fn content_file(&self, digest: &ContentDigest) -> Option<PathBuf> {
+        let path = self.content_path(digest);
+        if &ContentDigest::sha256_from_file(&path).await.ok()? != digest {
+            tracing::warn!("Evicting corrupt cache entry {path:?}");
+            let _ = tokio::fs::remove_file(&path).await;
+            return None;
+        }
+        tracing::debug!("Using cached content file {path:?}");
+        Some(path)
+    }
+
+    /// Waits until no other task in the process is downloading the given
+    /// content into this cache, then returns a guard that makes any others
+    /// wait for this task. Holding the guard while downloading means
+    /// concurrent fetches of the same content transfer it only once; the
+    /// waiting tasks then read it from the cache.
+    ///
+    /// The guard must be dropped before the same task fetches the same
+    /// content again.
+    pub async fn lock_content(&self, digest: &ContentDigest) -> OwnedMutexGuard<()> {
+        static LOCKS: OnceLock<Mutex<HashMap<PathBuf, Weak<tokio::sync::Mutex<()>>>>> =
+            OnceLock::new();
+        let lock = {
+            let mut locks = LOCKS.get_or_init(Default::default).lock().unwrap();
+            locks.retain(|_, lock| lock.strong_count() > 0);
+            let path = self.content_path(digest);
+            match locks.get(&path).and_then(Weak::upgrade) {
+                Some(lock) => lock,
+                None => {
+                    let lock = Arc::new(tokio::sync::Mutex::new(()));
+                    locks.insert(path, Arc::downgrade(&lock));
+                    lock
+                }
+            }
+        };
+        lock.lock_owned().await
+    }
+
+    /// Wraps a validated content stream, writing its chunks to the cache as
+    /// they are read. The cache entry is only committed if the stream
+    /// completes without error.
+    pub fn store_content<'a>(
+        &self,
+        digest: &ContentDigest,
+        stream: BoxStream<'a, Result<Bytes, Error>>,
+    ) -> BoxStream<'a, Result<Bytes, Error>> {
+        let writer = match CacheWriter::new(self.content_path(digest)) {
+            Ok(writer) => Some(writer),
+            Err(err) => {
+                tracing::warn!("Failed to cache content {digest}: {err}");
+                None
+            }
+        };
+        stream::unfold((stream, writer), |(mut stream, mut writer)| async move {
+            match stream.next().await {
+                Some(Ok(chunk)) => {
+                    if let Some(w) = &mut writer {
+                        if let Err(err) = w.file.write_all(&chunk) {
+                            tracing::warn!("Failed to write cache entry {:?}: {err}", w.path);
+                            writer = None;
+                        }
+                    }
+                    Some((Ok(chunk), (stream, writer)))
+                }
+                Some(Err(err)) => Some((Err(err), (stream, None))),
+                None => {
+                    if let Some(writer) = writer {
+                        writer.commit();
+                    }
+                    None
+                }
+            }
+        })
+        .boxed()
+    }
+}
+
+/// Writes content to a temporary file, persisted into place on commit and
+/// removed if dropped uncommitted.
+struct CacheWriter {
+    path: PathBuf,
+    file: NamedTempFile,
+}
+
+impl CacheWriter {
+    fn new(path: PathBuf) -> std::io::Result<Self> {
+        let file = tmp_file(&path)?;
+        Ok(Self { path, file })
+    }
+
+    fn commit(self) {
+        if let Err(err) = self.file.persist(&self.path) {
+            tracing::warn!("Failed to commit cache entry {:?}: {err}", self.path);
+        }
+    }
+}
+
+/// Returns a new uniquely named temporary file alongside `path`, so
+/// concurrent writers of the same entry never share one.
+fn tmp_file(path: &Path) -> std::io::Result<NamedTempFile> {
+    let dir = path.parent().unwrap_or(Path::new("."));
+    std::fs::create_dir_all(dir)?;
+    NamedTempFile::new_in(dir)
+}
+
+fn write_atomic(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let mut file = tmp_file(path)?;
+    file.write_all(contents)?;
+    file.persist(path)?;
+    Ok(())
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[derive(Deserialize, Serialize)]
+struct CachedVersions {
+    /// Unix timestamp (seconds) of when the versions were fetched.
+    fetched_at: u64,
+    versions: Vec<CachedVersion>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct CachedRelease {
+    content_digest: String,
+    /// The revision of the release details, as reported by the source.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    revision: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct CachedVersion {
+    version: String,
+    yanked: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    deprecation: Option<Deprecation>,
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::FutureExt;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("30s").unwrap(), Duration::from_secs(30));
+        assert_eq!(parse_duration("5m").unwrap(), Duration::from_secs(300));
+        assert_eq!(parse_duration("0").unwrap(), Duration::ZERO);
+        assert!(parse_duration("5 minutes").is_err());
+        assert!(parse_duration("m").is_err());
+        assert!(parse_duration(&format!("{}d", u64::MAX)).is_err());
+        assert_eq!(format_duration(Duration::from_secs(300)), "5m");
+        assert_eq!(format_duration(Duration::from_secs(90)), "90s");
+        assert_eq!(format_duration(Duration::from_secs(2 * 24 * 60 * 60)), "2d");
+        assert_eq!(format_duration(Duration::ZERO), "0s");
+    }
+
+    #[tokio::test]
+    async fn test_cache() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let cache = Cache::new(dir);
+        let package: PackageRef = "test:pkg".parse().unwrap();
+
+        let versions = vec![VersionInfo {
+            version: "1.0.0".parse().unwrap(),
+            yanked: false,
+            deprecation: None,
+        }];
+        cache.store_versions("example.com:5000", &package, &versions);
+        let ttl = Duration::from_secs(60);
+        assert_eq!(
+            cache.versions("example.com:5000", &package, ttl),
+            Some(versions)
+        );
+        assert_eq!(
+            cache.versions("example.com:5000", &package, Duration::ZERO),
+            None
+        );
+
+        let content = b"content";
+        let digest = ContentDigest::sha256(content);
+        assert!(cache.content(&digest).await.is_none());
+        let stream = stream::iter([Ok(Bytes::from_static(content))]).boxed();
+        let stored: Vec<u8> = cache
+            .store_content(&digest, stream)
+            .map_ok(|chunk| chunk.to_vec())
+            .try_concat()
+            .await
+            .unwrap();
+        assert_eq!(stored, content);
+        let cached: Vec<u8> = cache
+            .content(&digest)
+            .await
+            .unwrap()
+            .map_ok(|chunk| chunk.to_vec())
+            .try_concat()
+            .await
+            .unwrap();
+        assert_eq!(cached, content);
+        let path = cache.content_file(&digest).await.unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), content);
+        std::fs::write(&path, b"corrupt").unwrap();
+        assert!(cache.content_file(&digest).await.is_none());
+        assert!(!path.exists());
+
+        // Corrupt content is caught as it's streamed
+        std::fs::write(&path, b"corrupt").unwrap();
+        let res: Result<Vec<u8>, _> = cache
+            .content(&digest)
+            .await
+            .unwrap()
+            .map_ok(|chunk| chunk.to_vec())
+            .try_concat()
+            .await;
+        assert!(matches!(res, Err(Error::InvalidContent(_))));
+        assert!(!path.exists());
+
+        let guard = cache.lock_content(&digest).await;
+        assert!(cache.lock_content(&digest).now_or_never().is_none());
+        drop(guard);
+        assert!(cache.lock_content(&digest).now_or_never().is_some());
+
+        let release = Release {
+            version: "1.0.0".parse().unwrap(),
+            content_digest: digest,
+        };
+        assert_eq!(
+            cache.release("example.com:5000", &package, &release.version),
+            None
+        );
+        cache.store_release("example.com:5000", &package, &release, Some("rev1"));
+        let cached = cache.release("example.com:5000", &package, &release.version);
+        assert_eq!(
+            cache.release_by_digest("example.com:5000", &package, &release.content_digest),
+            Some(release.clone())
+        );
+        let revalidated =
+            cache.revalidated_release("example.com:5000", &package, &release.version, "rev1");
+        let changed =
+            cache.revalidated_release("example.com:5000", &package, &release.version, "rev2");
+        assert_eq!(cached.as_ref(), Some(&release));
+        assert_eq!(revalidated, Some(release));
+        assert_eq!(changed, None);
+    }
+}
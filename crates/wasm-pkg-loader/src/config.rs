@@ -1,13 +1,29 @@
+mod entries;
+mod schema;
 mod toml;
 
-use std::{collections::HashMap, path::PathBuf};
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    path::{Path, PathBuf},
+};
 
 use oci_distribution::client::ClientConfig as OciClientConfig;
 use secrecy::SecretString;
 
 use crate::{
-    source::{local::LocalConfig, oci::OciConfig, warg::WargConfig},
-    Error, PackageRef,
+    download::{DownloadConfig, DEFAULT_CHUNK_SIZE, DEFAULT_PARALLELISM, DEFAULT_RETRIES},
+    http::HttpConfig,
+    label::Label,
+    limits::{ConcurrencyConfig, ConcurrencyLimits},
+    source::{
+        github::GitHubConfig,
+        local::LocalConfig,
+        oci::{OciCompat, OciConfig, OciLayout},
+        path::PackageOverride,
+        warg::WargConfig,
+    },
+    CachePolicy, CachePolicyConfig, Error, PackageRef, ProvenancePolicy, RegistryPolicy,
+    ResolutionStrategy, VersionPolicy, VersionPolicyConfig,
 };
 
 /// Configuration for [`super::Client`].
@@ -17,8 +33,62 @@ pub struct ClientConfig {
     default_registry: Option<String>,
     /// Per-namespace registry, overriding `default_registry` (if present).
     namespace_registries: HashMap<String, String>,
+    /// Per-package registry, overriding `namespace_registries` (if present).
+    package_registries: HashMap<PackageRef, String>,
+    /// Short aliases for registry names, usable anywhere a registry is named.
+    registry_aliases: HashMap<String, String>,
     /// Per-registry configuration.
     pub(crate) registry_configs: HashMap<String, RegistryConfig>,
+    /// Per-registry credentials, typically loaded from a separate credentials file.
+    pub(crate) registry_credentials: HashMap<String, RegistryCredentials>,
+    /// Per-package local path overrides, taking precedence over any registry.
+    package_overrides: HashMap<PackageRef, PackageOverride>,
+    /// Version selection policy settings.
+    version_policy: VersionPolicyConfig,
+    /// Per-namespace version selection policy settings, overriding
+    /// `version_policy`.
+    namespace_version_policies: HashMap<String, VersionPolicyConfig>,
+    /// The dependency resolution strategy.
+    resolution_strategy: Option<ResolutionStrategy>,
+    /// Cache policy settings.
+    cache_policy: CachePolicyConfig,
+    /// Per-namespace cache policy settings, overriding `cache_policy`.
+    namespace_cache_policies: HashMap<String, CachePolicyConfig>,
+    /// The cache directory, overriding the default.
+    cache_dir: Option<PathBuf>,
+    /// Whether network access is disabled.
+    offline: bool,
+    /// Whether the cache is bypassed entirely.
+    no_cache: bool,
+    /// Whether cached metadata is always revalidated.
+    refresh: bool,
+    /// Whether all fetched content must be verified against a digest
+    /// supplied ahead of download.
+    require_digest: Option<bool>,
+    /// Restrictions on which registries may be contacted.
+    registry_policy: RegistryPolicy,
+    /// Required provenance attestations.
+    provenance_policy: Option<ProvenancePolicy>,
+    /// The OpenPGP keyring fetched content must be signed by a key of.
+    openpgp_keyring: Option<PathBuf>,
+    /// Per-namespace OpenPGP keyrings, overriding `openpgp_keyring`.
+    namespace_openpgp_keyrings: HashMap<String, PathBuf>,
+    /// Per-namespace patterns a verified signer identity must match.
+    namespace_publishers: HashMap<String, Vec<String>>,
+    /// The file name template for fetched package output.
+    output_name_template: Option<String>,
+    /// How tools print WIT output.
+    wit_output: WitOutputConfig,
+    /// Parallel range download settings.
+    download_config: Option<DownloadConfig>,
+    /// HTTP connection settings.
+    http_config: Option<HttpConfig>,
+    /// Concurrent request limits.
+    concurrency: ConcurrencyConfig,
+    /// Per-registry concurrent request limits, overriding `concurrency`.
+    registry_concurrency: HashMap<String, ConcurrencyConfig>,
+    /// Named profiles, which override the rest of this config when applied.
+    profiles: HashMap<String, ClientConfig>,
 }
 
 impl ClientConfig {
@@ -33,12 +103,109 @@ impl ClientConfig {
         for (namespace, registry) in other.namespace_registries {
             self.set_namespace_registry(namespace, registry);
         }
+        for (package, registry) in other.package_registries {
+            self.set_package_registry(package, registry);
+        }
+        for (alias, registry) in other.registry_aliases {
+            self.set_registry_alias(alias, registry);
+        }
         for (registry, config) in other.registry_configs {
             self.registry_configs.insert(registry, config);
         }
+        for (registry, credentials) in other.registry_credentials {
+            self.registry_credentials.insert(registry, credentials);
+        }
+        for (package, package_override) in other.package_overrides {
+            self.package_overrides.insert(package, package_override);
+        }
+        self.version_policy.merge(other.version_policy);
+        for (namespace, policy) in other.namespace_version_policies {
+            self.namespace_version_policies
+                .entry(namespace)
+                .or_default()
+                .merge(policy);
+        }
+        if let Some(strategy) = other.resolution_strategy {
+            self.resolution_strategy = Some(strategy);
+        }
+        self.cache_policy.merge(other.cache_policy);
+        for (namespace, policy) in other.namespace_cache_policies {
+            self.namespace_cache_policies
+                .entry(namespace)
+                .or_default()
+                .merge(policy);
+        }
+        if let Some(cache_dir) = other.cache_dir {
+            self.cache_dir = Some(cache_dir);
+        }
+        self.offline |= other.offline;
+        self.no_cache |= other.no_cache;
+        self.refresh |= other.refresh;
+        if let Some(require_digest) = other.require_digest {
+            self.require_digest = Some(require_digest);
+        }
+        self.registry_policy.merge(other.registry_policy);
+        if let Some(provenance_policy) = other.provenance_policy {
+            self.provenance_policy = Some(provenance_policy);
+        }
+        if let Some(keyring) = other.openpgp_keyring {
+            self.openpgp_keyring = Some(keyring);
+        }
+        self.namespace_openpgp_keyrings
+            .extend(other.namespace_openpgp_keyrings);
+        // Like the registry policy, publisher pins only tighten: a later layer
+        // may narrow a namespace's pin but never replace it.
+        for (namespace, publishers) in other.namespace_publishers {
+            match self.namespace_publishers.entry(namespace) {
+                Entry::Occupied(mut entry) => entry
+                    .get_mut()
+                    .retain(|pattern| publishers.contains(pattern)),
+                Entry::Vacant(entry) => {
+                    entry.insert(publishers);
+                }
+            }
+        }
+        if let Some(template) = other.output_name_template {
+            self.output_name_template = Some(template);
+        }
+        self.wit_output.merge(other.wit_output);
+        if let Some(download_config) = other.download_config {
+            self.download_config = Some(download_config);
+        }
+        if let Some(http_config) = other.http_config {
+            self.http_config = Some(http_config);
+        }
+        self.concurrency.merge(other.concurrency);
+        for (registry, concurrency) in other.registry_concurrency {
+            self.registry_concurrency
+                .entry(registry)
+                .or_default()
+                .merge(concurrency);
+        }
+        for (name, profile) in other.profiles {
+            self.profiles.entry(name).or_default().merge_config(profile);
+        }
         self
     }
 
+    /// Merges the named profile's settings into this config, overriding
+    /// the default registry, namespace registries, registry configs, and
+    /// credentials it sets.
+    pub fn apply_profile(&mut self, name: &str) -> Result<&mut Self, Error> {
+        let Some(profile) = self.profiles.get(name).cloned() else {
+            return Err(Error::InvalidConfig(anyhow::anyhow!(
+                "unknown profile {name:?}"
+            )));
+        };
+        tracing::debug!("Applying config profile {name:?}");
+        Ok(self.merge_config(profile))
+    }
+
+    /// Returns an iterator of the names of all configured profiles.
+    pub fn profile_names(&self) -> impl Iterator<Item = &str> {
+        self.profiles.keys().map(String::as_str)
+    }
+
     pub fn set_default_registry(&mut self, registry: impl Into<String>) -> &mut Self {
         self.default_registry = Some(registry.into());
         self
@@ -58,6 +225,17 @@ impl ClientConfig {
         self
     }
 
+    /// Sets the registry for a specific package, overriding any namespace
+    /// or default registry.
+    pub fn set_package_registry(
+        &mut self,
+        package: PackageRef,
+        registry: impl Into<String>,
+    ) -> &mut Self {
+        self.package_registries.insert(package, registry.into());
+        self
+    }
+
     pub fn set_local_registry_config(
         &mut self,
         registry: impl Into<String>,
@@ -65,11 +243,25 @@ impl ClientConfig {
     ) -> &mut Self {
         self.registry_configs.insert(
             registry.into(),
-            RegistryConfig::Local(LocalConfig { root: root.into() }),
+            RegistryConfig::Local(LocalConfig {
+                root: root.into(),
+                tuf_root: None,
+            }),
         );
         self
     }
 
+    /// Sets a GitHub releases config for the given registry.
+    pub fn set_github_registry_config(
+        &mut self,
+        registry: impl Into<String>,
+        config: GitHubConfig,
+    ) -> &mut Self {
+        self.registry_configs
+            .insert(registry.into(), RegistryConfig::GitHub(config));
+        self
+    }
+
     pub fn set_oci_registry_config(
         &mut self,
         registry: impl Into<String>,
@@ -84,11 +276,52 @@ impl ClientConfig {
         let cfg = RegistryConfig::Oci(OciConfig {
             client_config,
             credentials,
+            layout: Default::default(),
+            compat: None,
         });
         self.registry_configs.insert(registry.into(), cfg);
         Ok(self)
     }
 
+    /// Sets the [`OciLayout`] used to map packages to OCI repositories and
+    /// tags for the given registry, which must already have an OCI config.
+    pub fn set_oci_registry_layout(
+        &mut self,
+        registry: impl AsRef<str>,
+        layout: OciLayout,
+    ) -> Result<&mut Self, Error> {
+        let registry = registry.as_ref();
+        match self.registry_configs.get_mut(registry) {
+            Some(RegistryConfig::Oci(oci_config)) => oci_config.layout = layout,
+            _ => {
+                return Err(Error::InvalidConfig(anyhow::anyhow!(
+                    "no OCI config for registry {registry:?}"
+                )))
+            }
+        }
+        Ok(self)
+    }
+
+    /// Sets the [`OciCompat`] workarounds used for the given registry, which
+    /// must already have an OCI config. By default these are detected from
+    /// the registry name.
+    pub fn set_oci_registry_compat(
+        &mut self,
+        registry: impl AsRef<str>,
+        compat: OciCompat,
+    ) -> Result<&mut Self, Error> {
+        let registry = registry.as_ref();
+        match self.registry_configs.get_mut(registry) {
+            Some(RegistryConfig::Oci(oci_config)) => oci_config.compat = Some(compat),
+            _ => {
+                return Err(Error::InvalidConfig(anyhow::anyhow!(
+                    "no OCI config for registry {registry:?}"
+                )))
+            }
+        }
+        Ok(self)
+    }
+
     pub fn set_warg_registry_config(
         &mut self,
         registry: impl Into<String>,
@@ -98,35 +331,492 @@ impl ClientConfig {
         let cfg = RegistryConfig::Warg(WargConfig {
             client_config,
             auth_token: auth_token.map(Into::into),
+            signing_key_file: None,
         });
         self.registry_configs.insert(registry.into(), cfg);
         Ok(self)
     }
 
+    /// Sets credentials for the given registry. These are used for any
+    /// registry config that doesn't specify its own credentials.
+    pub fn set_registry_credentials(
+        &mut self,
+        registry: impl Into<String>,
+        credentials: RegistryCredentials,
+    ) -> &mut Self {
+        self.registry_credentials
+            .insert(registry.into(), credentials);
+        self
+    }
+
+    /// Returns the credentials set for the given registry, if any.
+    pub fn registry_credentials(&self, registry: &str) -> Option<&RegistryCredentials> {
+        self.registry_credentials.get(registry)
+    }
+
+    /// Overrides the given package to be served from a local path instead of
+    /// any registry.
+    pub fn set_package_override(
+        &mut self,
+        package: PackageRef,
+        package_override: PackageOverride,
+    ) -> &mut Self {
+        self.package_overrides.insert(package, package_override);
+        self
+    }
+
+    /// Returns the local path override for the given package, if any.
+    pub fn package_override(&self, package: &PackageRef) -> Option<&PackageOverride> {
+        self.package_overrides.get(package)
+    }
+
+    /// Sets the version selection policy settings for all packages.
+    pub fn set_version_policy(&mut self, policy: VersionPolicyConfig) -> &mut Self {
+        self.version_policy = policy;
+        self
+    }
+
+    /// Sets the version selection policy settings for the given namespace,
+    /// overriding those set with [`Self::set_version_policy`].
+    pub fn set_namespace_version_policy(
+        &mut self,
+        namespace: impl Into<String>,
+        policy: VersionPolicyConfig,
+    ) -> &mut Self {
+        self.namespace_version_policies
+            .insert(namespace.into(), policy);
+        self
+    }
+
+    /// Returns the effective [`VersionPolicy`] for the given package.
+    pub fn version_policy(&self, package: &PackageRef) -> VersionPolicy {
+        self.version_policy_with_defaults(package, VersionPolicy::default())
+    }
+
+    /// Returns the effective [`VersionPolicy`] for the given package, taking
+    /// any settings not configured from `defaults`.
+    pub(crate) fn version_policy_with_defaults(
+        &self,
+        package: &PackageRef,
+        defaults: VersionPolicy,
+    ) -> VersionPolicy {
+        let mut policy = self.version_policy.apply(defaults);
+        if let Some(namespace_policy) = self
+            .namespace_version_policies
+            .get(package.namespace().as_ref())
+        {
+            policy = namespace_policy.apply(policy);
+        }
+        policy
+    }
+
+    /// Sets the strategy used to select among versions satisfying a
+    /// dependency requirement.
+    pub fn set_resolution_strategy(&mut self, strategy: ResolutionStrategy) -> &mut Self {
+        self.resolution_strategy = Some(strategy);
+        self
+    }
+
+    /// Returns the configured [`ResolutionStrategy`].
+    pub fn resolution_strategy(&self) -> ResolutionStrategy {
+        self.resolution_strategy.unwrap_or_default()
+    }
+
+    /// Sets the cache policy settings for all packages.
+    pub fn set_cache_policy(&mut self, policy: CachePolicyConfig) -> &mut Self {
+        self.cache_policy = policy;
+        self
+    }
+
+    /// Sets the cache policy settings for the given namespace, overriding
+    /// those set with [`Self::set_cache_policy`].
+    pub fn set_namespace_cache_policy(
+        &mut self,
+        namespace: impl Into<String>,
+        policy: CachePolicyConfig,
+    ) -> &mut Self {
+        self.namespace_cache_policies
+            .insert(namespace.into(), policy);
+        self
+    }
+
+    /// Returns the effective [`CachePolicy`] for the given package.
+    pub fn cache_policy(&self, package: &PackageRef) -> CachePolicy {
+        let mut policy = self.cache_policy.apply(CachePolicy::default());
+        if let Some(namespace_policy) = self
+            .namespace_cache_policies
+            .get(package.namespace().as_ref())
+        {
+            policy = namespace_policy.apply(policy);
+        }
+        policy
+    }
+
+    /// Sets the directory used to cache package metadata and content.
+    pub fn set_cache_dir(&mut self, cache_dir: impl Into<PathBuf>) -> &mut Self {
+        self.cache_dir = Some(cache_dir.into());
+        self
+    }
+
+    /// Returns the cache directory, if set.
+    pub fn cache_dir(&self) -> Option<&Path> {
+        self.cache_dir.as_deref()
+    }
+
+    /// Disables (or re-enables) network access. In offline mode only local
+    /// sources and cached metadata and content are used; anything else
+    /// fails with [`Error::Offline`].
+    pub fn set_offline(&mut self, offline: bool) -> &mut Self {
+        self.offline = offline;
+        self
+    }
+
+    /// Returns true if network access is disabled.
+    pub fn offline(&self) -> bool {
+        self.offline
+    }
+
+    /// Disables (or re-enables) the local cache: nothing is read from or
+    /// written to it, whatever the [`CachePolicy`].
+    pub fn set_no_cache(&mut self, no_cache: bool) -> &mut Self {
+        self.no_cache = no_cache;
+        self
+    }
+
+    /// Returns true if the local cache is disabled.
+    pub fn no_cache(&self) -> bool {
+        self.no_cache
+    }
+
+    /// Forces (or stops forcing) cached metadata to be refetched, ignoring
+    /// [`CachePolicy::metadata_ttl`] and cached release details. Cached
+    /// content is still used, as it's verified against the refetched
+    /// release details.
+    pub fn set_refresh(&mut self, refresh: bool) -> &mut Self {
+        self.refresh = refresh;
+        self
+    }
+
+    /// Returns true if cached metadata is always refetched.
+    pub fn refresh(&self) -> bool {
+        self.refresh
+    }
+
+    /// Requires that all fetched content be verified by the client against
+    /// the content digest of its release, obtained before the download
+    /// starts. Content that can't be verified fails with
+    /// [`Error::InvalidContent`].
+    pub fn set_require_digest(&mut self, require_digest: bool) -> &mut Self {
+        self.require_digest = Some(require_digest);
+        self
+    }
+
+    /// Returns true if all fetched content must be verified against a
+    /// content digest.
+    pub fn require_digest(&self) -> bool {
+        self.require_digest.unwrap_or_default()
+    }
+
+    /// Restricts the registries that may be contacted. Restrictions can
+    /// only be tightened; see [`RegistryPolicy`].
+    pub fn restrict_registries(&mut self, policy: RegistryPolicy) -> &mut Self {
+        self.registry_policy.merge(policy);
+        self
+    }
+
+    /// Sets the provenance attestations required to fetch content.
+    pub fn set_provenance_policy(&mut self, policy: ProvenancePolicy) -> &mut Self {
+        self.provenance_policy = Some(policy);
+        self
+    }
+
+    /// Returns the provenance attestations required to fetch content.
+    pub fn provenance_policy(&self) -> &ProvenancePolicy {
+        const DEFAULT: &ProvenancePolicy = &ProvenancePolicy {
+            require: false,
+            builder_ids: Vec::new(),
+            require_log_inclusion: false,
+            min_log_age: None,
+        };
+        self.provenance_policy.as_ref().unwrap_or(DEFAULT)
+    }
+
+    /// Requires fetched content to have an OpenPGP signature by a key in the
+    /// given keyring (a binary keyring, as exported by `gpg --export`).
+    pub fn set_openpgp_keyring(&mut self, keyring: impl Into<PathBuf>) -> &mut Self {
+        self.openpgp_keyring = Some(keyring.into());
+        self
+    }
+
+    /// Sets the OpenPGP keyring for a specific namespace, overriding any
+    /// keyring set with [`Self::set_openpgp_keyring`].
+    pub fn set_namespace_openpgp_keyring(
+        &mut self,
+        namespace: impl Into<String>,
+        keyring: impl Into<PathBuf>,
+    ) -> &mut Self {
+        self.namespace_openpgp_keyrings
+            .insert(namespace.into(), keyring.into());
+        self
+    }
+
+    /// Returns the OpenPGP keyring the given package's content must be
+    /// signed by a key of, if any.
+    pub fn openpgp_keyring(&self, package: &PackageRef) -> Option<&Path> {
+        self.namespace_openpgp_keyrings
+            .get(package.namespace().as_ref())
+            .or(self.openpgp_keyring.as_ref())
+            .map(PathBuf::as_path)
+    }
+
+    /// Pins the publishers of a namespace: fetched content must have a
+    /// verified OpenPGP signature whose signer identity (user ID, email, or
+    /// fingerprint) matches one of the given patterns, in which `*` matches
+    /// any sequence of characters.
+    pub fn set_namespace_publishers(
+        &mut self,
+        namespace: impl Into<String>,
+        publishers: impl IntoIterator<Item = impl Into<String>>,
+    ) -> &mut Self {
+        self.namespace_publishers.insert(
+            namespace.into(),
+            publishers.into_iter().map(Into::into).collect(),
+        );
+        self
+    }
+
+    /// Returns the publisher patterns pinned for the given package's
+    /// namespace, if any.
+    pub fn publishers(&self, package: &PackageRef) -> Option<&[String]> {
+        self.namespace_publishers
+            .get(package.namespace().as_ref())
+            .map(Vec::as_slice)
+    }
+
+    /// Returns the restrictions on which registries may be contacted.
+    pub fn registry_policy(&self) -> &RegistryPolicy {
+        &self.registry_policy
+    }
+
+    /// Sets the file name template for fetched package output, e.g.
+    /// `{name}-{version}.{ext}`. Templates are interpreted by tools (e.g.
+    /// `wkg get`); the client itself doesn't use them.
+    pub fn set_output_name_template(&mut self, template: impl Into<String>) -> &mut Self {
+        self.output_name_template = Some(template.into());
+        self
+    }
+
+    /// Returns the file name template for fetched package output, if set.
+    pub fn output_name_template(&self) -> Option<&str> {
+        self.output_name_template.as_deref()
+    }
+
+    /// Sets how tools print WIT output.
+    pub fn set_wit_output(&mut self, wit_output: WitOutputConfig) -> &mut Self {
+        self.wit_output = wit_output;
+        self
+    }
+
+    /// Returns how tools print WIT output.
+    pub fn wit_output(&self) -> &WitOutputConfig {
+        &self.wit_output
+    }
+
+    /// Sets how large content is downloaded in parallel ranges, from
+    /// sources that support range requests, and how failed ranges are
+    /// retried.
+    pub fn set_download_config(&mut self, download_config: DownloadConfig) -> &mut Self {
+        self.download_config = Some(download_config);
+        self
+    }
+
+    /// Returns the parallel range download settings.
+    pub fn download_config(&self) -> &DownloadConfig {
+        const DEFAULT: &DownloadConfig = &DownloadConfig {
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            parallelism: DEFAULT_PARALLELISM,
+            retries: DEFAULT_RETRIES,
+        };
+        self.download_config.as_ref().unwrap_or(DEFAULT)
+    }
+
+    /// Sets HTTP connection pool and keep-alive settings.
+    pub fn set_http_config(&mut self, http_config: HttpConfig) -> &mut Self {
+        self.http_config = Some(http_config);
+        self
+    }
+
+    /// Returns HTTP connection pool and keep-alive settings.
+    pub fn http_config(&self) -> &HttpConfig {
+        const DEFAULT: &HttpConfig = &HttpConfig {
+            pool_max_idle_per_host: None,
+            pool_idle_timeout: None,
+            keep_alive_interval: None,
+        };
+        self.http_config.as_ref().unwrap_or(DEFAULT)
+    }
+
+    /// Sets the concurrent request limits for all registries.
+    pub fn set_concurrency(&mut self, concurrency: ConcurrencyConfig) -> &mut Self {
+        self.concurrency = concurrency;
+        self
+    }
+
+    /// Sets the concurrent request limits for the given registry,
+    /// overriding those set with [`Self::set_concurrency`].
+    pub fn set_registry_concurrency(
+        &mut self,
+        registry: impl Into<String>,
+        concurrency: ConcurrencyConfig,
+    ) -> &mut Self {
+        self.registry_concurrency
+            .insert(registry.into(), concurrency);
+        self
+    }
+
+    /// Returns the effective [`ConcurrencyLimits`] for the given registry,
+    /// or for all registries if None.
+    pub fn concurrency(&self, registry: Option<&str>) -> ConcurrencyLimits {
+        let mut limits = self.concurrency.apply(ConcurrencyLimits::default());
+        if let Some(registry_concurrency) =
+            registry.and_then(|registry| self.registry_concurrency.get(registry))
+        {
+            limits = registry_concurrency.apply(limits);
+        }
+        limits
+    }
+
+    /// Returns an iterator of configured namespace to registry mappings.
+    pub fn namespace_registries(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.namespace_registries
+            .iter()
+            .map(|(namespace, registry)| (namespace.as_str(), registry.as_str()))
+    }
+
+    /// Defines `alias` as a short name for `registry`. Aliases may be used in
+    /// place of registry names for the default registry and namespace
+    /// registries; they are not resolved recursively.
+    pub fn set_registry_alias(
+        &mut self,
+        alias: impl Into<String>,
+        registry: impl Into<String>,
+    ) -> &mut Self {
+        self.registry_aliases.insert(alias.into(), registry.into());
+        self
+    }
+
+    /// Returns the registry name for the given alias, or the given name
+    /// unchanged if it isn't an alias.
+    pub fn resolve_registry_alias<'a>(&'a self, registry: &'a str) -> &'a str {
+        match self.registry_aliases.get(registry) {
+            Some(resolved) => {
+                tracing::debug!("Resolved registry alias {registry:?} to {resolved:?}");
+                resolved
+            }
+            None => registry,
+        }
+    }
+
+    /// Returns an iterator of configured registry aliases and the registry
+    /// names they resolve to.
+    pub fn registry_aliases(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.registry_aliases
+            .iter()
+            .map(|(alias, registry)| (alias.as_str(), registry.as_str()))
+    }
+
+    /// Returns an iterator of configured registries and their configs.
+    pub fn registry_configs(&self) -> impl Iterator<Item = (&str, &RegistryConfig)> {
+        self.registry_configs
+            .iter()
+            .map(|(registry, config)| (registry.as_str(), config))
+    }
+
     pub(crate) fn resolve_package_registry(&self, package: &PackageRef) -> Result<&str, Error> {
         let namespace = package.namespace();
         tracing::debug!("Resolving registry for {namespace:?}");
 
-        if let Some(registry) = self.namespace_registries.get(namespace.as_ref()) {
-            tracing::debug!("Found namespace-specific registry {registry:?}");
+        if let Some(registry) = self.package_registries.get(package) {
+            tracing::debug!("Found package-specific registry {registry:?}");
+            let registry = self.resolve_registry_alias(registry);
+            self.registry_policy.check(namespace.as_ref(), registry)?;
             return Ok(registry);
         }
-        if let Some(registry) = &self.default_registry {
+        self.resolve_namespace_registry(namespace)
+    }
+
+    /// Resolves the registry for a namespace, ignoring any package-specific
+    /// registries.
+    pub(crate) fn resolve_namespace_registry(&self, namespace: &Label) -> Result<&str, Error> {
+        let registry = if let Some(registry) = self.namespace_registries.get(namespace.as_ref()) {
+            tracing::debug!("Found namespace-specific registry {registry:?}");
+            registry
+        } else if let Some(registry) = &self.default_registry {
             tracing::debug!("No namespace-specific registry; using default {registry:?}");
-            return Ok(registry);
-        }
-        Err(Error::NoRegistryForNamespace(namespace.to_owned()))
+            registry
+        } else {
+            return Err(Error::NoRegistryForNamespace(namespace.to_owned()));
+        };
+        let registry = self.resolve_registry_alias(registry);
+        self.registry_policy.check(namespace.as_ref(), registry)?;
+        Ok(registry)
     }
 }
 
 /// Configuration for a specific registry.
 #[derive(Clone, Debug)]
 pub enum RegistryConfig {
+    GitHub(GitHubConfig),
     Local(LocalConfig),
     Oci(OciConfig),
     Warg(WargConfig),
 }
 
+impl RegistryConfig {
+    /// Returns the name of this config's registry type, e.g. "oci".
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Self::GitHub(_) => "github",
+            Self::Local(_) => "local",
+            Self::Oci(_) => "oci",
+            Self::Warg(_) => "warg",
+        }
+    }
+
+    /// Returns true if this config includes its own credentials.
+    pub fn has_credentials(&self) -> bool {
+        match self {
+            Self::GitHub(config) => config.auth_token.is_some(),
+            Self::Local(_) => false,
+            Self::Oci(config) => config.credentials.is_some(),
+            Self::Warg(config) => config.auth_token.is_some(),
+        }
+    }
+
+    /// Fills in any credentials missing from this config from the given
+    /// [`RegistryCredentials`].
+    pub(crate) fn apply_credentials(&mut self, credentials: &RegistryCredentials) {
+        match self {
+            Self::GitHub(config) => {
+                if config.auth_token.is_none() {
+                    config.auth_token = credentials.auth_token.clone();
+                }
+            }
+            Self::Local(_) => (),
+            Self::Oci(config) => {
+                if config.credentials.is_none() {
+                    config.credentials = credentials.basic.clone();
+                }
+            }
+            Self::Warg(config) => {
+                if config.auth_token.is_none() {
+                    config.auth_token = credentials.auth_token.clone();
+                }
+            }
+        }
+    }
+}
+
 impl Default for RegistryConfig {
     fn default() -> Self {
         Self::Oci(Default::default())
@@ -138,3 +828,39 @@ pub struct BasicCredentials {
     pub username: String,
     pub password: SecretString,
 }
+
+/// How tools (e.g. `wkg get`) print WIT output; the client itself doesn't
+/// print WIT. Unset settings are left to tools' defaults.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct WitOutputConfig {
+    /// Whether to include doc comments.
+    pub docs: Option<bool>,
+    /// How to order interfaces and worlds: "declaration" or "alphabetical".
+    pub order: Option<String>,
+    /// Whether to add a comment naming the registry a package came from.
+    pub source_comments: Option<bool>,
+}
+
+impl WitOutputConfig {
+    fn merge(&mut self, other: WitOutputConfig) {
+        if let Some(docs) = other.docs {
+            self.docs = Some(docs);
+        }
+        if let Some(order) = other.order {
+            self.order = Some(order);
+        }
+        if let Some(source_comments) = other.source_comments {
+            self.source_comments = Some(source_comments);
+        }
+    }
+}
+
+/// Credentials for a specific registry, stored separately from the main
+/// config (see [`ClientConfig::load_credentials_file`]).
+#[derive(Clone, Debug, Default)]
+pub struct RegistryCredentials {
+    /// Basic credentials, used by OCI registries.
+    pub basic: Option<BasicCredentials>,
+    /// Auth token, used by Warg registries.
+    pub auth_token: Option<SecretString>,
+}
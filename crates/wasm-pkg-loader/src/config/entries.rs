@@ -0,0 +1,395 @@
+use std::collections::BTreeSet;
+
+use oci_distribution::client::ClientProtocol;
+
+use crate::{
+    cache::format_duration, CachePolicyConfig, ConcurrencyConfig, ContentCachePolicy,
+    ResolutionStrategy, VersionPolicyConfig, YankedPolicy,
+};
+
+use super::{ClientConfig, RegistryConfig};
+
+impl ClientConfig {
+    /// Returns every setting of this config as `key = value` pairs, sorted by
+    /// key. Keys follow the TOML config file's layout where the setting has
+    /// one, and values are in TOML-like syntax. Unset settings are omitted
+    /// and credentials are redacted.
+    pub fn entries(&self) -> Vec<(String, String)> {
+        let mut entries = Entries::default();
+        self.push_entries("", &mut entries);
+        entries.0.sort();
+        entries.0
+    }
+
+    fn push_entries(&self, prefix: &str, entries: &mut Entries) {
+        // Destructured so that a new setting can't be left out
+        let ClientConfig {
+            default_registry,
+            namespace_registries,
+            package_registries,
+            registry_aliases,
+            registry_configs,
+            registry_credentials,
+            package_overrides,
+            version_policy,
+            namespace_version_policies,
+            resolution_strategy,
+            cache_policy,
+            namespace_cache_policies,
+            cache_dir,
+            offline,
+            no_cache,
+            refresh,
+            require_digest,
+            registry_policy,
+            provenance_policy,
+            openpgp_keyring,
+            namespace_openpgp_keyrings,
+            namespace_publishers,
+            output_name_template,
+            wit_output,
+            download_config,
+            http_config,
+            concurrency,
+            registry_concurrency,
+            profiles,
+        } = self;
+        let key = |key: String| format!("{prefix}{key}");
+
+        entries.debug(key("default_registry".into()), default_registry.as_ref());
+        for (namespace, registry) in namespace_registries {
+            entries.debug(
+                key(format!("namespace.{namespace}.registry")),
+                Some(registry),
+            );
+        }
+        for (package, registry) in package_registries {
+            let package = package.to_string();
+            entries.debug(key(format!("package.{package:?}.registry")), Some(registry));
+        }
+        for (alias, registry) in registry_aliases {
+            entries.debug(key(format!("registry_alias.{alias}")), Some(registry));
+        }
+
+        let registries: BTreeSet<_> = registry_configs
+            .keys()
+            .chain(registry_credentials.keys())
+            .collect();
+        for registry in registries {
+            let key = |name: &str| key(format!("registry.{registry:?}.{name}"));
+            let registry_config = registry_configs.get(registry);
+            if registry_config.is_some_and(RegistryConfig::has_credentials)
+                || registry_credentials.contains_key(registry)
+            {
+                entries.push(key("credentials"), "<redacted>");
+            }
+            let Some(registry_config) = registry_config else {
+                continue;
+            };
+            entries.debug(key("type"), Some(registry_config.type_name()));
+            match registry_config {
+                RegistryConfig::GitHub(config) => {
+                    entries.debug(key("repository"), Some(config.repository_template()));
+                    entries.debug(key("tag"), Some(config.tag_template()));
+                    entries.debug(key("asset"), Some(config.asset_template()));
+                    entries.debug(key("api_url"), config.api_url.as_ref());
+                }
+                RegistryConfig::Local(config) => {
+                    entries.debug(key("root"), Some(&config.root));
+                    entries.debug(key("tuf_root"), config.tuf_root.as_ref());
+                }
+                RegistryConfig::Oci(config) => {
+                    let protocol = match &config.client_config.protocol {
+                        ClientProtocol::Http => "\"http\"".to_string(),
+                        ClientProtocol::Https => "\"https\"".to_string(),
+                        protocol => format!("{protocol:?}"),
+                    };
+                    entries.push(key("protocol"), protocol);
+                    entries.debug(key("repository"), Some(config.layout.repository_template()));
+                    entries.debug(key("tag"), Some(config.layout.tag_template()));
+                    if let Some(compat) = &config.compat {
+                        entries.flag(key("compat.paginate_tags"), compat.paginate_tags);
+                        entries.flag(key("compat.avoid_head"), compat.avoid_head);
+                        entries.flag(key("compat.lenient_manifests"), compat.lenient_manifests);
+                    }
+                }
+                RegistryConfig::Warg(config) => {
+                    let home_url = config
+                        .client_config
+                        .as_ref()
+                        .and_then(|config| config.home_url.as_ref());
+                    entries.debug(key("home_url"), home_url);
+                    entries.debug(key("signing_key_file"), config.signing_key_file.as_ref());
+                }
+            }
+        }
+
+        for (package, package_override) in package_overrides {
+            let package = package.to_string();
+            let key = |name: &str| key(format!("override.{package:?}.{name}"));
+            entries.debug(key("path"), Some(&package_override.path));
+            let version = package_override.version.as_ref().map(ToString::to_string);
+            entries.debug(key("version"), version.as_ref());
+        }
+
+        entries.version_policy(&key("resolution.".into()), version_policy);
+        for (namespace, policy) in namespace_version_policies {
+            entries.version_policy(&key(format!("namespace.{namespace}.")), policy);
+        }
+        let strategy = resolution_strategy.map(|strategy| match strategy {
+            ResolutionStrategy::Highest => "highest",
+            ResolutionStrategy::Minimal => "minimal",
+        });
+        entries.debug(key("resolution.strategy".into()), strategy.as_ref());
+
+        entries.cache_policy(&key("cache.".into()), cache_policy);
+        for (namespace, policy) in namespace_cache_policies {
+            entries.cache_policy(&key(format!("namespace.{namespace}.cache.")), policy);
+        }
+        entries.debug(key("cache_dir".into()), cache_dir.as_ref());
+        entries.flag(key("offline".into()), *offline);
+        entries.flag(key("no_cache".into()), *no_cache);
+        entries.flag(key("refresh".into()), *refresh);
+
+        if let Some(require_digest) = require_digest {
+            entries.push(
+                key("security.require_digest".into()),
+                require_digest.to_string(),
+            );
+        }
+        let sorted = |registries: &BTreeSet<String>| format!("{:?}", Vec::from_iter(registries));
+        if let Some(allowed) = registry_policy.allowed() {
+            entries.push(key("security.allowed_registries".into()), sorted(allowed));
+        }
+        if !registry_policy.denied().is_empty() {
+            let denied = sorted(registry_policy.denied());
+            entries.push(key("security.denied_registries".into()), denied);
+        }
+        for (namespace, allowed) in registry_policy.namespaces() {
+            let key = key(format!("security.namespace_registries.{namespace}"));
+            entries.push(key, sorted(allowed));
+        }
+        if let Some(policy) = provenance_policy {
+            entries.push(
+                key("security.require_provenance".into()),
+                policy.require.to_string(),
+            );
+            if !policy.builder_ids.is_empty() {
+                let builder_ids = format!("{:?}", policy.builder_ids);
+                entries.push(key("security.provenance_builders".into()), builder_ids);
+            }
+            let require_log_inclusion = policy.require_log_inclusion;
+            entries.flag(
+                key("security.require_log_inclusion".into()),
+                require_log_inclusion,
+            );
+            let min_log_age = policy.min_log_age.map(format_duration);
+            entries.debug(key("security.min_log_age".into()), min_log_age.as_ref());
+        }
+        entries.debug(
+            key("security.openpgp_keyring".into()),
+            openpgp_keyring.as_ref(),
+        );
+        for (namespace, keyring) in namespace_openpgp_keyrings {
+            entries.debug(
+                key(format!("namespace.{namespace}.openpgp_keyring")),
+                Some(keyring),
+            );
+        }
+        for (namespace, publishers) in namespace_publishers {
+            let key = key(format!("trust.{namespace}.publishers"));
+            entries.push(key, format!("{publishers:?}"));
+        }
+
+        entries.debug(
+            key("output.name_template".into()),
+            output_name_template.as_ref(),
+        );
+        if let Some(docs) = wit_output.docs {
+            entries.push(key("output.wit.docs".into()), docs.to_string());
+        }
+        entries.debug(key("output.wit.order".into()), wit_output.order.as_ref());
+        if let Some(source_comments) = wit_output.source_comments {
+            let key = key("output.wit.source_comments".into());
+            entries.push(key, source_comments.to_string());
+        }
+
+        if let Some(config) = download_config {
+            entries.push(
+                key("download.chunk_size".into()),
+                config.chunk_size.to_string(),
+            );
+            entries.push(
+                key("download.parallelism".into()),
+                config.parallelism.to_string(),
+            );
+            entries.push(key("download.retries".into()), config.retries.to_string());
+        }
+        if let Some(config) = http_config {
+            if let Some(max_idle) = config.pool_max_idle_per_host {
+                entries.push(
+                    key("http.pool_max_idle_per_host".into()),
+                    max_idle.to_string(),
+                );
+            }
+            let idle_timeout = config.pool_idle_timeout.map(format_duration);
+            entries.debug(key("http.pool_idle_timeout".into()), idle_timeout.as_ref());
+            let keep_alive = config.keep_alive_interval.map(format_duration);
+            entries.debug(key("http.keep_alive_interval".into()), keep_alive.as_ref());
+        }
+        entries.concurrency(&key("concurrency.".into()), concurrency);
+        for (registry, concurrency) in registry_concurrency {
+            let prefix = key(format!("concurrency.registry.{registry:?}."));
+            entries.concurrency(&prefix, concurrency);
+        }
+
+        for (name, profile) in profiles {
+            profile.push_entries(&key(format!("profile.{name}.")), entries);
+        }
+    }
+}
+
+#[derive(Default)]
+struct Entries(Vec<(String, String)>);
+
+impl Entries {
+    fn push(&mut self, key: String, value: impl Into<String>) {
+        self.0.push((key, value.into()));
+    }
+
+    /// Pushes the given value, if set, in its `Debug` form, which quotes
+    /// strings and paths as TOML does.
+    fn debug(&mut self, key: String, value: Option<impl std::fmt::Debug>) {
+        if let Some(value) = value {
+            self.push(key, format!("{value:?}"));
+        }
+    }
+
+    /// Pushes the given flag if it is set, as flags can only be enabled.
+    fn flag(&mut self, key: String, value: bool) {
+        if value {
+            self.push(key, "true");
+        }
+    }
+
+    fn version_policy(&mut self, prefix: &str, policy: &VersionPolicyConfig) {
+        if let Some(prereleases) = policy.prereleases {
+            self.push(format!("{prefix}prereleases"), prereleases.to_string());
+        }
+        let yanked = policy.yanked.map(|yanked| match yanked {
+            YankedPolicy::Error => "error",
+            YankedPolicy::Warn => "warn",
+            YankedPolicy::Allow => "allow",
+        });
+        self.debug(format!("{prefix}yanked"), yanked);
+    }
+
+    fn cache_policy(&mut self, prefix: &str, policy: &CachePolicyConfig) {
+        let metadata_ttl = policy.metadata_ttl.map(format_duration);
+        self.debug(format!("{prefix}metadata_ttl"), metadata_ttl);
+        let content = policy.content.map(|content| match content {
+            ContentCachePolicy::Store => "store",
+            ContentCachePolicy::NoStore => "no-store",
+        });
+        self.debug(format!("{prefix}content"), content);
+    }
+
+    fn concurrency(&mut self, prefix: &str, config: &ConcurrencyConfig) {
+        let limits = [
+            ("max_concurrent_downloads", config.max_concurrent_downloads),
+            ("max_concurrent_metadata", config.max_concurrent_metadata),
+        ];
+        for (name, limit) in limits {
+            if let Some(limit) = limit {
+                self.push(format!("{prefix}{name}"), limit.to_string());
+            }
+        }
+        if let Some(adaptive) = config.adaptive {
+            self.push(format!("{prefix}adaptive"), adaptive.to_string());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entries() {
+        let config = ClientConfig::from_toml(
+            r#"
+            default_registry = "example.com"
+
+            [namespace.wasi]
+            registry = "wasi.dev"
+            yanked = "warn"
+            cache = { metadata_ttl = "1h" }
+
+            [registry."example.com"]
+            type = "oci"
+            tag = "v{version}"
+            auth = { username = "user", password = "secret" }
+
+            [resolution]
+            strategy = "minimal"
+
+            [security]
+            allowed_registries = ["wasi.dev", "example.com"]
+            require_provenance = true
+            min_log_age = "30m"
+
+            [trust.wasi]
+            publishers = ["*@wasi.dev"]
+
+            [override."my:pkg"]
+            path = "/src/my-pkg"
+
+            [download]
+            retries = 5
+
+            [http]
+            keep_alive_interval = "60s"
+
+            [concurrency]
+            max_concurrent_downloads = 4
+
+            [profile.ci]
+            default_registry = "ci.example.com"
+            "#,
+        )
+        .unwrap();
+        let entries = config.entries();
+        let get = |key: &str| {
+            entries
+                .iter()
+                .find(|(k, _)| k == key)
+                .map(|(_, value)| value.as_str())
+        };
+        assert_eq!(get("default_registry"), Some("\"example.com\""));
+        assert_eq!(get("namespace.wasi.yanked"), Some("\"warn\""));
+        assert_eq!(get("namespace.wasi.cache.metadata_ttl"), Some("\"1h\""));
+        assert_eq!(get("registry.\"example.com\".tag"), Some("\"v{version}\""));
+        assert_eq!(
+            get("registry.\"example.com\".credentials"),
+            Some("<redacted>")
+        );
+        assert_eq!(get("resolution.strategy"), Some("\"minimal\""));
+        assert_eq!(
+            get("security.allowed_registries"),
+            Some("[\"example.com\", \"wasi.dev\"]")
+        );
+        assert_eq!(get("security.require_provenance"), Some("true"));
+        assert_eq!(get("security.min_log_age"), Some("\"30m\""));
+        assert_eq!(get("trust.wasi.publishers"), Some("[\"*@wasi.dev\"]"));
+        assert_eq!(get("override.\"my:pkg\".path"), Some("\"/src/my-pkg\""));
+        assert_eq!(get("download.retries"), Some("5"));
+        assert_eq!(get("http.keep_alive_interval"), Some("\"1m\""));
+        assert_eq!(get("concurrency.max_concurrent_downloads"), Some("4"));
+        assert_eq!(
+            get("profile.ci.default_registry"),
+            Some("\"ci.example.com\"")
+        );
+        assert!(entries.iter().all(|(_, value)| !value.contains("secret")));
+        assert!(entries.windows(2).all(|pair| pair[0].0 <= pair[1].0));
+    }
+}
@@ -0,0 +1,558 @@
+//! Structural validation of TOML config, producing precise diagnostics
+//! (key path, expected form, near-miss suggestions) before deserialization.
+
+use toml::{Table, Value};
+
+use crate::{label::Label, suggest::closest_name, PackageRef};
+
+/// The expected shape of a config value.
+#[derive(Clone, Copy)]
+enum Shape {
+    Bool,
+    /// A non-negative integer.
+    Count,
+    String,
+    /// One of the given strings.
+    OneOf(&'static [&'static str]),
+    /// A string naming a registry, e.g. `example.com` or `localhost:5000`.
+    RegistryName,
+    /// An array of values of the given shape.
+    List(&'static Shape),
+    /// Either a string or a table with the given keys.
+    StringOrTable(&'static [(&'static str, Shape)]),
+    /// A table with the given known keys.
+    Table(&'static [(&'static str, Shape)]),
+    /// A table with arbitrary keys of the given kind.
+    Map(KeyKind, &'static Shape),
+    /// A registry config table whose known keys depend on its `type`.
+    Registry,
+}
+
+#[derive(Clone, Copy)]
+enum KeyKind {
+    Any,
+    Namespace,
+    Package,
+    Registry,
+}
+
+const YANKED_POLICY: Shape = Shape::OneOf(&["error", "warn", "allow"]);
+
+const CACHE: Shape = Shape::Table(&[
+    ("metadata_ttl", Shape::String),
+    ("content", Shape::OneOf(&["store", "no-store"])),
+]);
+
+const NAMESPACE_CONFIG: Shape = Shape::Table(&[
+    ("registry", Shape::RegistryName),
+    ("prereleases", Shape::Bool),
+    ("yanked", YANKED_POLICY),
+    ("cache", CACHE),
+    ("openpgp_keyring", Shape::String),
+]);
+
+const RESOLUTION: Shape = Shape::Table(&[
+    ("prereleases", Shape::Bool),
+    ("yanked", YANKED_POLICY),
+    ("strategy", Shape::OneOf(&["highest", "minimal"])),
+]);
+
+const SECURITY: Shape = Shape::Table(&[
+    ("require_digest", Shape::Bool),
+    ("allowed_registries", Shape::List(&Shape::RegistryName)),
+    ("denied_registries", Shape::List(&Shape::RegistryName)),
+    (
+        "namespace_registries",
+        Shape::Map(KeyKind::Namespace, &Shape::List(&Shape::RegistryName)),
+    ),
+    ("require_provenance", Shape::Bool),
+    ("provenance_builders", Shape::List(&Shape::String)),
+    ("require_log_inclusion", Shape::Bool),
+    ("min_log_age", Shape::String),
+    ("openpgp_keyring", Shape::String),
+]);
+
+const TRUST: Shape = Shape::Table(&[("publishers", Shape::List(&Shape::String))]);
+
+const OUTPUT: Shape = Shape::Table(&[("name_template", Shape::String), ("wit", WIT_OUTPUT)]);
+
+const WIT_OUTPUT: Shape = Shape::Table(&[
+    ("docs", Shape::Bool),
+    ("order", Shape::OneOf(&["declaration", "alphabetical"])),
+    ("source_comments", Shape::Bool),
+]);
+
+const DOWNLOAD: Shape = Shape::Table(&[
+    ("chunk_size", Shape::Count),
+    ("parallelism", Shape::Count),
+    ("retries", Shape::Count),
+]);
+
+const HTTP: Shape = Shape::Table(&[
+    ("pool_max_idle_per_host", Shape::Count),
+    ("pool_idle_timeout", Shape::String),
+    ("keep_alive_interval", Shape::String),
+]);
+
+const CONCURRENCY_LIMITS: Shape = Shape::Table(&[
+    ("max_concurrent_downloads", Shape::Count),
+    ("max_concurrent_metadata", Shape::Count),
+    ("adaptive", Shape::Bool),
+]);
+
+const CONCURRENCY: Shape = Shape::Table(&[
+    ("max_concurrent_downloads", Shape::Count),
+    ("max_concurrent_metadata", Shape::Count),
+    ("adaptive", Shape::Bool),
+    (
+        "registry",
+        Shape::Map(KeyKind::Registry, &CONCURRENCY_LIMITS),
+    ),
+]);
+
+const OVERRIDE: Shape = Shape::Table(&[("path", Shape::String), ("version", Shape::String)]);
+
+const PROFILE: Shape = Shape::Table(&[
+    ("default_registry", Shape::RegistryName),
+    (
+        "namespace",
+        Shape::Map(KeyKind::Namespace, &NAMESPACE_CONFIG),
+    ),
+    (
+        "registry_alias",
+        Shape::Map(KeyKind::Any, &Shape::RegistryName),
+    ),
+    ("registry", Shape::Map(KeyKind::Registry, &Shape::Registry)),
+    ("resolution", RESOLUTION),
+    ("cache", CACHE),
+    ("security", SECURITY),
+    ("output", OUTPUT),
+    ("download", DOWNLOAD),
+    ("http", HTTP),
+    ("concurrency", CONCURRENCY),
+    ("override", Shape::Map(KeyKind::Package, &OVERRIDE)),
+    ("trust", Shape::Map(KeyKind::Namespace, &TRUST)),
+]);
+
+const ROOT: Shape = Shape::Table(&[
+    ("default_registry", Shape::RegistryName),
+    (
+        "namespace",
+        Shape::Map(KeyKind::Namespace, &NAMESPACE_CONFIG),
+    ),
+    (
+        "registry_alias",
+        Shape::Map(KeyKind::Any, &Shape::RegistryName),
+    ),
+    ("registry", Shape::Map(KeyKind::Registry, &Shape::Registry)),
+    ("resolution", RESOLUTION),
+    ("cache", CACHE),
+    ("security", SECURITY),
+    ("output", OUTPUT),
+    ("download", DOWNLOAD),
+    ("http", HTTP),
+    ("concurrency", CONCURRENCY),
+    ("override", Shape::Map(KeyKind::Package, &OVERRIDE)),
+    ("trust", Shape::Map(KeyKind::Namespace, &TRUST)),
+    ("profile", Shape::Map(KeyKind::Any, &PROFILE)),
+    ("include", Shape::List(&Shape::String)),
+]);
+
+const AUTH: Shape =
+    Shape::StringOrTable(&[("username", Shape::String), ("password", Shape::String)]);
+
+const REGISTRY_CREDENTIALS: Shape = Shape::Map(
+    KeyKind::Registry,
+    &Shape::Table(&[("auth", AUTH), ("auth_token", Shape::String)]),
+);
+
+const CREDENTIALS_ROOT: Shape = Shape::Table(&[
+    ("registry", REGISTRY_CREDENTIALS),
+    (
+        "profile",
+        Shape::Map(
+            KeyKind::Any,
+            &Shape::Table(&[("registry", REGISTRY_CREDENTIALS)]),
+        ),
+    ),
+]);
+
+const REGISTRY_TYPES: &[(&str, &[(&str, Shape)])] = &[
+    (
+        "github",
+        &[
+            ("repository", Shape::String),
+            ("tag", Shape::String),
+            ("asset", Shape::String),
+            ("api_url", Shape::String),
+            ("auth_token", Shape::String),
+        ],
+    ),
+    (
+        "local",
+        &[("root", Shape::String), ("tuf_root", Shape::String)],
+    ),
+    (
+        "oci",
+        &[
+            ("auth", AUTH),
+            ("protocol", Shape::String),
+            ("repository", Shape::String),
+            ("tag", Shape::String),
+            (
+                "compat",
+                Shape::StringOrTable(&[
+                    ("paginate_tags", Shape::Bool),
+                    ("avoid_head", Shape::Bool),
+                    ("lenient_manifests", Shape::Bool),
+                ]),
+            ),
+        ],
+    ),
+    (
+        "warg",
+        &[
+            ("auth_token", Shape::String),
+            ("config_file", Shape::String),
+            ("signing_key_file", Shape::String),
+        ],
+    ),
+];
+
+/// Validates the structure of a parsed config table, returning an error
+/// describing every problem found.
+pub(crate) fn validate(table: &Table) -> anyhow::Result<()> {
+    validate_shape(table, &ROOT)
+}
+
+/// Validates the structure of a parsed credentials table.
+pub(crate) fn validate_credentials(table: &Table) -> anyhow::Result<()> {
+    validate_shape(table, &CREDENTIALS_ROOT)
+}
+
+fn validate_shape(table: &Table, shape: &Shape) -> anyhow::Result<()> {
+    let mut errors = vec![];
+    check_table(&mut errors, &[], table, table_fields(shape));
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!("{}", errors.join("\n")))
+    }
+}
+
+fn table_fields(shape: &Shape) -> &'static [(&'static str, Shape)] {
+    match shape {
+        Shape::Table(fields) | Shape::StringOrTable(fields) => fields,
+        _ => &[],
+    }
+}
+
+fn check_value(errors: &mut Vec<String>, path: &[&str], value: &Value, shape: &Shape) {
+    match (shape, value) {
+        (Shape::Bool, Value::Boolean(_)) | (Shape::String, Value::String(_)) => (),
+        (Shape::Count, Value::Integer(n)) => {
+            if *n < 0 {
+                errors.push(format!(
+                    "{}: invalid value {n}; expected a non-negative integer",
+                    key_path(path)
+                ));
+            }
+        }
+        (Shape::OneOf(options), Value::String(s)) => {
+            if !options.contains(&s.as_str()) {
+                let mut msg = format!(
+                    "{}: invalid value {s:?}; expected one of {}",
+                    key_path(path),
+                    one_of(options.iter().copied())
+                );
+                if let Some(suggestion) = closest_name(s, options.iter().copied()) {
+                    msg.push_str(&format!("; did you mean {suggestion:?}?"));
+                }
+                errors.push(msg);
+            }
+        }
+        (Shape::RegistryName, Value::String(name)) => {
+            if let Err(reason) = validate_registry_name(name) {
+                errors.push(format!(
+                    "{}: invalid registry {name:?}: {reason}; expected a domain with optional port, e.g. \"example.com\" or \"localhost:5000\"",
+                    key_path(path)
+                ));
+            }
+        }
+        (Shape::List(inner), Value::Array(items)) => {
+            for item in items {
+                check_value(errors, path, item, inner);
+            }
+        }
+        (Shape::StringOrTable(_), Value::String(_)) => (),
+        (Shape::Table(fields) | Shape::StringOrTable(fields), Value::Table(table)) => {
+            check_table(errors, path, table, fields)
+        }
+        (Shape::Map(kind, inner), Value::Table(table)) => {
+            for (key, value) in table {
+                let key_invalid = match kind {
+                    KeyKind::Any => None,
+                    KeyKind::Namespace => key
+                        .parse::<Label>()
+                        .err()
+                        .map(|err| format!("invalid namespace {key:?}: {err}")),
+                    KeyKind::Package => key
+                        .parse::<PackageRef>()
+                        .err()
+                        .map(|err| format!("invalid package {key:?}: {err}")),
+                    KeyKind::Registry => validate_registry_name(key)
+                        .err()
+                        .map(|reason| format!("invalid registry {key:?}: {reason}")),
+                };
+                let mut path = path.to_vec();
+                path.push(key);
+                if let Some(msg) = key_invalid {
+                    errors.push(format!("{}: {msg}", key_path(&path)));
+                }
+                check_value(errors, &path, value, inner);
+            }
+        }
+        (Shape::Registry, Value::Table(table)) => check_registry(errors, path, table),
+        (shape, value) => errors.push(format!(
+            "{}: expected {}, found {}",
+            key_path(path),
+            expected_form(shape),
+            value.type_str()
+        )),
+    }
+}
+
+fn check_table(
+    errors: &mut Vec<String>,
+    path: &[&str],
+    table: &Table,
+    fields: &[(&'static str, Shape)],
+) {
+    for (key, value) in table {
+        let mut path = path.to_vec();
+        path.push(key);
+        match fields.iter().find(|(name, _)| name == key) {
+            Some((_, shape)) => check_value(errors, &path, value, shape),
+            None => errors.push(unknown_key(
+                &path,
+                key,
+                fields.iter().map(|(name, _)| *name),
+            )),
+        }
+    }
+}
+
+fn check_registry(errors: &mut Vec<String>, path: &[&str], table: &Table) {
+    let type_names = REGISTRY_TYPES.iter().map(|(name, _)| *name);
+    let mut type_path = path.to_vec();
+    type_path.push("type");
+    let fields = match table.get("type") {
+        None => {
+            errors.push(format!(
+                "{}: missing required key; expected one of {}",
+                key_path(&type_path),
+                one_of(type_names)
+            ));
+            return;
+        }
+        Some(Value::String(ty)) => match REGISTRY_TYPES.iter().find(|(name, _)| name == ty) {
+            Some((_, fields)) => *fields,
+            None => {
+                let mut msg = format!(
+                    "{}: unknown registry type {ty:?}; expected one of {}",
+                    key_path(&type_path),
+                    one_of(type_names.clone())
+                );
+                if let Some(suggestion) = closest_name(ty, type_names) {
+                    msg.push_str(&format!("; did you mean {suggestion:?}?"));
+                }
+                errors.push(msg);
+                return;
+            }
+        },
+        Some(other) => {
+            errors.push(format!(
+                "{}: expected a string, found {}",
+                key_path(&type_path),
+                other.type_str()
+            ));
+            return;
+        }
+    };
+    for (key, value) in table {
+        if key == "type" {
+            continue;
+        }
+        let mut path = path.to_vec();
+        path.push(key);
+        match fields.iter().find(|(name, _)| name == key) {
+            Some((_, shape)) => check_value(errors, &path, value, shape),
+            None => errors.push(unknown_key(
+                &path,
+                key,
+                std::iter::once("type").chain(fields.iter().map(|(name, _)| *name)),
+            )),
+        }
+    }
+}
+
+fn unknown_key<'a>(
+    path: &[&str],
+    key: &str,
+    known: impl Iterator<Item = &'a str> + Clone,
+) -> String {
+    let mut msg = format!("{}: unknown key", key_path(path));
+    if known.clone().next().is_some() {
+        msg.push_str(&format!("; expected one of {}", one_of(known.clone())));
+    }
+    if let Some(suggestion) = closest_name(key, known) {
+        msg.push_str(&format!("; did you mean `{suggestion}`?"));
+    }
+    msg
+}
+
+fn expected_form(shape: &Shape) -> &'static str {
+    match shape {
+        Shape::Bool => "a boolean",
+        Shape::Count => "a non-negative integer",
+        Shape::String | Shape::OneOf(_) => "a string",
+        Shape::RegistryName => "a registry domain string",
+        Shape::List(_) => "an array",
+        Shape::StringOrTable(_) => "a string or table",
+        Shape::Table(_) | Shape::Map(..) | Shape::Registry => "a table",
+    }
+}
+
+fn one_of<'a>(names: impl Iterator<Item = &'a str>) -> String {
+    names
+        .map(|name| format!("`{name}`"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Formats a key path as it would appear in TOML, e.g. `registry."example.com".auth`.
+fn key_path(path: &[&str]) -> String {
+    path.iter()
+        .map(|key| {
+            let bare = !key.is_empty()
+                && key
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+            if bare {
+                key.to_string()
+            } else {
+                format!("{key:?}")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+fn validate_registry_name(name: &str) -> Result<(), &'static str> {
+    if name.contains("://") {
+        return Err("must not include a URL scheme");
+    }
+    let (host, port) = match name.rsplit_once(':') {
+        Some((host, port)) => (host, Some(port)),
+        None => (name, None),
+    };
+    if host.is_empty() {
+        return Err("host may not be empty");
+    }
+    if !host
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.')
+    {
+        return Err("host may contain only alphanumeric ASCII characters, '-', and '.'");
+    }
+    if port.is_some_and(|port| port.parse::<u16>().is_err()) {
+        return Err("port must be a number between 0 and 65535");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn validate_str(s: &str) -> anyhow::Result<()> {
+        validate(&s.parse().unwrap())
+    }
+
+    #[test]
+    fn test_valid() {
+        validate_str(
+            r#"
+            default_registry = "localhost:5000"
+            [namespace.wasi]
+            registry = "wasi.dev"
+            [registry."wasi.dev"]
+            type = "oci"
+            auth = { username = "open", password = "sesame" }
+            "#,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_unknown_key_suggestion() {
+        let err = validate_str(
+            r#"
+            [registry."example.com"]
+            type = "oci"
+            protcol = "http"
+            "#,
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(
+            err.contains(r#"registry."example.com".protcol: unknown key"#),
+            "{err}"
+        );
+        assert!(err.contains("did you mean `protocol`?"), "{err}");
+    }
+
+    #[test]
+    fn test_wrong_type() {
+        let err = validate_str("default_registry = 1")
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            err,
+            "default_registry: expected a registry domain string, found integer"
+        );
+    }
+
+    #[test]
+    fn test_invalid_one_of() {
+        let err = validate_str(
+            r#"
+            [namespace.wasi]
+            yanked = "warning"
+            "#,
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(
+            err.starts_with(r#"namespace.wasi.yanked: invalid value "warning""#),
+            "{err}"
+        );
+        assert!(err.contains(r#"did you mean "warn"?"#), "{err}");
+    }
+
+    #[test]
+    fn test_invalid_registry() {
+        let err = validate_str(
+            r#"
+            [namespace.wasi]
+            registry = "https://wasi.dev"
+            "#,
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(
+            err.starts_with("namespace.wasi.registry: invalid registry"),
+            "{err}"
+        );
+    }
+}
@@ -12,37 +12,343 @@ use secrecy::{ExposeSecret, SecretString};
 use serde::Deserialize;
 
 use crate::{
-    source::{local::LocalConfig, oci::OciConfig, warg::WargConfig},
-    Error,
+    source::{
+        github::GitHubConfig,
+        local::LocalConfig,
+        oci::{OciCompat, OciConfig, OciLayout},
+        path::PackageOverride,
+        warg::WargConfig,
+    },
+    CachePolicyConfig, ConcurrencyConfig, DownloadConfig, Error, HttpConfig, PackageRef,
+    ProvenancePolicy, RegistryPolicy, VersionPolicyConfig,
 };
 
-use super::BasicCredentials;
+use super::{BasicCredentials, RegistryCredentials};
+
+const CONFIG_FILE_NAME: &str = "config.toml";
+const CREDENTIALS_FILE_NAME: &str = "credentials.toml";
 
 impl super::ClientConfig {
     pub fn from_toml(s: &str) -> Result<Self, Error> {
-        let toml_cfg: TomlConfig = toml::from_str(s)
+        let (config, includes) = Self::parse_toml(s)?;
+        if !includes.is_empty() {
+            return Err(Error::InvalidConfig(anyhow::anyhow!(
+                "`include` is only supported when loading a config file"
+            )));
+        }
+        Ok(config)
+    }
+
+    /// Parses a config, returning it along with the paths it includes.
+    fn parse_toml(s: &str) -> Result<(Self, Vec<PathBuf>), Error> {
+        let mut table: toml::Table = s
+            .parse()
             .context("error parsing TOML")
             .map_err(Error::InvalidConfig)?;
-        toml_cfg.try_into().map_err(Error::InvalidConfig)
+        super::schema::validate(&table).map_err(Error::InvalidConfig)?;
+        let includes = table
+            .remove("include")
+            .map(toml::Value::try_into)
+            .transpose()
+            .context("error parsing include")
+            .map_err(Error::InvalidConfig)?
+            .unwrap_or_default();
+        let toml_cfg: TomlConfig = toml::Value::Table(table)
+            .try_into()
+            .context("error parsing config")
+            .map_err(Error::InvalidConfig)?;
+        let config = toml_cfg.try_into().map_err(Error::InvalidConfig)?;
+        Ok((config, includes))
     }
 
+    /// Reads a config file. Any files listed in its `include` array (relative
+    /// to the including file) are loaded first, with the including file's
+    /// settings layered on top, e.g.:
+    ///
+    /// ```toml
+    /// include = ["team-registries.toml"]
+    /// ```
     pub fn from_file(path: impl AsRef<Path>) -> Result<Self, Error> {
-        tracing::debug!("Reading config file from {:?}", path.as_ref());
-        Self::from_toml(std::fs::read_to_string(path)?.as_str())
+        Self::from_file_with_includes(path.as_ref(), &mut vec![])
+    }
+
+    /// Reads a config file and its includes; `stack` holds the canonical
+    /// paths of the files currently being loaded, to detect include cycles.
+    fn from_file_with_includes(path: &Path, stack: &mut Vec<PathBuf>) -> Result<Self, Error> {
+        tracing::debug!("Reading config file from {path:?}");
+        let canonical = path.canonicalize()?;
+        if stack.contains(&canonical) {
+            return Err(Error::InvalidConfig(anyhow::anyhow!(
+                "config file {path:?} includes itself"
+            )));
+        }
+        let in_file = |err: Error| match err {
+            Error::InvalidConfig(err) => {
+                Error::InvalidConfig(err.context(format!("in config file {path:?}")))
+            }
+            err => err,
+        };
+        let (mut config, includes) =
+            Self::parse_toml(std::fs::read_to_string(path)?.as_str()).map_err(in_file)?;
+        let base = path.parent().unwrap_or(Path::new(""));
+        config.resolve_override_paths(base);
+
+        stack.push(canonical);
+        let mut merged = Self::default();
+        for include in includes {
+            let included =
+                Self::from_file_with_includes(&base.join(include), stack).map_err(in_file)?;
+            merged.merge_config(included);
+        }
+        stack.pop();
+        merged.merge_config(config);
+        Ok(merged)
+    }
+
+    /// Makes relative override and keyring paths relative to the given base
+    /// directory (that of the config file they were read from).
+    fn resolve_override_paths(&mut self, base: &Path) {
+        for package_override in self.package_overrides.values_mut() {
+            if package_override.path.is_relative() {
+                package_override.path = base.join(&package_override.path);
+            }
+        }
+        let keyrings = self
+            .openpgp_keyring
+            .iter_mut()
+            .chain(self.namespace_openpgp_keyrings.values_mut());
+        for keyring in keyrings {
+            if keyring.is_relative() {
+                *keyring = base.join(&*keyring);
+            }
+        }
+        for profile in self.profiles.values_mut() {
+            profile.resolve_override_paths(base);
+        }
     }
 
+    /// Returns a config read from the default config file path, with
+    /// credentials loaded from the default credentials file path (if present).
+    /// Returns Ok(None) if neither file exists.
     pub fn from_default_file() -> Result<Option<Self>, Error> {
-        let Some(config_dir) = dirs::config_dir() else {
+        let Some(config_dir) = crate::paths::config_dir() else {
             return Ok(None);
         };
-        let path = config_dir.join("wasm-pkg").join("config.toml");
-        if !path.exists() {
+        let path = config_dir.join(CONFIG_FILE_NAME);
+        let credentials_path = config_dir.join(CREDENTIALS_FILE_NAME);
+        if !path.exists() && !credentials_path.exists() {
             return Ok(None);
         }
-        Ok(Some(Self::from_file(path)?))
+        let mut config = if path.exists() {
+            Self::from_file(path)?
+        } else {
+            Self::default()
+        };
+        if credentials_path.exists() {
+            config.load_credentials_file(credentials_path)?;
+        }
+        Ok(Some(config))
+    }
+
+    /// Returns the default config file path, e.g.
+    /// `~/.config/wasm-pkg/config.toml`.
+    pub fn default_config_path() -> Option<PathBuf> {
+        Some(crate::paths::config_dir()?.join(CONFIG_FILE_NAME))
+    }
+
+    /// Returns the default cache directory, e.g. `~/.cache/wasm-pkg`.
+    pub fn default_cache_dir() -> Option<PathBuf> {
+        crate::paths::cache_dir()
+    }
+
+    /// Returns the default data directory, e.g. `~/.local/share/wasm-pkg`.
+    pub fn default_data_dir() -> Option<PathBuf> {
+        crate::paths::data_dir()
+    }
+
+    /// Returns the default credentials file path, e.g.
+    /// `~/.config/wasm-pkg/credentials.toml`.
+    pub fn default_credentials_path() -> Option<PathBuf> {
+        Some(crate::paths::config_dir()?.join(CREDENTIALS_FILE_NAME))
+    }
+
+    /// Moves the default config, cache, and data directories from their
+    /// legacy (platform default) locations to those given by
+    /// `XDG_CONFIG_HOME`, `XDG_CACHE_HOME`, and `XDG_DATA_HOME`, where only
+    /// the legacy directory exists. Returns the directories moved from and
+    /// to.
+    pub fn migrate_default_dirs() -> Result<Vec<(PathBuf, PathBuf)>, Error> {
+        Ok(crate::paths::migrate_dirs()?)
+    }
+
+    /// Loads registry credentials from the given credentials file, e.g.:
+    ///
+    /// ```toml
+    /// [registry."example.com"]
+    /// auth = { username = "open", password = "sesame" }
+    ///
+    /// [registry."warg.example.com"]
+    /// auth_token = "..."
+    /// ```
+    pub fn load_credentials_file(&mut self, path: impl AsRef<Path>) -> Result<&mut Self, Error> {
+        let path = path.as_ref();
+        tracing::debug!("Reading credentials file from {path:?}");
+        warn_if_not_private(path);
+        let credentials = parse_credentials(std::fs::read_to_string(path)?.as_str())
+            .with_context(|| format!("in credentials file {path:?}"))
+            .map_err(Error::InvalidConfig)?;
+        Ok(self.merge_config(credentials))
     }
 }
 
+impl RegistryCredentials {
+    /// Stores these credentials for the given registry (and optional
+    /// profile) in the credentials file at the given path, replacing any
+    /// existing credentials for that registry. A new file is created with
+    /// owner-only (0600) permissions.
+    pub fn store(
+        &self,
+        path: impl AsRef<Path>,
+        profile: Option<&str>,
+        registry: &str,
+    ) -> Result<(), Error> {
+        let path = path.as_ref();
+        let mut table: toml::Table = match std::fs::read_to_string(path) {
+            Ok(contents) => contents
+                .parse()
+                .with_context(|| format!("error parsing credentials file {path:?}"))
+                .map_err(Error::InvalidConfig)?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Default::default(),
+            Err(err) => return Err(err.into()),
+        };
+        let mut keys = vec![];
+        if let Some(profile) = profile {
+            keys.extend(["profile", profile]);
+        }
+        keys.push("registry");
+        let mut registries = &mut table;
+        for key in keys {
+            registries = registries
+                .entry(key)
+                .or_insert_with(|| toml::Value::Table(Default::default()))
+                .as_table_mut()
+                .with_context(|| {
+                    format!("invalid credentials file {path:?}: {key:?} must be a table")
+                })
+                .map_err(Error::InvalidConfig)?;
+        }
+
+        let mut entry = toml::Table::new();
+        if let Some(BasicCredentials { username, password }) = &self.basic {
+            let mut auth = toml::Table::new();
+            auth.insert("username".into(), username.clone().into());
+            auth.insert("password".into(), password.expose_secret().clone().into());
+            entry.insert("auth".into(), toml::Value::Table(auth));
+        }
+        if let Some(auth_token) = &self.auth_token {
+            entry.insert(
+                "auth_token".into(),
+                auth_token.expose_secret().clone().into(),
+            );
+        }
+        registries.insert(registry.into(), toml::Value::Table(entry));
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        write_private_file(path, table.to_string().as_bytes())?;
+        Ok(())
+    }
+}
+
+fn write_private_file(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(path)?;
+    // The mode above only applies to newly-created files
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+    }
+    file.write_all(contents)
+}
+
+fn warn_if_not_private(path: &Path) {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if let Ok(metadata) = std::fs::metadata(path) {
+            let mode = metadata.permissions().mode();
+            if mode & 0o077 != 0 {
+                tracing::warn!(
+                    "Credentials file {path:?} is accessible by other users (mode {:o}); consider `chmod 600`",
+                    mode & 0o777
+                );
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+}
+
+fn parse_credentials(s: &str) -> anyhow::Result<super::ClientConfig> {
+    let table: toml::Table = s.parse().context("error parsing TOML")?;
+    super::schema::validate_credentials(&table)?;
+    let toml_creds: TomlCredentials = toml::Value::Table(table)
+        .try_into()
+        .context("error parsing credentials")?;
+    toml_creds.try_into()
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TomlCredentials {
+    #[serde(default)]
+    registry: HashMap<String, TomlRegistryCredentials>,
+    #[serde(default)]
+    profile: HashMap<String, TomlCredentials>,
+}
+
+impl TryFrom<TomlCredentials> for super::ClientConfig {
+    type Error = anyhow::Error;
+
+    fn try_from(value: TomlCredentials) -> Result<Self, Self::Error> {
+        let TomlCredentials { registry, profile } = value;
+        let registry_credentials = registry
+            .into_iter()
+            .map(|(registry, creds)| {
+                let basic = creds.auth.map(TryInto::try_into).transpose()?;
+                let credentials = RegistryCredentials {
+                    basic,
+                    auth_token: creds.auth_token,
+                };
+                Ok((registry, credentials))
+            })
+            .collect::<Result<_, Self::Error>>()?;
+        let profiles = profile
+            .into_iter()
+            .map(|(name, creds)| Ok((name, creds.try_into()?)))
+            .collect::<Result<_, Self::Error>>()?;
+        Ok(Self {
+            registry_credentials,
+            profiles,
+            ..Default::default()
+        })
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TomlRegistryCredentials {
+    auth: Option<TomlAuth>,
+    auth_token: Option<SecretString>,
+}
+
 #[derive(Deserialize)]
 #[serde(deny_unknown_fields)]
 struct TomlConfig {
@@ -50,7 +356,22 @@ struct TomlConfig {
     #[serde(default)]
     namespace: HashMap<String, TomlNamespaceConfig>,
     #[serde(default)]
+    registry_alias: HashMap<String, String>,
+    #[serde(default)]
     registry: HashMap<String, TomlRegistryConfig>,
+    resolution: Option<TomlResolution>,
+    cache: Option<TomlCachePolicy>,
+    security: Option<TomlSecurity>,
+    output: Option<TomlOutput>,
+    download: Option<TomlDownload>,
+    http: Option<TomlHttp>,
+    concurrency: Option<TomlConcurrency>,
+    #[serde(default, rename = "override")]
+    overrides: HashMap<String, TomlOverride>,
+    #[serde(default)]
+    trust: HashMap<String, TomlTrust>,
+    #[serde(default)]
+    profile: HashMap<String, TomlConfig>,
 }
 
 impl TryFrom<TomlConfig> for super::ClientConfig {
@@ -60,20 +381,162 @@ impl TryFrom<TomlConfig> for super::ClientConfig {
         let TomlConfig {
             default_registry,
             namespace,
+            registry_alias,
             registry,
+            resolution,
+            cache,
+            security,
+            output,
+            download,
+            http,
+            concurrency,
+            overrides,
+            trust,
+            profile,
         } = value;
-        let namespace_registries = namespace
-            .into_iter()
-            .map(|(name, config)| (name, config.registry))
-            .collect();
+        let mut namespace_registries = HashMap::new();
+        let mut namespace_version_policies = HashMap::new();
+        let mut namespace_cache_policies = HashMap::new();
+        let mut namespace_openpgp_keyrings = HashMap::new();
+        for (name, config) in namespace {
+            let TomlNamespaceConfig {
+                registry,
+                prereleases,
+                yanked,
+                cache,
+                openpgp_keyring,
+            } = config;
+            if let Some(keyring) = openpgp_keyring {
+                namespace_openpgp_keyrings.insert(name.clone(), keyring);
+            }
+            if let Some(cache) = cache {
+                let policy = cache
+                    .try_into()
+                    .with_context(|| format!("in namespace {name:?}"))?;
+                namespace_cache_policies.insert(name.clone(), policy);
+            }
+            let policy = TomlVersionPolicy {
+                prereleases,
+                yanked,
+            }
+            .try_into()
+            .with_context(|| format!("in namespace {name:?}"))?;
+            namespace_version_policies.insert(name.clone(), policy);
+            if let Some(registry) = registry {
+                namespace_registries.insert(name, registry);
+            }
+        }
+        let TomlResolution {
+            prereleases,
+            yanked,
+            strategy,
+        } = resolution.unwrap_or_default();
+        let version_policy = TomlVersionPolicy {
+            prereleases,
+            yanked,
+        }
+        .try_into()?;
+        let resolution_strategy = strategy.map(|strategy| strategy.parse()).transpose()?;
+        let cache_policy = cache
+            .map(TryInto::try_into)
+            .transpose()?
+            .unwrap_or_default();
+        let TomlSecurity {
+            require_digest,
+            allowed_registries,
+            denied_registries,
+            namespace_registries: allowed_namespace_registries,
+            require_provenance,
+            provenance_builders,
+            require_log_inclusion,
+            min_log_age,
+            openpgp_keyring,
+        } = security.unwrap_or_default();
+        let min_log_age = min_log_age
+            .map(|age| crate::cache::parse_duration(&age))
+            .transpose()?;
+        let require_log_inclusion = require_log_inclusion.unwrap_or_default();
+        let provenance_policy = (require_provenance.is_some()
+            || provenance_builders.is_some()
+            || require_log_inclusion
+            || min_log_age.is_some())
+        .then(|| ProvenancePolicy {
+            // Transparency log requirements imply a required attestation
+            require: require_provenance.unwrap_or_default()
+                || require_log_inclusion
+                || min_log_age.is_some(),
+            builder_ids: provenance_builders.unwrap_or_default(),
+            require_log_inclusion,
+            min_log_age,
+        });
+        let mut registry_policy = RegistryPolicy::default();
+        if let Some(allowed) = allowed_registries {
+            registry_policy.allow(allowed);
+        }
+        registry_policy.deny(denied_registries);
+        for (namespace, allowed) in allowed_namespace_registries {
+            registry_policy.allow_for_namespace(namespace, allowed);
+        }
         let registry_configs = registry
             .into_iter()
             .map(|(k, v)| Ok((k, v.try_into()?)))
             .collect::<Result<_, Self::Error>>()?;
+        let package_overrides = overrides
+            .into_iter()
+            .map(|(package, TomlOverride { path, version })| {
+                let package: PackageRef = package.parse()?;
+                let version = version
+                    .map(|ver| ver.parse())
+                    .transpose()
+                    .with_context(|| format!("invalid override version for {package}"))?;
+                Ok((package, PackageOverride { path, version }))
+            })
+            .collect::<Result<_, Self::Error>>()?;
+        let TomlConcurrency {
+            limits: concurrency,
+            registry: registry_concurrency,
+        } = concurrency.unwrap_or_default();
+        let concurrency = concurrency.into();
+        let registry_concurrency = registry_concurrency
+            .into_iter()
+            .map(|(registry, limits)| (registry, limits.into()))
+            .collect();
+        let profiles = profile
+            .into_iter()
+            .map(|(name, config)| Ok((name, config.try_into()?)))
+            .collect::<Result<_, Self::Error>>()?;
+        let TomlOutput {
+            name_template: output_name_template,
+            wit: wit_output,
+        } = output.unwrap_or_default();
         Ok(Self {
             default_registry,
             namespace_registries,
+            registry_aliases: registry_alias,
             registry_configs,
+            package_overrides,
+            version_policy,
+            namespace_version_policies,
+            resolution_strategy,
+            cache_policy,
+            namespace_cache_policies,
+            require_digest,
+            registry_policy,
+            provenance_policy,
+            openpgp_keyring,
+            namespace_openpgp_keyrings,
+            namespace_publishers: trust
+                .into_iter()
+                .map(|(namespace, trust)| (namespace, trust.publishers))
+                .collect(),
+            output_name_template,
+            wit_output: wit_output.map(Into::into).unwrap_or_default(),
+            download_config: download.map(Into::into),
+            http_config: http.map(TryInto::try_into).transpose()?,
+            concurrency,
+            registry_concurrency,
+            profiles,
+            ..Default::default()
         })
     }
 }
@@ -81,23 +544,223 @@ impl TryFrom<TomlConfig> for super::ClientConfig {
 #[derive(Deserialize)]
 #[serde(deny_unknown_fields)]
 struct TomlNamespaceConfig {
-    registry: String,
+    registry: Option<String>,
+    prereleases: Option<bool>,
+    yanked: Option<String>,
+    cache: Option<TomlCachePolicy>,
+    openpgp_keyring: Option<PathBuf>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TomlResolution {
+    prereleases: Option<bool>,
+    yanked: Option<String>,
+    strategy: Option<String>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TomlSecurity {
+    require_digest: Option<bool>,
+    allowed_registries: Option<Vec<String>>,
+    #[serde(default)]
+    denied_registries: Vec<String>,
+    /// Registries allowed per namespace. Local `[override]` paths count as
+    /// the registry "override" (see [`crate::OVERRIDE_REGISTRY`]).
+    #[serde(default)]
+    namespace_registries: HashMap<String, Vec<String>>,
+    /// Whether fetched packages must have a provenance attestation.
+    require_provenance: Option<bool>,
+    /// Builder IDs accepted in required provenance attestations.
+    provenance_builders: Option<Vec<String>>,
+    /// Whether required provenance attestations must be recorded in a
+    /// transparency log.
+    require_log_inclusion: Option<bool>,
+    /// How long ago required provenance attestations must have been recorded
+    /// in a transparency log, e.g. "30m".
+    min_log_age: Option<String>,
+    /// The OpenPGP keyring fetched content must be signed by a key of.
+    openpgp_keyring: Option<PathBuf>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TomlTrust {
+    /// Patterns a verified signer identity must match.
+    publishers: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TomlVersionPolicy {
+    prereleases: Option<bool>,
+    yanked: Option<String>,
+}
+
+impl TryFrom<TomlVersionPolicy> for VersionPolicyConfig {
+    type Error = anyhow::Error;
+
+    fn try_from(value: TomlVersionPolicy) -> Result<Self, Self::Error> {
+        Ok(Self {
+            prereleases: value.prereleases,
+            yanked: value.yanked.map(|yanked| yanked.parse()).transpose()?,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TomlCachePolicy {
+    metadata_ttl: Option<String>,
+    content: Option<String>,
+}
+
+impl TryFrom<TomlCachePolicy> for CachePolicyConfig {
+    type Error = anyhow::Error;
+
+    fn try_from(value: TomlCachePolicy) -> Result<Self, Self::Error> {
+        Ok(Self {
+            metadata_ttl: value
+                .metadata_ttl
+                .map(|ttl| crate::cache::parse_duration(&ttl))
+                .transpose()?,
+            content: value.content.map(|content| content.parse()).transpose()?,
+        })
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TomlOutput {
+    /// The file name template for fetched package output.
+    name_template: Option<String>,
+    wit: Option<TomlWitOutput>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TomlWitOutput {
+    docs: Option<bool>,
+    order: Option<String>,
+    source_comments: Option<bool>,
+}
+
+impl From<TomlWitOutput> for super::WitOutputConfig {
+    fn from(value: TomlWitOutput) -> Self {
+        Self {
+            docs: value.docs,
+            order: value.order,
+            source_comments: value.source_comments,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TomlDownload {
+    chunk_size: Option<u64>,
+    parallelism: Option<usize>,
+    retries: Option<u32>,
+}
+
+impl From<TomlDownload> for DownloadConfig {
+    fn from(value: TomlDownload) -> Self {
+        let default = DownloadConfig::default();
+        Self {
+            chunk_size: value.chunk_size.unwrap_or(default.chunk_size),
+            parallelism: value.parallelism.unwrap_or(default.parallelism),
+            retries: value.retries.unwrap_or(default.retries),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TomlHttp {
+    pool_max_idle_per_host: Option<usize>,
+    pool_idle_timeout: Option<String>,
+    keep_alive_interval: Option<String>,
+}
+
+impl TryFrom<TomlHttp> for HttpConfig {
+    type Error = anyhow::Error;
+
+    fn try_from(value: TomlHttp) -> Result<Self, Self::Error> {
+        let parse = |duration: Option<String>| {
+            duration
+                .map(|duration| crate::cache::parse_duration(&duration))
+                .transpose()
+        };
+        Ok(Self {
+            pool_max_idle_per_host: value.pool_max_idle_per_host,
+            pool_idle_timeout: parse(value.pool_idle_timeout)?,
+            keep_alive_interval: parse(value.keep_alive_interval)?,
+        })
+    }
+}
+
+#[derive(Default, Deserialize)]
+struct TomlConcurrency {
+    #[serde(flatten)]
+    limits: TomlConcurrencyLimits,
+    /// Per-registry limits.
+    #[serde(default)]
+    registry: HashMap<String, TomlConcurrencyLimits>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TomlConcurrencyLimits {
+    max_concurrent_downloads: Option<usize>,
+    max_concurrent_metadata: Option<usize>,
+    adaptive: Option<bool>,
+}
+
+impl From<TomlConcurrencyLimits> for ConcurrencyConfig {
+    fn from(value: TomlConcurrencyLimits) -> Self {
+        Self {
+            max_concurrent_downloads: value.max_concurrent_downloads,
+            max_concurrent_metadata: value.max_concurrent_metadata,
+            adaptive: value.adaptive,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TomlOverride {
+    path: PathBuf,
+    version: Option<String>,
 }
 
 #[derive(Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 #[serde(deny_unknown_fields)]
 enum TomlRegistryConfig {
+    #[serde(rename = "github")]
+    GitHub {
+        repository: String,
+        tag: Option<String>,
+        asset: Option<String>,
+        api_url: Option<String>,
+        auth_token: Option<SecretString>,
+    },
     Local {
         root: PathBuf,
+        tuf_root: Option<PathBuf>,
     },
     Oci {
         auth: Option<TomlAuth>,
         protocol: Option<String>,
+        repository: Option<String>,
+        tag: Option<String>,
+        compat: Option<TomlOciCompat>,
     },
     Warg {
         auth_token: Option<SecretString>,
         config_file: Option<PathBuf>,
+        signing_key_file: Option<PathBuf>,
     },
 }
 
@@ -106,21 +769,62 @@ impl TryFrom<TomlRegistryConfig> for super::RegistryConfig {
 
     fn try_from(value: TomlRegistryConfig) -> Result<Self, Self::Error> {
         Ok(match value {
-            TomlRegistryConfig::Local { root } => Self::Local(LocalConfig { root }),
-            TomlRegistryConfig::Oci { auth, protocol } => {
+            TomlRegistryConfig::GitHub {
+                repository,
+                tag,
+                asset,
+                api_url,
+                auth_token,
+            } => {
+                let mut config = GitHubConfig::new(repository)?;
+                if let Some(tag) = tag {
+                    config.set_tag_template(tag)?;
+                }
+                if let Some(asset) = asset {
+                    config.set_asset_template(asset);
+                }
+                config.api_url = api_url;
+                config.auth_token = auth_token;
+                Self::GitHub(config)
+            }
+            TomlRegistryConfig::Local { root, tuf_root } => {
+                Self::Local(LocalConfig { root, tuf_root })
+            }
+            TomlRegistryConfig::Oci {
+                auth,
+                protocol,
+                repository,
+                tag,
+                compat,
+            } => {
                 let mut client_config = oci_distribution::client::ClientConfig::default();
                 if let Some(protocol) = protocol {
                     client_config.protocol = oci_client_protocol(&protocol)?;
                 };
                 let credentials = auth.map(TryInto::try_into).transpose()?;
+                let layout = if repository.is_some() || tag.is_some() {
+                    let default = OciLayout::default();
+                    OciLayout::new(
+                        repository.unwrap_or_else(|| default.repository_template().into()),
+                        tag.unwrap_or_else(|| default.tag_template().into()),
+                    )?
+                } else {
+                    OciLayout::default()
+                };
                 Self::Oci(OciConfig {
                     client_config,
                     credentials,
+                    layout,
+                    compat: compat
+                        .map(TomlOciCompat::into_compat)
+                        .transpose()?
+                        .flatten(),
                 })
             }
             TomlRegistryConfig::Warg {
                 auth_token,
                 config_file,
+                signing_key_file,
             } => {
                 let client_config = match config_file {
                     Some(path) => Some(warg_client::Config::from_file(path)?),
@@ -129,12 +833,51 @@ impl TryFrom<TomlRegistryConfig> for super::RegistryConfig {
                 Self::Warg(WargConfig {
                     auth_token,
                     client_config,
+                    signing_key_file,
                 })
             }
         })
     }
 }
 
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TomlOciCompat {
+    Preset(String),
+    Toggles {
+        #[serde(default)]
+        paginate_tags: bool,
+        #[serde(default)]
+        avoid_head: bool,
+        #[serde(default)]
+        lenient_manifests: bool,
+    },
+}
+
+impl TomlOciCompat {
+    /// Returns the configured workarounds, or `None` for "auto" to detect
+    /// them from the registry name.
+    fn into_compat(self) -> anyhow::Result<Option<OciCompat>> {
+        Ok(match self {
+            Self::Preset(name) if name == "auto" => None,
+            Self::Preset(name) => Some(OciCompat::preset(&name).with_context(|| {
+                format!(
+                    "unknown OCI compat preset {name:?}; expected \"auto\", \"artifactory\", \"nexus\", \"harbor\", or \"none\""
+                )
+            })?),
+            Self::Toggles {
+                paginate_tags,
+                avoid_head,
+                lenient_manifests,
+            } => Some(OciCompat {
+                paginate_tags,
+                avoid_head,
+                lenient_manifests,
+            }),
+        })
+    }
+}
+
 #[derive(Deserialize)]
 #[serde(untagged)]
 #[serde(deny_unknown_fields)]
@@ -189,7 +932,13 @@ fn oci_client_protocol(text: &str) -> Result<oci_distribution::client::ClientPro
 
 #[cfg(test)]
 mod tests {
-    use crate::config::{ClientConfig, RegistryConfig};
+    use std::time::Duration;
+
+    use crate::{
+        config::{ClientConfig, RegistryConfig},
+        CachePolicy, ConcurrencyLimits, ContentCachePolicy, ResolutionStrategy, VersionPolicy,
+        WitOutputConfig, YankedPolicy,
+    };
 
     use super::*;
 
@@ -209,6 +958,7 @@ mod tests {
             [registry."wasi.dev"]
             type = "oci"
             auth = "cGluZzpwb25n"
+            repository = "wasm/{namespace}/{name}"
         "#;
         let cfg = ClientConfig::from_toml(toml_config).unwrap();
 
@@ -232,5 +982,582 @@ mod tests {
         let BasicCredentials { username, password } = oci_config.credentials.as_ref().unwrap();
         assert_eq!(username, "ping");
         assert_eq!(password.expose_secret(), "pong");
+        assert_eq!(
+            oci_config.layout,
+            OciLayout::new("wasm/{namespace}/{name}", "{version}").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_github_registry() {
+        let toml_config = r#"
+            [namespace.my-ns]
+            registry = "gh"
+
+            [registry.gh]
+            type = "github"
+            repository = "my-org/{name}"
+            tag = "{name}-v{version}"
+            auth_token = "secret"
+        "#;
+        let cfg = ClientConfig::from_toml(toml_config).unwrap();
+        let RegistryConfig::GitHub(github_config) = &cfg.registry_configs["gh"] else {
+            panic!("not a github config");
+        };
+        assert_eq!(github_config.repository_template(), "my-org/{name}");
+        assert_eq!(github_config.tag_template(), "{name}-v{version}");
+        assert_eq!(github_config.asset_template(), "{name}.wasm");
+        assert_eq!(
+            github_config.auth_token.as_ref().unwrap().expose_secret(),
+            "secret"
+        );
+
+        let toml_config = r#"
+            [registry.gh]
+            type = "github"
+            repository = "my-org/{name}"
+            tag = "latest"
+        "#;
+        assert!(ClientConfig::from_toml(toml_config).is_err());
+    }
+
+    #[test]
+    fn test_oci_compat() {
+        let toml_config = r#"
+            [registry."artifacts.example.com"]
+            type = "oci"
+            compat = "artifactory"
+
+            [registry."registry.example.com"]
+            type = "oci"
+            compat = { avoid_head = true }
+
+            [registry."auto.example.com"]
+            type = "oci"
+            compat = "auto"
+        "#;
+        let cfg = ClientConfig::from_toml(toml_config).unwrap();
+        let compat = |registry: &str| {
+            let RegistryConfig::Oci(oci_config) = &cfg.registry_configs[registry] else {
+                panic!("not an oci config");
+            };
+            oci_config.compat.clone()
+        };
+        assert_eq!(
+            compat("artifacts.example.com"),
+            OciCompat::preset("artifactory")
+        );
+        assert_eq!(
+            compat("registry.example.com"),
+            Some(OciCompat {
+                avoid_head: true,
+                ..Default::default()
+            })
+        );
+        assert_eq!(compat("auto.example.com"), None);
+
+        let toml_config = r#"
+            [registry."example.com"]
+            type = "oci"
+            compat = "quay"
+        "#;
+        assert!(ClientConfig::from_toml(toml_config).is_err());
+    }
+
+    #[test]
+    fn test_registry_aliases() {
+        let toml_config = r#"
+            default_registry = "corp"
+
+            [registry_alias]
+            corp = "packages.corp.internal:8443"
+
+            [namespace.wasi]
+            registry = "wasi.dev"
+        "#;
+        let cfg = ClientConfig::from_toml(toml_config).unwrap();
+        assert_eq!(
+            cfg.resolve_package_registry(&"corp:pkg".parse().unwrap())
+                .unwrap(),
+            "packages.corp.internal:8443"
+        );
+        assert_eq!(
+            cfg.resolve_package_registry(&"wasi:io".parse().unwrap())
+                .unwrap(),
+            "wasi.dev"
+        );
+    }
+
+    #[test]
+    fn test_overrides() {
+        let toml_config = r#"
+            [override."wasi:http"]
+            path = "../wit/http"
+
+            [override."wasi:io"]
+            path = "/wit/io"
+            version = "0.2.0"
+        "#;
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let path = dir.join(CONFIG_FILE_NAME);
+        std::fs::write(&path, toml_config).unwrap();
+        let cfg = ClientConfig::from_file(&path).unwrap();
+
+        let http = cfg.package_override(&"wasi:http".parse().unwrap()).unwrap();
+        assert_eq!(http.path, dir.join("../wit/http"));
+        assert_eq!(http.version, None);
+
+        let io = cfg.package_override(&"wasi:io".parse().unwrap()).unwrap();
+        assert_eq!(io.path, PathBuf::from("/wit/io"));
+        assert_eq!(io.version, Some("0.2.0".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_version_policy() {
+        let toml_config = r#"
+            [resolution]
+            yanked = "warn"
+            strategy = "minimal"
+
+            [namespace.wasi]
+            prereleases = true
+
+            [namespace.corp]
+            registry = "corp.example.com"
+            yanked = "allow"
+        "#;
+        let cfg = ClientConfig::from_toml(toml_config).unwrap();
+        assert_eq!(
+            cfg.version_policy(&"other:pkg".parse().unwrap()),
+            VersionPolicy {
+                prereleases: false,
+                yanked: YankedPolicy::Warn,
+            }
+        );
+        assert_eq!(
+            cfg.version_policy(&"wasi:io".parse().unwrap()),
+            VersionPolicy {
+                prereleases: true,
+                yanked: YankedPolicy::Warn,
+            }
+        );
+        assert_eq!(
+            cfg.version_policy(&"corp:pkg".parse().unwrap()).yanked,
+            YankedPolicy::Allow
+        );
+        assert!(!cfg.namespace_registries.contains_key("wasi"));
+        assert_eq!(cfg.resolution_strategy(), ResolutionStrategy::Minimal);
+    }
+
+    #[test]
+    fn test_includes() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        std::fs::create_dir_all(dir.join("team")).unwrap();
+        std::fs::write(
+            dir.join("team/registries.toml"),
+            r#"
+            default_registry = "team.example.com"
+
+            [namespace.team]
+            registry = "team.example.com"
+
+            [override."team:pkg"]
+            path = "wit"
+            "#,
+        )
+        .unwrap();
+        let path = dir.join(CONFIG_FILE_NAME);
+        std::fs::write(
+            &path,
+            r#"
+            include = ["team/registries.toml"]
+            default_registry = "example.com"
+            "#,
+        )
+        .unwrap();
+        let cfg = ClientConfig::from_file(&path).unwrap();
+
+        std::fs::write(
+            dir.join("team/registries.toml"),
+            r#"include = ["../config.toml"]"#,
+        )
+        .unwrap();
+        let err = ClientConfig::from_file(&path)
+            .err()
+            .expect("include cycle should be rejected")
+            .to_string();
+        assert!(err.contains("includes itself"), "{err}");
+
+        assert_eq!(cfg.default_registry(), Some("example.com"));
+        assert_eq!(cfg.namespace_registries["team"], "team.example.com");
+        let team = cfg.package_override(&"team:pkg".parse().unwrap()).unwrap();
+        assert_eq!(team.path, dir.join("team/wit"));
+
+        assert!(ClientConfig::from_toml(r#"include = ["other.toml"]"#).is_err());
+    }
+
+    #[test]
+    fn test_cache_policy() {
+        let toml_config = r#"
+            [cache]
+            metadata_ttl = "1d"
+
+            [namespace.my-org.cache]
+            metadata_ttl = "30s"
+            content = "no-store"
+        "#;
+        let cfg = ClientConfig::from_toml(toml_config).unwrap();
+        assert_eq!(
+            cfg.cache_policy(&"wasi:io".parse().unwrap()),
+            CachePolicy {
+                metadata_ttl: Duration::from_secs(24 * 60 * 60),
+                content: ContentCachePolicy::Store,
+            }
+        );
+        assert_eq!(
+            cfg.cache_policy(&"my-org:pkg".parse().unwrap()),
+            CachePolicy {
+                metadata_ttl: Duration::from_secs(30),
+                content: ContentCachePolicy::NoStore,
+            }
+        );
+    }
+
+    #[test]
+    fn test_require_digest() {
+        let toml_config = r#"
+            [security]
+            require_digest = true
+
+            [profile.dev.security]
+            require_digest = false
+        "#;
+        let mut cfg = ClientConfig::from_toml(toml_config).unwrap();
+        assert!(cfg.require_digest());
+        cfg.apply_profile("dev").unwrap();
+        assert!(!cfg.require_digest());
+    }
+
+    #[test]
+    fn test_provenance_policy() {
+        let toml_config = r#"
+            [security]
+            require_provenance = true
+            provenance_builders = ["https://ci.example.com"]
+        "#;
+        let cfg = ClientConfig::from_toml(toml_config).unwrap();
+        assert_eq!(
+            cfg.provenance_policy(),
+            &ProvenancePolicy {
+                require: true,
+                builder_ids: vec!["https://ci.example.com".into()],
+                ..Default::default()
+            }
+        );
+        assert!(!ClientConfig::default().provenance_policy().require);
+
+        let toml_config = r#"
+            [security]
+            min_log_age = "30m"
+        "#;
+        let cfg = ClientConfig::from_toml(toml_config).unwrap();
+        assert_eq!(
+            cfg.provenance_policy(),
+            &ProvenancePolicy {
+                require: true,
+                min_log_age: Some(std::time::Duration::from_secs(1800)),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_trust_publishers() {
+        let toml_config = r#"
+            [trust."wasi"]
+            publishers = ["https://github.com/WebAssembly/*"]
+        "#;
+        let cfg = ClientConfig::from_toml(toml_config).unwrap();
+        assert_eq!(
+            cfg.publishers(&"wasi:io".parse().unwrap()),
+            Some(&["https://github.com/WebAssembly/*".to_string()][..])
+        );
+        assert_eq!(cfg.publishers(&"my-org:pkg".parse().unwrap()), None);
+
+        // A later layer can narrow a pin but not loosen or replace it
+        let mut merged = cfg.clone();
+        merged.merge_config(
+            ClientConfig::from_toml(
+                r#"
+                [trust."wasi"]
+                publishers = ["*", "https://github.com/WebAssembly/*"]
+                "#,
+            )
+            .unwrap(),
+        );
+        assert_eq!(
+            merged.publishers(&"wasi:io".parse().unwrap()),
+            Some(&["https://github.com/WebAssembly/*".to_string()][..])
+        );
+        merged.merge_config(
+            ClientConfig::from_toml(
+                r#"
+                [trust."wasi"]
+                publishers = ["*"]
+                "#,
+            )
+            .unwrap(),
+        );
+        assert_eq!(
+            merged.publishers(&"wasi:io".parse().unwrap()),
+            Some(&[][..])
+        );
+    }
+
+    #[test]
+    fn test_openpgp_keyring() {
+        let toml_config = r#"
+            [security]
+            openpgp_keyring = "/etc/wkg/trusted.gpg"
+
+            [namespace.my-org]
+            openpgp_keyring = "/etc/wkg/my-org.gpg"
+        "#;
+        let cfg = ClientConfig::from_toml(toml_config).unwrap();
+        assert_eq!(
+            cfg.openpgp_keyring(&"my-org:pkg".parse().unwrap()),
+            Some(Path::new("/etc/wkg/my-org.gpg"))
+        );
+        assert_eq!(
+            cfg.openpgp_keyring(&"wasi:io".parse().unwrap()),
+            Some(Path::new("/etc/wkg/trusted.gpg"))
+        );
+        assert_eq!(
+            ClientConfig::default().openpgp_keyring(&"wasi:io".parse().unwrap()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_output_name_template() {
+        let toml_config = r#"
+            [output]
+            name_template = "{name}-{version}.{ext}"
+
+            [profile.ci.output]
+            name_template = "{namespace}.{name}.{ext}"
+        "#;
+        let mut cfg = ClientConfig::from_toml(toml_config).unwrap();
+        assert_eq!(cfg.output_name_template(), Some("{name}-{version}.{ext}"));
+        cfg.apply_profile("ci").unwrap();
+        assert_eq!(cfg.output_name_template(), Some("{namespace}.{name}.{ext}"));
+        assert_eq!(ClientConfig::default().output_name_template(), None);
+    }
+
+    #[test]
+    fn test_wit_output() {
+        let toml_config = r#"
+            [output.wit]
+            docs = false
+            order = "alphabetical"
+
+            [profile.ci.output.wit]
+            source_comments = true
+        "#;
+        let mut cfg = ClientConfig::from_toml(toml_config).unwrap();
+        cfg.apply_profile("ci").unwrap();
+        assert_eq!(
+            cfg.wit_output(),
+            &WitOutputConfig {
+                docs: Some(false),
+                order: Some("alphabetical".into()),
+                source_comments: Some(true),
+            }
+        );
+    }
+
+    #[test]
+    fn test_download_config() {
+        let toml_config = r#"
+            [download]
+            parallelism = 8
+            retries = 0
+        "#;
+        let cfg = ClientConfig::from_toml(toml_config).unwrap();
+        assert_eq!(
+            cfg.download_config(),
+            &DownloadConfig {
+                parallelism: 8,
+                retries: 0,
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            ClientConfig::default().download_config(),
+            &DownloadConfig::default()
+        );
+
+        let toml_config = r#"
+            [download]
+            chunk_size = -1
+        "#;
+        assert!(ClientConfig::from_toml(toml_config).is_err());
+    }
+
+    #[test]
+    fn test_http_config() {
+        let toml_config = r#"
+            [http]
+            pool_max_idle_per_host = 2
+            keep_alive_interval = "30s"
+        "#;
+        let cfg = ClientConfig::from_toml(toml_config).unwrap();
+        assert_eq!(
+            cfg.http_config(),
+            &HttpConfig {
+                pool_max_idle_per_host: Some(2),
+                pool_idle_timeout: None,
+                keep_alive_interval: Some(Duration::from_secs(30)),
+            }
+        );
+    }
+
+    #[test]
+    fn test_concurrency() {
+        let toml_config = r#"
+            [concurrency]
+            max_concurrent_downloads = 4
+
+            [concurrency.registry."ghcr.io"]
+            max_concurrent_metadata = 2
+            adaptive = false
+        "#;
+        let cfg = ClientConfig::from_toml(toml_config).unwrap();
+        assert_eq!(
+            cfg.concurrency(None),
+            ConcurrencyLimits {
+                max_concurrent_downloads: 4,
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            cfg.concurrency(Some("ghcr.io")),
+            ConcurrencyLimits {
+                max_concurrent_downloads: 4,
+                max_concurrent_metadata: 2,
+                adaptive: false,
+            }
+        );
+    }
+
+    #[test]
+    fn test_registry_policy() {
+        let toml_config = r#"
+            default_registry = "example.com"
+
+            [namespace.wasi]
+            registry = "wasi.dev"
+
+            [security]
+            allowed_registries = ["example.com", "wasi.dev"]
+
+            [security.namespace_registries]
+            wasi = ["wasi.dev"]
+
+            [profile.evil.namespace.wasi]
+            registry = "example.com"
+
+            [profile.evil.security]
+            allowed_registries = ["example.com", "evil.com"]
+        "#;
+        let mut cfg = ClientConfig::from_toml(toml_config).unwrap();
+        let wasi_io = "wasi:io".parse().unwrap();
+        assert_eq!(cfg.resolve_package_registry(&wasi_io).unwrap(), "wasi.dev");
+        cfg.set_namespace_registry("other", "evil.com");
+        assert!(matches!(
+            cfg.resolve_package_registry(&"other:pkg".parse().unwrap()),
+            Err(Error::RegistryNotAllowed(..))
+        ));
+        cfg.apply_profile("evil").unwrap();
+        assert!(matches!(
+            cfg.resolve_package_registry(&wasi_io),
+            Err(Error::RegistryNotAllowed(..))
+        ));
+    }
+
+    #[test]
+    fn test_profiles() {
+        let toml_config = r#"
+            default_registry = "example.com"
+
+            [namespace.wasi]
+            registry = "wasi.dev"
+
+            [profile.staging]
+            default_registry = "staging.example.com"
+
+            [profile.staging.namespace.wasi]
+            registry = "staging.wasi.dev"
+        "#;
+        let mut cfg = ClientConfig::from_toml(toml_config).unwrap();
+        assert!(cfg.apply_profile("nonexistent").is_err());
+
+        cfg.apply_profile("staging").unwrap();
+        assert_eq!(cfg.default_registry(), Some("staging.example.com"));
+        assert_eq!(cfg.namespace_registries["wasi"], "staging.wasi.dev");
+    }
+
+    #[test]
+    fn test_credentials_file() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let path = dir.join(CREDENTIALS_FILE_NAME);
+
+        let basic = BasicCredentials {
+            username: "open".into(),
+            password: "sesame".to_string().into(),
+        };
+        RegistryCredentials {
+            basic: Some(basic),
+            auth_token: None,
+        }
+        .store(&path, None, "example.com")
+        .unwrap();
+        RegistryCredentials {
+            basic: None,
+            auth_token: Some("token".to_string().into()),
+        }
+        .store(&path, Some("staging"), "warg.example.com")
+        .unwrap();
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        let mut cfg = ClientConfig::default();
+        cfg.load_credentials_file(&path).unwrap();
+        assert!(cfg.registry_credentials("warg.example.com").is_none());
+
+        let BasicCredentials { username, password } = cfg
+            .registry_credentials("example.com")
+            .unwrap()
+            .basic
+            .as_ref()
+            .unwrap();
+        assert_eq!(username, "open");
+        assert_eq!(password.expose_secret(), "sesame");
+        cfg.apply_profile("staging").unwrap();
+        let token = cfg
+            .registry_credentials("warg.example.com")
+            .unwrap()
+            .auth_token
+            .as_ref()
+            .unwrap();
+        assert_eq!(token.expose_secret(), "token");
     }
 }
@@ -0,0 +1,78 @@
+//! Deprecation notices, attached by publishers to releases to warn users
+//! away from them (e.g. an unmaintained package or a release with a known
+//! bug) and suggest a replacement.
+
+use serde::{Deserialize, Serialize};
+
+use crate::Error;
+
+/// A deprecation notice for a release, or for a whole package.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Deprecation {
+    /// The notice shown to users.
+    pub message: String,
+    /// A package (or version of it) to use instead, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replacement: Option<String>,
+    /// Whether the whole package, rather than only this release, is
+    /// deprecated.
+    #[serde(default)]
+    pub package: bool,
+    /// The date (YYYY-MM-DD) after which the release is no longer
+    /// supported, if announced.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_of_life: Option<String>,
+}
+
+impl Deprecation {
+    /// Parses a notice as stored by sources.
+    pub fn from_json(bytes: &[u8]) -> Result<Self, Error> {
+        serde_json::from_slice(bytes)
+            .map_err(|err| Error::InvalidContent(format!("invalid deprecation notice: {err}")))
+    }
+
+    /// Serializes the notice to be stored by sources.
+    pub fn to_json(&self) -> Vec<u8> {
+        serde_json::to_vec(self).expect("deprecation notices serialize")
+    }
+}
+
+impl std::fmt::Display for Deprecation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)?;
+        if let Some(replacement) = &self.replacement {
+            write!(f, " (use {replacement} instead)")?;
+        }
+        if let Some(end_of_life) = &self.end_of_life {
+            write!(f, " (end of life: {end_of_life})")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deprecation_json() {
+        let deprecation = Deprecation {
+            message: "Unmaintained".into(),
+            replacement: Some("my-org:gateway2".into()),
+            package: true,
+            end_of_life: Some("2025-06-30".into()),
+        };
+        assert_eq!(
+            Deprecation::from_json(&deprecation.to_json()).unwrap(),
+            deprecation
+        );
+        assert_eq!(
+            deprecation.to_string(),
+            "Unmaintained (use my-org:gateway2 instead) (end of life: 2025-06-30)"
+        );
+        let minimal = Deprecation::from_json(br#"{"message":"Broken"}"#).unwrap();
+        assert_eq!(minimal.replacement, None);
+        assert!(!minimal.package);
+        assert_eq!(minimal.end_of_life, None);
+    }
+}
@@ -0,0 +1,224 @@
+//! Parallel downloads of content as concurrent HTTP range requests.
+
+use std::time::Duration;
+
+use bytes::Bytes;
+use futures_util::{stream::BoxStream, StreamExt, TryStreamExt};
+use reqwest::{
+    header::{HeaderMap, CONTENT_RANGE, RANGE},
+    StatusCode,
+};
+
+use crate::Error;
+
+pub(crate) const DEFAULT_CHUNK_SIZE: u64 = 8 * 1024 * 1024;
+
+pub(crate) const DEFAULT_PARALLELISM: usize = 4;
+
+pub(crate) const DEFAULT_RETRIES: u32 = 3;
+
+/// The delay before the first retry of a failed range, doubled for each
+/// subsequent retry.
+const RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// Settings for downloading large content in parallel ranges, from sources
+/// that support it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DownloadConfig {
+    /// The size, in bytes, of each range. Content no larger than this is
+    /// downloaded with a single request.
+    pub chunk_size: u64,
+    /// The maximum number of ranges downloaded concurrently. Ranged
+    /// downloads are disabled if this is 1.
+    pub parallelism: usize,
+    /// The number of times a range that fails with a transient error (see
+    /// [`Error::is_transient`]) is retried, so an interrupted download
+    /// resumes from that range rather than failing.
+    pub retries: u32,
+}
+
+impl Default for DownloadConfig {
+    fn default() -> Self {
+        Self {
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            parallelism: DEFAULT_PARALLELISM,
+            retries: DEFAULT_RETRIES,
+        }
+    }
+}
+
+/// An HTTP download that may support range requests.
+pub struct RangedDownload {
+    pub client: reqwest::Client,
+    pub url: String,
+    /// Headers (e.g. authorization) sent with requests to `url`. These are
+    /// not sent to any other URL it redirects to.
+    pub headers: HeaderMap,
+}
+
+impl RangedDownload {
+    /// Returns a stream of the content, downloaded in ranges as configured,
+    /// or None if the server doesn't support range requests or the content
+    /// fits in a single range. The content is not validated.
+    pub(crate) async fn stream(
+        self,
+        config: &DownloadConfig,
+    ) -> Result<Option<BoxStream<'static, Result<Bytes, Error>>>, Error> {
+        if config.parallelism <= 1 || config.chunk_size == 0 {
+            return Ok(None);
+        }
+        let Self {
+            client,
+            url,
+            headers,
+        } = self;
+
+        // Probe for range support and the content size
+        let resp = client
+            .get(&url)
+            .headers(headers.clone())
+            .header(RANGE, "bytes=0-0")
+            .send()
+            .await?
+            .error_for_status()?;
+        if resp.status() != StatusCode::PARTIAL_CONTENT {
+            tracing::debug!("Range requests not supported by {url:?}");
+            return Ok(None);
+        }
+        let Some(size) = resp
+            .headers()
+            .get(CONTENT_RANGE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(content_range_size)
+        else {
+            tracing::debug!("No complete Content-Range from {url:?}");
+            return Ok(None);
+        };
+        if size <= config.chunk_size {
+            return Ok(None);
+        }
+
+        // Request ranges from the final URL after any redirects, e.g. from
+        // an API endpoint to a signed storage URL.
+        let (url, headers) = if resp.url().as_str() == url {
+            (url, headers)
+        } else {
+            (resp.url().to_string(), HeaderMap::new())
+        };
+        tracing::debug!(
+            size,
+            chunk_size = config.chunk_size,
+            parallelism = config.parallelism,
+            "Downloading {url:?} in ranges"
+        );
+        let chunk_size = config.chunk_size;
+        let retries = config.retries;
+        let ranges = (0..size)
+            .step_by(chunk_size as usize)
+            .map(move |start| (start, (start + chunk_size).min(size) - 1));
+        let stream = futures_util::stream::iter(ranges)
+            .map(move |(start, end)| {
+                let request = client
+                    .get(&url)
+                    .headers(headers.clone())
+                    .header(RANGE, format!("bytes={start}-{end}"));
+                async move {
+                    let mut attempt = 0;
+                    loop {
+                        let request = request.try_clone().expect("range requests have no body");
+                        match fetch_range(request, start, end).await {
+                            Err(err) if attempt < retries && err.is_transient() => {
+                                let delay = RETRY_DELAY * 2u32.pow(attempt);
+                                attempt += 1;
+                                tracing::warn!(
+                                    "Range {start}-{end} failed: {err}; retrying in {delay:?} ({attempt}/{retries})"
+                                );
+                                tokio::time::sleep(delay).await;
+                            }
+                            result => break result,
+                        }
+                    }
+                }
+            })
+            // Ranges are downloaded concurrently but yielded in order
+            .buffered(config.parallelism);
+        Ok(Some(stream.boxed()))
+    }
+
+    /// Returns a stream of the content from byte `offset` onward, e.g. to
+    /// resume an interrupted download, or None if the server doesn't honor
+    /// the range. The content is not validated.
+    pub(crate) async fn stream_from(
+        self,
+        offset: u64,
+    ) -> Result<Option<BoxStream<'static, Result<Bytes, Error>>>, Error> {
+        let resp = self
+            .client
+            .get(&self.url)
+            .headers(self.headers)
+            .header(RANGE, format!("bytes={offset}-"))
+            .send()
+            .await?
+            .error_for_status()?;
+        let start = resp
+            .headers()
+            .get(CONTENT_RANGE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(content_range_start);
+        if resp.status() != StatusCode::PARTIAL_CONTENT || start != Some(offset) {
+            tracing::debug!("Can't resume {:?} from byte {offset}", self.url);
+            return Ok(None);
+        }
+        Ok(Some(resp.bytes_stream().map_err(Error::from).boxed()))
+    }
+}
+
+async fn fetch_range(
+    request: reqwest::RequestBuilder,
+    start: u64,
+    end: u64,
+) -> Result<Bytes, Error> {
+    let resp = request.send().await?.error_for_status()?;
+    if resp.status() != StatusCode::PARTIAL_CONTENT {
+        return Err(Error::InvalidContent(format!(
+            "expected partial content for range {start}-{end}; got {}",
+            resp.status()
+        )));
+    }
+    let bytes = resp.bytes().await?;
+    if bytes.len() as u64 != end - start + 1 {
+        return Err(Error::InvalidContent(format!(
+            "range {start}-{end} returned {} bytes",
+            bytes.len()
+        )));
+    }
+    Ok(bytes)
+}
+
+/// Parses the complete size from a Content-Range value, e.g.
+/// `bytes 0-0/1234`.
+fn content_range_size(value: &str) -> Option<u64> {
+    let (_, size) = value.strip_prefix("bytes ")?.split_once('/')?;
+    size.parse().ok()
+}
+
+/// Parses the start of the range from a Content-Range value, e.g.
+/// `bytes 100-1233/1234`.
+fn content_range_start(value: &str) -> Option<u64> {
+    let (start, _) = value.strip_prefix("bytes ")?.split_once('-')?;
+    start.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_content_range_size() {
+        assert_eq!(content_range_size("bytes 0-0/1234"), Some(1234));
+        assert_eq!(content_range_size("bytes 0-0/*"), None);
+        assert_eq!(content_range_size("items 0-0/1234"), None);
+        assert_eq!(content_range_start("bytes 100-1233/1234"), Some(100));
+        assert_eq!(content_range_start("bytes */1234"), None);
+    }
+}
@@ -0,0 +1,263 @@
+//! A resolved dependency graph, for consumption by external build tools.
+
+use semver::{Version, VersionReq};
+
+use crate::{ContentDigest, DependencySet, Error, PackageRef};
+
+/// A package version in a [`DependencyGraph`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct GraphNode {
+    pub package: PackageRef,
+    pub version: Version,
+    pub content_digest: ContentDigest,
+    /// The registry the package is fetched from, or `None` if the package
+    /// is overridden by a local path.
+    pub registry: Option<String>,
+}
+
+/// A WIT reference from one [`GraphNode`] to another, by node index.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GraphEdge {
+    pub from: usize,
+    pub to: usize,
+    /// The requirement implied by the reference.
+    pub req: VersionReq,
+}
+
+/// A resolved dependency graph. Nodes are sorted by package name.
+#[derive(Clone, Debug, Default)]
+pub struct DependencyGraph {
+    nodes: Vec<GraphNode>,
+    edges: Vec<GraphEdge>,
+    roots: Vec<usize>,
+}
+
+impl DependencyGraph {
+    /// Builds a graph from a resolved dependency set, looking up each
+    /// package's registry with the given function.
+    pub(crate) fn new(
+        deps: &DependencySet,
+        roots: &[PackageRef],
+        mut registry: impl FnMut(&PackageRef) -> Result<Option<String>, Error>,
+    ) -> Result<Self, Error> {
+        let nodes = deps
+            .iter()
+            .map(|resolved| {
+                Ok(GraphNode {
+                    package: resolved.package.clone(),
+                    version: resolved.version.clone(),
+                    content_digest: resolved.release.content_digest.clone(),
+                    registry: registry(&resolved.package)?,
+                })
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        let mut graph = Self {
+            nodes,
+            ..Default::default()
+        };
+        for (from, resolved) in deps.iter().enumerate() {
+            for (dep, req) in &resolved.dependencies {
+                if let Some(to) = graph.find(dep) {
+                    graph.edges.push(GraphEdge {
+                        from,
+                        to,
+                        req: req.clone(),
+                    });
+                }
+            }
+        }
+        graph.roots = roots.iter().filter_map(|root| graph.find(root)).collect();
+        Ok(graph)
+    }
+
+    pub fn nodes(&self) -> &[GraphNode] {
+        &self.nodes
+    }
+
+    pub fn edges(&self) -> &[GraphEdge] {
+        &self.edges
+    }
+
+    /// Returns the indexes of the nodes for the root requirements.
+    pub fn roots(&self) -> &[usize] {
+        &self.roots
+    }
+
+    /// Returns the index of the node for the given package, if present.
+    pub fn find(&self, package: &PackageRef) -> Option<usize> {
+        self.nodes
+            .binary_search_by(|node| node.package.cmp(package))
+            .ok()
+    }
+
+    /// Returns the edges from the node at the given index.
+    pub fn dependencies(&self, index: usize) -> impl Iterator<Item = &GraphEdge> {
+        self.edges.iter().filter(move |edge| edge.from == index)
+    }
+
+    /// Returns the edges to the node at the given index.
+    pub fn dependents(&self, index: usize) -> impl Iterator<Item = &GraphEdge> {
+        self.edges.iter().filter(move |edge| edge.to == index)
+    }
+
+    /// Returns the paths of WIT references from the roots to the node at
+    /// the given index, as node indexes from a root to the node, shortest
+    /// first. At most `limit` paths are returned.
+    pub fn paths_to(&self, index: usize, limit: usize) -> Vec<Vec<usize>> {
+        let mut paths = vec![];
+        // Walk dependents back towards the roots, depth-first, skipping
+        // nodes already on the current path to avoid cycles
+        let mut stack = vec![vec![index]];
+        while let Some(path) = stack.pop() {
+            if paths.len() >= limit {
+                break;
+            }
+            let head = *path.last().unwrap();
+            if self.roots.contains(&head) {
+                paths.push(path.iter().rev().copied().collect::<Vec<_>>());
+            }
+            for edge in self.dependents(head) {
+                if !path.contains(&edge.from) {
+                    let mut next = path.clone();
+                    next.push(edge.from);
+                    stack.push(next);
+                }
+            }
+        }
+        paths.sort_by_key(Vec::len);
+        paths
+    }
+
+    /// Renders the graph in Graphviz DOT syntax, with each edge labeled by
+    /// its requirement. If `project` is given, it is added as a node
+    /// referencing the roots.
+    pub fn to_dot(&self, project: Option<&str>) -> String {
+        let quote =
+            |label: &str| format!("\"{}\"", label.replace('\\', "\\\\").replace('"', "\\\""));
+        let mut dot = String::from("digraph dependencies {\n");
+        if let Some(project) = project {
+            dot.push_str(&format!("    {} [shape=box];\n", quote(project)));
+            for &root in &self.roots {
+                let root = quote(&self.node_label(root));
+                dot.push_str(&format!("    {} -> {root};\n", quote(project)));
+            }
+        }
+        for index in 0..self.nodes.len() {
+            dot.push_str(&format!("    {};\n", quote(&self.node_label(index))));
+        }
+        for edge in &self.edges {
+            dot.push_str(&format!(
+                "    {} -> {} [label={}];\n",
+                quote(&self.node_label(edge.from)),
+                quote(&self.node_label(edge.to)),
+                quote(&edge.req.to_string()),
+            ));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Renders the graph as a Mermaid flowchart, with each edge labeled by
+    /// its requirement. If `project` is given, it is added as a node
+    /// referencing the roots.
+    pub fn to_mermaid(&self, project: Option<&str>) -> String {
+        let quote = |label: &str| format!("\"{}\"", label.replace('"', "#quot;"));
+        let mut mermaid = String::from("graph TD\n");
+        if let Some(project) = project {
+            mermaid.push_str(&format!("    project[{}]\n", quote(project)));
+            for &root in &self.roots {
+                mermaid.push_str(&format!("    project --> n{root}\n"));
+            }
+        }
+        for index in 0..self.nodes.len() {
+            mermaid.push_str(&format!(
+                "    n{index}[{}]\n",
+                quote(&self.node_label(index))
+            ));
+        }
+        for edge in &self.edges {
+            mermaid.push_str(&format!(
+                "    n{} -->|{}| n{}\n",
+                edge.from,
+                quote(&edge.req.to_string()),
+                edge.to,
+            ));
+        }
+        mermaid
+    }
+
+    /// Returns the label of the node at the given index, e.g.
+    /// `wasi:io@0.2.0`.
+    fn node_label(&self, index: usize) -> String {
+        let node = &self.nodes[index];
+        format!("{}@{}", node.package, node.version)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paths_to() {
+        let node = |package: &str| GraphNode {
+            package: package.parse().unwrap(),
+            version: "0.2.0".parse().unwrap(),
+            content_digest: format!("sha256:{}", "0".repeat(64)).parse().unwrap(),
+            registry: None,
+        };
+        let edge = |from, to| GraphEdge {
+            from,
+            to,
+            req: "^0.2.0".parse().unwrap(),
+        };
+        // wasi:cli -> wasi:http -> wasi:io, wasi:cli -> wasi:io, and a
+        // cycle between wasi:http and wasi:io
+        let graph = DependencyGraph {
+            nodes: vec![node("wasi:cli"), node("wasi:http"), node("wasi:io")],
+            edges: vec![edge(0, 1), edge(1, 2), edge(0, 2), edge(2, 1)],
+            roots: vec![0],
+        };
+        assert_eq!(graph.paths_to(2, 10), vec![vec![0, 2], vec![0, 1, 2]]);
+        assert_eq!(graph.paths_to(0, 10), vec![vec![0]]);
+        assert_eq!(graph.paths_to(2, 1).len(), 1);
+    }
+
+    #[test]
+    fn test_to_dot_and_mermaid() {
+        let node = |package: &str| GraphNode {
+            package: package.parse().unwrap(),
+            version: "0.2.0".parse().unwrap(),
+            content_digest: format!("sha256:{}", "0".repeat(64)).parse().unwrap(),
+            registry: None,
+        };
+        let graph = DependencyGraph {
+            nodes: vec![node("wasi:http"), node("wasi:io")],
+            edges: vec![GraphEdge {
+                from: 0,
+                to: 1,
+                req: "^0.2.0".parse().unwrap(),
+            }],
+            roots: vec![0],
+        };
+        assert_eq!(
+            graph.to_dot(Some("my:app")),
+            r#"digraph dependencies {
+    "my:app" [shape=box];
+    "my:app" -> "wasi:http@0.2.0";
+    "wasi:http@0.2.0";
+    "wasi:io@0.2.0";
+    "wasi:http@0.2.0" -> "wasi:io@0.2.0" [label="^0.2.0"];
+}
+"#
+        );
+        assert_eq!(
+            graph.to_mermaid(None),
+            r#"graph TD
+    n0["wasi:http@0.2.0"]
+    n1["wasi:io@0.2.0"]
+    n0 -->|"^0.2.0"| n1
+"#
+        );
+    }
+}
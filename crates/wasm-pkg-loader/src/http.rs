@@ -0,0 +1,42 @@
+//! HTTP connection settings for the client shared by registry sources.
+
+use std::time::Duration;
+
+use crate::Error;
+
+/// Connection pool and keep-alive settings for HTTP requests made directly
+/// by the client (e.g. registry metadata, GitHub releases, and ranged
+/// downloads). Connections are reused, and multiplexed over HTTP/2 where
+/// servers support it, across all registries.
+///
+/// OCI registries are accessed with a separate HTTP client that doesn't use
+/// these settings.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HttpConfig {
+    /// The maximum number of idle connections kept open per host.
+    pub pool_max_idle_per_host: Option<usize>,
+    /// How long an idle connection is kept open.
+    pub pool_idle_timeout: Option<Duration>,
+    /// The interval of TCP and HTTP/2 keep-alive pings on open connections.
+    pub keep_alive_interval: Option<Duration>,
+}
+
+impl HttpConfig {
+    pub(crate) fn build_client(&self) -> Result<reqwest::Client, Error> {
+        let mut builder = reqwest::Client::builder()
+            .user_agent(concat!("wasm-pkg-loader/", env!("CARGO_PKG_VERSION")));
+        if let Some(max_idle) = self.pool_max_idle_per_host {
+            builder = builder.pool_max_idle_per_host(max_idle);
+        }
+        if let Some(timeout) = self.pool_idle_timeout {
+            builder = builder.pool_idle_timeout(timeout);
+        }
+        if let Some(interval) = self.keep_alive_interval {
+            builder = builder
+                .tcp_keepalive(interval)
+                .http2_keep_alive_interval(interval)
+                .http2_keep_alive_while_idle(true);
+        }
+        Ok(builder.build()?)
+    }
+}
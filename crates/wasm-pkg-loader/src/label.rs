@@ -1,5 +1,5 @@
 /// A Component Model kebab-case label.
-#[derive(Clone, PartialEq, Eq, Hash)]
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Label(String);
 
 impl AsRef<str> for Label {
@@ -1,49 +1,114 @@
+mod cache;
 mod config;
+mod deprecation;
+mod download;
+mod graph;
+mod http;
 mod label;
+mod limits;
 mod meta;
+mod openpgp;
 mod package;
+mod paths;
+mod policy;
+mod provenance;
 mod release;
+mod resolver;
 mod source;
+mod suggest;
+mod trust;
 
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
 
 use bytes::Bytes;
-use futures_util::stream::BoxStream;
-use oci_distribution::errors::OciDistributionError;
-pub use semver::Version;
+use futures_util::{
+    stream::{self, BoxStream},
+    StreamExt, TryStreamExt,
+};
+use oci_distribution::errors::{OciDistributionError, OciErrorCode};
+pub use semver::{Version, VersionReq};
 use source::{
+    github::GitHubSource,
     local::LocalSource,
     oci::{OciConfig, OciSource},
+    path::PathSource,
     warg::{WargConfig, WargSource},
-    PackageSource, VersionInfo,
+    PackageSource,
 };
 
 /// Re-exported to ease configuration.
 pub use oci_distribution::client as oci_client;
 
+/// Versions as listed by [`Client::list_all_versions`], public so callers
+/// can inspect yanked status and deprecation notices and pass lists to
+/// [`VersionPolicy::resolve`].
+pub use crate::source::VersionInfo;
+
 pub use crate::{
-    config::ClientConfig,
+    cache::{CachePolicy, CachePolicyConfig, ContentCachePolicy},
+    config::{
+        BasicCredentials, ClientConfig, RegistryConfig, RegistryCredentials, WitOutputConfig,
+    },
+    deprecation::Deprecation,
+    download::DownloadConfig,
+    graph::{DependencyGraph, GraphEdge, GraphNode},
+    http::HttpConfig,
+    limits::{ConcurrencyConfig, ConcurrencyLimits},
     package::PackageRef,
+    policy::{
+        RegistryPolicy, ResolutionStrategy, VersionPolicy, VersionPolicyConfig, YankedPolicy,
+        OVERRIDE_REGISTRY,
+    },
+    provenance::{LogEntry, Provenance, ProvenancePolicy},
     release::{ContentDigest, Release},
+    resolver::{
+        wit_path_dependencies, ConflictReport, DependencySet, Requirement, ResolveOptions,
+        ResolvedPackage, Resolver,
+    },
+    source::{
+        github::GitHubConfig,
+        oci::{OciCompat, OciLayout},
+        path::PackageOverride,
+    },
+    suggest::Suggestion,
 };
 use crate::{
-    config::RegistryConfig,
+    cache::Cache,
     label::{InvalidLabel, Label},
+    limits::{limited, RegistryLimiter, RequestKind},
     meta::RegistryMeta,
 };
 
-/// A read-only registry client.
+/// A registry client.
 pub struct Client {
     config: ClientConfig,
     sources: HashMap<String, Box<dyn PackageSource>>,
+    overrides: HashMap<PackageRef, PathSource>,
+    cache: Option<Cache>,
+    /// The HTTP client shared by sources, built on first use.
+    http_client: Option<reqwest::Client>,
 }
 
 impl Client {
     /// Returns a new client with the given [`ClientConfig`].
     pub fn new(config: ClientConfig) -> Self {
+        let cache = config
+            .cache_dir()
+            .map(Into::into)
+            .or_else(Cache::default_dir)
+            .filter(|_| !config.no_cache())
+            .map(Cache::new);
         Self {
             config,
             sources: Default::default(),
+            overrides: Default::default(),
+            cache,
+            http_client: None,
         }
     }
 
@@ -54,12 +119,75 @@ impl Client {
     }
 
     /// Returns a list of all package [`Version`]s available for the given package.
+    ///
+    /// A cached list is used if it is within the package's
+    /// [`CachePolicy::metadata_ttl`] or, in offline mode, of any age. It is
+    /// never used if [`ClientConfig::refresh`] is set.
     pub async fn list_all_versions(
         &mut self,
         package: &PackageRef,
     ) -> Result<Vec<VersionInfo>, Error> {
+        let cache_registry = self.cache_registry(package)?;
+        if let (Some(cache), Some(registry)) = (&self.cache, &cache_registry) {
+            let ttl = if self.config.offline() {
+                Duration::MAX
+            } else if self.config.refresh() {
+                Duration::ZERO
+            } else {
+                self.config.cache_policy(package).metadata_ttl
+            };
+            if let Some(versions) = cache.versions(registry, package, ttl) {
+                return Ok(versions);
+            }
+        }
+        let limiter = self.limiter(package)?;
         let source = self.resolve_source(package).await?;
-        source.list_all_versions(package).await
+        let (versions, _) = limited!(
+            limiter.as_deref(),
+            RequestKind::Metadata,
+            source.list_all_versions(package).await
+        );
+        let versions = versions?;
+        if let (Some(cache), Some(registry)) = (&self.cache, &cache_registry) {
+            cache.store_versions(registry, package, &versions);
+        }
+        Ok(versions)
+    }
+
+    /// Returns the [`VersionPolicy`] configured for the given package.
+    pub fn version_policy(&self, package: &PackageRef) -> VersionPolicy {
+        self.config.version_policy(package)
+    }
+
+    /// Returns the configured [`ResolutionStrategy`].
+    pub fn resolution_strategy(&self) -> ResolutionStrategy {
+        self.config.resolution_strategy()
+    }
+
+    /// Returns the highest version of the given package satisfying `req`
+    /// under the given [`VersionPolicy`]; see [`VersionPolicy::resolve`].
+    pub async fn resolve_version(
+        &mut self,
+        package: &PackageRef,
+        req: &VersionReq,
+        policy: &VersionPolicy,
+    ) -> Result<Version, Error> {
+        let versions = self.list_all_versions(package).await?;
+        policy.resolve(package, versions, std::slice::from_ref(req))
+    }
+
+    /// Returns the latest version of the given package allowed by its
+    /// configured [`VersionPolicy`]. Unless `prereleases` is configured,
+    /// prerelease versions are considered, as the latest version always has
+    /// been.
+    pub async fn latest_version(&mut self, package: &PackageRef) -> Result<Version, Error> {
+        let defaults = VersionPolicy {
+            prereleases: true,
+            ..Default::default()
+        };
+        let policy = self.config.version_policy_with_defaults(package, defaults);
+        self.resolve_version(package, &VersionReq::STAR, &policy)
+            .await
     }
 
     /// Returns a [`Release`] for the given package version.
@@ -68,46 +196,649 @@ impl Client {
         package: &PackageRef,
         version: &Version,
     ) -> Result<Release, Error> {
+        let cache_registry = self.cache_registry(package)?;
+        if self.config.offline() {
+            if let (Some(cache), Some(registry)) = (&self.cache, &cache_registry) {
+                if let Some(release) = cache.release(registry, package, version) {
+                    return Ok(release);
+                }
+            }
+        }
+        let refresh = self.config.refresh();
+        let limiter = self.limiter(package)?;
+        let cache = self.cache.clone();
+        let source = self.resolve_source(package).await?;
+        // Revalidate cached details with a cheap check that they haven't
+        // changed, e.g. by a tag being moved, rather than refetching them
+        let mut revision = None;
+        if let (Some(cache), Some(registry)) = (&cache, &cache_registry) {
+            let (result, _) = limited!(
+                limiter.as_deref(),
+                RequestKind::Metadata,
+                source.release_revision(package, version).await
+            );
+            revision = result.unwrap_or_else(|err| {
+                tracing::debug!("Failed to get revision of {package}@{version}: {err}");
+                None
+            });
+            if let Some(release) = revision
+                .as_deref()
+                .filter(|_| !refresh)
+                .and_then(|revision| {
+                    cache.revalidated_release(registry, package, version, revision)
+                })
+            {
+                tracing::debug!("Cached release {package}@{version} is up to date");
+                return Ok(release);
+            }
+        }
+        let (release, _) = limited!(
+            limiter.as_deref(),
+            RequestKind::Metadata,
+            source.get_release(package, version).await
+        );
+        let release = release?;
+        if let (Some(cache), Some(registry)) = (&cache, &cache_registry) {
+            cache.store_release(registry, package, &release, revision.as_deref());
+        }
+        Ok(release)
+    }
+
+    /// Returns the [`Release`] of the given package whose content has the
+    /// given digest. Cached release details are checked first, then its
+    /// versions (including yanked ones) are searched newest first, skipping
+    /// any whose details can't be fetched. If no version points at the
+    /// content but the source can fetch it by digest alone (see
+    /// [`PackageSource::has_content`]), it's returned as a release with the
+    /// placeholder version `0.0.0+sha256.<digest prefix>`. Content fetched
+    /// for the release is validated against the digest, so it's exactly the
+    /// pinned content even if the version is later republished with other
+    /// content.
+    pub async fn get_release_by_digest(
+        &mut self,
+        package: &PackageRef,
+        digest: &ContentDigest,
+    ) -> Result<Release, Error> {
+        if let (Some(cache), Some(registry)) = (&self.cache, self.cache_registry(package)?) {
+            if let Some(release) = cache.release_by_digest(&registry, package, digest) {
+                tracing::debug!("Found cached release {package}@{}", release.version);
+                return Ok(release);
+            }
+        }
+        // Check that the content exists before searching for a version that
+        // points at it, where the source can
+        let mut has_content = None;
+        if !self.config.offline() {
+            let limiter = self.limiter(package)?;
+            let source = self.resolve_source(package).await?;
+            let (result, _) = limited!(
+                limiter.as_deref(),
+                RequestKind::Metadata,
+                source.has_content(package, digest).await
+            );
+            has_content = result?;
+            if has_content == Some(false) {
+                return Err(Error::DigestNotFound(package.clone(), digest.clone()));
+            }
+        }
+        let mut versions = match self.list_all_versions(package).await {
+            Ok(versions) => versions,
+            Err(err) if has_content.is_some() => {
+                tracing::warn!("Failed to list versions of {package}: {err}");
+                vec![]
+            }
+            Err(err) => return Err(err),
+        };
+        versions.sort();
+        for info in versions.into_iter().rev() {
+            match self.get_release(package, &info.version).await {
+                Ok(release) if &release.content_digest == digest => return Ok(release),
+                Ok(_) => (),
+                Err(err) => tracing::warn!("Skipping {package}@{}: {err}", info.version),
+            }
+        }
+        match has_content {
+            Some(true) => {
+                let ContentDigest::Sha256 { hex } = digest;
+                let prefix = &hex[..hex.len().min(12)];
+                let mut version = Version::new(0, 0, 0);
+                version.build = semver::BuildMetadata::new(&format!("sha256.{prefix}"))?;
+                tracing::debug!("No version of {package} points at {digest}");
+                Ok(Release {
+                    version,
+                    content_digest: digest.clone(),
+                })
+            }
+            _ => Err(Error::DigestNotFound(package.clone(), digest.clone())),
+        }
+    }
+
+    /// Imports a release and its content into the local cache, e.g. from an
+    /// offline bundle, so that it can be used in offline mode as if fetched
+    /// from the package's registry. The version is added to any cached
+    /// version list. Content is verified against the release's digest.
+    pub async fn import_release(
+        &mut self,
+        package: &PackageRef,
+        release: &Release,
+        content: Bytes,
+    ) -> Result<(), Error> {
+        let (Some(cache), Some(registry)) = (self.cache.clone(), self.cache_registry(package)?)
+        else {
+            return Err(Error::InvalidConfig(anyhow::anyhow!(
+                "no cache available to import {package} into"
+            )));
+        };
+        let content = stream::iter([Ok(content)]);
+        let validated = release.content_digest.validating_stream(content).boxed();
+        cache
+            .store_content(&release.content_digest, validated)
+            .try_for_each(|_| async { Ok(()) })
+            .await?;
+        cache.store_release(&registry, package, release, None);
+        let mut versions = cache
+            .versions(&registry, package, Duration::MAX)
+            .unwrap_or_default();
+        if !versions.iter().any(|info| info.version == release.version) {
+            versions.push(VersionInfo {
+                version: release.version.clone(),
+                yanked: false,
+                deprecation: None,
+            });
+            versions.sort();
+        }
+        cache.store_versions(&registry, package, &versions);
+        Ok(())
+    }
+
+    /// Publishes the given content as a new release of the given package to
+    /// the package's registry, returning the new release.
+    pub async fn publish(
+        &mut self,
+        package: &PackageRef,
+        version: &Version,
+        content: Bytes,
+    ) -> Result<Release, Error> {
+        let source = self.resolve_source(package).await?;
+        source.publish(package, version, content).await
+    }
+
+    /// Returns the version the given channel tag (e.g. "stable") of the
+    /// package points at.
+    pub async fn resolve_tag(&mut self, package: &PackageRef, tag: &str) -> Result<Version, Error> {
+        if !is_valid_tag(tag) {
+            return Err(Error::InvalidTag(tag.into()));
+        }
+        let limiter = self.limiter(package)?;
+        let source = self.resolve_source(package).await?;
+        let (version, _) = limited!(
+            limiter.as_deref(),
+            RequestKind::Metadata,
+            source.resolve_tag(package, tag).await
+        );
+        version?.ok_or_else(|| Error::TagNotFound(format!("{package}@{tag}")))
+    }
+
+    /// Points the given channel tag (e.g. "stable") of the package at an
+    /// existing release, replacing any previous target.
+    pub async fn set_tag(
+        &mut self,
+        package: &PackageRef,
+        tag: &str,
+        version: &Version,
+    ) -> Result<(), Error> {
+        if !is_valid_tag(tag) {
+            return Err(Error::InvalidTag(tag.into()));
+        }
+        let source = self.resolve_source(package).await?;
+        source.set_tag(package, tag, version).await
+    }
+
+    /// Permanently deletes the given release from its registry. Lock files
+    /// and caches may still refer to it.
+    pub async fn delete(&mut self, package: &PackageRef, version: &Version) -> Result<(), Error> {
+        let source = self.resolve_source(package).await?;
+        source.delete(package, version).await
+    }
+
+    /// Returns the provenance attestations published for the given release.
+    pub async fn provenance(
+        &mut self,
+        package: &PackageRef,
+        release: &Release,
+    ) -> Result<Vec<Provenance>, Error> {
+        let limiter = self.limiter(package)?;
+        let source = self.resolve_source(package).await?;
+        limited!(
+            limiter.as_deref(),
+            RequestKind::Metadata,
+            source.provenance(package, release).await
+        )
+        .0
+    }
+
+    /// Returns the deprecation notice attached to the given release, if
+    /// any.
+    pub async fn deprecation(
+        &mut self,
+        package: &PackageRef,
+        release: &Release,
+    ) -> Result<Option<Deprecation>, Error> {
+        let limiter = self.limiter(package)?;
+        let source = self.resolve_source(package).await?;
+        limited!(
+            limiter.as_deref(),
+            RequestKind::Metadata,
+            source.deprecation(package, release).await
+        )
+        .0
+    }
+
+    /// Attaches a deprecation notice to the given release, replacing any
+    /// previous one.
+    pub async fn deprecate(
+        &mut self,
+        package: &PackageRef,
+        release: &Release,
+        deprecation: &Deprecation,
+    ) -> Result<(), Error> {
+        let source = self.resolve_source(package).await?;
+        source.deprecate(package, release, deprecation).await
+    }
+
+    /// Returns the OpenPGP signatures attached to the given release.
+    pub async fn openpgp_signatures(
+        &mut self,
+        package: &PackageRef,
+        release: &Release,
+    ) -> Result<Vec<Vec<u8>>, Error> {
+        let limiter = self.limiter(package)?;
+        let source = self.resolve_source(package).await?;
+        limited!(
+            limiter.as_deref(),
+            RequestKind::Metadata,
+            source.openpgp_signatures(package, release).await
+        )
+        .0
+    }
+
+    /// Signs the given release with `gpg`, using the given key or else the
+    /// default signing key, and attaches the signature to the release.
+    pub async fn sign_openpgp(
+        &mut self,
+        package: &PackageRef,
+        release: &Release,
+        key: Option<&str>,
+    ) -> Result<(), Error> {
+        let signature = openpgp::sign(release, key).await?;
         let source = self.resolve_source(package).await?;
-        source.get_release(package, version).await
+        source
+            .attach_openpgp_signature(package, release, &signature)
+            .await
     }
 
     /// Returns a [`BoxStream`] of content chunks. Contents are validated
-    /// against the given [`Release::content_digest`].
+    /// against the given [`Release::content_digest`], and served from or
+    /// written to the local cache as allowed by the package's
+    /// [`CachePolicy`]. If the configured [`ProvenancePolicy`] requires
+    /// provenance, the release's attestations are checked first, as are its
+    /// OpenPGP signatures if a keyring is configured for the package
+    /// (packages overridden by a local path are exempt).
     pub async fn stream_content(
         &mut self,
         package: &PackageRef,
         release: &Release,
-    ) -> Result<BoxStream<Result<Bytes, Error>>, Error> {
+    ) -> Result<BoxStream<'static, Result<Bytes, Error>>, Error> {
+        self.check_provenance(package, release).await?;
+        self.check_openpgp_signatures(package, release).await?;
+        let cache = self.content_cache(package);
+        if let Some(cache) = &cache {
+            if let Some(stream) = cache.content(&release.content_digest).await {
+                return Ok(stream);
+            }
+        }
+        // Coalesce concurrent fetches of the same content: wait for any
+        // other download of it to finish, then check the cache again
+        let content_lock = match &cache {
+            Some(cache) => {
+                let guard = cache.lock_content(&release.content_digest).await;
+                if let Some(stream) = cache.content(&release.content_digest).await {
+                    return Ok(stream);
+                }
+                Some(guard)
+            }
+            None => None,
+        };
+        let require_digest = self.config.require_digest();
+        let download_config = self.config.download_config().clone();
+        let limiter = self.limiter(package)?;
         let source = self.resolve_source(package).await?;
-        source.stream_content(package, release).await
+        let (stream, download_permit) = limited!(
+            limiter.as_deref(),
+            RequestKind::Download,
+            fetch_content(
+                &mut *source,
+                package,
+                release,
+                &download_config,
+                require_digest
+            )
+            .await
+        );
+        // Hold the download permit until the stream is done
+        let stream = stream?
+            .inspect(move |_| {
+                let _ = &download_permit;
+            })
+            .boxed();
+        Ok(match cache {
+            Some(cache) => cache
+                .store_content(&release.content_digest, stream)
+                // Hold the lock until the stream (and its cache entry) is done
+                .inspect(move |_| {
+                    let _ = &content_lock;
+                })
+                .boxed(),
+            None => stream,
+        })
+    }
+
+    /// Returns a stream of the given release's content from byte `offset`
+    /// onward, to resume a [`Client::stream_content`] stream interrupted
+    /// after that many bytes, or None if the source can't resume downloads.
+    /// The resumed content can't be validated against the release's digest
+    /// on its own, so callers must check the digest of the whole content
+    /// (e.g. with [`ContentDigest::sha256`]).
+    pub async fn resume_content(
+        &mut self,
+        package: &PackageRef,
+        release: &Release,
+        offset: u64,
+    ) -> Result<Option<BoxStream<'static, Result<Bytes, Error>>>, Error> {
+        let limiter = self.limiter(package)?;
+        let source = self.resolve_source(package).await?;
+        let (stream, download_permit) = limited!(
+            limiter.as_deref(),
+            RequestKind::Download,
+            match source.ranged_download(package, release).await {
+                Ok(Some(download)) => download.stream_from(offset).await,
+                Ok(None) => Ok(None),
+                Err(err) => Err(err),
+            }
+        );
+        // Hold the download permit until the stream is done
+        Ok(stream?.map(|stream| {
+            stream
+                .inspect(move |_| {
+                    let _ = &download_permit;
+                })
+                .boxed()
+        }))
+    }
+
+    /// Returns the path of the given release's content in the local cache,
+    /// if cached and allowed by the package's [`CachePolicy`], so it can be
+    /// copied without streaming it through memory. The content and
+    /// provenance and signatures are checked as by
+    /// [`Client::stream_content`].
+    pub async fn cached_content_file(
+        &mut self,
+        package: &PackageRef,
+        release: &Release,
+    ) -> Result<Option<PathBuf>, Error> {
+        let Some(cache) = self.content_cache(package) else {
+            return Ok(None);
+        };
+        let Some(path) = cache.content_file(&release.content_digest).await else {
+            return Ok(None);
+        };
+        self.check_provenance(package, release).await?;
+        self.check_openpgp_signatures(package, release).await?;
+        Ok(Some(path))
+    }
+
+    /// Returns true if the given release's content is in the local cache
+    /// and allowed to be read from it by the package's [`CachePolicy`], i.e.
+    /// fetching it won't need to download it. The cached content isn't
+    /// verified until it's read.
+    pub fn is_content_cached(&self, package: &PackageRef, release: &Release) -> bool {
+        self.content_cache(package)
+            .is_some_and(|cache| cache.has_content(&release.content_digest))
+    }
+
+    /// Checks the release's attestations if the configured
+    /// [`ProvenancePolicy`] requires provenance.
+    async fn check_provenance(
+        &mut self,
+        package: &PackageRef,
+        release: &Release,
+    ) -> Result<(), Error> {
+        let provenance_policy = self.config.provenance_policy().clone();
+        if provenance_policy.require && self.config.package_override(package).is_none() {
+            let provenance = self.provenance(package, release).await?;
+            provenance_policy.check(package, &provenance)?;
+        }
+        Ok(())
+    }
+
+    /// Checks the release's OpenPGP signatures if a keyring is configured
+    /// for the package, and that a signer is an allowed publisher if
+    /// publishers are pinned for its namespace.
+    async fn check_openpgp_signatures(
+        &mut self,
+        package: &PackageRef,
+        release: &Release,
+    ) -> Result<(), Error> {
+        let keyring = self.config.openpgp_keyring(package).map(Path::to_path_buf);
+        let publishers = self.config.publishers(package).map(<[String]>::to_vec);
+        if (keyring.is_none() && publishers.is_none())
+            || self.config.package_override(package).is_some()
+        {
+            return Ok(());
+        }
+        let Some(keyring) = keyring else {
+            return Err(Error::SignatureRejected(
+                package.clone(),
+                "publishers are pinned, but no OpenPGP keyring is configured".into(),
+            ));
+        };
+        let signatures = self.openpgp_signatures(package, release).await?;
+        let identities = openpgp::verify(package, release, &keyring, &signatures).await?;
+        match publishers {
+            Some(publishers) => trust::check_publishers(package, &publishers, &identities),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns the cache content may be read from and written to for the
+    /// given package, if any.
+    fn content_cache(&self, package: &PackageRef) -> Option<Cache> {
+        match self.config.cache_policy(package).content {
+            ContentCachePolicy::Store if self.config.package_override(package).is_none() => {
+                self.cache.clone()
+            }
+            _ => None,
+        }
+    }
+
+    /// Resolves the given root requirements and all of their transitive
+    /// dependencies into a [`DependencyGraph`].
+    pub async fn resolve_graph(
+        &mut self,
+        roots: impl IntoIterator<Item = (PackageRef, VersionReq)>,
+        options: &ResolveOptions,
+    ) -> Result<DependencyGraph, Error> {
+        let mut resolver = Resolver::new(self);
+        if let Some(strategy) = options.strategy {
+            resolver.strategy(strategy);
+        }
+        resolver.allow_yanked(options.allow_yanked);
+        for (package, version) in &options.preferred {
+            resolver.prefer_version(package.clone(), version.clone());
+        }
+        resolver.resolve_graph(roots).await
+    }
+
+    /// Returns the registry the given package is fetched from, or None if
+    /// the package is overridden by a local path.
+    pub fn package_registry(&self, package: &PackageRef) -> Result<Option<&str>, Error> {
+        if self.config.package_override(package).is_some() {
+            return Ok(None);
+        }
+        Ok(Some(self.config.resolve_package_registry(package)?))
+    }
+
+    /// Returns a suggested alternative to the given package, or version of
+    /// it, that wasn't found: the nearest existing version if the package
+    /// exists, or else the closest named package in its namespace if its
+    /// source can list them.
+    pub async fn suggest(
+        &mut self,
+        package: &PackageRef,
+        version: Option<&Version>,
+    ) -> Result<Option<Suggestion>, Error> {
+        match self.list_all_versions(package).await {
+            Ok(versions) if !versions.is_empty() => {
+                return Ok(version
+                    .and_then(|version| suggest::nearest_version(version, &versions))
+                    .map(|version| Suggestion::Version(package.clone(), version.clone())));
+            }
+            Ok(_) => (),
+            Err(err) => tracing::debug!("Failed to list versions of {package}: {err}"),
+        }
+        let limiter = self.limiter(package)?;
+        let source = self.resolve_source(package).await?;
+        let (packages, _) = limited!(
+            limiter.as_deref(),
+            RequestKind::Metadata,
+            source.list_packages(package.namespace()).await
+        );
+        Ok(packages?.and_then(|packages| {
+            suggest::closest_package(package, &packages)
+                .cloned()
+                .map(Suggestion::Package)
+        }))
+    }
+
+    /// Returns the packages the given namespace's registry has in the
+    /// namespace, or None if the registry can't list packages. Packages
+    /// configured to use other registries aren't included.
+    pub async fn list_packages(
+        &mut self,
+        namespace: &str,
+    ) -> Result<Option<Vec<PackageRef>>, Error> {
+        let namespace: Label = namespace.parse()?;
+        let registry = self
+            .config
+            .resolve_namespace_registry(&namespace)?
+            .to_owned();
+        let limiter = RegistryLimiter::get(&registry, self.config.concurrency(Some(&registry)));
+        let source = self.registry_source(registry).await?;
+        let (packages, _) = limited!(
+            Some(&*limiter),
+            RequestKind::Metadata,
+            source.list_packages(&namespace).await
+        );
+        let mut packages = packages?;
+        if let Some(packages) = &mut packages {
+            packages.sort();
+            packages.dedup();
+        }
+        Ok(packages)
+    }
+
+    /// Returns the limiter for requests to the given package's registry, or
+    /// None if the package is overridden by a local path.
+    fn limiter(&self, package: &PackageRef) -> Result<Option<Arc<RegistryLimiter>>, Error> {
+        Ok(self.package_registry(package)?.map(|registry| {
+            RegistryLimiter::get(registry, self.config.concurrency(Some(registry)))
+        }))
+    }
+
+    /// Returns the registry to key cache entries for the given package by,
+    /// or None if the package isn't cached.
+    fn cache_registry(&self, package: &PackageRef) -> Result<Option<String>, Error> {
+        if self.cache.is_none() {
+            return Ok(None);
+        }
+        Ok(self.package_registry(package)?.map(ToOwned::to_owned))
     }
 
     async fn resolve_source(
         &mut self,
         package: &PackageRef,
     ) -> Result<&mut dyn PackageSource, Error> {
+        if let Some(package_override) = self.config.package_override(package) {
+            self.config
+                .registry_policy()
+                .check(package.namespace().as_ref(), OVERRIDE_REGISTRY)?;
+            let source = self
+                .overrides
+                .entry(package.clone())
+                .or_insert_with(|| PathSource::new(package_override.clone()));
+            return Ok(source);
+        }
+
         let registry = self.config.resolve_package_registry(package)?.to_owned();
+        self.registry_source(registry).await
+    }
+
+    /// Returns the source for the given registry, building it if needed.
+    async fn registry_source(&mut self, registry: String) -> Result<&mut dyn PackageSource, Error> {
         if !self.sources.contains_key(&registry) {
             let registry_config = self.config.registry_configs.get(&registry).cloned();
+            if self.config.offline() && !matches!(registry_config, Some(RegistryConfig::Local(_))) {
+                return Err(Error::Offline(registry));
+            }
 
             tracing::debug!("Resolved registry config: {registry_config:?}");
 
-            let registry_meta = RegistryMeta::fetch_or_default(&registry).await;
+            let http_client = self.http_client()?;
+            let registry_meta = RegistryMeta::fetch_or_default(&http_client, &registry).await;
 
-            let registry_config = registry_config.unwrap_or_else(|| {
+            let mut registry_config = registry_config.unwrap_or_else(|| {
                 if registry_meta.warg_url.is_some() {
                     RegistryConfig::Warg(Default::default())
                 } else {
                     RegistryConfig::Oci(Default::default())
                 }
             });
+            // The metadata may point elsewhere; the policy applies there too
+            let redirect = match &registry_config {
+                RegistryConfig::Oci(_) => registry_meta.oci_registry.clone(),
+                RegistryConfig::Warg(_) => registry_meta
+                    .warg_url
+                    .as_deref()
+                    .and_then(|url| url::Url::parse(url).ok())
+                    .and_then(|url| {
+                        let host = url.host_str()?;
+                        Some(match url.port() {
+                            Some(port) => format!("{host}:{port}"),
+                            None => host.to_string(),
+                        })
+                    }),
+                _ => None,
+            };
+            if let Some(host) = redirect {
+                self.config
+                    .registry_policy()
+                    .check_redirect(&registry, &host)?;
+            }
+            if let Some(credentials) = self.config.registry_credentials(&registry) {
+                registry_config.apply_credentials(credentials);
+            }
 
             let source: Box<dyn PackageSource> = match registry_config {
                 config::RegistryConfig::Local(config) => Box::new(LocalSource::new(config)),
-                config::RegistryConfig::Oci(config) => {
-                    Box::new(self.build_oci_client(&registry, registry_meta, config)?)
+                config::RegistryConfig::GitHub(config) => {
+                    Box::new(GitHubSource::new(config, http_client))
                 }
+                config::RegistryConfig::Oci(config) => Box::new(self.build_oci_client(
+                    &registry,
+                    registry_meta,
+                    config,
+                    http_client,
+                )?),
                 config::RegistryConfig::Warg(config) => {
                     Box::new(self.build_warg_client(&registry, registry_meta, config).await?)
                 }
@@ -122,9 +853,18 @@ impl Client {
         registry: &str,
         registry_meta: RegistryMeta,
         config: OciConfig,
+        http_client: reqwest::Client,
     ) -> Result<OciSource, Error> {
         tracing::debug!("Building new OCI client for {registry:?}");
-        OciSource::new(registry.to_string(), config, registry_meta)
+        OciSource::new(registry.to_string(), config, registry_meta, http_client)
+    }
+
+    /// Returns the HTTP client shared by sources, building it if needed.
+    fn http_client(&mut self) -> Result<reqwest::Client, Error> {
+        if self.http_client.is_none() {
+            self.http_client = Some(self.config.http_config().build_client()?);
+        }
+        Ok(self.http_client.clone().unwrap())
     }
 
     async fn build_warg_client(
@@ -138,11 +878,58 @@ impl Client {
     }
 }
 
+/// Requests a release's content from the source, in parallel ranges if
+/// configured and supported.
+async fn fetch_content(
+    source: &mut dyn PackageSource,
+    package: &PackageRef,
+    release: &Release,
+    download_config: &DownloadConfig,
+    require_digest: bool,
+) -> Result<BoxStream<'static, Result<Bytes, Error>>, Error> {
+    if download_config.parallelism > 1 {
+        if let Some(download) = source.ranged_download(package, release).await? {
+            if let Some(stream) = download.stream(download_config).await? {
+                return Ok(release.content_digest.validating_stream(stream).boxed());
+            }
+        }
+    }
+    if require_digest {
+        // Verify here rather than trusting the source to, as some
+        // (e.g. warg) validate against their own records instead.
+        let stream = source.stream_content_unvalidated(package, release).await?;
+        Ok(release.content_digest.validating_stream(stream).boxed())
+    } else {
+        source.stream_content(package, release).await
+    }
+}
+
+/// Returns true if the given string is a valid channel tag name (e.g.
+/// "stable"): a letter followed by up to 127 letters, digits, '.', '_', or
+/// '-'. Tags can't be mistaken for versions, which start with a digit.
+pub fn is_valid_tag(tag: &str) -> bool {
+    tag.len() <= 128
+        && tag.starts_with(|c: char| c.is_ascii_alphabetic())
+        && tag
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+}
+
 #[derive(Debug, thiserror::Error)]
 #[non_exhaustive]
 pub enum Error {
     #[error("failed to get registry credentials: {0:#}")]
     CredentialError(anyhow::Error),
+    #[error("{0}")]
+    DependencyConflict(Box<ConflictReport>),
+    #[error("deleting releases is not supported by this registry: {0}")]
+    DeleteUnsupported(String),
+    #[error("deprecation notices are not supported by this registry: {0}")]
+    DeprecationUnsupported(String),
+    #[error("no release of {0} has content digest {1}")]
+    DigestNotFound(PackageRef, ContentDigest),
+    #[error("HTTP error: {0}")]
+    HttpError(#[from] reqwest::Error),
     #[error("invalid config: {0:#}")]
     InvalidConfig(anyhow::Error),
     #[error("invalid content: {0}")]
@@ -155,16 +942,42 @@ pub enum Error {
     InvalidPackageRef(String),
     #[error("invalid package manifest: {0}")]
     InvalidPackageManifest(String),
+    #[error("invalid tag {0:?}: tags must start with a letter and contain only letters, digits, '.', '_', and '-'")]
+    InvalidTag(String),
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
+    #[error("cannot access registry {0:?} in offline mode")]
+    Offline(String),
     #[error("OCI error: {0}")]
     OciError(#[from] OciDistributionError),
+    #[error("no matching version found for {0}")]
+    NoMatchingVersion(PackageRef),
     #[error("no registry configured for namespace {0:?}")]
     NoRegistryForNamespace(Label),
+    #[error("{0} rejected by provenance policy: {1}")]
+    ProvenanceRejected(PackageRef, String),
+    #[error("publishing is not supported by this registry: {0}")]
+    PublishUnsupported(String),
+    #[error("registry {0:?} is not allowed for namespace {1:?}")]
+    RegistryNotAllowed(String, String),
+    #[error("registry {0:?} redirects to {1:?}, which is not allowed")]
+    RegistryRedirectNotAllowed(String, String),
     #[error("registry metadata error: {0:#}")]
     RegistryMeta(#[source] anyhow::Error),
+    #[error("{0} rejected by signature policy: {1}")]
+    SignatureRejected(PackageRef, String),
+    #[error("signatures are not supported by this registry: {0}")]
+    SignaturesUnsupported(String),
+    #[error("failed to sign release: {0}")]
+    SigningFailed(String),
+    #[error("tag not found: {0}")]
+    TagNotFound(String),
+    #[error("tags are not supported by this registry: {0}")]
+    TagsUnsupported(String),
     #[error("invalid version: {0}")]
     VersionError(#[from] semver::Error),
+    #[error("version already exists: {0}")]
+    VersionExists(Version),
     #[error("version not found: {0}")]
     VersionNotFound(Version),
     #[error("version yanked: {0}")]
@@ -174,3 +987,90 @@ pub enum Error {
     #[error("Warg error: {0}")]
     WargAnyhowError(#[from] anyhow::Error),
 }
+
+impl Error {
+    /// Returns true if the error indicates that the requested package or
+    /// version doesn't exist (see [`Client::suggest`] for alternatives).
+    pub fn is_not_found(&self) -> bool {
+        match self {
+            Error::DigestNotFound(..)
+            | Error::NoMatchingVersion(_)
+            | Error::TagNotFound(_)
+            | Error::VersionNotFound(_) => true,
+            Error::HttpError(err) | Error::OciError(OciDistributionError::RequestError(err)) => {
+                err.status() == Some(reqwest::StatusCode::NOT_FOUND)
+            }
+            Error::IoError(err) => err.kind() == std::io::ErrorKind::NotFound,
+            Error::OciError(OciDistributionError::ImageManifestNotFoundError(_)) => true,
+            Error::OciError(OciDistributionError::ServerError { code, .. }) => *code == 404,
+            Error::OciError(OciDistributionError::RegistryError { envelope, .. }) => {
+                envelope.errors.iter().any(|err| {
+                    matches!(
+                        err.code,
+                        OciErrorCode::ManifestUnknown | OciErrorCode::NameUnknown
+                    )
+                })
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns true if the error indicates that the registry rejected or
+    /// couldn't get credentials.
+    pub fn is_unauthorized(&self) -> bool {
+        let is_unauthorized_status = |status: Option<reqwest::StatusCode>| {
+            matches!(
+                status,
+                Some(reqwest::StatusCode::UNAUTHORIZED | reqwest::StatusCode::FORBIDDEN)
+            )
+        };
+        match self {
+            Error::CredentialError(_) => true,
+            Error::HttpError(err) | Error::OciError(OciDistributionError::RequestError(err)) => {
+                is_unauthorized_status(err.status())
+            }
+            Error::OciError(
+                OciDistributionError::AuthenticationFailure(_)
+                | OciDistributionError::UnauthorizedError { .. },
+            ) => true,
+            Error::OciError(OciDistributionError::ServerError { code, .. }) => {
+                is_unauthorized_status(reqwest::StatusCode::from_u16(*code).ok())
+            }
+            Error::OciError(OciDistributionError::RegistryError { envelope, .. }) => envelope
+                .errors
+                .iter()
+                .any(|err| matches!(err.code, OciErrorCode::Unauthorized | OciErrorCode::Denied)),
+            _ => false,
+        }
+    }
+
+    /// Returns true if the error may not recur if the request is retried:
+    /// a network failure (other than being offline) or a server error.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Error::Offline(_) => false,
+            Error::HttpError(err) | Error::OciError(OciDistributionError::RequestError(err))
+                if err.status().is_some_and(|status| status.is_server_error()) =>
+            {
+                true
+            }
+            Error::OciError(OciDistributionError::ServerError { code, .. }) => {
+                (500..600).contains(code)
+            }
+            err => err.is_network(),
+        }
+    }
+
+    /// Returns true if the error is a failure to connect to or communicate
+    /// with a registry, including being unable to in offline mode.
+    pub fn is_network(&self) -> bool {
+        match self {
+            Error::Offline(_) => true,
+            Error::HttpError(err) | Error::OciError(OciDistributionError::RequestError(err)) => {
+                err.status().is_none()
+                    && (err.is_connect() || err.is_timeout() || err.is_request() || err.is_body())
+            }
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,291 @@
+//! Limits on concurrent requests to each registry, with adaptive back-off
+//! when a registry throttles requests (HTTP 429 Too Many Requests).
+//!
+//! Limiters are shared by all clients in the process with the same limits,
+//! so that batch operations spread over several clients respect them
+//! together.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, OnceLock},
+    time::Duration,
+};
+
+use oci_distribution::errors::{OciDistributionError, OciErrorCode};
+use reqwest::StatusCode;
+use tokio::{
+    sync::{OwnedSemaphorePermit, Semaphore},
+    time::Instant,
+};
+
+use crate::Error;
+
+pub(crate) const DEFAULT_MAX_CONCURRENT_DOWNLOADS: usize = 8;
+
+pub(crate) const DEFAULT_MAX_CONCURRENT_METADATA: usize = 16;
+
+/// The number of times a throttled request is retried.
+const MAX_RETRIES: u32 = 5;
+
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Limits on concurrent requests to a registry.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ConcurrencyLimits {
+    /// The maximum number of content downloads from the registry at once.
+    pub max_concurrent_downloads: usize,
+    /// The maximum number of metadata requests (e.g. version lists and
+    /// release details) to the registry at once.
+    pub max_concurrent_metadata: usize,
+    /// Whether to back off when the registry throttles requests, retrying
+    /// them with reduced concurrency.
+    pub adaptive: bool,
+}
+
+impl Default for ConcurrencyLimits {
+    fn default() -> Self {
+        Self {
+            max_concurrent_downloads: DEFAULT_MAX_CONCURRENT_DOWNLOADS,
+            max_concurrent_metadata: DEFAULT_MAX_CONCURRENT_METADATA,
+            adaptive: true,
+        }
+    }
+}
+
+/// Per-scope [`ConcurrencyLimits`] settings; unset fields fall back to a
+/// broader scope.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ConcurrencyConfig {
+    pub max_concurrent_downloads: Option<usize>,
+    pub max_concurrent_metadata: Option<usize>,
+    pub adaptive: Option<bool>,
+}
+
+impl ConcurrencyConfig {
+    /// Overrides fields of this config with any set in `other`.
+    pub fn merge(&mut self, other: ConcurrencyConfig) {
+        self.max_concurrent_downloads = other
+            .max_concurrent_downloads
+            .or(self.max_concurrent_downloads);
+        self.max_concurrent_metadata = other
+            .max_concurrent_metadata
+            .or(self.max_concurrent_metadata);
+        self.adaptive = other.adaptive.or(self.adaptive);
+    }
+
+    /// Applies the fields set in this config to the given limits.
+    pub fn apply(&self, limits: ConcurrencyLimits) -> ConcurrencyLimits {
+        ConcurrencyLimits {
+            max_concurrent_downloads: self
+                .max_concurrent_downloads
+                .unwrap_or(limits.max_concurrent_downloads),
+            max_concurrent_metadata: self
+                .max_concurrent_metadata
+                .unwrap_or(limits.max_concurrent_metadata),
+            adaptive: self.adaptive.unwrap_or(limits.adaptive),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum RequestKind {
+    Download,
+    Metadata,
+}
+
+/// Limits concurrent requests to one registry.
+pub(crate) struct RegistryLimiter {
+    registry: String,
+    adaptive: bool,
+    downloads: Arc<Semaphore>,
+    metadata: Arc<Semaphore>,
+    backoff: Mutex<Backoff>,
+}
+
+#[derive(Default)]
+struct Backoff {
+    /// Requests wait until this time once the registry throttles one.
+    paused_until: Option<Instant>,
+    delay: Duration,
+    /// Permits withheld to reduce concurrency, by [`RequestKind`].
+    withheld: [usize; 2],
+}
+
+impl RegistryLimiter {
+    /// Returns the process-wide limiter for the given registry and limits.
+    pub fn get(registry: &str, limits: ConcurrencyLimits) -> Arc<Self> {
+        type Limiters = HashMap<(String, ConcurrencyLimits), Arc<RegistryLimiter>>;
+        static LIMITERS: OnceLock<Mutex<Limiters>> = OnceLock::new();
+        LIMITERS
+            .get_or_init(Default::default)
+            .lock()
+            .unwrap()
+            .entry((registry.to_string(), limits))
+            .or_insert_with(|| {
+                Arc::new(Self {
+                    registry: registry.to_string(),
+                    adaptive: limits.adaptive,
+                    downloads: Arc::new(Semaphore::new(limits.max_concurrent_downloads.max(1))),
+                    metadata: Arc::new(Semaphore::new(limits.max_concurrent_metadata.max(1))),
+                    backoff: Default::default(),
+                })
+            })
+            .clone()
+    }
+
+    fn semaphore(&self, kind: RequestKind) -> &Arc<Semaphore> {
+        match kind {
+            RequestKind::Download => &self.downloads,
+            RequestKind::Metadata => &self.metadata,
+        }
+    }
+
+    /// Waits for any back-off to pass, then for a permit to make a request
+    /// of the given kind.
+    pub async fn acquire(&self, kind: RequestKind) -> OwnedSemaphorePermit {
+        let paused_until = self.backoff.lock().unwrap().paused_until;
+        if let Some(paused_until) = paused_until {
+            tokio::time::sleep_until(paused_until).await;
+        }
+        self.semaphore(kind)
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("limiter semaphores are never closed")
+    }
+
+    /// Records the result of a request. Returns true if the request was
+    /// throttled and should be retried (after [`Self::acquire`] waits out
+    /// the back-off).
+    pub fn should_retry<T>(
+        &self,
+        kind: RequestKind,
+        result: &Result<T, Error>,
+        attempt: u32,
+    ) -> bool {
+        let mut guard = self.backoff.lock().unwrap();
+        let backoff = &mut *guard;
+        let withheld = &mut backoff.withheld[kind as usize];
+        match result {
+            Err(err) if is_throttled(err) => (),
+            Ok(_) => {
+                // Recover from any back-off one request at a time
+                if *withheld > 0 {
+                    *withheld -= 1;
+                    self.semaphore(kind).add_permits(1);
+                }
+                backoff.delay = Duration::ZERO;
+                return false;
+            }
+            Err(_) => return false,
+        }
+        if !self.adaptive || attempt >= MAX_RETRIES {
+            return false;
+        }
+        // Halve the available concurrency by withholding permits
+        let semaphore = self.semaphore(kind);
+        let target = semaphore.available_permits() / 2;
+        if let Ok(permits) = semaphore.try_acquire_many(target as u32) {
+            permits.forget();
+            *withheld += target;
+        }
+        backoff.delay = (backoff.delay * 2).clamp(INITIAL_BACKOFF, MAX_BACKOFF);
+        backoff.paused_until = Some(Instant::now() + backoff.delay);
+        tracing::warn!(
+            "Registry {:?} is throttling requests; retrying in {:?}",
+            self.registry,
+            backoff.delay
+        );
+        true
+    }
+}
+
+/// Returns true if the given error is a registry throttling requests.
+fn is_throttled(err: &Error) -> bool {
+    match err {
+        Error::HttpError(err) | Error::OciError(OciDistributionError::RequestError(err)) => {
+            err.status() == Some(StatusCode::TOO_MANY_REQUESTS)
+        }
+        Error::OciError(OciDistributionError::ServerError { code, .. }) => {
+            *code == StatusCode::TOO_MANY_REQUESTS.as_u16()
+        }
+        Error::OciError(OciDistributionError::RegistryError { envelope, .. }) => envelope
+            .errors
+            .iter()
+            .any(|err| err.code == OciErrorCode::Toomanyrequests),
+        _ => false,
+    }
+}
+
+/// Runs a request expression within the given (optional) limiter's limits,
+/// re-evaluating it if it was throttled. Evaluates to the request's result
+/// and the permit it was made with, which may be held e.g. for the lifetime
+/// of a content stream.
+macro_rules! limited {
+    ($limiter:expr, $kind:expr, $request:expr) => {{
+        let limiter: Option<&$crate::limits::RegistryLimiter> = $limiter;
+        let mut attempt = 0;
+        loop {
+            let permit = match limiter {
+                Some(limiter) => Some(limiter.acquire($kind).await),
+                None => None,
+            };
+            let result = $request;
+            match limiter {
+                Some(limiter) if limiter.should_retry($kind, &result, attempt) => attempt += 1,
+                _ => break (result, permit),
+            }
+        }
+    }};
+}
+
+pub(crate) use limited;
+
+#[cfg(test)]
+mod tests {
+    use futures_util::FutureExt;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_limiter() {
+        let limits = ConcurrencyLimits {
+            max_concurrent_downloads: 2,
+            max_concurrent_metadata: 4,
+            adaptive: true,
+        };
+        let limiter = RegistryLimiter::get("limits.test", limits);
+        assert!(Arc::ptr_eq(
+            &limiter,
+            &RegistryLimiter::get("limits.test", limits)
+        ));
+
+        let first = limiter.acquire(RequestKind::Download).await;
+        let _second = limiter.acquire(RequestKind::Download).await;
+        assert!(limiter
+            .acquire(RequestKind::Download)
+            .now_or_never()
+            .is_none());
+        drop(first);
+        assert!(limiter
+            .acquire(RequestKind::Download)
+            .now_or_never()
+            .is_some());
+
+        let not_found: Result<(), Error> = Err(Error::VersionNotFound("1.0.0".parse().unwrap()));
+        assert!(!limiter.should_retry(RequestKind::Metadata, &not_found, 0));
+        let throttled: Result<(), Error> =
+            Err(Error::OciError(OciDistributionError::ServerError {
+                code: 429,
+                url: "https://limits.test".into(),
+                message: "slow down".into(),
+            }));
+        assert!(limiter.should_retry(RequestKind::Metadata, &throttled, 0));
+        assert_eq!(limiter.metadata.available_permits(), 2);
+        assert!(!limiter.should_retry(RequestKind::Metadata, &throttled, MAX_RETRIES));
+        assert!(!limiter.should_retry(RequestKind::Metadata, &Ok(()), 1));
+        assert_eq!(limiter.metadata.available_permits(), 3);
+    }
+}
@@ -86,15 +86,10 @@ async fn fetch_package_content(
         Some(version) => version,
         None => {
             eprintln!("No version specified; looking up latest release...");
-            let versions = client
-                .list_all_versions(&package)
+            client
+                .latest_version(&package)
                 .await
-                .with_context(|| format!("error listing {package} releases"))?;
-            versions
-                .into_iter()
-                .map(|v| v.version)
-                .max()
-                .with_context(|| format!("no releases found for {package}"))?
+                .with_context(|| format!("error resolving latest {package} release"))?
         }
     };
     eprintln!("Fetching release details for {package}@{version}...");
@@ -15,8 +15,8 @@ pub struct RegistryMeta {
 }
 
 impl RegistryMeta {
-    pub async fn fetch_or_default(domain: &str) -> Self {
-        match Self::fetch(domain).await {
+    pub async fn fetch_or_default(client: &reqwest::Client, domain: &str) -> Self {
+        match Self::fetch(client, domain).await {
             Ok(Some(meta)) => {
                 tracing::debug!("Got registry metadata {meta:?}");
                 meta
@@ -32,22 +32,22 @@ impl RegistryMeta {
         }
     }
 
-    pub async fn fetch(domain: &str) -> Result<Option<Self>, Error> {
+    pub async fn fetch(client: &reqwest::Client, domain: &str) -> Result<Option<Self>, Error> {
         let scheme = if domain.starts_with("localhost:") {
             "http"
         } else {
             "https"
         };
         let url = format!("{scheme}://{domain}/{WELL_KNOWN_PATH}");
-        Self::fetch_url(&url)
+        Self::fetch_url(client, &url)
             .await
             .with_context(|| format!("error fetching registry metadata from {url:?}"))
             .map_err(Error::RegistryMeta)
     }
 
-    async fn fetch_url(url: &str) -> anyhow::Result<Option<Self>> {
+    async fn fetch_url(client: &reqwest::Client, url: &str) -> anyhow::Result<Option<Self>> {
         tracing::debug!("Fetching registry metadata from {url:?}");
-        let resp = reqwest::get(url).await?;
+        let resp = client.get(url).send().await?;
         if resp.status() == StatusCode::NOT_FOUND {
             return Ok(None);
         }
@@ -0,0 +1,212 @@
+//! OpenPGP signatures of releases, for organizations that standardize on
+//! GPG keys rather than Sigstore. Signing and verification run GnuPG's `gpg`
+//! and `gpgv`.
+//!
+//! A signature is a detached, ASCII-armored signature over the release's
+//! content digest as a string (e.g. `sha256:...`), so it can be verified
+//! before the content is downloaded; the content itself is then validated
+//! against the digest as usual.
+
+use std::{
+    ffi::OsStr,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crate::{Error, PackageRef, Release};
+
+/// Returns the payload signed for the given release.
+pub fn signed_payload(release: &Release) -> Vec<u8> {
+    release.content_digest.to_string().into_bytes()
+}
+
+/// Signs the given release with `gpg`, using the given key (a key ID,
+/// fingerprint, or user ID) or else the default signing key.
+pub async fn sign(release: &Release, key: Option<&str>) -> Result<Vec<u8>, Error> {
+    let payload_file = TempFile::new(&signed_payload(release))?;
+    let mut args = vec![OsStr::new("--batch"), OsStr::new("--armor")];
+    if let Some(key) = key {
+        args.extend([OsStr::new("--local-user"), OsStr::new(key)]);
+    }
+    args.extend([
+        OsStr::new("--output"),
+        OsStr::new("-"),
+        OsStr::new("--detach-sign"),
+        payload_file.0.as_os_str(),
+    ]);
+    let output = run("gpg", &args).await?;
+    if !output.status.success() {
+        return Err(Error::SigningFailed(format!(
+            "gpg exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(output.stdout)
+}
+
+/// Checks that at least one of the given signatures of the release is
+/// verified by a key in the given keyring, a binary OpenPGP keyring as
+/// exported by `gpg --export`, returning the signer identities (see
+/// [`signer_identities`]) of every verified signature.
+pub async fn verify(
+    package: &PackageRef,
+    release: &Release,
+    keyring: &Path,
+    signatures: &[Vec<u8>],
+) -> Result<Vec<String>, Error> {
+    if signatures.is_empty() {
+        return Err(Error::SignatureRejected(
+            package.clone(),
+            "no OpenPGP signature found".into(),
+        ));
+    }
+    // gpgv looks up keyrings without a directory in its home directory
+    let keyring = if keyring.is_relative() {
+        Path::new(".").join(keyring)
+    } else {
+        keyring.to_path_buf()
+    };
+    let payload_file = TempFile::new(&signed_payload(release))?;
+    let mut reasons = vec![];
+    let mut identities = vec![];
+    let mut verified = false;
+    for signature in signatures {
+        let signature_file = TempFile::new(signature)?;
+        let args = [
+            OsStr::new("--status-fd"),
+            OsStr::new("1"),
+            OsStr::new("--keyring"),
+            keyring.as_os_str(),
+            signature_file.0.as_os_str(),
+            payload_file.0.as_os_str(),
+        ];
+        let output = run("gpgv", &args).await?;
+        if output.status.success() {
+            verified = true;
+            identities.extend(signer_identities(&String::from_utf8_lossy(&output.stdout)));
+            continue;
+        }
+        reasons.push(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    if verified {
+        return Ok(identities);
+    }
+    Err(Error::SignatureRejected(
+        package.clone(),
+        format!(
+            "no OpenPGP signature verified by keyring {keyring:?}: {}",
+            reasons.join("; ")
+        ),
+    ))
+}
+
+/// Returns the identities of a verified signer from `gpgv --status-fd`
+/// output: the signing key's primary key fingerprint, its user ID, and the
+/// email address in the user ID, if any.
+fn signer_identities(status: &str) -> Vec<String> {
+    let mut identities = vec![];
+    for line in status.lines() {
+        let mut fields = line.split(' ');
+        match (fields.next(), fields.next()) {
+            (Some("[GNUPG:]"), Some("GOODSIG")) => {
+                let user_id = fields.skip(1).collect::<Vec<_>>().join(" ");
+                if let Some((_, email)) = user_id.rsplit_once('<') {
+                    if let Some(email) = email.strip_suffix('>') {
+                        identities.push(email.to_string());
+                    }
+                }
+                identities.push(user_id);
+            }
+            // The primary key fingerprint is the last field
+            (Some("[GNUPG:]"), Some("VALIDSIG")) => {
+                if let Some(fingerprint) = fields.next_back() {
+                    identities.push(fingerprint.to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+    identities
+}
+
+/// Runs a GnuPG program, capturing its output.
+async fn run(program: &str, args: &[&OsStr]) -> Result<std::process::Output, Error> {
+    tokio::process::Command::new(program)
+        .args(args)
+        .output()
+        .await
+        .map_err(|err| {
+            Error::IoError(std::io::Error::new(
+                err.kind(),
+                format!("failed to run {program:?}: {err}"),
+            ))
+        })
+}
+
+/// A temporary file holding a payload or signature, removed on drop.
+struct TempFile(PathBuf);
+
+impl TempFile {
+    fn new(contents: &[u8]) -> Result<Self, Error> {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "wasm-pkg-signature-{}-{}",
+            std::process::id(),
+            COUNT.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::write(&path, contents)?;
+        Ok(Self(path))
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn release() -> Release {
+        Release {
+            version: "1.0.0".parse().unwrap(),
+            content_digest: format!("sha256:{}", "0".repeat(64)).parse().unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_signed_payload() {
+        assert_eq!(
+            signed_payload(&release()),
+            format!("sha256:{}", "0".repeat(64)).into_bytes()
+        );
+    }
+
+    #[test]
+    fn test_signer_identities() {
+        let status = "[GNUPG:] NEWSIG\n\
+            [GNUPG:] GOODSIG 0123456789ABCDEF Release Bot <releases@example.com>\n\
+            [GNUPG:] VALIDSIG AAAA 2024-01-01 1704067200 0 4 0 1 10 00 BBBB\n";
+        assert_eq!(
+            signer_identities(status),
+            [
+                "releases@example.com",
+                "Release Bot <releases@example.com>",
+                "BBBB"
+            ]
+        );
+        assert!(signer_identities("[GNUPG:] BADSIG 0123 Someone\n").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_verify_without_signatures() {
+        let package: PackageRef = "test:pkg".parse().unwrap();
+        let err = verify(&package, &release(), Path::new("keyring.gpg"), &[])
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::SignatureRejected(..)));
+    }
+}
@@ -1,7 +1,7 @@
 use crate::{label::Label, Error};
 
 /// A package reference, consisting of kebab-case namespace and name, e.g. `wasm-pkg:loader`.
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct PackageRef {
     namespace: Label,
     name: Label,
@@ -0,0 +1,188 @@
+//! Default locations for config (including credentials), cache, and data
+//! files.
+//!
+//! The XDG base directory variables (`XDG_CONFIG_HOME`, `XDG_CACHE_HOME`,
+//! `XDG_DATA_HOME`) are honored on every platform when set; otherwise the
+//! platform's conventional directories are used.
+
+use std::{
+    collections::BTreeSet,
+    ffi::OsString,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+const APP_DIR_NAME: &str = "wasm-pkg";
+
+/// A kind of directory and where it's found.
+#[derive(Clone, Copy)]
+struct DirKind {
+    name: &'static str,
+    xdg_var: &'static str,
+    platform_default: fn() -> Option<PathBuf>,
+}
+
+const CONFIG: DirKind = DirKind {
+    name: "config",
+    xdg_var: "XDG_CONFIG_HOME",
+    platform_default: dirs::config_dir,
+};
+
+const CACHE: DirKind = DirKind {
+    name: "cache",
+    xdg_var: "XDG_CACHE_HOME",
+    platform_default: dirs::cache_dir,
+};
+
+const DATA: DirKind = DirKind {
+    name: "data",
+    xdg_var: "XDG_DATA_HOME",
+    platform_default: dirs::data_dir,
+};
+
+/// Returns the config directory, e.g. `~/.config/wasm-pkg`.
+pub(crate) fn config_dir() -> Option<PathBuf> {
+    resolve(CONFIG)
+}
+
+/// Returns the cache directory, e.g. `~/.cache/wasm-pkg`.
+pub(crate) fn cache_dir() -> Option<PathBuf> {
+    resolve(CACHE)
+}
+
+/// Returns the data directory, e.g. `~/.local/share/wasm-pkg`.
+pub(crate) fn data_dir() -> Option<PathBuf> {
+    resolve(DATA)
+}
+
+/// Moves each directory that is only found at its legacy location to the
+/// location given by its XDG variable, returning the directories moved from
+/// and to.
+pub(crate) fn migrate_dirs() -> std::io::Result<Vec<(PathBuf, PathBuf)>> {
+    let mut migrated = Vec::new();
+    for kind in [CONFIG, CACHE, DATA] {
+        let (Some(legacy), Some(dir)) = (legacy_dir(kind), xdg_dir(kind)) else {
+            continue;
+        };
+        if uses_legacy(&legacy, &dir) {
+            migrate(&legacy, &dir)?;
+            tracing::info!("Moved wasm-pkg {} from {legacy:?} to {dir:?}", kind.name);
+            migrated.push((legacy, dir));
+        }
+    }
+    Ok(migrated)
+}
+
+/// Returns the directory of the given kind. If its XDG variable moves it
+/// away from its legacy (platform default) location and only the legacy
+/// directory exists, the legacy directory is used, with a notice (once per
+/// process) until it's migrated with [`migrate_dirs`].
+fn resolve(kind: DirKind) -> Option<PathBuf> {
+    static WARNED: Mutex<BTreeSet<&str>> = Mutex::new(BTreeSet::new());
+    let dir = xdg_dir(kind)?;
+    match legacy_dir(kind) {
+        Some(legacy) if uses_legacy(&legacy, &dir) => {
+            if WARNED.lock().unwrap().insert(kind.name) {
+                tracing::warn!(
+                    "Using wasm-pkg {} from legacy location {legacy:?}; run `wkg config migrate` to move it to {dir:?}",
+                    kind.name
+                );
+            }
+            Some(legacy)
+        }
+        _ => Some(dir),
+    }
+}
+
+fn xdg_dir(kind: DirKind) -> Option<PathBuf> {
+    Some(base_dir(std::env::var_os(kind.xdg_var), (kind.platform_default)())?.join(APP_DIR_NAME))
+}
+
+fn legacy_dir(kind: DirKind) -> Option<PathBuf> {
+    Some((kind.platform_default)()?.join(APP_DIR_NAME))
+}
+
+/// Returns the base directory given by an XDG variable's value, falling back
+/// to the platform default. Per the XDG spec, relative paths are ignored.
+fn base_dir(xdg_value: Option<OsString>, platform_default: Option<PathBuf>) -> Option<PathBuf> {
+    xdg_value
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or(platform_default)
+}
+
+/// Returns true if only the legacy directory exists.
+fn uses_legacy(legacy: &Path, dir: &Path) -> bool {
+    legacy != dir && !dir.exists() && legacy.is_dir()
+}
+
+/// Moves `legacy` to `dir`. If they're on different filesystems, the
+/// directory is copied (staged next to `dir` so a failed migration never
+/// leaves a partial directory in use) and the legacy directory removed only
+/// once the copy is complete, so credentials and keys aren't left behind.
+fn migrate(legacy: &Path, dir: &Path) -> std::io::Result<()> {
+    if let Some(parent) = dir.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    if std::fs::rename(legacy, dir).is_ok() {
+        return Ok(());
+    }
+    let staging = dir.with_file_name(format!("{APP_DIR_NAME}.migrating"));
+    if staging.exists() {
+        std::fs::remove_dir_all(&staging)?;
+    }
+    copy_dir(legacy, &staging)?;
+    std::fs::rename(&staging, dir)?;
+    std::fs::remove_dir_all(legacy)
+}
+
+fn copy_dir(from: &Path, to: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(to)?;
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            std::fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base_dir() {
+        let default = Some(PathBuf::from("/home/user/.config"));
+        assert_eq!(
+            base_dir(Some("/xdg/config".into()), default.clone()),
+            Some(PathBuf::from("/xdg/config"))
+        );
+        assert_eq!(base_dir(Some("relative".into()), default.clone()), default);
+        assert_eq!(base_dir(None, default.clone()), default);
+    }
+
+    #[test]
+    fn test_migrate() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        let legacy = root.join("legacy/wasm-pkg");
+        let dir = root.join("xdg/wasm-pkg");
+        std::fs::create_dir_all(legacy.join("keys")).unwrap();
+        std::fs::write(legacy.join("config.toml"), "").unwrap();
+        std::fs::write(legacy.join("keys/signing.key"), "").unwrap();
+        assert!(uses_legacy(&legacy, &dir));
+
+        migrate(&legacy, &dir).unwrap();
+        assert!(!legacy.exists());
+        assert!(dir.join("config.toml").exists());
+        assert!(dir.join("keys/signing.key").exists());
+        assert!(!root.join("xdg/wasm-pkg.migrating").exists());
+
+        // Once migrated, the new directory is used
+        assert!(!uses_legacy(&legacy, &dir));
+    }
+}
@@ -0,0 +1,441 @@
+use std::collections::{BTreeSet, HashMap};
+
+use semver::{Comparator, Op, Version, VersionReq};
+
+use crate::{source::VersionInfo, Error, PackageRef};
+
+/// What to do when the only versions matching a request are yanked.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum YankedPolicy {
+    /// Fail resolution.
+    #[default]
+    Error,
+    /// Select the yanked version, logging a warning.
+    Warn,
+    /// Select the yanked version silently.
+    Allow,
+}
+
+impl std::str::FromStr for YankedPolicy {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "error" => Ok(Self::Error),
+            "warn" => Ok(Self::Warn),
+            "allow" => Ok(Self::Allow),
+            _ => Err(Error::InvalidConfig(anyhow::anyhow!(
+                "invalid yanked policy {s:?}; expected one of \"error\", \"warn\", \"allow\""
+            ))),
+        }
+    }
+}
+
+/// Which of the versions satisfying a requirement is selected.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ResolutionStrategy {
+    /// Select the highest matching version.
+    #[default]
+    Highest,
+    /// Select the lowest matching version, e.g. to verify that declared
+    /// minimum bounds are correct.
+    Minimal,
+}
+
+impl std::str::FromStr for ResolutionStrategy {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "highest" => Ok(Self::Highest),
+            "minimal" => Ok(Self::Minimal),
+            _ => Err(Error::InvalidConfig(anyhow::anyhow!(
+                "invalid resolution strategy {s:?}; expected \"highest\" or \"minimal\""
+            ))),
+        }
+    }
+}
+
+/// Policy for selecting a version from those available for a package.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct VersionPolicy {
+    /// Whether prerelease versions may be selected.
+    pub prereleases: bool,
+    /// What to do when only yanked versions match.
+    pub yanked: YankedPolicy,
+}
+
+impl VersionPolicy {
+    /// Returns true if `version` satisfies `req` under this policy.
+    ///
+    /// Prerelease versions follow the usual semver rules, satisfying a
+    /// requirement only if one of its comparators names a prerelease of the
+    /// same `major.minor.patch`. If this policy allows prereleases, a
+    /// prerelease also satisfies any requirement satisfied by its release
+    /// version whose lower bounds it doesn't fall below, e.g. `1.2.0-rc.1`
+    /// satisfies `^1.1` but not `^1.2` or `>=1.2.0`. Build metadata is
+    /// ignored.
+    pub fn matches(&self, req: &VersionReq, version: &Version) -> bool {
+        if req.matches(version) {
+            return true;
+        }
+        if version.pre.is_empty() || !self.prereleases {
+            return false;
+        }
+        req.matches(&Version::new(version.major, version.minor, version.patch))
+            && req
+                .comparators
+                .iter()
+                .all(|comparator| lower_bound(comparator).is_none_or(|bound| *version >= bound))
+    }
+
+    /// Returns the highest of the given versions of `package` satisfying
+    /// all of `reqs` under this policy. Yanked versions are only selected if
+    /// no other version matches, as allowed by [`Self::yanked`]. Versions
+    /// differing only in build metadata are ordered by that metadata so the
+    /// result is deterministic. Selecting a version with a deprecation
+    /// notice (see [`VersionInfo::deprecation`]) logs a warning.
+    pub fn resolve(
+        &self,
+        package: &PackageRef,
+        versions: impl IntoIterator<Item = VersionInfo>,
+        reqs: &[VersionReq],
+    ) -> Result<Version, Error> {
+        self.resolve_with_strategy(package, versions, reqs, ResolutionStrategy::Highest)
+    }
+
+    /// Like [`Self::resolve`], but selects the version according to the
+    /// given [`ResolutionStrategy`].
+    pub fn resolve_with_strategy(
+        &self,
+        package: &PackageRef,
+        versions: impl IntoIterator<Item = VersionInfo>,
+        reqs: &[VersionReq],
+        strategy: ResolutionStrategy,
+    ) -> Result<Version, Error> {
+        let pick = |infos: Vec<VersionInfo>| match strategy {
+            ResolutionStrategy::Highest => infos.into_iter().max(),
+            ResolutionStrategy::Minimal => infos.into_iter().min(),
+        };
+        let (yanked, available): (Vec<_>, Vec<_>) = versions
+            .into_iter()
+            .filter(|info| reqs.iter().all(|req| self.matches(req, &info.version)))
+            .partition(|info| info.yanked);
+        let info = match pick(available) {
+            Some(info) => info,
+            None => {
+                let Some(info) = pick(yanked) else {
+                    return Err(Error::NoMatchingVersion(package.clone()));
+                };
+                match self.yanked {
+                    YankedPolicy::Error => return Err(Error::VersionYanked(info.version)),
+                    YankedPolicy::Warn => {
+                        tracing::warn!("Selecting yanked version {package}@{}", info.version);
+                        info
+                    }
+                    YankedPolicy::Allow => info,
+                }
+            }
+        };
+        if let Some(deprecation) = &info.deprecation {
+            tracing::warn!(
+                "Selecting deprecated version {package}@{}: {deprecation}",
+                info.version
+            );
+        }
+        Ok(info.version)
+    }
+}
+
+/// Per-scope [`VersionPolicy`] settings; unset fields fall back to a
+/// broader scope.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct VersionPolicyConfig {
+    pub prereleases: Option<bool>,
+    pub yanked: Option<YankedPolicy>,
+}
+
+impl VersionPolicyConfig {
+    /// Overrides fields of this config with any set in `other`.
+    pub fn merge(&mut self, other: VersionPolicyConfig) {
+        self.prereleases = other.prereleases.or(self.prereleases);
+        self.yanked = other.yanked.or(self.yanked);
+    }
+
+    /// Applies the fields set in this config to the given policy.
+    pub fn apply(&self, policy: VersionPolicy) -> VersionPolicy {
+        VersionPolicy {
+            prereleases: self.prereleases.unwrap_or(policy.prereleases),
+            yanked: self.yanked.unwrap_or(policy.yanked),
+        }
+    }
+}
+
+/// The registry name that local `[override]` paths are checked as by a
+/// [`RegistryPolicy`].
+pub const OVERRIDE_REGISTRY: &str = "override";
+
+/// Restrictions on which registries may be contacted.
+///
+/// Merging policies (e.g. from a profile or another config file) can only
+/// tighten them: allowlists are intersected and denylists are combined, so a
+/// later config can't re-allow a registry.
+///
+/// Local `[override]` paths are checked as if served by the registry named
+/// [`OVERRIDE_REGISTRY`], so a namespace restricted to particular registries
+/// can't be overridden unless that name is allowed too.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RegistryPolicy {
+    allowed: Option<BTreeSet<String>>,
+    denied: BTreeSet<String>,
+    namespaces: HashMap<String, BTreeSet<String>>,
+}
+
+impl RegistryPolicy {
+    /// Restricts all packages to the given registries.
+    pub fn allow<T: Into<String>>(&mut self, registries: impl IntoIterator<Item = T>) -> &mut Self {
+        intersect(
+            &mut self.allowed,
+            registries.into_iter().map(Into::into).collect(),
+        );
+        self
+    }
+
+    /// Forbids contacting the given registries.
+    pub fn deny<T: Into<String>>(&mut self, registries: impl IntoIterator<Item = T>) -> &mut Self {
+        self.denied.extend(registries.into_iter().map(Into::into));
+        self
+    }
+
+    /// Restricts packages in the given namespace to the given registries.
+    pub fn allow_for_namespace<T: Into<String>>(
+        &mut self,
+        namespace: impl Into<String>,
+        registries: impl IntoIterator<Item = T>,
+    ) -> &mut Self {
+        let namespace = namespace.into();
+        let mut allowed = self.namespaces.remove(&namespace);
+        intersect(
+            &mut allowed,
+            registries.into_iter().map(Into::into).collect(),
+        );
+        self.namespaces
+            .extend(allowed.map(|allowed| (namespace, allowed)));
+        self
+    }
+
+    /// Returns the registries all packages are restricted to, if restricted.
+    pub fn allowed(&self) -> Option<&BTreeSet<String>> {
+        self.allowed.as_ref()
+    }
+
+    /// Returns the registries that may not be contacted.
+    pub fn denied(&self) -> &BTreeSet<String> {
+        &self.denied
+    }
+
+    /// Returns an iterator of namespaces and the registries their packages
+    /// are restricted to.
+    pub fn namespaces(&self) -> impl Iterator<Item = (&str, &BTreeSet<String>)> {
+        self.namespaces
+            .iter()
+            .map(|(namespace, registries)| (namespace.as_str(), registries))
+    }
+
+    /// Tightens this policy with the restrictions of `other`.
+    pub fn merge(&mut self, other: RegistryPolicy) {
+        if let Some(allowed) = other.allowed {
+            intersect(&mut self.allowed, allowed);
+        }
+        self.denied.extend(other.denied);
+        for (namespace, registries) in other.namespaces {
+            self.allow_for_namespace(namespace, registries);
+        }
+    }
+
+    /// Returns an error if the given registry may not serve the host that its
+    /// registry metadata redirects to, i.e. if the host is denied or missing
+    /// from the registry allowlist. Namespace allowlists name registries
+    /// rather than hosts, so don't apply.
+    pub fn check_redirect(&self, registry: &str, host: &str) -> Result<(), Error> {
+        let permitted = host == registry
+            || (!self.denied.contains(host)
+                && self
+                    .allowed
+                    .as_ref()
+                    .is_none_or(|allowed| allowed.contains(host)));
+        if permitted {
+            Ok(())
+        } else {
+            Err(Error::RegistryRedirectNotAllowed(
+                registry.to_string(),
+                host.to_string(),
+            ))
+        }
+    }
+
+    /// Returns an error if packages in the given namespace may not be
+    /// fetched from the given registry.
+    pub fn check(&self, namespace: &str, registry: &str) -> Result<(), Error> {
+        let allowed = |registries: &BTreeSet<String>| registries.contains(registry);
+        let permitted = !self.denied.contains(registry)
+            && self.allowed.as_ref().is_none_or(allowed)
+            && self.namespaces.get(namespace).is_none_or(allowed);
+        if permitted {
+            Ok(())
+        } else {
+            Err(Error::RegistryNotAllowed(
+                registry.to_string(),
+                namespace.to_string(),
+            ))
+        }
+    }
+}
+
+fn intersect(allowed: &mut Option<BTreeSet<String>>, registries: BTreeSet<String>) {
+    *allowed = Some(match allowed.take() {
+        Some(allowed) => allowed.intersection(&registries).cloned().collect(),
+        None => registries,
+    });
+}
+
+/// Returns the lowest version allowed by `comparator`, if it has a lower bound.
+fn lower_bound(comparator: &Comparator) -> Option<Version> {
+    match comparator.op {
+        Op::Less | Op::LessEq => None,
+        _ => Some(Version {
+            major: comparator.major,
+            minor: comparator.minor.unwrap_or(0),
+            patch: comparator.patch.unwrap_or(0),
+            pre: comparator.pre.clone(),
+            build: Default::default(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn versions(versions: &[(&str, bool)]) -> Vec<VersionInfo> {
+        versions
+            .iter()
+            .map(|(version, yanked)| VersionInfo {
+                version: version.parse().unwrap(),
+                yanked: *yanked,
+                deprecation: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_resolve() {
+        let package: PackageRef = "test:pkg".parse().unwrap();
+        let available = versions(&[("1.0.0", false), ("1.1.0", true), ("2.0.0-rc.1", false)]);
+        let resolve = |policy: &VersionPolicy, req: &str| {
+            policy
+                .resolve(&package, available.clone(), &[req.parse().unwrap()])
+                .map(|ver| ver.to_string())
+        };
+
+        let policy = VersionPolicy::default();
+        assert_eq!(resolve(&policy, "*").unwrap(), "1.0.0");
+        assert_eq!(resolve(&policy, "=2.0.0-rc.1").unwrap(), "2.0.0-rc.1");
+        assert!(matches!(
+            resolve(&policy, "^2"),
+            Err(Error::NoMatchingVersion(_))
+        ));
+
+        let policy = VersionPolicy {
+            prereleases: true,
+            ..Default::default()
+        };
+        assert_eq!(resolve(&policy, "*").unwrap(), "2.0.0-rc.1");
+        assert_eq!(resolve(&policy, "^1").unwrap(), "1.0.0");
+        assert_eq!(resolve(&policy, ">=1.5, <3").unwrap(), "2.0.0-rc.1");
+        for req in ["^2", ">=2.0.0", "~2.0"] {
+            assert!(
+                matches!(resolve(&policy, req), Err(Error::NoMatchingVersion(_))),
+                "{req}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_resolve_minimal() {
+        let package: PackageRef = "test:pkg".parse().unwrap();
+        let available = versions(&[("1.0.0", true), ("1.0.1", false), ("1.2.0", false)]);
+        let resolved = VersionPolicy::default()
+            .resolve_with_strategy(
+                &package,
+                available,
+                &["^1".parse().unwrap()],
+                ResolutionStrategy::Minimal,
+            )
+            .unwrap();
+        assert_eq!(resolved.to_string(), "1.0.1");
+    }
+
+    #[test]
+    fn test_resolve_yanked() {
+        let package: PackageRef = "test:pkg".parse().unwrap();
+        let available = versions(&[("1.0.0", false), ("1.1.0", true)]);
+        let reqs = ["^1.1".parse().unwrap()];
+
+        let policy = VersionPolicy::default();
+        assert!(matches!(
+            policy.resolve(&package, available.clone(), &reqs),
+            Err(Error::VersionYanked(_))
+        ));
+
+        let policy = VersionPolicy {
+            yanked: YankedPolicy::Allow,
+            ..Default::default()
+        };
+        let resolved = policy.resolve(&package, available, &reqs).unwrap();
+        assert_eq!(resolved.to_string(), "1.1.0");
+    }
+
+    #[test]
+    fn test_registry_policy() {
+        let mut policy = RegistryPolicy::default();
+        policy
+            .allow(["bytecodealliance.org", "wasi.dev", "example.com"])
+            .deny(["example.com"])
+            .allow_for_namespace("wasi", ["wasi.dev"]);
+        assert!(policy.check("wasi", "wasi.dev").is_ok());
+        assert!(policy.check("other", "bytecodealliance.org").is_ok());
+        assert!(policy.check("wasi", "bytecodealliance.org").is_err());
+        assert!(policy.check("other", "example.com").is_err());
+        assert!(policy.check("other", "evil.com").is_err());
+
+        // Merging can't loosen restrictions
+        let mut other = RegistryPolicy::default();
+        other
+            .allow(["evil.com", "bytecodealliance.org"])
+            .allow_for_namespace("wasi", ["evil.com"]);
+        policy.merge(other);
+        assert!(policy.check("other", "bytecodealliance.org").is_ok());
+        assert!(policy.check("other", "evil.com").is_err());
+        assert!(policy.check("wasi", "wasi.dev").is_err());
+        assert!(policy.check("other", OVERRIDE_REGISTRY).is_err());
+
+        let mut policy = RegistryPolicy::default();
+        assert!(policy.check("wasi", OVERRIDE_REGISTRY).is_ok());
+        assert!(policy.check_redirect("wasi.dev", "ghcr.io").is_ok());
+        policy.allow(["wasi.dev", "ghcr.io"]).deny(["evil.com"]);
+        assert!(policy.check_redirect("wasi.dev", "ghcr.io").is_ok());
+        assert!(policy.check_redirect("wasi.dev", "evil.com").is_err());
+        assert!(policy.check_redirect("wasi.dev", "example.com").is_err());
+    }
+
+    #[test]
+    fn test_resolve_build_metadata() {
+        let package: PackageRef = "test:pkg".parse().unwrap();
+        let available = versions(&[("1.0.0+b", false), ("1.0.0+a", false)]);
+        let resolved = VersionPolicy::default()
+            .resolve(&package, available, &["=1.0.0".parse().unwrap()])
+            .unwrap();
+        assert_eq!(resolved.to_string(), "1.0.0+b");
+    }
+}
@@ -0,0 +1,583 @@
+//! Build provenance attestations (e.g. [SLSA](https://slsa.dev/provenance/))
+//! and the policy they're checked against.
+//!
+//! Attestations are in-toto statements, optionally wrapped in a DSSE envelope
+//! or Sigstore bundle. Note that attestation signatures are not verified;
+//! only the statement's subject and contents are checked.
+//!
+//! The transparency log (e.g. Rekor) entries of a Sigstore bundle are
+//! checked against their inclusion proofs, so a policy can require that the
+//! signing event was logged, and logged long enough ago to have been
+//! noticed. Log checkpoint signatures are not verified.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use crate::{ContentDigest, Error, PackageRef};
+
+const SLSA_PROVENANCE_PREFIX: &str = "https://slsa.dev/provenance/";
+
+/// A provenance attestation for a package release.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Provenance {
+    /// The attestation's predicate type, e.g.
+    /// `https://slsa.dev/provenance/v1`.
+    pub predicate_type: String,
+    /// The ID of the builder that produced the release, if given.
+    pub builder_id: Option<String>,
+    /// The transparency log entries recording the attestation, with
+    /// verified inclusion proofs.
+    pub log_entries: Vec<LogEntry>,
+}
+
+/// A transparency log entry recording an attestation's signing event.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LogEntry {
+    /// The entry's index in the log.
+    pub log_index: u64,
+    /// When the entry was added to the log, in seconds since the Unix epoch.
+    pub integrated_time: u64,
+}
+
+impl Provenance {
+    /// Parses a provenance attestation whose subject is one of the given
+    /// digests. Returns Ok(None) if `bytes` holds some other kind of
+    /// attestation or one for a different subject.
+    pub fn parse(bytes: &[u8], subjects: &[ContentDigest]) -> Result<Option<Self>, Error> {
+        let envelope: AttestationEnvelope = serde_json::from_slice(bytes)
+            .map_err(|err| Error::InvalidContent(format!("invalid attestation: {err}")))?;
+        let (statement, log_entries) = match envelope {
+            AttestationEnvelope::Bundle {
+                dsse_envelope,
+                verification_material,
+            } => {
+                let log_entries = match verification_material {
+                    Some(material) => material.log_entries(&dsse_envelope.payload()?)?,
+                    None => vec![],
+                };
+                (dsse_envelope.statement()?, log_entries)
+            }
+            AttestationEnvelope::Dsse(dsse) => (dsse.statement()?, vec![]),
+            AttestationEnvelope::Statement(statement) => (statement, vec![]),
+        };
+        if !statement.predicate_type.starts_with(SLSA_PROVENANCE_PREFIX) {
+            return Ok(None);
+        }
+        let matches_subject = statement.subject.iter().any(|subject| {
+            let Some(hex) = &subject.digest.sha256 else {
+                return false;
+            };
+            subjects
+                .iter()
+                .any(|ContentDigest::Sha256 { hex: want }| hex.eq_ignore_ascii_case(want))
+        });
+        if !matches_subject {
+            tracing::debug!("Ignoring provenance attestation for another subject");
+            return Ok(None);
+        }
+        let predicate = statement.predicate.unwrap_or_default();
+        let builder_id = predicate
+            .builder
+            .or(predicate.run_details.and_then(|details| details.builder))
+            .map(|builder| builder.id);
+        Ok(Some(Self {
+            predicate_type: statement.predicate_type,
+            builder_id,
+            log_entries,
+        }))
+    }
+}
+
+impl std::fmt::Display for Provenance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.builder_id {
+            Some(builder_id) => write!(f, "{} (builder: {builder_id})", self.predicate_type)?,
+            None => write!(f, "{}", self.predicate_type)?,
+        }
+        if let Some(entry) = self.log_entries.first() {
+            write!(f, " [logged at index {}]", entry.log_index)?;
+        }
+        Ok(())
+    }
+}
+
+/// Policy for the provenance attestations a package must have to be fetched.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProvenancePolicy {
+    /// Whether a provenance attestation is required.
+    pub require: bool,
+    /// If non-empty, a required attestation must name one of these builders.
+    pub builder_ids: Vec<String>,
+    /// Whether a required attestation must be recorded in a transparency
+    /// log, with a verified inclusion proof.
+    pub require_log_inclusion: bool,
+    /// If set, a required attestation must have been recorded in a
+    /// transparency log at least this long ago.
+    pub min_log_age: Option<Duration>,
+}
+
+impl ProvenancePolicy {
+    /// Returns an error if the given attestations don't satisfy this policy.
+    pub fn check(&self, package: &PackageRef, provenance: &[Provenance]) -> Result<(), Error> {
+        self.check_at(package, provenance, SystemTime::now())
+    }
+
+    fn check_at(
+        &self,
+        package: &PackageRef,
+        provenance: &[Provenance],
+        now: SystemTime,
+    ) -> Result<(), Error> {
+        if !self.require {
+            return Ok(());
+        }
+        let from_allowed_builder = |provenance: &Provenance| {
+            self.builder_ids.is_empty()
+                || provenance
+                    .builder_id
+                    .as_ref()
+                    .is_some_and(|id| self.builder_ids.contains(id))
+        };
+        let logged = |provenance: &Provenance| {
+            if !self.require_log_inclusion && self.min_log_age.is_none() {
+                return true;
+            }
+            provenance.log_entries.iter().any(|entry| {
+                let logged_at = UNIX_EPOCH + Duration::from_secs(entry.integrated_time);
+                logged_at + self.min_log_age.unwrap_or_default() <= now
+            })
+        };
+        if provenance
+            .iter()
+            .filter(|provenance| from_allowed_builder(provenance))
+            .any(logged)
+        {
+            return Ok(());
+        }
+        let reason = if provenance.is_empty() {
+            "no provenance attestation found".to_string()
+        } else if !provenance.iter().any(from_allowed_builder) {
+            format!(
+                "no provenance attestation from an allowed builder ({})",
+                self.builder_ids.join(", ")
+            )
+        } else if let Some(min_age) = self.min_log_age {
+            format!(
+                "no provenance attestation recorded in a transparency log at least {}s ago",
+                min_age.as_secs()
+            )
+        } else {
+            "no provenance attestation recorded in a transparency log".to_string()
+        };
+        Err(Error::ProvenanceRejected(package.clone(), reason))
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum AttestationEnvelope {
+    Bundle {
+        #[serde(rename = "dsseEnvelope")]
+        dsse_envelope: DsseEnvelope,
+        #[serde(rename = "verificationMaterial")]
+        verification_material: Option<VerificationMaterial>,
+    },
+    Dsse(DsseEnvelope),
+    Statement(Statement),
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DsseEnvelope {
+    payload_type: String,
+    payload: String,
+}
+
+impl DsseEnvelope {
+    fn payload(&self) -> Result<Vec<u8>, Error> {
+        STANDARD
+            .decode(&self.payload)
+            .map_err(|err| Error::InvalidContent(format!("invalid attestation payload: {err}")))
+    }
+
+    fn statement(&self) -> Result<Statement, Error> {
+        if self.payload_type != "application/vnd.in-toto+json" {
+            return Err(Error::InvalidContent(format!(
+                "unsupported attestation payload type {:?}",
+                self.payload_type
+            )));
+        }
+        serde_json::from_slice(&self.payload()?)
+            .map_err(|err| Error::InvalidContent(format!("invalid attestation payload: {err}")))
+    }
+}
+
+/// The verification material of a Sigstore bundle. Its protobuf JSON
+/// encoding gives 64-bit integers as strings and bytes as base64.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct VerificationMaterial {
+    #[serde(default)]
+    tlog_entries: Vec<TlogEntry>,
+}
+
+impl VerificationMaterial {
+    /// Returns the entries recording the given DSSE payload, failing if any
+    /// such entry's inclusion proof doesn't verify. Entries without a proof
+    /// are skipped.
+    fn log_entries(&self, payload: &[u8]) -> Result<Vec<LogEntry>, Error> {
+        let payload_hash = hex(&Sha256::digest(payload));
+        let mut entries = vec![];
+        for entry in &self.tlog_entries {
+            let body = decode_base64(&entry.canonicalized_body)?;
+            if !records_payload(&body, &payload_hash) {
+                tracing::debug!("Ignoring transparency log entry for another payload");
+                continue;
+            }
+            let Some(proof) = &entry.inclusion_proof else {
+                tracing::debug!("Ignoring transparency log entry without an inclusion proof");
+                continue;
+            };
+            proof.verify(&body)?;
+            entries.push(LogEntry {
+                log_index: entry.log_index.get()?,
+                integrated_time: entry.integrated_time.get()?,
+            });
+        }
+        Ok(entries)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TlogEntry {
+    log_index: ProtoInt,
+    integrated_time: ProtoInt,
+    inclusion_proof: Option<InclusionProof>,
+    canonicalized_body: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct InclusionProof {
+    /// The entry's index in the (shard's) Merkle tree.
+    log_index: ProtoInt,
+    root_hash: String,
+    tree_size: ProtoInt,
+    #[serde(default)]
+    hashes: Vec<String>,
+}
+
+impl InclusionProof {
+    /// Verifies that the given entry body is a leaf of the tree with the
+    /// proof's root hash, per RFC 9162 section 2.1.3.2.
+    fn verify(&self, body: &[u8]) -> Result<(), Error> {
+        let invalid =
+            || Error::InvalidContent("transparency log inclusion proof doesn't verify".into());
+        let (index, size) = (self.log_index.get()?, self.tree_size.get()?);
+        if index >= size {
+            return Err(invalid());
+        }
+        let node = |left: &[u8], right: &[u8]| -> Vec<u8> {
+            Sha256::new_with_prefix([1])
+                .chain_update(left)
+                .chain_update(right)
+                .finalize()
+                .to_vec()
+        };
+        let mut hash = Sha256::new_with_prefix([0])
+            .chain_update(body)
+            .finalize()
+            .to_vec();
+        let (mut fnode, mut snode) = (index, size - 1);
+        for sibling in &self.hashes {
+            let sibling = decode_base64(sibling)?;
+            if snode == 0 {
+                return Err(invalid());
+            }
+            if fnode & 1 == 1 || fnode == snode {
+                hash = node(&sibling, &hash);
+                while fnode & 1 == 0 && fnode != 0 {
+                    fnode >>= 1;
+                    snode >>= 1;
+                }
+            } else {
+                hash = node(&hash, &sibling);
+            }
+            fnode >>= 1;
+            snode >>= 1;
+        }
+        if snode != 0 || hash != decode_base64(&self.root_hash)? {
+            return Err(invalid());
+        }
+        Ok(())
+    }
+}
+
+/// A protobuf JSON 64-bit integer, usually given as a string.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ProtoInt {
+    Number(u64),
+    String(String),
+}
+
+impl ProtoInt {
+    fn get(&self) -> Result<u64, Error> {
+        match self {
+            Self::Number(value) => Ok(*value),
+            Self::String(value) => value.parse().map_err(|_| {
+                Error::InvalidContent(format!("invalid transparency log integer {value:?}"))
+            }),
+        }
+    }
+}
+
+/// Returns true if the given Rekor entry body (of the `dsse` or `intoto`
+/// kinds) records a DSSE envelope with the given payload hash.
+fn records_payload(body: &[u8], payload_hash: &str) -> bool {
+    let Ok(body) = serde_json::from_slice::<serde_json::Value>(body) else {
+        return false;
+    };
+    let spec = &body["spec"];
+    let found = [&spec["payloadHash"], &spec["content"]["payloadHash"]]
+        .into_iter()
+        .any(|hash| {
+            hash["algorithm"] == "sha256"
+                && hash["value"]
+                    .as_str()
+                    .is_some_and(|value| value.eq_ignore_ascii_case(payload_hash))
+        });
+    found
+}
+
+fn decode_base64(value: &str) -> Result<Vec<u8>, Error> {
+    STANDARD
+        .decode(value)
+        .map_err(|err| Error::InvalidContent(format!("invalid transparency log entry: {err}")))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Statement {
+    #[serde(default)]
+    subject: Vec<Subject>,
+    predicate_type: String,
+    predicate: Option<Predicate>,
+}
+
+#[derive(Deserialize)]
+struct Subject {
+    digest: SubjectDigest,
+}
+
+#[derive(Deserialize)]
+struct SubjectDigest {
+    sha256: Option<String>,
+}
+
+/// The fields of SLSA v0.2 (`builder`) and v1 (`runDetails.builder`)
+/// provenance predicates identifying the builder.
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Predicate {
+    builder: Option<Builder>,
+    run_details: Option<RunDetails>,
+}
+
+#[derive(Deserialize)]
+struct RunDetails {
+    builder: Option<Builder>,
+}
+
+#[derive(Deserialize)]
+struct Builder {
+    id: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEX: &str = "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b";
+
+    fn statement(predicate_type: &str, hex: &str) -> String {
+        format!(
+            r#"{{
+                "_type": "https://in-toto.io/Statement/v1",
+                "subject": [{{"name": "pkg", "digest": {{"sha256": "{hex}"}}}}],
+                "predicateType": "{predicate_type}",
+                "predicate": {{"runDetails": {{"builder": {{"id": "https://ci.example.com"}}}}}}
+            }}"#
+        )
+    }
+
+    #[test]
+    fn test_parse() {
+        let subjects = [ContentDigest::Sha256 { hex: HEX.into() }];
+        let expected = Provenance {
+            predicate_type: "https://slsa.dev/provenance/v1".into(),
+            builder_id: Some("https://ci.example.com".into()),
+            log_entries: vec![],
+        };
+
+        let raw = statement("https://slsa.dev/provenance/v1", HEX);
+        let parsed = Provenance::parse(raw.as_bytes(), &subjects).unwrap();
+        assert_eq!(parsed.as_ref(), Some(&expected));
+
+        let dsse = format!(
+            r#"{{"payloadType": "application/vnd.in-toto+json", "payload": "{}", "signatures": []}}"#,
+            STANDARD.encode(&raw)
+        );
+        let parsed = Provenance::parse(dsse.as_bytes(), &subjects).unwrap();
+        assert_eq!(parsed.as_ref(), Some(&expected));
+
+        let other_subject = statement("https://slsa.dev/provenance/v1", &"0".repeat(64));
+        assert_eq!(
+            Provenance::parse(other_subject.as_bytes(), &subjects).unwrap(),
+            None
+        );
+        let other_predicate = statement("https://spdx.dev/Document", HEX);
+        assert_eq!(
+            Provenance::parse(other_predicate.as_bytes(), &subjects).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_policy() {
+        let package: PackageRef = "test:pkg".parse().unwrap();
+        let provenance = [Provenance {
+            predicate_type: "https://slsa.dev/provenance/v1".into(),
+            builder_id: Some("https://ci.example.com".into()),
+            log_entries: vec![],
+        }];
+        let mut policy = ProvenancePolicy::default();
+        assert!(policy.check(&package, &[]).is_ok());
+
+        policy.require = true;
+        assert!(policy.check(&package, &[]).is_err());
+        assert!(policy.check(&package, &provenance).is_ok());
+
+        policy.builder_ids = vec!["https://other.example.com".into()];
+        assert!(policy.check(&package, &provenance).is_err());
+    }
+
+    fn leaf(body: &[u8]) -> Vec<u8> {
+        Sha256::new_with_prefix([0])
+            .chain_update(body)
+            .finalize()
+            .to_vec()
+    }
+
+    fn node(left: &[u8], right: &[u8]) -> Vec<u8> {
+        Sha256::new_with_prefix([1])
+            .chain_update(left)
+            .chain_update(right)
+            .finalize()
+            .to_vec()
+    }
+
+    fn proof(index: u64, root: &[u8], hashes: &[&[u8]]) -> InclusionProof {
+        InclusionProof {
+            log_index: ProtoInt::Number(index),
+            root_hash: STANDARD.encode(root),
+            tree_size: ProtoInt::String("3".into()),
+            hashes: hashes.iter().map(|hash| STANDARD.encode(hash)).collect(),
+        }
+    }
+
+    #[test]
+    fn test_inclusion_proof() {
+        let bodies: [&[u8]; 3] = [b"a", b"b", b"c"];
+        let [h0, h1, h2] = bodies.map(leaf);
+        let root = node(&node(&h0, &h1), &h2);
+
+        assert!(proof(0, &root, &[&h1, &h2]).verify(b"a").is_ok());
+        assert!(proof(1, &root, &[&h0, &h2]).verify(b"b").is_ok());
+        assert!(proof(2, &root, &[&node(&h0, &h1)]).verify(b"c").is_ok());
+
+        assert!(proof(0, &root, &[&h1, &h2]).verify(b"x").is_err());
+        assert!(proof(1, &root, &[&h1, &h2]).verify(b"a").is_err());
+        assert!(proof(0, &root, &[&h1]).verify(b"a").is_err());
+        assert!(proof(3, &root, &[&h1, &h2]).verify(b"a").is_err());
+    }
+
+    #[test]
+    fn test_parse_bundle_log_entries() {
+        let subjects = [ContentDigest::Sha256 { hex: HEX.into() }];
+        let raw = statement("https://slsa.dev/provenance/v1", HEX);
+        let body = serde_json::json!({
+            "kind": "dsse",
+            "spec": {"payloadHash": {"algorithm": "sha256", "value": hex(&Sha256::digest(&raw))}},
+        })
+        .to_string();
+        let bundle = serde_json::json!({
+            "dsseEnvelope": {
+                "payloadType": "application/vnd.in-toto+json",
+                "payload": STANDARD.encode(&raw),
+                "signatures": [],
+            },
+            "verificationMaterial": {"tlogEntries": [{
+                "logIndex": "42",
+                "integratedTime": "1700000000",
+                "inclusionProof": {
+                    "logIndex": "0",
+                    "rootHash": STANDARD.encode(leaf(body.as_bytes())),
+                    "treeSize": "1",
+                    "hashes": [],
+                },
+                "canonicalizedBody": STANDARD.encode(&body),
+            }]},
+        })
+        .to_string();
+        let parsed = Provenance::parse(bundle.as_bytes(), &subjects)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            parsed.log_entries,
+            [LogEntry {
+                log_index: 42,
+                integrated_time: 1700000000,
+            }]
+        );
+
+        let tampered = bundle.replace(&STANDARD.encode(leaf(body.as_bytes())), &"A".repeat(44));
+        assert!(Provenance::parse(tampered.as_bytes(), &subjects).is_err());
+    }
+
+    #[test]
+    fn test_policy_log_age() {
+        let package: PackageRef = "test:pkg".parse().unwrap();
+        let mut provenance = [Provenance {
+            predicate_type: "https://slsa.dev/provenance/v1".into(),
+            builder_id: None,
+            log_entries: vec![],
+        }];
+        let mut policy = ProvenancePolicy {
+            require: true,
+            require_log_inclusion: true,
+            ..Default::default()
+        };
+        let now = UNIX_EPOCH + Duration::from_secs(10_000);
+        assert!(policy.check_at(&package, &provenance, now).is_err());
+
+        provenance[0].log_entries.push(LogEntry {
+            log_index: 1,
+            integrated_time: 9_000,
+        });
+        assert!(policy.check_at(&package, &provenance, now).is_ok());
+
+        policy.min_log_age = Some(Duration::from_secs(600));
+        assert!(policy.check_at(&package, &provenance, now).is_ok());
+        policy.min_log_age = Some(Duration::from_secs(3600));
+        assert!(policy.check_at(&package, &provenance, now).is_err());
+    }
+}
@@ -8,7 +8,7 @@ use tokio::io::AsyncReadExt;
 
 use crate::Error;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Release {
     pub version: Version,
     pub content_digest: ContentDigest,
@@ -20,6 +20,11 @@ pub enum ContentDigest {
 }
 
 impl ContentDigest {
+    /// Returns the SHA-256 digest of the given content.
+    pub fn sha256(content: &[u8]) -> Self {
+        Sha256::new_with_prefix(content).into()
+    }
+
     pub async fn sha256_from_file(path: impl AsRef<Path>) -> Result<Self, std::io::Error> {
         let mut file = tokio::fs::File::open(path).await?;
         let mut hasher = Sha256::new();
@@ -123,7 +128,7 @@ mod tests {
     #[tokio::test]
     async fn test_validating_stream() {
         let input = b"input";
-        let digest = ContentDigest::from(Sha256::new_with_prefix(input));
+        let digest = ContentDigest::sha256(input);
         let stream = stream::iter(input.chunks(2));
         let validating = digest.validating_stream(stream.map(|bytes| Ok(bytes.into())));
         assert_eq!(
@@ -132,6 +137,14 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_sha256() {
+        assert_eq!(
+            ContentDigest::sha256(b"").to_string(),
+            "sha256:e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+    }
+
     #[tokio::test]
     async fn test_invalidating_stream() {
         let input = b"input";
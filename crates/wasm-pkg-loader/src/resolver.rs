@@ -0,0 +1,603 @@
+//! Transitive dependency resolution.
+//!
+//! Starting from a set of root requirements (or the foreign package
+//! references of a local WIT package), the [`Resolver`] selects a version of
+//! each package, fetches and decodes its content to find the packages it in
+//! turn references, and repeats until the complete dependency set is known.
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
+    path::Path,
+};
+
+use futures_util::TryStreamExt;
+use semver::{Version, VersionReq};
+use wit_component::DecodedWasm;
+use wit_parser::{PackageName, UnresolvedPackage};
+
+use crate::{
+    source::VersionInfo, Client, DependencyGraph, Error, PackageRef, Release, ResolutionStrategy,
+    YankedPolicy,
+};
+
+/// A package version selected by a [`Resolver`].
+#[derive(Clone, Debug)]
+pub struct ResolvedPackage {
+    pub package: PackageRef,
+    pub version: Version,
+    pub release: Release,
+    /// The packages referenced by this package's WIT, with the requirement
+    /// implied by each reference.
+    pub dependencies: Vec<(PackageRef, VersionReq)>,
+}
+
+/// The complete set of packages required by some root requirements.
+#[derive(Clone, Debug, Default)]
+pub struct DependencySet {
+    packages: BTreeMap<PackageRef, ResolvedPackage>,
+}
+
+impl DependencySet {
+    /// Returns the resolved entry for the given package, if present.
+    pub fn get(&self, package: &PackageRef) -> Option<&ResolvedPackage> {
+        self.packages.get(package)
+    }
+
+    /// Returns an iterator of all resolved packages, sorted by name.
+    pub fn iter(&self) -> impl Iterator<Item = &ResolvedPackage> {
+        self.packages.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.packages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.packages.is_empty()
+    }
+}
+
+/// A version requirement on a package and where it came from.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Requirement {
+    pub req: VersionReq,
+    /// The package version whose WIT references the package, or `None` for
+    /// a root requirement.
+    pub required_by: Option<(PackageRef, Version)>,
+}
+
+impl std::fmt::Display for Requirement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.required_by {
+            Some((package, version)) => write!(f, "{} required by {package}@{version}", self.req),
+            None => write!(f, "{} required by root", self.req),
+        }
+    }
+}
+
+/// A report of requirements on a package that no single version satisfies.
+#[derive(Clone, Debug)]
+pub struct ConflictReport {
+    pub package: PackageRef,
+    pub requirements: Vec<Requirement>,
+    /// All available (non-yanked) versions of the package, ascending.
+    pub available: Vec<Version>,
+}
+
+impl std::fmt::Display for ConflictReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "no version of {} satisfies all requirements:",
+            self.package
+        )?;
+        for requirement in &self.requirements {
+            writeln!(f, "  {requirement}")?;
+        }
+        if self.available.is_empty() {
+            write!(f, "no versions are available")
+        } else {
+            let available: Vec<_> = self.available.iter().map(ToString::to_string).collect();
+            write!(f, "available versions: {}", available.join(", "))
+        }
+    }
+}
+
+/// Options for [`Client::resolve_graph`].
+#[derive(Clone, Debug, Default)]
+pub struct ResolveOptions {
+    /// Overrides the configured [`ResolutionStrategy`].
+    pub strategy: Option<ResolutionStrategy>,
+    /// See [`Resolver::allow_yanked`].
+    pub allow_yanked: bool,
+    /// Versions to prefer, e.g. from a lock file. See
+    /// [`Resolver::prefer_version`].
+    pub preferred: HashMap<PackageRef, Version>,
+}
+
+/// Resolves the transitive dependencies of packages using a [`Client`].
+pub struct Resolver<'a> {
+    client: &'a mut Client,
+    preferred: HashMap<PackageRef, Version>,
+    versions: HashMap<PackageRef, Vec<VersionInfo>>,
+    fetched: HashMap<(PackageRef, Version), FetchedPackage>,
+    strategy: ResolutionStrategy,
+    allow_yanked: bool,
+}
+
+type FetchedPackage = (Release, Vec<(PackageRef, VersionReq)>);
+
+impl<'a> Resolver<'a> {
+    /// Returns a new resolver using the client's configured
+    /// [`ResolutionStrategy`].
+    pub fn new(client: &'a mut Client) -> Self {
+        let strategy = client.resolution_strategy();
+        Self {
+            client,
+            preferred: Default::default(),
+            versions: Default::default(),
+            fetched: Default::default(),
+            strategy,
+            allow_yanked: false,
+        }
+    }
+
+    /// Overrides the configured [`ResolutionStrategy`].
+    pub fn strategy(&mut self, strategy: ResolutionStrategy) -> &mut Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Allows selecting yanked versions, overriding the configured
+    /// [`YankedPolicy`] with [`YankedPolicy::Warn`].
+    pub fn allow_yanked(&mut self, allow: bool) -> &mut Self {
+        self.allow_yanked = allow;
+        self
+    }
+
+    /// Prefers the given version of a package (e.g. one from a lock file)
+    /// as long as it satisfies every requirement on that package. Preferred
+    /// versions are ignored by the [`ResolutionStrategy::Minimal`] strategy.
+    ///
+    /// If a preferred version has since been yanked it is handled according
+    /// to the package's [`YankedPolicy`]: with [`YankedPolicy::Error`] (the
+    /// default) another version satisfying the requirements is selected
+    /// with a warning, otherwise the preferred version is kept.
+    pub fn prefer_version(&mut self, package: PackageRef, version: Version) -> &mut Self {
+        self.preferred.insert(package, version);
+        self
+    }
+
+    /// Resolves the given root requirements and all of their transitive
+    /// dependencies.
+    pub async fn resolve(
+        &mut self,
+        roots: impl IntoIterator<Item = (PackageRef, VersionReq)>,
+    ) -> Result<DependencySet, Error> {
+        let roots: Vec<_> = roots.into_iter().collect();
+        let mut queue: VecDeque<_> = roots
+            .iter()
+            .map(|(package, req)| {
+                let requirement = Requirement {
+                    req: req.clone(),
+                    required_by: None,
+                };
+                (package.clone(), requirement)
+            })
+            .collect();
+        let mut requirements: HashMap<PackageRef, Vec<Requirement>> = HashMap::new();
+        let mut packages: BTreeMap<PackageRef, ResolvedPackage> = BTreeMap::new();
+
+        while let Some((package, requirement)) = queue.pop_front() {
+            // Skip requirements queued by versions that have since been
+            // deselected
+            if let Some((by_package, by_version)) = &requirement.required_by {
+                if packages.get(by_package).map(|resolved| &resolved.version) != Some(by_version) {
+                    continue;
+                }
+            }
+            let reqs = requirements.entry(package.clone()).or_default();
+            if !reqs.contains(&requirement) {
+                reqs.push(requirement);
+            }
+            if let Some(resolved) = packages.get(&package) {
+                let policy = self.client.version_policy(&package);
+                if reqs
+                    .iter()
+                    .all(|r| policy.matches(&r.req, &resolved.version))
+                {
+                    continue;
+                }
+                tracing::debug!(
+                    "{package}@{} no longer satisfies all requirements",
+                    resolved.version
+                );
+            }
+            let reqs = reqs.clone();
+            let version = self.select_version(&package, &reqs).await?;
+            let (release, dependencies) = self.fetch_dependencies(&package, &version).await?;
+            tracing::debug!("Resolved {package}@{version}");
+            if let Some(previous) = packages.remove(&package) {
+                drop_requirements(
+                    &mut requirements,
+                    &mut packages,
+                    &package,
+                    &previous.version,
+                );
+            }
+            queue.extend(dependencies.iter().map(|(dep, req)| {
+                let requirement = Requirement {
+                    req: req.clone(),
+                    required_by: Some((package.clone(), version.clone())),
+                };
+                (dep.clone(), requirement)
+            }));
+            packages.insert(
+                package.clone(),
+                ResolvedPackage {
+                    package,
+                    version,
+                    release,
+                    dependencies,
+                },
+            );
+        }
+
+        // Packages may have been reselected during resolution, orphaning
+        // dependencies of versions that are no longer used.
+        let mut reachable = HashSet::new();
+        let mut stack: Vec<_> = roots.into_iter().map(|(package, _)| package).collect();
+        while let Some(package) = stack.pop() {
+            if reachable.insert(package.clone()) {
+                stack.extend(
+                    packages[&package]
+                        .dependencies
+                        .iter()
+                        .map(|(dep, _)| dep.clone()),
+                );
+            }
+        }
+        packages.retain(|package, _| reachable.contains(package));
+        Ok(DependencySet { packages })
+    }
+
+    /// Like [`Self::resolve`], but returns a [`DependencyGraph`] including
+    /// each package's content digest and source registry.
+    pub async fn resolve_graph(
+        &mut self,
+        roots: impl IntoIterator<Item = (PackageRef, VersionReq)>,
+    ) -> Result<DependencyGraph, Error> {
+        let roots: Vec<_> = roots.into_iter().collect();
+        let deps = self.resolve(roots.iter().cloned()).await?;
+        let root_packages: Vec<_> = roots.into_iter().map(|(package, _)| package).collect();
+        DependencyGraph::new(&deps, &root_packages, |package| {
+            Ok(self
+                .client
+                .package_registry(package)?
+                .map(ToOwned::to_owned))
+        })
+    }
+
+    /// Resolves the foreign package references of the local WIT package at
+    /// the given path (file or directory) and all of their transitive
+    /// dependencies.
+    pub async fn resolve_wit_path(&mut self, path: &Path) -> Result<DependencySet, Error> {
+        let roots = wit_path_dependencies(path)?;
+        self.resolve(roots).await
+    }
+
+    async fn select_version(
+        &mut self,
+        package: &PackageRef,
+        requirements: &[Requirement],
+    ) -> Result<Version, Error> {
+        let mut policy = self.client.version_policy(package);
+        if self.allow_yanked {
+            policy.yanked = YankedPolicy::Warn;
+        }
+        let reqs: Vec<_> = requirements.iter().map(|r| r.req.clone()).collect();
+        if !self.versions.contains_key(package) {
+            let versions = self.client.list_all_versions(package).await?;
+            self.versions.insert(package.clone(), versions);
+        }
+        let versions = &self.versions[package];
+        let preferred = match self.strategy {
+            ResolutionStrategy::Highest => self.preferred.get(package),
+            ResolutionStrategy::Minimal => None,
+        };
+        if let Some(preferred) = preferred {
+            if reqs.iter().all(|req| policy.matches(req, preferred)) {
+                let yanked = versions
+                    .iter()
+                    .any(|info| &info.version == preferred && info.yanked);
+                match (yanked, policy.yanked) {
+                    (false, _) | (true, YankedPolicy::Allow) => return Ok(preferred.clone()),
+                    (true, YankedPolicy::Warn) => {
+                        tracing::warn!("Keeping yanked version {package}@{preferred}");
+                        return Ok(preferred.clone());
+                    }
+                    (true, YankedPolicy::Error) => {
+                        tracing::warn!(
+                            "{package}@{preferred} has been yanked; selecting another version"
+                        );
+                    }
+                }
+            }
+        }
+        policy
+            .resolve_with_strategy(package, versions.iter().cloned(), &reqs, self.strategy)
+            .map_err(|err| match err {
+                Error::NoMatchingVersion(_) if requirements.len() > 1 => {
+                    let mut available: Vec<_> = versions
+                        .iter()
+                        .filter(|info| !info.yanked)
+                        .map(|info| info.version.clone())
+                        .collect();
+                    available.sort();
+                    Error::DependencyConflict(Box::new(ConflictReport {
+                        package: package.clone(),
+                        requirements: requirements.to_vec(),
+                        available,
+                    }))
+                }
+                err => err,
+            })
+    }
+
+    /// Returns the release and dependencies of the given package version,
+    /// fetching its content only the first time it's selected; packages
+    /// shared by several dependents (or reselected) are downloaded once.
+    async fn fetch_dependencies(
+        &mut self,
+        package: &PackageRef,
+        version: &Version,
+    ) -> Result<FetchedPackage, Error> {
+        let key = (package.clone(), version.clone());
+        if let Some(fetched) = self.fetched.get(&key) {
+            return Ok(fetched.clone());
+        }
+        let release = self.client.get_release(package, version).await?;
+        let content: Vec<u8> = self
+            .client
+            .stream_content(package, &release)
+            .await?
+            .map_ok(|chunk| chunk.to_vec())
+            .try_concat()
+            .await?;
+        let decoded = wit_component::decode(&content).map_err(|err| {
+            Error::InvalidContent(format!("error decoding {package}@{version}: {err:#}"))
+        })?;
+        let (resolve, own_package) = match &decoded {
+            DecodedWasm::WitPackage(resolve, pkg) => (resolve, *pkg),
+            DecodedWasm::Component(resolve, world) => {
+                let Some(pkg) = resolve.worlds[*world].package else {
+                    return Err(Error::InvalidContent(format!(
+                        "{package}@{version} world has no package"
+                    )));
+                };
+                (resolve, pkg)
+            }
+        };
+        let dependencies = resolve
+            .packages
+            .iter()
+            .filter(|(id, _)| *id != own_package)
+            .map(|(_, pkg)| package_requirement(&pkg.name))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.fetched
+            .insert(key, (release.clone(), dependencies.clone()));
+        Ok((release, dependencies))
+    }
+}
+
+/// Drops the requirements added by `package@version`, along with any
+/// package left with no requirements and, recursively, its requirements.
+fn drop_requirements(
+    requirements: &mut HashMap<PackageRef, Vec<Requirement>>,
+    packages: &mut BTreeMap<PackageRef, ResolvedPackage>,
+    package: &PackageRef,
+    version: &Version,
+) {
+    let mut orphaned = Vec::new();
+    for (dep, reqs) in requirements.iter_mut() {
+        let len = reqs.len();
+        reqs.retain(|r| !matches!(&r.required_by, Some((p, v)) if p == package && v == version));
+        if reqs.is_empty() && len > 0 {
+            orphaned.push(dep.clone());
+        }
+    }
+    for dep in orphaned {
+        if let Some(resolved) = packages.remove(&dep) {
+            drop_requirements(requirements, packages, &dep, &resolved.version);
+        }
+    }
+}
+
+/// Returns the foreign package references of the local WIT package at the
+/// given path (file or directory).
+pub fn wit_path_dependencies(path: &Path) -> Result<Vec<(PackageRef, VersionReq)>, Error> {
+    let unresolved = UnresolvedPackage::parse_path(path)
+        .map_err(|err| Error::InvalidContent(format!("error parsing WIT at {path:?}: {err:#}")))?;
+    unresolved
+        .foreign_deps
+        .keys()
+        .map(package_requirement)
+        .collect()
+}
+
+/// Returns the package and version requirement for a WIT package reference.
+/// A versioned reference like `wasi:io@0.2.0` is satisfied by any
+/// semver-compatible version.
+fn package_requirement(name: &PackageName) -> Result<(PackageRef, VersionReq), Error> {
+    let package = format!("{}:{}", name.namespace, name.name).parse()?;
+    let req = match &name.version {
+        Some(version) => VersionReq::parse(&format!("^{version}"))?,
+        None => VersionReq::STAR,
+    };
+    Ok((package, req))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ClientConfig, PackageOverride};
+
+    #[test]
+    fn test_conflict_report() {
+        let report = ConflictReport {
+            package: "wasi:io".parse().unwrap(),
+            requirements: vec![
+                Requirement {
+                    req: "^0.2.0".parse().unwrap(),
+                    required_by: None,
+                },
+                Requirement {
+                    req: "^0.3.0".parse().unwrap(),
+                    required_by: Some(("wasi:http".parse().unwrap(), "0.3.1".parse().unwrap())),
+                },
+            ],
+            available: vec!["0.2.0".parse().unwrap(), "0.2.1".parse().unwrap()],
+        };
+        assert_eq!(
+            report.to_string(),
+            "no version of wasi:io satisfies all requirements:\n  \
+             ^0.2.0 required by root\n  \
+             ^0.3.0 required by wasi:http@0.3.1\n\
+             available versions: 0.2.0, 0.2.1"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_resolve_wit_path() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let write = |path: &str, contents: &str| {
+            let path = dir.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        };
+        write(
+            "b/b.wit",
+            "package test:b@0.1.0; interface types { type id = u32; }",
+        );
+        write(
+            "a/a.wit",
+            "package test:a@1.2.0; interface api { use test:b/types@0.1.0.{id}; get: func() -> id; }",
+        );
+        write(
+            "a/deps/b/b.wit",
+            "package test:b@0.1.0; interface types { type id = u32; }",
+        );
+        write(
+            "app/app.wit",
+            "package test:app; world app { import test:a/api@1.2.0; }",
+        );
+
+        let mut config = ClientConfig::default();
+        for name in ["a", "b"] {
+            config.set_package_override(
+                format!("test:{name}").parse().unwrap(),
+                PackageOverride {
+                    path: dir.join(name),
+                    version: None,
+                },
+            );
+        }
+        let mut client = config.to_client();
+        let deps = Resolver::new(&mut client)
+            .resolve_wit_path(&dir.join("app"))
+            .await;
+        let graph = client
+            .resolve_graph(
+                [("test:a".parse().unwrap(), "^1.2.0".parse().unwrap())],
+                &ResolveOptions::default(),
+            )
+            .await;
+        let deps = deps.unwrap();
+        let graph = graph.unwrap();
+
+        let versions: Vec<_> = deps
+            .iter()
+            .map(|resolved| format!("{}@{}", resolved.package, resolved.version))
+            .collect();
+        assert_eq!(versions, ["test:a@1.2.0", "test:b@0.1.0"]);
+        let a = deps.get(&"test:a".parse().unwrap()).unwrap();
+        assert_eq!(a.dependencies.len(), 1);
+        assert_eq!(a.dependencies[0].1.to_string(), "^0.1.0");
+
+        assert_eq!(graph.nodes().len(), 2);
+        assert_eq!(graph.roots(), [0]);
+        assert_eq!(graph.nodes()[0].registry, None);
+        assert_eq!(graph.nodes()[0].content_digest, a.release.content_digest);
+        let b = graph.find(&"test:b".parse().unwrap()).unwrap();
+        let edges: Vec<_> = graph.dependents(b).collect();
+        assert_eq!(edges.len(), 1);
+        assert_eq!(
+            (edges[0].from, edges[0].req.to_string().as_str()),
+            (0, "^0.1.0")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_resolve_reselected_dependent() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let publish = |sources: &[&str]| {
+            let mut resolve = wit_parser::Resolve::default();
+            let mut pkg = None;
+            for source in sources {
+                let unresolved = UnresolvedPackage::parse(Path::new("test.wit"), source).unwrap();
+                pkg = Some(resolve.push(unresolved).unwrap());
+            }
+            let pkg = pkg.unwrap();
+            let name = &resolve.packages[pkg].name;
+            let path = dir
+                .join(&name.namespace)
+                .join(&name.name)
+                .join(format!("{}.wasm", name.version.as_ref().unwrap()));
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            let content = wit_component::encode(Some(true), &resolve, pkg).unwrap();
+            std::fs::write(path, content).unwrap();
+        };
+        let a1 = "package test:a@1.0.0; interface api { type handle = u32; }";
+        let c1 = "package test:c@1.0.0; interface types { type id = u32; }";
+        let c2 = "package test:c@2.0.0; interface types { type id = u32; }";
+        let b2 =
+            "package test:b@2.0.0; interface types { use test:c/types@2.0.0.{id}; type bid = id; }";
+        publish(&[a1]);
+        publish(&[c1]);
+        publish(&[c2]);
+        publish(&[c2, b2]);
+        publish(&[
+            c2,
+            b2,
+            "package test:a@2.0.0; interface api { use test:b/types@2.0.0.{bid}; type handle = bid; }",
+        ]);
+        publish(&[
+            a1,
+            c1,
+            "package test:d@1.0.0; interface api { use test:a/api@1.0.0.{handle}; use test:c/types@1.0.0.{id}; }",
+        ]);
+
+        let mut config = ClientConfig::default();
+        config
+            .set_local_registry_config("local", dir)
+            .set_default_registry("local");
+        let mut client = config.to_client();
+        // test:a@2.0.0 is selected first, requiring test:b ^2 and, through
+        // it, test:c ^2, until test:d's requirement reselects test:a@1.0.0
+        let deps = Resolver::new(&mut client)
+            .resolve([
+                ("test:a".parse().unwrap(), VersionReq::STAR),
+                ("test:d".parse().unwrap(), VersionReq::STAR),
+            ])
+            .await;
+
+        let versions: Vec<_> = deps
+            .unwrap()
+            .iter()
+            .map(|resolved| format!("{}@{}", resolved.package, resolved.version))
+            .collect();
+        assert_eq!(versions, ["test:a@1.0.0", "test:c@1.0.0", "test:d@1.0.0"]);
+    }
+}
@@ -4,16 +4,26 @@ use futures_util::{stream::BoxStream, StreamExt};
 use semver::Version;
 use std::cmp::Ordering;
 
-use crate::{Error, PackageRef, Release};
+use crate::{
+    download::RangedDownload, label::Label, ContentDigest, Deprecation, Error, PackageRef,
+    Provenance, Release,
+};
 
+pub mod github;
 pub mod local;
 pub mod oci;
+pub mod path;
 pub mod warg;
 
 #[derive(Clone, Debug, Eq)]
 pub struct VersionInfo {
     pub version: Version,
     pub yanked: bool,
+    /// The version's deprecation notice, if any, for sources that report
+    /// notices when listing versions. Others leave this unset; notices are
+    /// then only found when fetching a release (see
+    /// [`PackageSource::deprecation`]).
+    pub deprecation: Option<Deprecation>,
 }
 
 impl Ord for VersionInfo {
@@ -24,7 +34,7 @@ impl Ord for VersionInfo {
 
 impl PartialOrd for VersionInfo {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.version.cmp(&other.version))
+        Some(self.cmp(other))
     }
 }
 
@@ -50,18 +60,181 @@ pub trait PackageSource: Send {
         version: &Version,
     ) -> Result<Release, Error>;
 
+    /// Returns a stream of the release's content. Streams don't borrow the
+    /// source, so a fetch throttled by the registry can be retried with it.
     async fn stream_content_unvalidated(
         &mut self,
         package: &PackageRef,
         release: &Release,
-    ) -> Result<BoxStream<Result<Bytes, Error>>, Error>;
+    ) -> Result<BoxStream<'static, Result<Bytes, Error>>, Error>;
 
     async fn stream_content(
         &mut self,
         package: &PackageRef,
         release: &Release,
-    ) -> Result<BoxStream<Result<Bytes, Error>>, Error> {
+    ) -> Result<BoxStream<'static, Result<Bytes, Error>>, Error> {
         let stream = self.stream_content_unvalidated(package, release).await?;
         Ok(release.content_digest.validating_stream(stream).boxed())
     }
+
+    /// Returns whether content with the given digest can be fetched for the
+    /// package directly by its digest (e.g. as an OCI blob), whether or not
+    /// any current version points at it. Sources that can only fetch
+    /// content by version return None.
+    async fn has_content(
+        &mut self,
+        package: &PackageRef,
+        digest: &ContentDigest,
+    ) -> Result<Option<bool>, Error> {
+        let _ = (package, digest);
+        Ok(None)
+    }
+
+    /// Returns a cheaply fetched identifier of the current revision of the
+    /// given release's details (e.g. an OCI manifest digest), used to
+    /// revalidate cached details without fetching them in full. Sources
+    /// without one return None.
+    async fn release_revision(
+        &mut self,
+        package: &PackageRef,
+        version: &Version,
+    ) -> Result<Option<String>, Error> {
+        let _ = (package, version);
+        Ok(None)
+    }
+
+    /// Returns the packages in the given namespace, used for discovery and
+    /// to suggest alternatives to packages that weren't found. Sources that
+    /// can't list packages return None.
+    async fn list_packages(&mut self, namespace: &Label) -> Result<Option<Vec<PackageRef>>, Error> {
+        let _ = namespace;
+        Ok(None)
+    }
+
+    /// Returns the provenance attestations published for the given release.
+    /// Sources that don't support attestations return none.
+    async fn provenance(
+        &mut self,
+        package: &PackageRef,
+        release: &Release,
+    ) -> Result<Vec<Provenance>, Error> {
+        let _ = (package, release);
+        Ok(vec![])
+    }
+
+    /// Returns the deprecation notice attached to the given release, if
+    /// any. Sources that don't support notices return none.
+    async fn deprecation(
+        &mut self,
+        package: &PackageRef,
+        release: &Release,
+    ) -> Result<Option<Deprecation>, Error> {
+        let _ = (package, release);
+        Ok(None)
+    }
+
+    /// Attaches a deprecation notice to the given release, replacing any
+    /// previous one. Sources that don't support notices return
+    /// [`Error::DeprecationUnsupported`].
+    async fn deprecate(
+        &mut self,
+        package: &PackageRef,
+        release: &Release,
+        deprecation: &Deprecation,
+    ) -> Result<(), Error> {
+        let _ = (package, release, deprecation);
+        Err(Error::DeprecationUnsupported(
+            "not implemented for this registry type".into(),
+        ))
+    }
+
+    /// Returns the OpenPGP signatures attached to the given release. Sources
+    /// that don't support signatures return none.
+    async fn openpgp_signatures(
+        &mut self,
+        package: &PackageRef,
+        release: &Release,
+    ) -> Result<Vec<Vec<u8>>, Error> {
+        let _ = (package, release);
+        Ok(vec![])
+    }
+
+    /// Attaches an OpenPGP signature to the given release, alongside any
+    /// previous ones. Sources that don't support signatures return
+    /// [`Error::SignaturesUnsupported`].
+    async fn attach_openpgp_signature(
+        &mut self,
+        package: &PackageRef,
+        release: &Release,
+        signature: &[u8],
+    ) -> Result<(), Error> {
+        let _ = (package, release, signature);
+        Err(Error::SignaturesUnsupported(
+            "not implemented for this registry type".into(),
+        ))
+    }
+
+    /// Returns an HTTP download of the given release's content that may
+    /// support range requests, for downloading large content in parallel.
+    /// Sources without one return None.
+    async fn ranged_download(
+        &mut self,
+        package: &PackageRef,
+        release: &Release,
+    ) -> Result<Option<RangedDownload>, Error> {
+        let _ = (package, release);
+        Ok(None)
+    }
+
+    /// Returns the version the given channel tag (e.g. "stable") points at,
+    /// or None if the tag isn't set. Sources without mutable tags return
+    /// [`Error::TagsUnsupported`].
+    async fn resolve_tag(
+        &mut self,
+        package: &PackageRef,
+        tag: &str,
+    ) -> Result<Option<Version>, Error> {
+        let _ = (package, tag);
+        Err(Error::TagsUnsupported(
+            "not implemented for this registry type".into(),
+        ))
+    }
+
+    /// Points the given channel tag at a release, replacing any previous
+    /// target. Sources without mutable tags return
+    /// [`Error::TagsUnsupported`].
+    async fn set_tag(
+        &mut self,
+        package: &PackageRef,
+        tag: &str,
+        version: &Version,
+    ) -> Result<(), Error> {
+        let _ = (package, tag, version);
+        Err(Error::TagsUnsupported(
+            "not implemented for this registry type".into(),
+        ))
+    }
+
+    /// Permanently deletes the given release. Sources that don't support
+    /// deletion return [`Error::DeleteUnsupported`].
+    async fn delete(&mut self, package: &PackageRef, version: &Version) -> Result<(), Error> {
+        let _ = (package, version);
+        Err(Error::DeleteUnsupported(
+            "not implemented for this registry type".into(),
+        ))
+    }
+
+    /// Publishes the given content as a new release. Sources that don't
+    /// support publishing return [`Error::PublishUnsupported`].
+    async fn publish(
+        &mut self,
+        package: &PackageRef,
+        version: &Version,
+        content: Bytes,
+    ) -> Result<Release, Error> {
+        let _ = (package, version, content);
+        Err(Error::PublishUnsupported(
+            "not implemented for this registry type".into(),
+        ))
+    }
 }
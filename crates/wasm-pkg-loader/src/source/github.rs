@@ -0,0 +1,461 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures_util::{stream::BoxStream, StreamExt, TryStreamExt};
+use reqwest::{
+    header::{self, HeaderMap, HeaderValue},
+    RequestBuilder, StatusCode,
+};
+use secrecy::{ExposeSecret, SecretString};
+use semver::Version;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    download::RangedDownload,
+    source::{PackageSource, VersionInfo},
+    ContentDigest, Deprecation, Error, PackageRef, Release,
+};
+
+const DEFAULT_API_URL: &str = "https://api.github.com";
+
+const API_VERSION: &str = "2022-11-28";
+
+/// Configuration for a registry backed by GitHub release assets.
+///
+/// Templates may reference `{namespace}` and `{name}`, and tag templates
+/// also `{version}`, e.g. `{name}-v{version}` for a repository releasing
+/// several packages.
+#[derive(Clone, Debug)]
+pub struct GitHubConfig {
+    repository_template: String,
+    tag_template: String,
+    asset_template: String,
+    /// The API base URL, for GitHub Enterprise Server.
+    pub api_url: Option<String>,
+    /// A token for accessing private repositories.
+    pub auth_token: Option<SecretString>,
+}
+
+impl GitHubConfig {
+    /// Returns a new config for releases in the given repository, e.g.
+    /// `my-org/{name}`, with `v{version}` tags and `{name}.wasm` assets.
+    pub fn new(repository_template: impl Into<String>) -> Result<Self, Error> {
+        let repository_template = repository_template.into();
+        if repository_template.split('/').count() != 2 {
+            return Err(Error::InvalidConfig(anyhow::anyhow!(
+                "GitHub repository {repository_template:?} must be of the form '<owner>/<repo>'"
+            )));
+        }
+        Ok(Self {
+            repository_template,
+            tag_template: "v{version}".into(),
+            asset_template: "{name}.wasm".into(),
+            api_url: None,
+            auth_token: None,
+        })
+    }
+
+    /// Sets the release tag template, which must contain `{version}`
+    /// exactly once.
+    pub fn set_tag_template(&mut self, tag_template: impl Into<String>) -> Result<(), Error> {
+        let tag_template = tag_template.into();
+        if tag_template.matches("{version}").count() != 1 {
+            return Err(Error::InvalidConfig(anyhow::anyhow!(
+                "GitHub tag template {tag_template:?} must contain '{{version}}' exactly once"
+            )));
+        }
+        self.tag_template = tag_template;
+        Ok(())
+    }
+
+    /// Sets the release asset name template.
+    pub fn set_asset_template(&mut self, asset_template: impl Into<String>) {
+        self.asset_template = asset_template.into();
+    }
+
+    /// Returns the repository template.
+    pub fn repository_template(&self) -> &str {
+        &self.repository_template
+    }
+
+    /// Returns the release tag template.
+    pub fn tag_template(&self) -> &str {
+        &self.tag_template
+    }
+
+    /// Returns the release asset name template.
+    pub fn asset_template(&self) -> &str {
+        &self.asset_template
+    }
+
+    fn repository(&self, package: &PackageRef) -> String {
+        render(&self.repository_template, package)
+    }
+
+    fn tag(&self, package: &PackageRef, version: &Version) -> String {
+        render(&self.tag_template, package).replace("{version}", &version.to_string())
+    }
+
+    /// Parses a version from a release tag, returning `None` if the tag
+    /// doesn't match the tag template.
+    fn parse_tag(&self, package: &PackageRef, tag: &str) -> Option<Version> {
+        let template = render(&self.tag_template, package);
+        let (prefix, suffix) = template.split_once("{version}")?;
+        let version = tag.strip_prefix(prefix)?.strip_suffix(suffix)?;
+        Version::parse(version).ok()
+    }
+
+    fn asset(&self, package: &PackageRef) -> String {
+        render(&self.asset_template, package)
+    }
+}
+
+fn render(template: &str, package: &PackageRef) -> String {
+    template
+        .replace("{namespace}", package.namespace().as_ref())
+        .replace("{name}", package.name().as_ref())
+}
+
+/// A PackageSource for GitHub repository releases.
+///
+/// Versions are parsed from release tags and content is downloaded from a
+/// release asset.
+pub struct GitHubSource {
+    config: GitHubConfig,
+    client: reqwest::Client,
+    /// Asset API URLs of releases returned by `get_release`.
+    assets: HashMap<(PackageRef, Version), String>,
+}
+
+#[derive(Deserialize)]
+struct GitHubRepository {
+    archived: bool,
+}
+
+#[derive(Deserialize)]
+struct GitHubRelease {
+    tag_name: String,
+    draft: bool,
+    assets: Vec<GitHubAsset>,
+}
+
+#[derive(Deserialize)]
+struct GitHubAsset {
+    name: String,
+    url: String,
+    /// The asset's digest, e.g. `sha256:<hex>`, if GitHub has computed one.
+    digest: Option<String>,
+}
+
+impl GitHubSource {
+    pub fn new(config: GitHubConfig, client: reqwest::Client) -> Self {
+        Self {
+            config,
+            client,
+            assets: HashMap::new(),
+        }
+    }
+
+    fn get(&self, url: &str) -> RequestBuilder {
+        let request = self
+            .client
+            .get(url)
+            .header("X-GitHub-Api-Version", API_VERSION);
+        match &self.config.auth_token {
+            Some(token) => request.bearer_auth(token.expose_secret()),
+            None => request,
+        }
+    }
+
+    fn repo_url(&self, package: &PackageRef) -> String {
+        let api_url = self.config.api_url.as_deref().unwrap_or(DEFAULT_API_URL);
+        format!(
+            "{}/repos/{}",
+            api_url.trim_end_matches('/'),
+            self.config.repository(package)
+        )
+    }
+
+    /// Returns a package-wide deprecation notice if the package's repository
+    /// is archived, which is the only deprecation GitHub exposes.
+    async fn repository_deprecation(
+        &self,
+        package: &PackageRef,
+    ) -> Result<Option<Deprecation>, Error> {
+        let url = self.repo_url(package);
+        tracing::debug!("Fetching GitHub repository from {url:?}");
+        let repository: GitHubRepository = self
+            .get(&url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(repository.archived.then(|| Deprecation {
+            message: format!(
+                "the {} repository is archived",
+                self.config.repository(package)
+            ),
+            replacement: None,
+            package: true,
+            end_of_life: None,
+        }))
+    }
+
+    async fn download(&self, url: &str) -> Result<reqwest::Response, Error> {
+        Ok(self
+            .get(url)
+            .header(header::ACCEPT, "application/octet-stream")
+            .send()
+            .await?
+            .error_for_status()?)
+    }
+
+    /// Returns the asset API URL for the given release, fetching the
+    /// release if it hasn't been already.
+    async fn asset_url(&mut self, package: &PackageRef, version: &Version) -> Result<&str, Error> {
+        let key = (package.clone(), version.clone());
+        if !self.assets.contains_key(&key) {
+            self.get_release(package, version).await?;
+        }
+        Ok(&self.assets[&key])
+    }
+}
+
+#[async_trait]
+impl PackageSource for GitHubSource {
+    async fn list_all_versions(&mut self, package: &PackageRef) -> Result<Vec<VersionInfo>, Error> {
+        let deprecation = self.repository_deprecation(package).await?;
+        let url = format!("{}/releases", self.repo_url(package));
+        let mut versions = vec![];
+        for page in 1.. {
+            tracing::debug!("Listing GitHub releases from {url:?} (page {page})");
+            let releases: Vec<GitHubRelease> = self
+                .get(&url)
+                .query(&[("per_page", "100"), ("page", &page.to_string())])
+                .send()
+                .await?
+                .error_for_status()?
+                .json()
+                .await?;
+            if releases.is_empty() {
+                break;
+            }
+            versions.extend(
+                releases
+                    .iter()
+                    .filter(|release| !release.draft)
+                    .filter_map(|release| self.config.parse_tag(package, &release.tag_name))
+                    .map(|version| VersionInfo {
+                        version,
+                        yanked: false,
+                        deprecation: deprecation.clone(),
+                    }),
+            );
+        }
+        Ok(versions)
+    }
+
+    async fn get_release(
+        &mut self,
+        package: &PackageRef,
+        version: &Version,
+    ) -> Result<Release, Error> {
+        let tag = self.config.tag(package, version);
+        let url = format!("{}/releases/tags/{tag}", self.repo_url(package));
+        tracing::debug!("Fetching GitHub release from {url:?}");
+        let resp = self.get(&url).send().await?;
+        if resp.status() == StatusCode::NOT_FOUND {
+            return Err(Error::VersionNotFound(version.clone()));
+        }
+        let release: GitHubRelease = resp.error_for_status()?.json().await?;
+
+        let asset_name = self.config.asset(package);
+        let asset = release
+            .assets
+            .into_iter()
+            .find(|asset| asset.name == asset_name)
+            .ok_or_else(|| {
+                Error::InvalidPackageManifest(format!("release {tag} has no asset {asset_name:?}"))
+            })?;
+        let content_digest: ContentDigest = match asset.digest {
+            Some(digest) => digest.parse()?,
+            None => {
+                // Older assets have no digest; compute one from the content.
+                let content = self.download(&asset.url).await?.bytes().await?;
+                Sha256::new_with_prefix(&content).into()
+            }
+        };
+        self.assets
+            .insert((package.clone(), version.clone()), asset.url);
+        Ok(Release {
+            version: version.clone(),
+            content_digest,
+        })
+    }
+
+    async fn stream_content_unvalidated(
+        &mut self,
+        package: &PackageRef,
+        release: &Release,
+    ) -> Result<BoxStream<'static, Result<Bytes, Error>>, Error> {
+        let url = self.asset_url(package, &release.version).await?.to_string();
+        tracing::debug!("Streaming content from {url:?}");
+        let resp = self.download(&url).await?;
+        Ok(resp.bytes_stream().map_err(Into::into).boxed())
+    }
+
+    async fn deprecation(
+        &mut self,
+        package: &PackageRef,
+        _release: &Release,
+    ) -> Result<Option<Deprecation>, Error> {
+        self.repository_deprecation(package).await
+    }
+
+    async fn ranged_download(
+        &mut self,
+        package: &PackageRef,
+        release: &Release,
+    ) -> Result<Option<RangedDownload>, Error> {
+        let url = self.asset_url(package, &release.version).await?.to_string();
+        // Asset downloads redirect to storage that supports range requests
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::ACCEPT,
+            HeaderValue::from_static("application/octet-stream"),
+        );
+        headers.insert(
+            "X-GitHub-Api-Version",
+            HeaderValue::from_static(API_VERSION),
+        );
+        if let Some(token) = &self.config.auth_token {
+            let mut value = HeaderValue::try_from(format!("Bearer {}", token.expose_secret()))
+                .map_err(|err| {
+                    Error::InvalidConfig(anyhow::anyhow!("invalid auth token: {err}"))
+                })?;
+            value.set_sensitive(true);
+            headers.insert(header::AUTHORIZATION, value);
+        }
+        Ok(Some(RangedDownload {
+            client: self.client.clone(),
+            url,
+            headers,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    use super::*;
+    use crate::VersionPolicy;
+
+    /// Serves canned GitHub API responses for an archived `my-org/my-pkg`
+    /// repository with a single release, returning the API URL.
+    async fn serve_archived_repository() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut request = vec![0; 4096];
+                let len = stream.read(&mut request).await.unwrap();
+                let request = String::from_utf8_lossy(&request[..len]);
+                let path = request.split(' ').nth(1).unwrap_or_default();
+                let body = match path.split_once('?') {
+                    Some(("/repos/my-org/my-pkg/releases", query))
+                        if query.split('&').any(|param| param == "page=1") =>
+                    {
+                        r#"[{"tag_name": "v1.0.0", "draft": false, "assets": []}]"#
+                    }
+                    Some(("/repos/my-org/my-pkg/releases", _)) => "[]",
+                    _ => r#"{"archived": true}"#,
+                };
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        format!("http://{addr}")
+    }
+
+    #[test]
+    fn test_templates() {
+        let package: PackageRef = "my-ns:my-pkg".parse().unwrap();
+        let mut config = GitHubConfig::new("my-org/{name}").unwrap();
+        assert_eq!(config.repository(&package), "my-org/my-pkg");
+        assert_eq!(config.asset(&package), "my-pkg.wasm");
+        assert_eq!(config.tag(&package, &"1.2.3".parse().unwrap()), "v1.2.3");
+        assert_eq!(
+            config.parse_tag(&package, "v1.2.3"),
+            Some("1.2.3".parse().unwrap())
+        );
+        assert_eq!(config.parse_tag(&package, "other"), None);
+
+        config.set_tag_template("{name}-v{version}").unwrap();
+        assert_eq!(
+            config.parse_tag(&package, "my-pkg-v2.0.0-rc.1"),
+            Some("2.0.0-rc.1".parse().unwrap())
+        );
+        assert_eq!(config.parse_tag(&package, "other-pkg-v2.0.0"), None);
+        assert!(config.set_tag_template("latest").is_err());
+        assert!(GitHubConfig::new("my-org").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_archived_repository_deprecation() {
+        let mut config = GitHubConfig::new("my-org/{name}").unwrap();
+        config.api_url = Some(serve_archived_repository().await);
+        let mut source = GitHubSource::new(config, reqwest::Client::new());
+        let package: PackageRef = "my-ns:my-pkg".parse().unwrap();
+        let versions = source.list_all_versions(&package).await.unwrap();
+        let deprecation = versions[0].deprecation.clone().unwrap();
+        assert!(deprecation.package);
+        assert_eq!(
+            deprecation.message,
+            "the my-org/my-pkg repository is archived"
+        );
+
+        // The notice reaches the warning logged when the version is selected
+        #[derive(Clone, Default)]
+        struct Logs(Arc<Mutex<Vec<u8>>>);
+        impl std::io::Write for Logs {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.lock().unwrap().extend_from_slice(buf);
+                Ok(buf.len())
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+        let logs = Logs::default();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer({
+                let logs = logs.clone();
+                move || logs.clone()
+            })
+            .finish();
+        let version = tracing::subscriber::with_default(subscriber, || {
+            VersionPolicy::default().resolve(&package, versions, &["*".parse().unwrap()])
+        })
+        .unwrap();
+        assert_eq!(version, "1.0.0".parse().unwrap());
+        let logs = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+        assert!(
+            logs.contains(
+                "Selecting deprecated version my-ns:my-pkg@1.0.0: the my-org/my-pkg repository is archived"
+            ),
+            "{logs}"
+        );
+    }
+}
@@ -1,31 +1,72 @@
-use std::path::PathBuf;
+use std::{collections::BTreeSet, path::PathBuf, time::SystemTime};
 
 use async_trait::async_trait;
 use bytes::Bytes;
 use futures_util::{stream::BoxStream, StreamExt, TryStreamExt};
 use semver::Version;
+use sha2::{Digest, Sha256};
+use tokio::io::AsyncWriteExt;
 use tokio_util::io::ReaderStream;
 
 use crate::{
+    label::Label,
     source::{PackageSource, VersionInfo},
-    ContentDigest, Error, PackageRef, Release,
+    ContentDigest, Deprecation, Error, PackageRef, Release,
 };
 
+mod tuf;
+
+use tuf::Targets;
+
+/// The signed targets metadata file, relative to the registry root.
+const TARGETS_FILE_NAME: &str = "targets.json";
+
 #[derive(Clone, Debug)]
 pub struct LocalConfig {
     pub root: PathBuf,
+    /// Trusted TUF-style root metadata. If set, version lists and content
+    /// digests are taken from the registry's signed `targets.json` rather
+    /// than trusting the files themselves.
+    pub tuf_root: Option<PathBuf>,
 }
 
 /// A simple local filesystem-based PackageSource.
 ///
-/// Each package release is a file: `<root>/<namespace>/<name>/<version>.wasm`
+/// Each package release is a file: `<root>/<namespace>/<name>/<version>.wasm`,
+/// with any deprecation notice alongside in `<version>.deprecated.json`.
 pub struct LocalSource {
     root: PathBuf,
+    tuf_root: Option<PathBuf>,
+    targets: Option<Targets>,
 }
 
 impl LocalSource {
     pub fn new(config: LocalConfig) -> Self {
-        Self { root: config.root }
+        Self {
+            root: config.root,
+            tuf_root: config.tuf_root,
+            targets: None,
+        }
+    }
+
+    /// Returns the verified targets metadata if this registry is configured
+    /// with a TUF root, loading it on first use.
+    async fn targets(&mut self) -> Result<Option<&Targets>, Error> {
+        let Some(tuf_root) = &self.tuf_root else {
+            return Ok(None);
+        };
+        if self.targets.is_none() {
+            let root = tokio::fs::read(tuf_root).await?;
+            let targets = tokio::fs::read(self.root.join(TARGETS_FILE_NAME)).await?;
+            self.targets = Some(Targets::verify(&root, &targets, SystemTime::now())?);
+        }
+        Ok(self.targets.as_ref())
+    }
+
+    /// Returns the path of the given release relative to the root, as used
+    /// in targets metadata.
+    fn target_path(package: &PackageRef, version: &Version) -> String {
+        format!("{}/{}/{version}.wasm", package.namespace(), package.name())
     }
 
     fn package_dir(&self, package: &PackageRef) -> PathBuf {
@@ -37,11 +78,45 @@ impl LocalSource {
     fn version_path(&self, package: &PackageRef, version: &Version) -> PathBuf {
         self.package_dir(package).join(format!("{version}.wasm"))
     }
+
+    fn deprecation_path(&self, package: &PackageRef, version: &Version) -> PathBuf {
+        self.package_dir(package)
+            .join(format!("{version}.deprecated.json"))
+    }
+
+    async fn read_deprecation(
+        &self,
+        package: &PackageRef,
+        version: &Version,
+    ) -> Result<Option<Deprecation>, Error> {
+        match tokio::fs::read(self.deprecation_path(package, version)).await {
+            Ok(bytes) => Ok(Some(Deprecation::from_json(&bytes)?)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
 }
 
 #[async_trait]
 impl PackageSource for LocalSource {
     async fn list_all_versions(&mut self, package: &PackageRef) -> Result<Vec<VersionInfo>, Error> {
+        if let Some(targets) = self.targets().await? {
+            let prefix = format!("{}/{}/", package.namespace(), package.name());
+            let versions: Vec<_> = targets
+                .paths()
+                .filter_map(|path| path.strip_prefix(&prefix)?.strip_suffix(".wasm"))
+                .filter_map(|version| Version::parse(version).ok())
+                .collect();
+            let mut infos = Vec::with_capacity(versions.len());
+            for version in versions {
+                infos.push(VersionInfo {
+                    deprecation: self.read_deprecation(package, &version).await?,
+                    version,
+                    yanked: false,
+                });
+            }
+            return Ok(infos);
+        }
         let mut versions = vec![];
         let package_dir = self.package_dir(package);
         tracing::debug!("Reading versions from {package_dir:?}");
@@ -61,6 +136,7 @@ impl PackageSource for LocalSource {
                 continue;
             };
             versions.push(VersionInfo {
+                deprecation: self.read_deprecation(package, &version).await?,
                 version,
                 yanked: false,
             });
@@ -68,11 +144,48 @@ impl PackageSource for LocalSource {
         Ok(versions)
     }
 
+    async fn list_packages(&mut self, namespace: &Label) -> Result<Option<Vec<PackageRef>>, Error> {
+        let package_ref = |name: &str| format!("{namespace}:{name}").parse::<PackageRef>().ok();
+        if let Some(targets) = self.targets().await? {
+            let prefix = format!("{namespace}/");
+            let packages: BTreeSet<_> = targets
+                .paths()
+                .filter_map(|path| Some(path.strip_prefix(&prefix)?.split_once('/')?.0))
+                .filter_map(package_ref)
+                .collect();
+            return Ok(Some(packages.into_iter().collect()));
+        }
+        let namespace_dir = self.root.join(namespace.as_ref());
+        tracing::debug!("Reading packages from {namespace_dir:?}");
+        let mut entries = match tokio::fs::read_dir(namespace_dir).await {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Some(vec![])),
+            Err(err) => return Err(err.into()),
+        };
+        let mut packages = vec![];
+        while let Some(entry) = entries.next_entry().await? {
+            if !entry.file_type().await?.is_dir() {
+                continue;
+            }
+            packages.extend(entry.file_name().to_str().and_then(package_ref));
+        }
+        Ok(Some(packages))
+    }
+
     async fn get_release(
         &mut self,
         package: &PackageRef,
         version: &Version,
     ) -> Result<Release, Error> {
+        if let Some(targets) = self.targets().await? {
+            let content_digest = targets
+                .digest(&Self::target_path(package, version))
+                .ok_or_else(|| Error::VersionNotFound(version.clone()))?;
+            return Ok(Release {
+                version: version.clone(),
+                content_digest: content_digest.clone(),
+            });
+        }
         let path = self.version_path(package, version);
         tracing::debug!("Reading content from {path:?}");
         let content_digest = ContentDigest::sha256_from_file(path).await?;
@@ -86,10 +199,218 @@ impl PackageSource for LocalSource {
         &mut self,
         package: &PackageRef,
         content: &Release,
-    ) -> Result<BoxStream<Result<Bytes, Error>>, Error> {
+    ) -> Result<BoxStream<'static, Result<Bytes, Error>>, Error> {
+        if let Some(targets) = self.targets().await? {
+            // Content is validated against the release digest, so it must be
+            // the signed one.
+            let target_path = Self::target_path(package, &content.version);
+            if targets.digest(&target_path) != Some(&content.content_digest) {
+                return Err(Error::InvalidContent(format!(
+                    "digest {} doesn't match signed targets metadata for {target_path:?}",
+                    content.content_digest
+                )));
+            }
+        }
         let path = self.version_path(package, &content.version);
         tracing::debug!("Streaming content from {path:?}");
         let file = tokio::fs::File::open(path).await?;
         Ok(ReaderStream::new(file).map_err(Into::into).boxed())
     }
+
+    async fn deprecation(
+        &mut self,
+        package: &PackageRef,
+        release: &Release,
+    ) -> Result<Option<Deprecation>, Error> {
+        self.read_deprecation(package, &release.version).await
+    }
+
+    async fn deprecate(
+        &mut self,
+        package: &PackageRef,
+        release: &Release,
+        deprecation: &Deprecation,
+    ) -> Result<(), Error> {
+        if self.tuf_root.is_some() {
+            return Err(Error::DeprecationUnsupported(
+                "local registries with signed targets metadata must be updated by their owner"
+                    .into(),
+            ));
+        }
+        if !self.version_path(package, &release.version).exists() {
+            return Err(Error::VersionNotFound(release.version.clone()));
+        }
+        let path = self.deprecation_path(package, &release.version);
+        tracing::debug!("Writing deprecation notice to {path:?}");
+        tokio::fs::write(path, deprecation.to_json()).await?;
+        Ok(())
+    }
+
+    async fn delete(&mut self, package: &PackageRef, version: &Version) -> Result<(), Error> {
+        if self.tuf_root.is_some() {
+            return Err(Error::DeleteUnsupported(
+                "local registries with signed targets metadata must be updated by their owner"
+                    .into(),
+            ));
+        }
+        match tokio::fs::remove_file(self.version_path(package, version)).await {
+            Ok(()) => (),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                return Err(Error::VersionNotFound(version.clone()));
+            }
+            Err(err) => return Err(err.into()),
+        }
+        match tokio::fs::remove_file(self.deprecation_path(package, version)).await {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err.into()),
+            _ => Ok(()),
+        }
+    }
+
+    async fn publish(
+        &mut self,
+        package: &PackageRef,
+        version: &Version,
+        content: Bytes,
+    ) -> Result<Release, Error> {
+        if self.tuf_root.is_some() {
+            // Releases must be added to the signed targets metadata, which
+            // requires the targets key.
+            return Err(Error::PublishUnsupported(
+                "local registries with signed targets metadata must be updated by their owner"
+                    .into(),
+            ));
+        }
+        tokio::fs::create_dir_all(self.package_dir(package)).await?;
+        let path = self.version_path(package, version);
+        tracing::debug!("Publishing content to {path:?}");
+        let mut file = match tokio::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .await
+        {
+            Ok(file) => file,
+            Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => {
+                return Err(Error::VersionExists(version.clone()));
+            }
+            Err(err) => return Err(err.into()),
+        };
+        if let Err(err) = async {
+            file.write_all(&content).await?;
+            file.flush().await
+        }
+        .await
+        {
+            // Don't leave a partial release behind
+            let _ = tokio::fs::remove_file(&path).await;
+            return Err(err.into());
+        }
+        Ok(Release {
+            version: version.clone(),
+            content_digest: Sha256::new_with_prefix(&content).into(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_publish() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        let mut source = LocalSource::new(LocalConfig {
+            root: root.to_path_buf(),
+            tuf_root: None,
+        });
+        let package: PackageRef = "test:pkg".parse().unwrap();
+        let version: Version = "1.0.0".parse().unwrap();
+
+        let release = source
+            .publish(&package, &version, Bytes::from_static(b"content"))
+            .await
+            .unwrap();
+        assert_eq!(
+            source.get_release(&package, &version).await.unwrap(),
+            release
+        );
+        let versions = source.list_all_versions(&package).await.unwrap();
+        assert_eq!(versions.len(), 1);
+        assert_eq!(versions[0].version, version);
+        assert!(matches!(
+            source
+                .publish(&package, &version, Bytes::from_static(b"other"))
+                .await,
+            Err(Error::VersionExists(_))
+        ));
+
+        assert_eq!(source.deprecation(&package, &release).await.unwrap(), None);
+        let deprecation = Deprecation {
+            message: "Broken".into(),
+            replacement: Some("test:pkg@1.0.1".into()),
+            package: false,
+            end_of_life: None,
+        };
+        source
+            .deprecate(&package, &release, &deprecation)
+            .await
+            .unwrap();
+        assert_eq!(
+            source.deprecation(&package, &release).await.unwrap(),
+            Some(deprecation.clone())
+        );
+        let versions = source.list_all_versions(&package).await.unwrap();
+        assert_eq!(versions.len(), 1);
+        assert_eq!(versions[0].deprecation, Some(deprecation));
+
+        source.delete(&package, &version).await.unwrap();
+        assert!(source.list_all_versions(&package).await.unwrap().is_empty());
+        assert!(matches!(
+            source.delete(&package, &version).await,
+            Err(Error::VersionNotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_release_by_digest() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        let mut config = crate::ClientConfig::default();
+        config
+            .set_local_registry_config("local", root)
+            .set_default_registry("local")
+            .set_no_cache(true);
+        let mut client = config.to_client();
+        let package: PackageRef = "test:pkg".parse().unwrap();
+        let old = client
+            .publish(
+                &package,
+                &"1.0.0".parse().unwrap(),
+                Bytes::from_static(b"old"),
+            )
+            .await
+            .unwrap();
+        client
+            .publish(
+                &package,
+                &"1.1.0".parse().unwrap(),
+                Bytes::from_static(b"new"),
+            )
+            .await
+            .unwrap();
+        // A newer version whose details can't be read is skipped
+        std::fs::create_dir(root.join("test/pkg/2.0.0.wasm")).unwrap();
+
+        let release = client
+            .get_release_by_digest(&package, &old.content_digest)
+            .await
+            .unwrap();
+        assert_eq!(release, old);
+        let missing = ContentDigest::sha256(b"missing");
+        assert!(matches!(
+            client.get_release_by_digest(&package, &missing).await,
+            Err(Error::DigestNotFound(..))
+        ));
+    }
 }
@@ -0,0 +1,374 @@
+//! TUF-style signed metadata for static registries, authenticating version
+//! lists and content digests independently of the file host serving them.
+//!
+//! A trusted root metadata file, distributed out of band and referenced by
+//! the registry config, lists the public keys allowed to sign each role.
+//! The registry serves `targets.json`, which maps each release file path
+//! (`<namespace>/<name>/<version>.wasm`) to its SHA-256 digest; other
+//! target fields (e.g. `length`) are ignored.
+//!
+//! Both are envelopes of the form:
+//!
+//! ```json
+//! {
+//!   "signed": "<base64 payload>",
+//!   "signatures": [{"keyid": "<key ID>", "sig": "ecdsa-p256:<base64>"}]
+//! }
+//! ```
+//!
+//! Signatures are over the decoded payload bytes, avoiding the need for
+//! canonical JSON. Root payloads look like:
+//!
+//! ```json
+//! {
+//!   "_type": "root",
+//!   "version": 1,
+//!   "expires": "2030-01-01T00:00:00Z",
+//!   "keys": {"<key ID>": "ecdsa-p256:<base64 public key>"},
+//!   "roles": {
+//!     "root": {"keyids": ["<key ID>"], "threshold": 1},
+//!     "targets": {"keyids": ["<key ID>"], "threshold": 1}
+//!   }
+//! }
+//! ```
+//!
+//! and targets payloads:
+//!
+//! ```json
+//! {
+//!   "_type": "targets",
+//!   "version": 7,
+//!   "expires": "2030-01-01T00:00:00Z",
+//!   "targets": {
+//!     "wasi/io/0.2.0.wasm": {"length": 1234, "hashes": {"sha256": "<hex>"}}
+//!   }
+//! }
+//! ```
+
+use std::{
+    collections::{HashMap, HashSet},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, bail, ensure, Context};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{de::DeserializeOwned, Deserialize};
+use warg_crypto::signing::{PublicKey, Signature};
+
+use crate::{ContentDigest, Error};
+
+/// Verified targets metadata.
+#[derive(Debug)]
+pub struct Targets {
+    targets: HashMap<String, ContentDigest>,
+}
+
+impl Targets {
+    /// Verifies the given root and targets metadata files' contents as of
+    /// the given time.
+    pub fn verify(root: &[u8], targets: &[u8], now: SystemTime) -> Result<Self, Error> {
+        Self::verify_inner(root, targets, now).map_err(Error::RegistryMeta)
+    }
+
+    fn verify_inner(root: &[u8], targets: &[u8], now: SystemTime) -> anyhow::Result<Self> {
+        let root_envelope: Envelope =
+            serde_json::from_slice(root).context("invalid root metadata")?;
+        // The root is trusted out of band, but its role keys must still
+        // have signed it.
+        let root_payload: RootPayload = root_envelope.payload()?;
+        ensure!(
+            root_payload.kind == "root",
+            "root metadata has type {:?}",
+            root_payload.kind
+        );
+        check_expires("root", &root_payload.expires, now)?;
+        let keys = root_payload
+            .keys
+            .iter()
+            .map(|(id, key)| {
+                let key: PublicKey = key
+                    .parse()
+                    .with_context(|| format!("invalid public key {id:?} in root metadata"))?;
+                Ok((id.as_str(), key))
+            })
+            .collect::<anyhow::Result<HashMap<_, _>>>()?;
+        let role = |name: &str| {
+            root_payload
+                .roles
+                .get(name)
+                .ok_or_else(|| anyhow!("root metadata has no {name:?} role"))
+        };
+        root_envelope
+            .verify(role("root")?, &keys)
+            .context("root metadata")?;
+
+        let targets_envelope: Envelope =
+            serde_json::from_slice(targets).context("invalid targets metadata")?;
+        targets_envelope
+            .verify(role("targets")?, &keys)
+            .context("targets metadata")?;
+        let targets_payload: TargetsPayload = targets_envelope.payload()?;
+        ensure!(
+            targets_payload.kind == "targets",
+            "targets metadata has type {:?}",
+            targets_payload.kind
+        );
+        check_expires("targets", &targets_payload.expires, now)?;
+        tracing::debug!(
+            root_version = root_payload.version,
+            targets_version = targets_payload.version,
+            "Verified TUF targets metadata"
+        );
+
+        let targets = targets_payload
+            .targets
+            .into_iter()
+            .map(|(path, target)| {
+                let digest = format!("sha256:{}", target.hashes.sha256)
+                    .parse()
+                    .with_context(|| format!("invalid digest for target {path:?}"))?;
+                Ok((path, digest))
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Ok(Self { targets })
+    }
+
+    /// Returns the digest of the target at the given path.
+    pub fn digest(&self, path: &str) -> Option<&ContentDigest> {
+        self.targets.get(path)
+    }
+
+    /// Returns the paths of all targets.
+    pub fn paths(&self) -> impl Iterator<Item = &str> {
+        self.targets.keys().map(String::as_str)
+    }
+}
+
+#[derive(Deserialize)]
+struct Envelope {
+    signed: String,
+    signatures: Vec<EnvelopeSignature>,
+}
+
+#[derive(Deserialize)]
+struct EnvelopeSignature {
+    keyid: String,
+    sig: String,
+}
+
+impl Envelope {
+    fn payload_bytes(&self) -> anyhow::Result<Vec<u8>> {
+        STANDARD
+            .decode(&self.signed)
+            .context("invalid base64 payload")
+    }
+
+    fn payload<T: DeserializeOwned>(&self) -> anyhow::Result<T> {
+        serde_json::from_slice(&self.payload_bytes()?).context("invalid payload")
+    }
+
+    /// Verifies that at least the role's threshold of its keys signed this
+    /// envelope.
+    fn verify(&self, role: &Role, keys: &HashMap<&str, PublicKey>) -> anyhow::Result<()> {
+        ensure!(role.threshold > 0, "role threshold must be at least 1");
+        let payload = self.payload_bytes()?;
+        let mut valid = HashSet::new();
+        for signature in &self.signatures {
+            if !role.keyids.contains(&signature.keyid) {
+                continue;
+            }
+            let Some(key) = keys.get(signature.keyid.as_str()) else {
+                continue;
+            };
+            let Ok(sig) = signature.sig.parse::<Signature>() else {
+                tracing::debug!(keyid = %signature.keyid, "Ignoring malformed signature");
+                continue;
+            };
+            if key.verify(&payload, &sig).is_ok() {
+                valid.insert(signature.keyid.as_str());
+            }
+        }
+        if valid.len() < role.threshold {
+            bail!(
+                "{} valid signature(s); {} required",
+                valid.len(),
+                role.threshold
+            );
+        }
+        Ok(())
+    }
+}
+
+#[derive(Deserialize)]
+struct Role {
+    keyids: Vec<String>,
+    threshold: usize,
+}
+
+#[derive(Deserialize)]
+struct RootPayload {
+    #[serde(rename = "_type")]
+    kind: String,
+    version: u64,
+    expires: String,
+    keys: HashMap<String, String>,
+    roles: HashMap<String, Role>,
+}
+
+#[derive(Deserialize)]
+struct TargetsPayload {
+    #[serde(rename = "_type")]
+    kind: String,
+    version: u64,
+    expires: String,
+    targets: HashMap<String, Target>,
+}
+
+#[derive(Deserialize)]
+struct Target {
+    hashes: TargetHashes,
+}
+
+#[derive(Deserialize)]
+struct TargetHashes {
+    sha256: String,
+}
+
+fn check_expires(role: &str, expires: &str, now: SystemTime) -> anyhow::Result<()> {
+    let expires_secs = parse_utc_timestamp(expires)
+        .with_context(|| format!("invalid {role} expiration {expires:?}"))?;
+    let now_secs = now.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    ensure!(
+        now_secs < expires_secs,
+        "{role} metadata expired at {expires}"
+    );
+    Ok(())
+}
+
+/// Parses a `YYYY-MM-DDTHH:MM:SSZ` timestamp into seconds since the Unix
+/// epoch.
+fn parse_utc_timestamp(s: &str) -> anyhow::Result<u64> {
+    let parse = |range: std::ops::Range<usize>| -> anyhow::Result<i64> {
+        let digits = s.get(range).context("too short")?;
+        ensure!(digits.bytes().all(|b| b.is_ascii_digit()), "not a number");
+        Ok(digits.parse()?)
+    };
+    ensure!(
+        s.len() == 20
+            && &s[4..5] == "-"
+            && &s[7..8] == "-"
+            && &s[10..11] == "T"
+            && s.ends_with('Z'),
+        "expected YYYY-MM-DDTHH:MM:SSZ"
+    );
+    let (year, month, day) = (parse(0..4)?, parse(5..7)?, parse(8..10)?);
+    let (hour, minute, second) = (parse(11..13)?, parse(14..16)?, parse(17..19)?);
+    ensure!(
+        (1..=12).contains(&month) && (1..=31).contains(&day),
+        "invalid date"
+    );
+    ensure!(hour < 24 && minute < 60 && second < 60, "invalid time");
+    // Days since the epoch from a civil date; see
+    // https://howardhinnant.github.io/date_algorithms.html#days_from_civil
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146097 + doe - 719468;
+    let secs = days * 86400 + hour * 3600 + minute * 60 + second;
+    u64::try_from(secs).context("before the Unix epoch")
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use warg_crypto::signing::PrivateKey;
+
+    use super::*;
+
+    const HEX: &str = "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b";
+
+    fn envelope(payload: serde_json::Value, signers: &[(&str, &PrivateKey)]) -> Vec<u8> {
+        let payload = serde_json::to_vec(&payload).unwrap();
+        let signatures: Vec<_> = signers
+            .iter()
+            .map(|(keyid, key)| json!({"keyid": keyid, "sig": key.sign(&payload).unwrap().to_string()}))
+            .collect();
+        serde_json::to_vec(&json!({
+            "signed": STANDARD.encode(&payload),
+            "signatures": signatures,
+        }))
+        .unwrap()
+    }
+
+    fn key(seed: u8) -> PrivateKey {
+        PrivateKey::decode(format!("ecdsa-p256:{}", STANDARD.encode([seed; 32]))).unwrap()
+    }
+
+    #[test]
+    fn test_verify() {
+        let (root_key, targets_key, other_key) = (key(1), key(2), key(3));
+        let root = envelope(
+            json!({
+                "_type": "root",
+                "version": 1,
+                "expires": "2100-01-01T00:00:00Z",
+                "keys": {
+                    "root": root_key.public_key().to_string(),
+                    "targets": targets_key.public_key().to_string(),
+                },
+                "roles": {
+                    "root": {"keyids": ["root"], "threshold": 1},
+                    "targets": {"keyids": ["targets"], "threshold": 1},
+                },
+            }),
+            &[("root", &root_key)],
+        );
+        let targets_payload = |expires: &str| {
+            json!({
+                "_type": "targets",
+                "version": 1,
+                "expires": expires,
+                "targets": {
+                    "test/pkg/1.0.0.wasm": {"length": 4, "hashes": {"sha256": HEX}},
+                },
+            })
+        };
+        let now = SystemTime::now();
+
+        let targets = envelope(
+            targets_payload("2100-01-01T00:00:00Z"),
+            &[("targets", &targets_key)],
+        );
+        let verified = Targets::verify(&root, &targets, now).unwrap();
+        assert_eq!(
+            verified.digest("test/pkg/1.0.0.wasm"),
+            Some(&ContentDigest::Sha256 { hex: HEX.into() })
+        );
+
+        // Signed by a key not trusted for the targets role
+        let untrusted = envelope(
+            targets_payload("2100-01-01T00:00:00Z"),
+            &[("targets", &other_key), ("root", &root_key)],
+        );
+        assert!(Targets::verify(&root, &untrusted, now).is_err());
+
+        let expired = envelope(
+            targets_payload("2000-01-01T00:00:00Z"),
+            &[("targets", &targets_key)],
+        );
+        assert!(Targets::verify(&root, &expired, now).is_err());
+    }
+
+    #[test]
+    fn test_parse_utc_timestamp() {
+        assert_eq!(parse_utc_timestamp("1970-01-01T00:00:00Z").unwrap(), 0);
+        assert_eq!(
+            parse_utc_timestamp("2024-06-15T12:30:45Z").unwrap(),
+            1_718_454_645
+        );
+        assert!(parse_utc_timestamp("2024-06-15 12:30:45").is_err());
+    }
+}
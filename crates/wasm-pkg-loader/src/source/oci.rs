@@ -1,29 +1,215 @@
+use std::{
+    collections::HashSet,
+    time::{Duration, SystemTime},
+};
+
+use anyhow::Context;
 use async_trait::async_trait;
 use bytes::Bytes;
 use docker_credential::{CredentialRetrievalError, DockerCredential};
 use futures_util::{stream::BoxStream, StreamExt, TryStreamExt};
 use oci_distribution::{
-    client::ClientConfig, manifest::OciDescriptor, secrets::RegistryAuth, Reference,
+    client::{ClientConfig, Config, ImageLayer},
+    manifest::{
+        OciDescriptor, OciImageManifest, IMAGE_MANIFEST_MEDIA_TYPE, OCI_IMAGE_INDEX_MEDIA_TYPE,
+        OCI_IMAGE_MEDIA_TYPE,
+    },
+    secrets::RegistryAuth,
+    Reference,
 };
 use secrecy::ExposeSecret;
 use semver::Version;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
 
 use crate::{
     config::BasicCredentials,
+    label::Label,
     meta::RegistryMeta,
     source::{PackageSource, VersionInfo},
-    Error, PackageRef, Release,
+    ContentDigest, Deprecation, Error, PackageRef, Provenance, Release,
 };
 
+mod acr;
+mod api;
+mod ecr;
+mod gcp;
+mod ghcr;
+
+use ecr::EcrRegistry;
+
 const WASM_LAYER_MEDIA_TYPES: &[&str] = &[
     "application/wasm",
     "application/vnd.wasm.content.layer.v1+wasm",
 ];
 
+/// Layer media types of packages released as gzipped tar archives (e.g. WIT
+/// source trees).
+const ARCHIVE_LAYER_MEDIA_TYPES: &[&str] = &[
+    "application/vnd.oci.image.layer.v1.tar+gzip",
+    "application/gzip",
+];
+
+/// The config media type of published package artifacts.
+const WASM_CONFIG_MEDIA_TYPE: &str = "application/vnd.wasm.config.v0+json";
+
+/// How long before expiry short-lived registry tokens are refreshed.
+const TOKEN_REFRESH_MARGIN: Duration = Duration::from_secs(5 * 60);
+
+/// The page size used to list tags when [`OciCompat::paginate_tags`] is set.
+const TAG_PAGE_SIZE: usize = 100;
+
 #[derive(Default)]
 pub struct OciConfig {
     pub client_config: ClientConfig,
     pub credentials: Option<BasicCredentials>,
+    pub layout: OciLayout,
+    /// Workarounds for registries that deviate from the distribution spec.
+    /// If unset, these are detected from the registry name.
+    pub compat: Option<OciCompat>,
+}
+
+/// Workarounds for OCI registries that deviate from the distribution spec.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OciCompat {
+    /// List tags a page at a time, for registries that truncate unpaginated
+    /// tag listings.
+    pub paginate_tags: bool,
+    /// Fetch manifest digests with GET rather than HEAD requests, for
+    /// registries whose HEAD responses are missing or wrong.
+    pub avoid_head: bool,
+    /// Accept manifests with nonstandard media types.
+    pub lenient_manifests: bool,
+}
+
+impl OciCompat {
+    /// Returns the workarounds for the named registry implementation:
+    /// "artifactory", "nexus", "harbor", or "none".
+    pub fn preset(name: &str) -> Option<Self> {
+        match name {
+            "artifactory" => Some(Self {
+                paginate_tags: true,
+                avoid_head: false,
+                lenient_manifests: true,
+            }),
+            "nexus" => Some(Self {
+                paginate_tags: false,
+                avoid_head: true,
+                lenient_manifests: true,
+            }),
+            "harbor" => Some(Self {
+                paginate_tags: true,
+                avoid_head: false,
+                lenient_manifests: false,
+            }),
+            "none" => Some(Self::default()),
+            _ => None,
+        }
+    }
+
+    /// Guesses the workarounds needed by the given registry from its name,
+    /// e.g. `example.jfrog.io` needs the "artifactory" workarounds.
+    pub fn detect(registry: &str) -> Self {
+        let host = registry.to_ascii_lowercase();
+        let preset = if host.contains("jfrog.io") || host.contains("artifactory") {
+            "artifactory"
+        } else if host.contains("nexus") {
+            "nexus"
+        } else if host.contains("harbor") {
+            "harbor"
+        } else {
+            "none"
+        };
+        Self::preset(preset).unwrap()
+    }
+}
+
+/// Controls how a [`PackageRef`] and [`Version`] map to an OCI repository
+/// and tag.
+///
+/// Templates may reference `{namespace}` and `{name}` (repository) and
+/// `{version}` (tag).
+#[derive(Clone, Debug, PartialEq)]
+pub struct OciLayout {
+    repository_template: String,
+    tag_template: String,
+}
+
+impl OciLayout {
+    /// Returns a new layout from the given repository and tag templates.
+    pub fn new(
+        repository_template: impl Into<String>,
+        tag_template: impl Into<String>,
+    ) -> Result<Self, Error> {
+        let repository_template = repository_template.into();
+        let tag_template = tag_template.into();
+        if !repository_template.contains("{name}") {
+            return Err(Error::InvalidConfig(anyhow::anyhow!(
+                "OCI repository template {repository_template:?} must contain '{{name}}'"
+            )));
+        }
+        if tag_template.matches("{version}").count() != 1 {
+            return Err(Error::InvalidConfig(anyhow::anyhow!(
+                "OCI tag template {tag_template:?} must contain '{{version}}' exactly once"
+            )));
+        }
+        Ok(Self {
+            repository_template,
+            tag_template,
+        })
+    }
+
+    /// Returns the repository template.
+    pub fn repository_template(&self) -> &str {
+        &self.repository_template
+    }
+
+    /// Returns the tag template.
+    pub fn tag_template(&self) -> &str {
+        &self.tag_template
+    }
+
+    /// Returns the OCI repository (without any registry namespace prefix)
+    /// for the given package.
+    pub fn repository(&self, package: &PackageRef) -> String {
+        self.repository_template
+            .replace("{namespace}", package.namespace().as_ref())
+            .replace("{name}", package.name().as_ref())
+    }
+
+    /// Returns the package name for the given OCI repository (without any
+    /// registry namespace prefix), or `None` if the repository doesn't match
+    /// this layout for the namespace.
+    pub fn parse_repository(&self, namespace: &Label, repository: &str) -> Option<String> {
+        let template = self
+            .repository_template
+            .replace("{namespace}", namespace.as_ref());
+        let (prefix, suffix) = template.split_once("{name}")?;
+        let name = repository.strip_prefix(prefix)?.strip_suffix(suffix)?;
+        (!name.is_empty() && !name.contains('/')).then(|| name.to_string())
+    }
+
+    /// Returns the OCI tag for the given version.
+    pub fn tag(&self, version: &Version) -> String {
+        self.tag_template.replace("{version}", &version.to_string())
+    }
+
+    /// Parses a version from an OCI tag, returning `None` if the tag does not
+    /// match this layout's tag template.
+    pub fn parse_tag(&self, tag: &str) -> Option<Result<Version, semver::Error>> {
+        let (prefix, suffix) = self.tag_template.split_once("{version}")?;
+        let version = tag.strip_prefix(prefix)?.strip_suffix(suffix)?;
+        Some(Version::parse(version))
+    }
+}
+
+impl Default for OciLayout {
+    fn default() -> Self {
+        Self {
+            repository_template: "{namespace}/{name}".into(),
+            tag_template: "{version}".into(),
+        }
+    }
 }
 
 impl Clone for OciConfig {
@@ -37,6 +223,8 @@ impl Clone for OciConfig {
         Self {
             client_config,
             credentials: self.credentials.clone(),
+            layout: self.layout.clone(),
+            compat: self.compat.clone(),
         }
     }
 }
@@ -46,6 +234,8 @@ impl std::fmt::Debug for OciConfig {
         f.debug_struct("OciConfig")
             .field("client_config", &"...")
             .field("credentials", &self.credentials)
+            .field("layout", &self.layout)
+            .field("compat", &self.compat)
             .finish()
     }
 }
@@ -55,7 +245,35 @@ pub struct OciSource {
     oci_registry: String,
     namespace_prefix: Option<String>,
     credentials: Option<BasicCredentials>,
-    registry_auth: Option<RegistryAuth>,
+    layout: OciLayout,
+    compat: OciCompat,
+    registry_auth: Option<Credentials>,
+    /// The client for requests outside the OCI API, e.g. token exchange.
+    http_client: reqwest::Client,
+    /// The protocol of `client`, for requests made with `http_client`.
+    protocol: oci_distribution::client::ClientProtocol,
+}
+
+/// Registry credentials, which may be a short-lived token.
+struct Credentials {
+    auth: RegistryAuth,
+    expires_at: Option<SystemTime>,
+}
+
+impl Credentials {
+    fn is_expired(&self) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| SystemTime::now() + TOKEN_REFRESH_MARGIN >= expires_at)
+    }
+}
+
+impl From<RegistryAuth> for Credentials {
+    fn from(auth: RegistryAuth) -> Self {
+        Self {
+            auth,
+            expires_at: None,
+        }
+    }
 }
 
 impl OciSource {
@@ -63,27 +281,47 @@ impl OciSource {
         registry: String,
         config: OciConfig,
         registry_meta: RegistryMeta,
+        http_client: reqwest::Client,
     ) -> Result<Self, Error> {
         let OciConfig {
             client_config,
             credentials,
+            layout,
+            compat,
         } = config;
+        let protocol = client_config.protocol.clone();
         let client = oci_distribution::Client::new(client_config);
 
         let oci_registry = registry_meta.oci_registry.unwrap_or(registry);
+        let compat = compat.unwrap_or_else(|| OciCompat::detect(&oci_registry));
+        tracing::debug!(
+            ?compat,
+            "OCI compatibility workarounds for {oci_registry:?}"
+        );
 
         Ok(Self {
             client,
             oci_registry,
             namespace_prefix: registry_meta.oci_namespace_prefix,
             credentials,
+            layout,
+            compat,
             registry_auth: None,
+            http_client,
+            protocol,
         })
     }
 
     async fn auth(&mut self, reference: &Reference) -> Result<RegistryAuth, Error> {
-        if self.registry_auth.is_none() {
-            let mut auth = self.get_credentials()?;
+        if self
+            .registry_auth
+            .as_ref()
+            .is_none_or(Credentials::is_expired)
+        {
+            let Credentials {
+                mut auth,
+                expires_at,
+            } = self.get_credentials().await?;
             // Preflight auth to check for validity; this isn't wasted
             // effort because the oci_distribution::Client caches it
             use oci_distribution::errors::OciDistributionError::AuthenticationFailure;
@@ -105,23 +343,48 @@ impl OciSource {
                 }
                 Err(err) => return Err(err.into()),
             }
-            self.registry_auth = Some(auth);
+            self.registry_auth = Some(Credentials { auth, expires_at });
         }
-        Ok(self.registry_auth.clone().unwrap())
+        Ok(self.registry_auth.as_ref().unwrap().auth.clone())
     }
 
-    fn get_credentials(&self) -> Result<RegistryAuth, Error> {
+    async fn get_credentials(&self) -> Result<Credentials, Error> {
         if let Some(BasicCredentials { username, password }) = &self.credentials {
-            return Ok(RegistryAuth::Basic(
-                username.clone(),
-                password.expose_secret().clone(),
-            ));
+            return Ok(
+                RegistryAuth::Basic(username.clone(), password.expose_secret().clone()).into(),
+            );
+        }
+
+        // Cloud registries are authenticated with short-lived tokens
+        // exchanged for ambient credentials
+        let cloud_credentials = if let Some(ecr) = EcrRegistry::parse(&self.oci_registry) {
+            Some(("ECR", ecr.get_credentials().await))
+        } else if gcp::is_gcp_registry(&self.oci_registry) {
+            Some((
+                "Google Cloud",
+                gcp::get_credentials(&self.http_client).await,
+            ))
+        } else if acr::is_acr_registry(&self.oci_registry) {
+            Some((
+                "ACR",
+                acr::get_credentials(&self.http_client, &self.oci_registry).await,
+            ))
+        } else {
+            None
+        };
+        match cloud_credentials {
+            Some((_, Ok(credentials))) => return Ok(credentials),
+            // Fall back to e.g. credentials from `docker login`
+            Some((provider, Err(err))) => {
+                tracing::debug!("Failed to get {provider} credentials: {err}")
+            }
+            None => (),
         }
 
         let server_url = format!("https://{}", self.oci_registry);
         match docker_credential::get_credential(&server_url) {
             Ok(DockerCredential::UsernamePassword(username, password)) => {
-                return Ok(RegistryAuth::Basic(username, password));
+                return Ok(RegistryAuth::Basic(username, password).into());
             }
             Ok(DockerCredential::IdentityToken(_)) => {
                 return Err(Error::CredentialError(anyhow::anyhow!(
@@ -142,21 +405,327 @@ impl OciSource {
             }
         }
 
-        Ok(RegistryAuth::Anonymous)
+        if ghcr::is_ghcr_registry(&self.oci_registry) {
+            if let Some(credentials) = ghcr::actions_credentials() {
+                tracing::debug!("Using GitHub Actions token for {:?}", self.oci_registry);
+                return Ok(credentials);
+            }
+        }
+
+        Ok(RegistryAuth::Anonymous.into())
     }
 
     fn make_reference(&self, package: &PackageRef, version: Option<&Version>) -> Reference {
         let repository = format!(
-            "{}{}/{}",
+            "{}{}",
             self.namespace_prefix.as_deref().unwrap_or_default(),
-            package.namespace(),
-            package.name()
+            self.layout.repository(package),
         );
         let tag = version
-            .map(|ver| ver.to_string())
+            .map(|ver| self.layout.tag(ver))
             .unwrap_or_else(|| "latest".into());
         Reference::with_tag(self.oci_registry.clone(), repository, tag)
     }
+
+    /// Returns the reference for the given channel tag, which is used as
+    /// the OCI tag as-is rather than through the layout's tag template.
+    fn make_channel_reference(&self, package: &PackageRef, tag: &str) -> Reference {
+        let reference = self.make_reference(package, None);
+        Reference::with_tag(
+            reference.registry().to_string(),
+            reference.repository().to_string(),
+            tag.to_string(),
+        )
+    }
+
+    /// Lists all tags of the given reference's repository.
+    async fn list_tags(
+        &self,
+        reference: &Reference,
+        auth: &RegistryAuth,
+    ) -> Result<Vec<String>, Error> {
+        if !self.compat.paginate_tags {
+            let resp = self.client.list_tags(reference, auth, None, None).await?;
+            tracing::trace!("List tags response: {resp:?}");
+            return Ok(resp.tags);
+        }
+        let mut tags: Vec<String> = vec![];
+        loop {
+            let last = tags.last().map(String::as_str);
+            let resp = self
+                .client
+                .list_tags(reference, auth, Some(TAG_PAGE_SIZE), last)
+                .await?;
+            tracing::trace!("List tags response: {resp:?}");
+            let page_len = resp.tags.len();
+            // Guard against registries that ignore `last`
+            if resp.tags.last().map(String::as_str) == last {
+                break;
+            }
+            tags.extend(resp.tags);
+            if page_len < TAG_PAGE_SIZE {
+                break;
+            }
+        }
+        Ok(tags)
+    }
+
+    /// Fetches the image manifest for the given reference.
+    async fn pull_manifest(
+        &self,
+        reference: &Reference,
+        auth: &RegistryAuth,
+    ) -> Result<OciImageManifest, Error> {
+        if !self.compat.lenient_manifests {
+            let (manifest, _digest) = self.client.pull_image_manifest(reference, auth).await?;
+            return Ok(manifest);
+        }
+        let (manifest, _digest) = self
+            .client
+            .pull_manifest_raw(
+                reference,
+                auth,
+                &[OCI_IMAGE_MEDIA_TYPE, IMAGE_MANIFEST_MEDIA_TYPE, "*/*"],
+            )
+            .await?;
+        serde_json::from_slice(&manifest)
+            .map_err(|err| Error::InvalidPackageManifest(format!("invalid manifest: {err}")))
+    }
+
+    /// Fetches the digest of the manifest for the given reference.
+    async fn manifest_digest(
+        &self,
+        reference: &Reference,
+        auth: &RegistryAuth,
+    ) -> Result<String, Error> {
+        if !self.compat.avoid_head {
+            return Ok(self.client.fetch_manifest_digest(reference, auth).await?);
+        }
+        let (_manifest, digest) = self
+            .client
+            .pull_manifest_raw(
+                reference,
+                auth,
+                &[OCI_IMAGE_MEDIA_TYPE, IMAGE_MANIFEST_MEDIA_TYPE],
+            )
+            .await?;
+        Ok(digest)
+    }
+
+    /// Returns the tag of the image index listing referrers of the given
+    /// manifest, per the OCI referrers tag schema.
+    fn referrers_reference(reference: &Reference, manifest_digest: &str) -> Reference {
+        Reference::with_tag(
+            reference.registry().to_string(),
+            reference.repository().to_string(),
+            manifest_digest.replacen(':', "-", 1),
+        )
+    }
+
+    /// Returns the raw image index listing referrers of the given manifest,
+    /// or None if it has none.
+    async fn referrers_index(
+        &self,
+        reference: &Reference,
+        manifest_digest: &str,
+        auth: &RegistryAuth,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        let result = self
+            .client
+            .pull_manifest_raw(
+                &Self::referrers_reference(reference, manifest_digest),
+                auth,
+                &[OCI_IMAGE_INDEX_MEDIA_TYPE],
+            )
+            .await;
+        match result.map_err(Error::from) {
+            Ok((index, _)) => Ok(Some(index)),
+            Err(err) if err.is_not_found() => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Returns the deprecation notice referring to the given manifest, if
+    /// any.
+    async fn referrer_deprecation(
+        &self,
+        reference: &Reference,
+        manifest_digest: &str,
+        auth: &RegistryAuth,
+    ) -> Result<Option<Deprecation>, Error> {
+        let Some(index) = self
+            .referrers_index(reference, manifest_digest, auth)
+            .await?
+        else {
+            return Ok(None);
+        };
+        let index: ReferrersIndex = serde_json::from_slice(&index).map_err(|err| {
+            Error::InvalidPackageManifest(format!("invalid referrers index: {err}"))
+        })?;
+        let Some(referrer) = index
+            .manifests
+            .into_iter()
+            .find(|referrer| referrer.artifact_type.as_deref() == Some(DEPRECATION_ARTIFACT_TYPE))
+        else {
+            return Ok(None);
+        };
+        self.referrer_content(reference, referrer.digest, auth)
+            .await?
+            .map(|bytes| Deprecation::from_json(&bytes))
+            .transpose()
+    }
+
+    /// Pulls and verifies the first layer of the given referrer manifest.
+    async fn referrer_content(
+        &self,
+        reference: &Reference,
+        referrer_digest: String,
+        auth: &RegistryAuth,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        let (manifest, _) = self
+            .client
+            .pull_manifest_raw(
+                &Reference::with_digest(
+                    reference.registry().to_string(),
+                    reference.repository().to_string(),
+                    referrer_digest,
+                ),
+                auth,
+                &[OCI_IMAGE_MEDIA_TYPE],
+            )
+            .await?;
+        let manifest: OciImageManifest = serde_json::from_slice(&manifest).map_err(|err| {
+            Error::InvalidPackageManifest(format!("invalid referrer manifest: {err}"))
+        })?;
+        let Some(layer) = manifest.layers.first() else {
+            return Ok(None);
+        };
+        let mut bytes = vec![];
+        self.client.pull_blob(reference, layer, &mut bytes).await?;
+        let digest: ContentDigest = layer.digest.parse()?;
+        if ContentDigest::from(Sha256::new_with_prefix(&bytes)) != digest {
+            return Err(Error::InvalidContent(format!(
+                "referrer layer doesn't match digest {digest}"
+            )));
+        }
+        Ok(Some(bytes))
+    }
+
+    /// Pushes the given content as an artifact referring to the release's
+    /// manifest and lists it in the referrers index, in place of any
+    /// previous referrers of the same artifact type if `replace` is set.
+    async fn push_referrer(
+        &mut self,
+        package: &PackageRef,
+        release: &Release,
+        artifact_type: &str,
+        content: Vec<u8>,
+        replace: bool,
+    ) -> Result<(), Error> {
+        let reference = self.make_reference(package, Some(&release.version));
+        let auth = self.get_credentials().await?.auth;
+        self.client
+            .auth(&reference, &auth, oci_distribution::RegistryOperation::Push)
+            .await?;
+        let (subject, subject_digest) = self
+            .client
+            .pull_manifest_raw(
+                &reference,
+                &auth,
+                &[OCI_IMAGE_MEDIA_TYPE, IMAGE_MANIFEST_MEDIA_TYPE],
+            )
+            .await?;
+        let subject_media_type = serde_json::from_slice::<serde_json::Value>(&subject)
+            .ok()
+            .and_then(|manifest| Some(manifest.get("mediaType")?.as_str()?.to_string()))
+            .unwrap_or_else(|| OCI_IMAGE_MEDIA_TYPE.into());
+
+        let mut descriptors = vec![];
+        for (media_type, blob) in [
+            (EMPTY_CONFIG_MEDIA_TYPE, b"{}".to_vec()),
+            (artifact_type, content),
+        ] {
+            let digest = ContentDigest::from(Sha256::new_with_prefix(&blob)).to_string();
+            self.client.push_blob(&reference, &blob, &digest).await?;
+            descriptors.push(serde_json::json!({
+                "mediaType": media_type,
+                "digest": digest,
+                "size": blob.len(),
+            }));
+        }
+        let layer = descriptors.pop().unwrap();
+        let config = descriptors.pop().unwrap();
+        let manifest = serde_json::to_vec(&serde_json::json!({
+            "schemaVersion": 2,
+            "mediaType": OCI_IMAGE_MEDIA_TYPE,
+            "artifactType": artifact_type,
+            "config": config,
+            "layers": [layer],
+            "subject": {
+                "mediaType": subject_media_type,
+                "digest": subject_digest,
+                "size": subject.len(),
+            },
+        }))
+        .map_err(|err| Error::InvalidPackageManifest(err.to_string()))?;
+        let manifest_digest = ContentDigest::from(Sha256::new_with_prefix(&manifest)).to_string();
+        let manifest_len = manifest.len();
+        tracing::debug!("Pushing {artifact_type} referrer {manifest_digest} for {reference:?}");
+        self.client
+            .push_manifest_raw(
+                &Reference::with_digest(
+                    reference.registry().to_string(),
+                    reference.repository().to_string(),
+                    manifest_digest.clone(),
+                ),
+                manifest,
+                reqwest::header::HeaderValue::from_static(OCI_IMAGE_MEDIA_TYPE),
+            )
+            .await?;
+
+        let mut index = match self
+            .referrers_index(&reference, &subject_digest, &auth)
+            .await?
+        {
+            Some(index) => serde_json::from_slice(&index).map_err(|err| {
+                Error::InvalidPackageManifest(format!("invalid referrers index: {err}"))
+            })?,
+            None => serde_json::json!({
+                "schemaVersion": 2,
+                "mediaType": OCI_IMAGE_INDEX_MEDIA_TYPE,
+                "manifests": [],
+            }),
+        };
+        let Some(manifests) = index
+            .get_mut("manifests")
+            .and_then(serde_json::Value::as_array_mut)
+        else {
+            return Err(Error::InvalidPackageManifest(
+                "referrers index has no manifests".into(),
+            ));
+        };
+        if replace {
+            manifests.retain(|referrer| {
+                referrer.get("artifactType").and_then(|ty| ty.as_str()) != Some(artifact_type)
+            });
+        }
+        manifests.push(serde_json::json!({
+            "mediaType": OCI_IMAGE_MEDIA_TYPE,
+            "digest": manifest_digest,
+            "size": manifest_len,
+            "artifactType": artifact_type,
+        }));
+        let index = serde_json::to_vec(&index)
+            .map_err(|err| Error::InvalidPackageManifest(err.to_string()))?;
+        self.client
+            .push_manifest_raw(
+                &Self::referrers_reference(&reference, &subject_digest),
+                index,
+                reqwest::header::HeaderValue::from_static(OCI_IMAGE_INDEX_MEDIA_TYPE),
+            )
+            .await?;
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -166,24 +735,54 @@ impl PackageSource for OciSource {
 
         tracing::debug!("Listing tags for OCI reference {reference:?}");
         let auth = self.auth(&reference).await?;
-        let resp = self.client.list_tags(&reference, &auth, None, None).await?;
-        tracing::trace!("List tags response: {resp:?}");
+        let tags = self.list_tags(&reference, &auth).await?;
 
-        // Return only tags that parse as valid semver versions.
-        let versions = resp
-            .tags
+        // Return only tags that match the layout and parse as valid semver
+        // versions, silently skipping channel tags.
+        let mut versions: Vec<VersionInfo> = tags
             .iter()
-            .flat_map(|tag| match Version::parse(tag) {
+            .flat_map(|tag| match self.layout.parse_tag(tag)? {
                 Ok(version) => Some(VersionInfo {
                     version,
                     yanked: false,
+                    deprecation: None,
                 }),
+                Err(_) if crate::is_valid_tag(tag) => None,
                 Err(err) => {
                     tracing::warn!("Ignoring invalid version tag {tag:?}: {err:?}");
                     None
                 }
             })
             .collect();
+
+        // Deprecation notices are referrers of a version's manifest, listed
+        // in an index tagged `sha256-<hex>`; only versions whose manifest has
+        // such a tag can have one, so skip the lookups if there are none.
+        let referrers_tags: HashSet<&str> = tags
+            .iter()
+            .map(String::as_str)
+            .filter(|tag| tag.starts_with("sha256-"))
+            .collect();
+        if referrers_tags.is_empty() {
+            return Ok(versions);
+        }
+        for info in &mut versions {
+            let reference = self.make_reference(package, Some(&info.version));
+            let result = async {
+                let manifest_digest = self.manifest_digest(&reference, &auth).await?;
+                let referrers_tag = Self::referrers_reference(&reference, &manifest_digest);
+                if !referrers_tags.contains(referrers_tag.tag().unwrap_or_default()) {
+                    return Ok(None);
+                }
+                self.referrer_deprecation(&reference, &manifest_digest, &auth)
+                    .await
+            }
+            .await;
+            info.deprecation = result.unwrap_or_else(|err| {
+                tracing::debug!("Failed to get deprecation of {reference:?}: {err}");
+                None
+            });
+        }
         Ok(versions)
     }
 
@@ -196,20 +795,24 @@ impl PackageSource for OciSource {
 
         tracing::debug!("Fetching image manifest for OCI reference {reference:?}");
         let auth = self.auth(&reference).await?;
-        let (manifest, _digest) = self.client.pull_image_manifest(&reference, &auth).await?;
+        let manifest = self.pull_manifest(&reference, &auth).await?;
         tracing::trace!("Got manifest {manifest:?}");
 
         // Pending standardization of an OCI manifest/config format, a package
-        // artifact must contain exactly one layer with a known wasm media type
-        // (other non-wasm layers may be present as well).
+        // artifact must contain exactly one layer with a known wasm (or
+        // archive) media type (other layers may be present as well).
         let wasm_layers = manifest
             .layers
             .into_iter()
-            .filter(|layer| WASM_LAYER_MEDIA_TYPES.contains(&layer.media_type.as_str()))
+            .filter(|layer| {
+                let media_type = layer.media_type.as_str();
+                WASM_LAYER_MEDIA_TYPES.contains(&media_type)
+                    || ARCHIVE_LAYER_MEDIA_TYPES.contains(&media_type)
+            })
             .collect::<Vec<_>>();
         if wasm_layers.len() != 1 {
             return Err(Error::InvalidPackageManifest(format!(
-                "expected 1 wasm layer; got {}",
+                "expected 1 wasm or archive layer; got {}",
                 wasm_layers.len()
             )));
         }
@@ -221,11 +824,26 @@ impl PackageSource for OciSource {
         })
     }
 
+    async fn release_revision(
+        &mut self,
+        package: &PackageRef,
+        version: &Version,
+    ) -> Result<Option<String>, Error> {
+        // Without HEAD requests, getting the digest costs as much as
+        // fetching the manifest
+        if self.compat.avoid_head {
+            return Ok(None);
+        }
+        let reference = self.make_reference(package, Some(version));
+        let auth = self.auth(&reference).await?;
+        Ok(Some(self.manifest_digest(&reference, &auth).await?))
+    }
+
     async fn stream_content_unvalidated(
         &mut self,
         package: &PackageRef,
         release: &Release,
-    ) -> Result<BoxStream<Result<Bytes, Error>>, Error> {
+    ) -> Result<BoxStream<'static, Result<Bytes, Error>>, Error> {
         let reference = self.make_reference(package, None);
         let descriptor = OciDescriptor {
             digest: release.content_digest.to_string(),
@@ -238,4 +856,421 @@ impl PackageSource for OciSource {
             .await?;
         Ok(stream.map_err(Into::into).boxed())
     }
+
+    /// Content is stored as blobs, which can be fetched by digest whether or
+    /// not a version's manifest references them.
+    async fn has_content(
+        &mut self,
+        package: &PackageRef,
+        digest: &ContentDigest,
+    ) -> Result<Option<bool>, Error> {
+        let reference = self.make_reference(package, None);
+        let auth = self.auth(&reference).await?;
+        api::RegistryApi::new(&self.http_client, &self.protocol, &self.oci_registry, &auth)
+            .has_blob(reference.repository(), &digest.to_string())
+            .await
+            .map(Some)
+    }
+
+    /// Finds attestations via the OCI referrers tag schema: referrers of a
+    /// manifest are listed in an image index tagged `sha256-<hex>`.
+    async fn provenance(
+        &mut self,
+        package: &PackageRef,
+        release: &Release,
+    ) -> Result<Vec<Provenance>, Error> {
+        let reference = self.make_reference(package, Some(&release.version));
+        let auth = self.auth(&reference).await?;
+        let manifest_digest = self.manifest_digest(&reference, &auth).await?;
+        let subjects = [manifest_digest.parse()?, release.content_digest.clone()];
+        let registry = reference.registry().to_string();
+        let repository = reference.repository().to_string();
+        let index = match self
+            .referrers_index(&reference, &manifest_digest, &auth)
+            .await
+        {
+            Ok(Some(index)) => index,
+            Ok(None) => return Ok(vec![]),
+            Err(err) => {
+                tracing::debug!("No referrers found for {reference}: {err}");
+                return Ok(vec![]);
+            }
+        };
+        let index: ReferrersIndex = serde_json::from_slice(&index).map_err(|err| {
+            Error::InvalidPackageManifest(format!("invalid referrers index: {err}"))
+        })?;
+
+        let mut provenance = vec![];
+        for referrer in index.manifests {
+            let is_attestation = referrer
+                .artifact_type
+                .as_deref()
+                .is_some_and(|ty| ATTESTATION_ARTIFACT_TYPES.contains(&ty));
+            if !is_attestation {
+                continue;
+            }
+            let (manifest, _) = self
+                .client
+                .pull_manifest_raw(
+                    &Reference::with_digest(registry.clone(), repository.clone(), referrer.digest),
+                    &auth,
+                    &[OCI_IMAGE_MEDIA_TYPE],
+                )
+                .await?;
+            let manifest: OciImageManifest = serde_json::from_slice(&manifest).map_err(|err| {
+                Error::InvalidPackageManifest(format!("invalid attestation manifest: {err}"))
+            })?;
+            for layer in &manifest.layers {
+                let mut bytes = vec![];
+                self.client.pull_blob(&reference, layer, &mut bytes).await?;
+                let digest: ContentDigest = layer.digest.parse()?;
+                if ContentDigest::from(Sha256::new_with_prefix(&bytes)) != digest {
+                    return Err(Error::InvalidContent(format!(
+                        "attestation layer doesn't match digest {digest}"
+                    )));
+                }
+                provenance.extend(Provenance::parse(&bytes, &subjects)?);
+            }
+        }
+        Ok(provenance)
+    }
+
+    /// Finds the notice via the OCI referrers tag schema, as for provenance.
+    async fn deprecation(
+        &mut self,
+        package: &PackageRef,
+        release: &Release,
+    ) -> Result<Option<Deprecation>, Error> {
+        let reference = self.make_reference(package, Some(&release.version));
+        let auth = self.auth(&reference).await?;
+        let manifest_digest = self.manifest_digest(&reference, &auth).await?;
+        self.referrer_deprecation(&reference, &manifest_digest, &auth)
+            .await
+    }
+
+    /// Pushes the notice as an artifact referring to the release's manifest
+    /// and lists it in the referrers index in place of any previous notice.
+    async fn deprecate(
+        &mut self,
+        package: &PackageRef,
+        release: &Release,
+        deprecation: &Deprecation,
+    ) -> Result<(), Error> {
+        self.push_referrer(
+            package,
+            release,
+            DEPRECATION_ARTIFACT_TYPE,
+            deprecation.to_json(),
+            true,
+        )
+        .await
+    }
+
+    /// Finds signatures via the OCI referrers tag schema, as for provenance.
+    async fn openpgp_signatures(
+        &mut self,
+        package: &PackageRef,
+        release: &Release,
+    ) -> Result<Vec<Vec<u8>>, Error> {
+        let reference = self.make_reference(package, Some(&release.version));
+        let auth = self.auth(&reference).await?;
+        let manifest_digest = self.manifest_digest(&reference, &auth).await?;
+        let Some(index) = self
+            .referrers_index(&reference, &manifest_digest, &auth)
+            .await?
+        else {
+            return Ok(vec![]);
+        };
+        let index: ReferrersIndex = serde_json::from_slice(&index).map_err(|err| {
+            Error::InvalidPackageManifest(format!("invalid referrers index: {err}"))
+        })?;
+        let mut signatures = vec![];
+        for referrer in index.manifests {
+            if referrer.artifact_type.as_deref() != Some(OPENPGP_SIGNATURE_ARTIFACT_TYPE) {
+                continue;
+            }
+            signatures.extend(
+                self.referrer_content(&reference, referrer.digest, &auth)
+                    .await?,
+            );
+        }
+        Ok(signatures)
+    }
+
+    /// Pushes the signature as an artifact referring to the release's
+    /// manifest, as for deprecation notices.
+    async fn attach_openpgp_signature(
+        &mut self,
+        package: &PackageRef,
+        release: &Release,
+        signature: &[u8],
+    ) -> Result<(), Error> {
+        self.push_referrer(
+            package,
+            release,
+            OPENPGP_SIGNATURE_ARTIFACT_TYPE,
+            signature.to_vec(),
+            false,
+        )
+        .await
+    }
+
+    /// Channel tags point at the same manifest as a version tag, so the
+    /// version is found by comparing manifest digests, newest version first
+    /// as channels usually track recent releases.
+    async fn resolve_tag(
+        &mut self,
+        package: &PackageRef,
+        tag: &str,
+    ) -> Result<Option<Version>, Error> {
+        let reference = self.make_channel_reference(package, tag);
+        let auth = self.auth(&reference).await?;
+        let digest = match self.manifest_digest(&reference, &auth).await {
+            Ok(digest) => digest,
+            Err(err) if err.is_not_found() => return Ok(None),
+            Err(err) => return Err(err),
+        };
+        let mut versions = self.list_all_versions(package).await?;
+        versions.sort();
+        for info in versions.into_iter().rev() {
+            let reference = self.make_reference(package, Some(&info.version));
+            if self.manifest_digest(&reference, &auth).await? == digest {
+                return Ok(Some(info.version));
+            }
+        }
+        Err(Error::InvalidPackageManifest(format!(
+            "tag {tag:?} doesn't point at any version of {package}"
+        )))
+    }
+
+    /// Pushes the version's manifest, unchanged, under the channel tag.
+    async fn set_tag(
+        &mut self,
+        package: &PackageRef,
+        tag: &str,
+        version: &Version,
+    ) -> Result<(), Error> {
+        let reference = self.make_reference(package, Some(version));
+        let auth = self.get_credentials().await?.auth;
+        self.client
+            .auth(&reference, &auth, oci_distribution::RegistryOperation::Push)
+            .await?;
+        let (manifest, digest) = self
+            .client
+            .pull_manifest_raw(
+                &reference,
+                &auth,
+                &[OCI_IMAGE_MEDIA_TYPE, IMAGE_MANIFEST_MEDIA_TYPE],
+            )
+            .await?;
+        let media_type = serde_json::from_slice::<serde_json::Value>(&manifest)
+            .ok()
+            .and_then(|manifest| Some(manifest.get("mediaType")?.as_str()?.to_string()))
+            .unwrap_or_else(|| OCI_IMAGE_MEDIA_TYPE.into());
+        let content_type = reqwest::header::HeaderValue::from_str(&media_type).map_err(|_| {
+            Error::InvalidPackageManifest(format!("invalid media type {media_type:?}"))
+        })?;
+        let tag_reference = self.make_channel_reference(package, tag);
+        tracing::debug!("Tagging {digest} as {tag_reference:?}");
+        self.client
+            .push_manifest_raw(&tag_reference, manifest, content_type)
+            .await?;
+        Ok(())
+    }
+
+    /// Deletes the version's manifest, which also removes any channel tags
+    /// pointing at it.
+    async fn delete(&mut self, package: &PackageRef, version: &Version) -> Result<(), Error> {
+        let reference = self.make_reference(package, Some(version));
+        let auth = self.get_credentials().await?.auth;
+        self.client
+            .auth(&reference, &auth, oci_distribution::RegistryOperation::Push)
+            .await?;
+        let digest = self.manifest_digest(&reference, &auth).await?;
+        tracing::debug!("Deleting manifest {digest} of {reference:?}");
+        api::RegistryApi::new(&self.http_client, &self.protocol, &self.oci_registry, &auth)
+            .delete_manifest(reference.repository(), &digest)
+            .await
+    }
+
+    /// Lists the registry's repositories, keeping those that map to
+    /// packages in the namespace through the layout.
+    async fn list_packages(&mut self, namespace: &Label) -> Result<Option<Vec<PackageRef>>, Error> {
+        let auth = self.get_credentials().await?.auth;
+        tracing::debug!("Listing repositories of {:?}", self.oci_registry);
+        let Some(repositories) =
+            api::RegistryApi::new(&self.http_client, &self.protocol, &self.oci_registry, &auth)
+                .catalog()
+                .await?
+        else {
+            return Ok(None);
+        };
+        let prefix = self.namespace_prefix.as_deref().unwrap_or_default();
+        let packages = repositories
+            .iter()
+            .filter_map(|repository| {
+                let name = self
+                    .layout
+                    .parse_repository(namespace, repository.strip_prefix(prefix)?)?;
+                format!("{namespace}:{name}").parse().ok()
+            })
+            .collect();
+        Ok(Some(packages))
+    }
+
+    async fn publish(
+        &mut self,
+        package: &PackageRef,
+        version: &Version,
+        content: Bytes,
+    ) -> Result<Release, Error> {
+        let reference = self.make_reference(package, Some(version));
+        let auth = self.get_credentials().await?.auth;
+        self.client
+            .auth(&reference, &auth, oci_distribution::RegistryOperation::Push)
+            .await?;
+        // Tags are mutable; refuse to replace an existing release, and don't
+        // publish unless the tag is known not to exist.
+        match self.manifest_digest(&reference, &auth).await {
+            Ok(_) => return Err(Error::VersionExists(version.clone())),
+            Err(err) if err.is_not_found() => (),
+            Err(err) => return Err(err),
+        }
+
+        // Gzipped content is published as an archive rather than wasm.
+        let media_type = if content.starts_with(&[0x1f, 0x8b]) {
+            ARCHIVE_LAYER_MEDIA_TYPES[0]
+        } else {
+            WASM_LAYER_MEDIA_TYPES[0]
+        };
+        let layer = ImageLayer::new(content.to_vec(), media_type.into(), None);
+        let content_digest = layer.sha256_digest().parse()?;
+        let config = Config::new(b"{}".to_vec(), WASM_CONFIG_MEDIA_TYPE.into(), None);
+        tracing::debug!("Pushing OCI reference {reference:?}");
+        let resp = self
+            .client
+            .push(&reference, &[layer], config, &auth, None)
+            .await?;
+        tracing::debug!(manifest_url = %resp.manifest_url, "Pushed");
+        Ok(Release {
+            version: version.clone(),
+            content_digest,
+        })
+    }
+}
+
+/// Runs a credential CLI (e.g. `gcloud`), returning its trimmed output.
+async fn cli_output(program: &str, args: &[&str]) -> anyhow::Result<String> {
+    let output = tokio::process::Command::new(program)
+        .args(args)
+        .output()
+        .await
+        .with_context(|| format!("failed to run {program:?}"))?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "{program:?} failed ({}): {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    let output =
+        String::from_utf8(output.stdout).with_context(|| format!("invalid {program:?} output"))?;
+    Ok(output.trim().to_string())
+}
+
+/// Artifact types of referrers that may hold provenance attestations.
+const ATTESTATION_ARTIFACT_TYPES: &[&str] = &[
+    "application/vnd.in-toto+json",
+    "application/vnd.dsse.envelope.v1+json",
+    "application/vnd.dev.sigstore.bundle+json;version=0.2",
+    "application/vnd.dev.sigstore.bundle.v0.3+json",
+];
+
+/// The artifact type of referrers holding deprecation notices, also used
+/// as the media type of the notice layer.
+const DEPRECATION_ARTIFACT_TYPE: &str = "application/vnd.wasm.deprecation.v1+json";
+
+/// The artifact type of referrers holding detached, ASCII-armored OpenPGP
+/// signatures, also used as the media type of the signature layer.
+const OPENPGP_SIGNATURE_ARTIFACT_TYPE: &str = "application/pgp-signature";
+
+/// The config media type of artifacts without a config.
+const EMPTY_CONFIG_MEDIA_TYPE: &str = "application/vnd.oci.empty.v1+json";
+
+/// An OCI image index listing referrers. [`oci_distribution`]'s own type
+/// drops `artifactType`.
+#[derive(Deserialize)]
+struct ReferrersIndex {
+    #[serde(default)]
+    manifests: Vec<ReferrerDescriptor>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ReferrerDescriptor {
+    digest: String,
+    artifact_type: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layout() {
+        let package: PackageRef = "my-ns:my-pkg".parse().unwrap();
+        let version: Version = "1.2.3".parse().unwrap();
+
+        let default = OciLayout::default();
+        assert_eq!(default.repository(&package), "my-ns/my-pkg");
+        assert_eq!(default.tag(&version), "1.2.3");
+
+        let flat = OciLayout::new("wasm/{namespace}-{name}", "v{version}").unwrap();
+        assert_eq!(flat.repository(&package), "wasm/my-ns-my-pkg");
+        assert_eq!(flat.tag(&version), "v1.2.3");
+        assert_eq!(flat.parse_tag("v1.2.3").unwrap().unwrap(), version);
+        assert!(flat.parse_tag("1.2.3").is_none());
+        assert!(flat.parse_tag("vlatest").unwrap().is_err());
+
+        let namespace: Label = "my-ns".parse().unwrap();
+        assert_eq!(
+            default.parse_repository(&namespace, "my-ns/my-pkg"),
+            Some("my-pkg".into())
+        );
+        assert_eq!(default.parse_repository(&namespace, "other/my-pkg"), None);
+        assert_eq!(default.parse_repository(&namespace, "my-ns/a/b"), None);
+        assert_eq!(
+            flat.parse_repository(&namespace, "wasm/my-ns-my-pkg"),
+            Some("my-pkg".into())
+        );
+        assert_eq!(flat.parse_repository(&namespace, "my-ns/my-pkg"), None);
+    }
+
+    #[test]
+    fn test_invalid_layout() {
+        assert!(OciLayout::new("{namespace}", "{version}").is_err());
+        assert!(OciLayout::new("{namespace}/{name}", "latest").is_err());
+    }
+
+    #[test]
+    fn test_channel_tags() {
+        assert!(crate::is_valid_tag("stable"));
+        assert!(crate::is_valid_tag("release-1.x"));
+        assert!(!crate::is_valid_tag("1.2.3"));
+        assert!(!crate::is_valid_tag("-canary"));
+        assert!(!crate::is_valid_tag("canary/2"));
+        assert!(!crate::is_valid_tag(&"a".repeat(129)));
+
+        let package: PackageRef = "my-ns:my-pkg".parse().unwrap();
+        let source = OciSource::new(
+            "example.com".into(),
+            Default::default(),
+            Default::default(),
+            reqwest::Client::new(),
+        )
+        .unwrap();
+        let reference = source.make_channel_reference(&package, "stable");
+        assert_eq!(reference.repository(), "my-ns/my-pkg");
+        assert_eq!(reference.tag(), Some("stable"));
+    }
 }
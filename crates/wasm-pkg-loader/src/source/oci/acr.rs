@@ -0,0 +1,152 @@
+//! Refresh tokens for Azure Container Registry.
+//!
+//! A Microsoft Entra access token, from workload identity or the Azure CLI,
+//! is exchanged with the registry for a refresh token that's accepted as a
+//! password.
+
+use std::time::{Duration, SystemTime};
+
+use anyhow::Context;
+use oci_distribution::secrets::RegistryAuth;
+use serde::Deserialize;
+
+use crate::Error;
+
+use super::{cli_output, Credentials};
+
+/// The username that ACR expects with a refresh token.
+const USERNAME: &str = "00000000-0000-0000-0000-000000000000";
+
+const RESOURCE: &str = "https://containerregistry.azure.net";
+
+const DEFAULT_AUTHORITY_HOST: &str = "https://login.microsoftonline.com/";
+
+/// The lifetime of ACR refresh tokens.
+const REFRESH_TOKEN_LIFETIME: Duration = Duration::from_secs(3 * 60 * 60);
+
+/// Returns true if the given registry is an ACR registry, e.g.
+/// `example.azurecr.io`.
+pub fn is_acr_registry(registry: &str) -> bool {
+    let host = registry.split(':').next().unwrap_or_default();
+    [".azurecr.io", ".azurecr.cn", ".azurecr.us"]
+        .iter()
+        .any(|suffix| host.ends_with(suffix))
+}
+
+pub async fn get_credentials(
+    client: &reqwest::Client,
+    registry: &str,
+) -> Result<Credentials, Error> {
+    let refresh_token = async {
+        let access_token = match workload_identity_token(client).await? {
+            Some(token) => token,
+            None => {
+                let resource = format!("--resource={RESOURCE}");
+                cli_output(
+                    "az",
+                    &[
+                        "account",
+                        "get-access-token",
+                        &resource,
+                        "--query=accessToken",
+                        "--output=tsv",
+                    ],
+                )
+                .await?
+            }
+        };
+        exchange_token(client, registry, &access_token).await
+    }
+    .await
+    .map_err(Error::CredentialError)?;
+    Ok(Credentials {
+        auth: RegistryAuth::Basic(USERNAME.into(), refresh_token),
+        expires_at: Some(SystemTime::now() + REFRESH_TOKEN_LIFETIME),
+    })
+}
+
+#[derive(Deserialize)]
+struct AccessTokenResponse {
+    access_token: String,
+}
+
+/// Gets an access token with workload identity federation, if configured
+/// (e.g. on AKS).
+async fn workload_identity_token(client: &reqwest::Client) -> anyhow::Result<Option<String>> {
+    let (Ok(token_file), Ok(client_id), Ok(tenant_id)) = (
+        std::env::var("AZURE_FEDERATED_TOKEN_FILE"),
+        std::env::var("AZURE_CLIENT_ID"),
+        std::env::var("AZURE_TENANT_ID"),
+    ) else {
+        return Ok(None);
+    };
+    let assertion = tokio::fs::read_to_string(&token_file)
+        .await
+        .with_context(|| format!("failed to read federated token file {token_file:?}"))?;
+    let authority_host =
+        std::env::var("AZURE_AUTHORITY_HOST").unwrap_or_else(|_| DEFAULT_AUTHORITY_HOST.into());
+    let url = format!(
+        "{}/{tenant_id}/oauth2/v2.0/token",
+        authority_host.trim_end_matches('/')
+    );
+    let scope = format!("{RESOURCE}/.default");
+    let resp: AccessTokenResponse = client
+        .post(url)
+        .form(&[
+            ("grant_type", "client_credentials"),
+            ("client_id", &client_id),
+            (
+                "client_assertion_type",
+                "urn:ietf:params:oauth:client-assertion-type:jwt-bearer",
+            ),
+            ("client_assertion", assertion.trim()),
+            ("scope", &scope),
+        ])
+        .send()
+        .await?
+        .error_for_status()
+        .context("workload identity token request failed")?
+        .json()
+        .await?;
+    Ok(Some(resp.access_token))
+}
+
+#[derive(Deserialize)]
+struct ExchangeResponse {
+    refresh_token: String,
+}
+
+/// Exchanges an access token for a registry refresh token.
+async fn exchange_token(
+    client: &reqwest::Client,
+    registry: &str,
+    access_token: &str,
+) -> anyhow::Result<String> {
+    let resp: ExchangeResponse = client
+        .post(format!("https://{registry}/oauth2/exchange"))
+        .form(&[
+            ("grant_type", "access_token"),
+            ("service", registry),
+            ("access_token", access_token),
+        ])
+        .send()
+        .await?
+        .error_for_status()
+        .context("ACR token exchange failed")?
+        .json()
+        .await?;
+    Ok(resp.refresh_token)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_acr_registry() {
+        assert!(is_acr_registry("example.azurecr.io"));
+        assert!(is_acr_registry("example.azurecr.cn:443"));
+        assert!(!is_acr_registry("azurecr.io.example.com"));
+        assert!(!is_acr_registry("ghcr.io"));
+    }
+}
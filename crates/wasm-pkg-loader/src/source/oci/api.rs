@@ -0,0 +1,227 @@
+//! Registry API requests that [`oci_distribution`] doesn't implement:
+//! manifest deletion, blob existence checks, and repository listing.
+//!
+//! Registries typically answer an unauthenticated request with a bearer
+//! token challenge; a token with the challenge's scope is then requested
+//! from its realm with the registry credentials.
+
+use oci_distribution::{client::ClientProtocol, secrets::RegistryAuth};
+use reqwest::{
+    header::{LINK, WWW_AUTHENTICATE},
+    Method, Response, StatusCode,
+};
+use serde::Deserialize;
+
+use crate::Error;
+
+/// The page size used to list repositories.
+const CATALOG_PAGE_SIZE: usize = 1000;
+
+/// A client for a registry's distribution API.
+pub struct RegistryApi<'a> {
+    client: &'a reqwest::Client,
+    base_url: String,
+    auth: &'a RegistryAuth,
+}
+
+impl<'a> RegistryApi<'a> {
+    pub fn new(
+        client: &'a reqwest::Client,
+        protocol: &ClientProtocol,
+        registry: &str,
+        auth: &'a RegistryAuth,
+    ) -> Self {
+        let scheme = match protocol {
+            ClientProtocol::Http => "http",
+            ClientProtocol::HttpsExcept(exceptions) if exceptions.iter().any(|r| r == registry) => {
+                "http"
+            }
+            _ => "https",
+        };
+        Self {
+            client,
+            base_url: format!("{scheme}://{registry}/v2"),
+            auth,
+        }
+    }
+
+    /// Deletes the manifest with the given digest from the repository.
+    pub async fn delete_manifest(&self, repository: &str, digest: &str) -> Result<(), Error> {
+        let resp = self
+            .send(Method::DELETE, &format!("/{repository}/manifests/{digest}"))
+            .await?;
+        if resp.status() == StatusCode::METHOD_NOT_ALLOWED {
+            return Err(Error::DeleteUnsupported(
+                "the registry doesn't allow deleting manifests".into(),
+            ));
+        }
+        resp.error_for_status()?;
+        Ok(())
+    }
+
+    /// Returns true if the repository has a blob with the given digest.
+    pub async fn has_blob(&self, repository: &str, digest: &str) -> Result<bool, Error> {
+        let resp = self
+            .send(Method::HEAD, &format!("/{repository}/blobs/{digest}"))
+            .await?;
+        if resp.status() == StatusCode::NOT_FOUND {
+            return Ok(false);
+        }
+        resp.error_for_status()?;
+        Ok(true)
+    }
+
+    /// Lists the registry's repositories, or returns None if the registry
+    /// doesn't support listing them.
+    pub async fn catalog(&self) -> Result<Option<Vec<String>>, Error> {
+        #[derive(Deserialize)]
+        struct Catalog {
+            #[serde(default)]
+            repositories: Vec<String>,
+        }
+
+        let mut repositories: Vec<String> = vec![];
+        loop {
+            let mut path = format!("/_catalog?n={CATALOG_PAGE_SIZE}");
+            if let Some(last) = repositories.last() {
+                path.push_str(&format!("&last={last}"));
+            }
+            let resp = self.send(Method::GET, &path).await?;
+            if matches!(
+                resp.status(),
+                StatusCode::NOT_FOUND
+                    | StatusCode::METHOD_NOT_ALLOWED
+                    | StatusCode::NOT_IMPLEMENTED
+            ) {
+                return Ok(None);
+            }
+            let resp = resp.error_for_status()?;
+            let has_next = resp.headers().contains_key(LINK);
+            let page: Catalog = resp.json().await?;
+            let done = !has_next || page.repositories.is_empty();
+            repositories.extend(page.repositories);
+            if done {
+                return Ok(Some(repositories));
+            }
+        }
+    }
+
+    /// Sends a request, retrying it with a bearer token if challenged for
+    /// one.
+    async fn send(&self, method: Method, path: &str) -> Result<Response, Error> {
+        let url = format!("{}{path}", self.base_url);
+        let request = |token: Option<&str>| {
+            let request = self.client.request(method.clone(), &url);
+            match (token, self.auth) {
+                (Some(token), _) => request.bearer_auth(token),
+                (None, RegistryAuth::Basic(username, password)) => {
+                    request.basic_auth(username, Some(password))
+                }
+                (None, RegistryAuth::Anonymous) => request,
+            }
+        };
+
+        let resp = request(None).send().await?;
+        if resp.status() != StatusCode::UNAUTHORIZED {
+            return Ok(resp);
+        }
+        let challenge = resp
+            .headers()
+            .get(WWW_AUTHENTICATE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(BearerChallenge::parse);
+        match challenge {
+            Some(challenge) => {
+                let token = challenge.token(self.client, self.auth).await?;
+                Ok(request(Some(&token)).send().await?)
+            }
+            None => Ok(resp),
+        }
+    }
+}
+
+/// A `WWW-Authenticate: Bearer ...` challenge.
+#[derive(Debug, Default, PartialEq)]
+struct BearerChallenge {
+    realm: String,
+    service: Option<String>,
+    scope: Option<String>,
+}
+
+impl BearerChallenge {
+    /// Parses a challenge, e.g. `Bearer realm="https://auth.example.com/token",
+    /// service="example.com",scope="repository:ns/pkg:delete"`.
+    fn parse(header: &str) -> Option<Self> {
+        let (scheme, mut params) = header.trim().split_once(' ')?;
+        if !scheme.eq_ignore_ascii_case("bearer") {
+            return None;
+        }
+        let mut challenge = Self::default();
+        loop {
+            params = params.trim_start_matches([',', ' ']);
+            if params.is_empty() {
+                break;
+            }
+            let (key, rest) = params.split_once('=')?;
+            // Values are quoted and may contain commas, e.g. scopes
+            let (value, rest) = match rest.strip_prefix('"') {
+                Some(quoted) => quoted.split_once('"')?,
+                None => rest.split_once(',').unwrap_or((rest, "")),
+            };
+            match key.trim() {
+                "realm" => challenge.realm = value.to_string(),
+                "service" => challenge.service = Some(value.to_string()),
+                "scope" => challenge.scope = Some(value.to_string()),
+                _ => (),
+            }
+            params = rest;
+        }
+        (!challenge.realm.is_empty()).then_some(challenge)
+    }
+
+    /// Requests a token for this challenge from its realm.
+    async fn token(&self, client: &reqwest::Client, auth: &RegistryAuth) -> Result<String, Error> {
+        #[derive(Deserialize)]
+        struct TokenResponse {
+            token: Option<String>,
+            access_token: Option<String>,
+        }
+
+        let mut request = client.get(&self.realm);
+        for (key, value) in [("service", &self.service), ("scope", &self.scope)] {
+            if let Some(value) = value {
+                request = request.query(&[(key, value)]);
+            }
+        }
+        if let RegistryAuth::Basic(username, password) = auth {
+            request = request.basic_auth(username, Some(password));
+        }
+        let resp: TokenResponse = request.send().await?.error_for_status()?.json().await?;
+        resp.token.or(resp.access_token).ok_or_else(|| {
+            Error::CredentialError(anyhow::anyhow!("no token in response from {}", self.realm))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_challenge() {
+        let challenge = BearerChallenge::parse(
+            r#"Bearer realm="https://auth.example.com/token",service="example.com",scope="repository:ns/pkg:pull,delete""#,
+        )
+        .unwrap();
+        assert_eq!(
+            challenge,
+            BearerChallenge {
+                realm: "https://auth.example.com/token".into(),
+                service: Some("example.com".into()),
+                scope: Some("repository:ns/pkg:pull,delete".into()),
+            }
+        );
+        assert!(BearerChallenge::parse(r#"Basic realm="example.com""#).is_none());
+        assert!(BearerChallenge::parse(r#"Bearer service="example.com""#).is_none());
+    }
+}
@@ -0,0 +1,132 @@
+//! Authorization tokens for AWS Elastic Container Registry.
+//!
+//! ECR doesn't accept long-lived credentials; clients exchange AWS
+//! credentials for a token that expires after 12 hours.
+
+use std::time::SystemTime;
+
+use anyhow::Context;
+use aws_config::{BehaviorVersion, Region};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use oci_distribution::secrets::RegistryAuth;
+
+use crate::Error;
+
+use super::Credentials;
+
+/// A private ECR registry, identified by its domain.
+#[derive(Debug, PartialEq)]
+pub struct EcrRegistry {
+    pub account: String,
+    pub region: String,
+}
+
+impl EcrRegistry {
+    /// Parses an ECR registry domain, e.g.
+    /// `123456789012.dkr.ecr.us-east-1.amazonaws.com`, returning `None` for
+    /// other registries.
+    pub fn parse(registry: &str) -> Option<Self> {
+        let host = registry.split(':').next()?;
+        let mut labels = host.split('.');
+        let account = labels.next()?;
+        if account.len() != 12 || !account.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        if labels.next()? != "dkr" || !matches!(labels.next()?, "ecr" | "ecr-fips") {
+            return None;
+        }
+        let region = labels.next()?;
+        if !matches!(
+            labels.collect::<Vec<_>>().as_slice(),
+            ["amazonaws", "com"] | ["amazonaws", "com", "cn"]
+        ) {
+            return None;
+        }
+        Some(Self {
+            account: account.into(),
+            region: region.into(),
+        })
+    }
+
+    /// Requests an authorization token using the default AWS credentials
+    /// chain (environment, shared config and profiles, instance roles...).
+    pub async fn get_credentials(&self) -> Result<Credentials, Error> {
+        let sdk_config = aws_config::defaults(BehaviorVersion::latest())
+            .region(Region::new(self.region.clone()))
+            .load()
+            .await;
+        let client = aws_sdk_ecr::Client::new(&sdk_config);
+        let output = client
+            .get_authorization_token()
+            .send()
+            .await
+            .with_context(|| {
+                format!(
+                    "failed to get ECR authorization token for account {} in {}",
+                    self.account, self.region
+                )
+            })
+            .map_err(Error::CredentialError)?;
+        let data = output
+            .authorization_data()
+            .first()
+            .context("no ECR authorization data returned")
+            .map_err(Error::CredentialError)?;
+        let token = data
+            .authorization_token()
+            .context("no ECR authorization token returned")
+            .map_err(Error::CredentialError)?;
+
+        // The token is base64-encoded "AWS:<password>"
+        let decoded = STANDARD
+            .decode(token)
+            .ok()
+            .and_then(|decoded| String::from_utf8(decoded).ok())
+            .context("invalid ECR authorization token")
+            .map_err(Error::CredentialError)?;
+        let (username, password) = decoded
+            .split_once(':')
+            .context("invalid ECR authorization token")
+            .map_err(Error::CredentialError)?;
+        Ok(Credentials {
+            auth: RegistryAuth::Basic(username.into(), password.into()),
+            expires_at: data
+                .expires_at()
+                .and_then(|expires_at| SystemTime::try_from(*expires_at).ok()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            EcrRegistry::parse("123456789012.dkr.ecr.us-east-1.amazonaws.com"),
+            Some(EcrRegistry {
+                account: "123456789012".into(),
+                region: "us-east-1".into(),
+            })
+        );
+        assert_eq!(
+            EcrRegistry::parse("123456789012.dkr.ecr-fips.us-gov-west-1.amazonaws.com")
+                .unwrap()
+                .region,
+            "us-gov-west-1"
+        );
+        assert_eq!(
+            EcrRegistry::parse("123456789012.dkr.ecr.cn-north-1.amazonaws.com.cn")
+                .unwrap()
+                .region,
+            "cn-north-1"
+        );
+        assert_eq!(EcrRegistry::parse("public.ecr.aws"), None);
+        assert_eq!(EcrRegistry::parse("ghcr.io"), None);
+        assert_eq!(
+            EcrRegistry::parse("123456789012.dkr.ecr.us-east-1.example.com"),
+            None
+        );
+    }
+}
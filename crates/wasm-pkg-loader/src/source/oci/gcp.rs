@@ -0,0 +1,83 @@
+//! Access tokens for Google Artifact Registry and Container Registry.
+//!
+//! Tokens come from the metadata server when running on Google Cloud
+//! (including GKE workload identity), falling back to the gcloud CLI.
+
+use std::time::{Duration, SystemTime};
+
+use anyhow::Context;
+use oci_distribution::secrets::RegistryAuth;
+use serde::Deserialize;
+
+use crate::Error;
+
+use super::{cli_output, Credentials};
+
+const METADATA_TOKEN_URL: &str =
+    "http://metadata.google.internal/computeMetadata/v1/instance/service-accounts/default/token";
+
+/// The username that registries expect with an OAuth access token.
+const USERNAME: &str = "oauth2accesstoken";
+
+/// The lifetime of gcloud access tokens, which it doesn't report.
+const GCLOUD_TOKEN_LIFETIME: Duration = Duration::from_secs(60 * 60);
+
+/// Returns true if the given registry is hosted by Google Cloud, e.g.
+/// `us-west1-docker.pkg.dev` or `gcr.io`.
+pub fn is_gcp_registry(registry: &str) -> bool {
+    let host = registry.split(':').next().unwrap_or_default();
+    host.ends_with("-docker.pkg.dev") || host == "gcr.io" || host.ends_with(".gcr.io")
+}
+
+pub async fn get_credentials(client: &reqwest::Client) -> Result<Credentials, Error> {
+    let (token, expires_at) = match metadata_token(client).await {
+        Ok(token) => token,
+        Err(err) => {
+            tracing::debug!("Failed to get token from GCP metadata server: {err:#}");
+            let token = cli_output("gcloud", &["auth", "print-access-token"])
+                .await
+                .map_err(Error::CredentialError)?;
+            (token, SystemTime::now() + GCLOUD_TOKEN_LIFETIME)
+        }
+    };
+    Ok(Credentials {
+        auth: RegistryAuth::Basic(USERNAME.into(), token),
+        expires_at: Some(expires_at),
+    })
+}
+
+#[derive(Deserialize)]
+struct MetadataToken {
+    access_token: String,
+    expires_in: u64,
+}
+
+async fn metadata_token(client: &reqwest::Client) -> anyhow::Result<(String, SystemTime)> {
+    let token: MetadataToken = client
+        .get(METADATA_TOKEN_URL)
+        .header("Metadata-Flavor", "Google")
+        // Fail fast when not running on Google Cloud
+        .timeout(Duration::from_secs(2))
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await
+        .context("invalid metadata server token response")?;
+    let expires_at = SystemTime::now() + Duration::from_secs(token.expires_in);
+    Ok((token.access_token, expires_at))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_gcp_registry() {
+        assert!(is_gcp_registry("us-west1-docker.pkg.dev"));
+        assert!(is_gcp_registry("gcr.io"));
+        assert!(is_gcp_registry("eu.gcr.io"));
+        assert!(!is_gcp_registry("docker.pkg.dev.example.com"));
+        assert!(!is_gcp_registry("ghcr.io"));
+    }
+}
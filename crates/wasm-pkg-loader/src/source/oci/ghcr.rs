@@ -0,0 +1,67 @@
+//! Credentials for GitHub Container Registry in GitHub Actions workflows,
+//! which commonly expose their token as `GITHUB_TOKEN` or `GH_TOKEN`.
+
+use oci_distribution::secrets::RegistryAuth;
+
+use super::Credentials;
+
+/// Returns true if the given registry is GitHub Container Registry.
+pub fn is_ghcr_registry(registry: &str) -> bool {
+    registry.split(':').next() == Some("ghcr.io")
+}
+
+/// Returns credentials from the workflow's token, if running in GitHub
+/// Actions with one set.
+pub fn actions_credentials() -> Option<Credentials> {
+    actions_credentials_from(|name| std::env::var(name).ok())
+}
+
+fn actions_credentials_from(var: impl Fn(&str) -> Option<String>) -> Option<Credentials> {
+    if var("GITHUB_ACTIONS").as_deref() != Some("true") {
+        return None;
+    }
+    let token = ["GITHUB_TOKEN", "GH_TOKEN"]
+        .into_iter()
+        .filter_map(&var)
+        .find(|token| !token.is_empty())?;
+    // ghcr.io ignores the username for token auth, but it must be set
+    let username = var("GITHUB_ACTOR")
+        .filter(|actor| !actor.is_empty())
+        .unwrap_or_else(|| "github-actions".into());
+    Some(RegistryAuth::Basic(username, token).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    #[test]
+    fn test_actions_credentials() {
+        let env = |vars: &[(&str, &str)]| {
+            let vars: HashMap<String, String> = vars
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect();
+            actions_credentials_from(move |name| vars.get(name).cloned()).map(|c| c.auth)
+        };
+        assert_eq!(env(&[("GITHUB_TOKEN", "token")]), None);
+        assert_eq!(env(&[("GITHUB_ACTIONS", "true")]), None);
+        assert_eq!(
+            env(&[
+                ("GITHUB_ACTIONS", "true"),
+                ("GITHUB_TOKEN", ""),
+                ("GH_TOKEN", "token"),
+                ("GITHUB_ACTOR", "octocat"),
+            ]),
+            Some(RegistryAuth::Basic("octocat".into(), "token".into()))
+        );
+        assert_eq!(
+            env(&[("GITHUB_ACTIONS", "true"), ("GITHUB_TOKEN", "token")]),
+            Some(RegistryAuth::Basic("github-actions".into(), "token".into()))
+        );
+        assert!(is_ghcr_registry("ghcr.io"));
+        assert!(!is_ghcr_registry("ghcr.io.example.com"));
+    }
+}
@@ -1,9 +1,15 @@
+use std::{path::PathBuf, time::Duration};
+
 use async_trait::async_trait;
 use bytes::Bytes;
 use futures_util::{stream::BoxStream, StreamExt, TryStreamExt};
 use secrecy::SecretString;
 use semver::Version;
-use warg_client::{storage::PackageInfo, ClientError, FileSystemClient};
+use warg_client::{
+    storage::{ContentStorage, PackageInfo, PublishEntry, PublishInfo},
+    ClientError, FileSystemClient,
+};
+use warg_crypto::signing::PrivateKey;
 use warg_protocol::registry::PackageName;
 
 use crate::{
@@ -12,14 +18,21 @@ use crate::{
     Error, PackageRef, Release,
 };
 
+/// How often to poll for a published record to be accepted.
+const PUBLISH_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
 #[derive(Clone, Debug, Default)]
 pub struct WargConfig {
     pub client_config: Option<warg_client::Config>,
     pub auth_token: Option<SecretString>,
+    /// A file containing the encoded key used to sign published records. If
+    /// unset, the key is taken from the warg keyring.
+    pub signing_key_file: Option<PathBuf>,
 }
 
 pub struct WargSource {
     client: FileSystemClient,
+    signing_key: Option<PrivateKey>,
 }
 
 impl WargSource {
@@ -32,6 +45,7 @@ impl WargSource {
         let WargConfig {
             client_config,
             auth_token,
+            signing_key_file,
         } = config;
 
         let client_config = if let Some(client_config) = client_config {
@@ -43,7 +57,22 @@ impl WargSource {
         };
         let client =
             FileSystemClient::new_with_config(Some(url.as_str()), &client_config, auth_token).await?;
-        Ok(Self { client })
+        let signing_key = signing_key_file
+            .map(|path| {
+                let encoded = std::fs::read_to_string(&path).map_err(|err| {
+                    Error::InvalidConfig(anyhow::anyhow!(
+                        "failed to read signing key file {path:?}: {err}"
+                    ))
+                })?;
+                PrivateKey::decode(encoded.trim().to_string()).map_err(|err| {
+                    Error::InvalidConfig(anyhow::anyhow!("invalid signing key in {path:?}: {err}"))
+                })
+            })
+            .transpose()?;
+        Ok(Self {
+            client,
+            signing_key,
+        })
     }
 
     async fn fetch_package_info(&mut self, package: &PackageRef) -> Result<PackageInfo, Error> {
@@ -56,12 +85,14 @@ impl WargSource {
 impl PackageSource for WargSource {
     async fn list_all_versions(&mut self, package: &PackageRef) -> Result<Vec<VersionInfo>, Error> {
         let info = self.fetch_package_info(package).await?;
+        // Package logs record yanks, but have no deprecation notices
         Ok(info
             .state
             .releases()
             .map(|r| VersionInfo {
                 version: r.version.clone(),
                 yanked: r.yanked(),
+                deprecation: None,
             })
             .collect())
     }
@@ -90,7 +121,7 @@ impl PackageSource for WargSource {
         &mut self,
         package: &PackageRef,
         release: &Release,
-    ) -> Result<BoxStream<Result<Bytes, Error>>, Error> {
+    ) -> Result<BoxStream<'static, Result<Bytes, Error>>, Error> {
         self.stream_content(package, release).await
     }
 
@@ -98,7 +129,7 @@ impl PackageSource for WargSource {
         &mut self,
         package: &PackageRef,
         release: &Release,
-    ) -> Result<BoxStream<Result<Bytes, Error>>, Error> {
+    ) -> Result<BoxStream<'static, Result<Bytes, Error>>, Error> {
         let package_name = package.try_into()?;
 
         // warg client validates the digest matches the content
@@ -108,6 +139,70 @@ impl PackageSource for WargSource {
             .await?;
         Ok(stream.map_err(Into::into).boxed())
     }
+
+    async fn publish(
+        &mut self,
+        package: &PackageRef,
+        version: &Version,
+        content: Bytes,
+    ) -> Result<Release, Error> {
+        let package_name: PackageName = package.try_into()?;
+        let initializing = match self.client.package(&package_name).await {
+            Ok(info) => {
+                if info.state.release(version).is_some() {
+                    return Err(Error::VersionExists(version.clone()));
+                }
+                false
+            }
+            Err(ClientError::PackageDoesNotExist { .. }) => true,
+            Err(err) => return Err(err.into()),
+        };
+
+        // The client uploads content from its own content storage
+        let content_digest = self
+            .client
+            .content()
+            .store_content(
+                Box::pin(futures_util::stream::once(async move { Ok(content) })),
+                None,
+            )
+            .await
+            .map_err(ClientError::Other)?;
+
+        let mut entries = vec![];
+        if initializing {
+            entries.push(PublishEntry::Init);
+        }
+        entries.push(PublishEntry::Release {
+            version: version.clone(),
+            content: content_digest.clone(),
+        });
+        let publish_info = PublishInfo {
+            name: package_name.clone(),
+            head: None,
+            entries,
+        };
+        tracing::debug!(?publish_info, "Publishing warg record");
+        let record_id = match &self.signing_key {
+            Some(signing_key) => {
+                self.client
+                    .publish_with_info(signing_key, publish_info)
+                    .await?
+            }
+            None => {
+                self.client
+                    .sign_with_keyring_and_publish(Some(publish_info))
+                    .await?
+            }
+        };
+        self.client
+            .wait_for_publish(&package_name, &record_id, PUBLISH_POLL_INTERVAL)
+            .await?;
+        Ok(Release {
+            version: version.clone(),
+            content_digest: content_digest.to_string().parse()?,
+        })
+    }
 }
 
 impl TryFrom<&PackageRef> for PackageName {
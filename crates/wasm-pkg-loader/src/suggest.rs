@@ -0,0 +1,151 @@
+//! "Did you mean" suggestions for packages and versions that weren't found.
+
+use semver::Version;
+
+use crate::{source::VersionInfo, PackageRef};
+
+/// A suggested alternative to a package or version that wasn't found.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Suggestion {
+    /// A similarly named package in the same namespace.
+    Package(PackageRef),
+    /// The nearest existing version of the package.
+    Version(PackageRef, Version),
+}
+
+impl std::fmt::Display for Suggestion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Package(package) => write!(f, "did you mean `{package}`?"),
+            Self::Version(package, version) => write!(f, "did you mean `{package}@{version}`?"),
+        }
+    }
+}
+
+/// Returns the candidate with the name closest to the given package's, if
+/// close enough to be a likely typo.
+pub(crate) fn closest_package<'a>(
+    package: &PackageRef,
+    candidates: impl IntoIterator<Item = &'a PackageRef>,
+) -> Option<&'a PackageRef> {
+    closest(
+        package.name().as_ref(),
+        candidates
+            .into_iter()
+            .filter(|candidate| *candidate != package),
+        |candidate| candidate.name().as_ref(),
+    )
+}
+
+/// Returns the candidate closest to `name`, if close enough to be a likely
+/// typo.
+pub(crate) fn closest_name<'a>(
+    name: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> Option<&'a str> {
+    closest(name, candidates, |candidate| candidate)
+}
+
+/// Returns the candidate whose name (as given by `name_of`) is closest to
+/// `name`. A candidate is only considered if within an edit distance of a
+/// third of the longer name's length, or if either name is a prefix of the
+/// other, e.g. `warn` for `warning`.
+fn closest<T>(
+    name: &str,
+    candidates: impl IntoIterator<Item = T>,
+    name_of: impl for<'b> Fn(&'b T) -> &'b str,
+) -> Option<T> {
+    candidates
+        .into_iter()
+        .filter_map(|candidate| {
+            let other = name_of(&candidate);
+            let distance = edit_distance(name, other);
+            let close = distance <= (name.len().max(other.len()) / 3).max(1)
+                || (!name.is_empty()
+                    && !other.is_empty()
+                    && (name.starts_with(other) || other.starts_with(name)));
+            close.then_some((distance, candidate))
+        })
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// Returns the existing version nearest to the given one, preferring versions
+/// that aren't yanked and, among equally near versions, the newest.
+pub(crate) fn nearest_version<'a>(
+    version: &Version,
+    versions: &'a [VersionInfo],
+) -> Option<&'a Version> {
+    let distance = |other: &Version| {
+        (
+            version.major.abs_diff(other.major),
+            version.minor.abs_diff(other.minor),
+            version.patch.abs_diff(other.patch),
+            version.pre != other.pre,
+        )
+    };
+    versions
+        .iter()
+        .filter(|info| info.version != *version)
+        .min_by(|a, b| {
+            (a.yanked, distance(&a.version))
+                .cmp(&(b.yanked, distance(&b.version)))
+                .then_with(|| b.version.cmp(&a.version))
+        })
+        .map(|info| &info.version)
+}
+
+/// Returns the Levenshtein distance between the given strings.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut curr = vec![0; b.len() + 1];
+    for (i, a_char) in a.chars().enumerate() {
+        curr[0] = i + 1;
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = prev[j] + usize::from(a_char != *b_char);
+            curr[j + 1] = substitution.min(prev[j + 1] + 1).min(curr[j] + 1);
+        }
+        std::mem::swap(&mut prev, &mut curr);
+    }
+    prev[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_suggestions() {
+        assert_eq!(edit_distance("http", "htpp"), 1);
+        assert_eq!(edit_distance("", "io"), 2);
+        assert_eq!(closest_name("warning", ["error", "warn"]), Some("warn"));
+        assert_eq!(closest_name("typs", ["types", "worlds"]), Some("types"));
+        assert_eq!(closest_name("oci", ["warg", "local"]), None);
+
+        let packages: Vec<PackageRef> = ["wasi:http", "wasi:io", "wasi:clocks"]
+            .iter()
+            .map(|package| package.parse().unwrap())
+            .collect();
+        let closest = |package: &str| closest_package(&package.parse().unwrap(), &packages);
+        assert_eq!(closest("wasi:htpp"), Some(&packages[0]));
+        assert_eq!(closest("wasi:clock"), Some(&packages[2]));
+        assert_eq!(closest("wasi:filesystem"), None);
+        assert_eq!(closest("wasi:io"), None);
+
+        let versions: Vec<VersionInfo> = [("0.2.0", false), ("0.2.1", true), ("1.0.0", false)]
+            .iter()
+            .map(|(version, yanked)| VersionInfo {
+                version: version.parse().unwrap(),
+                yanked: *yanked,
+                deprecation: None,
+            })
+            .collect();
+        let nearest = |version: &str| {
+            nearest_version(&version.parse().unwrap(), &versions).map(ToString::to_string)
+        };
+        assert_eq!(nearest("0.2.2").as_deref(), Some("0.2.0"));
+        assert_eq!(nearest("1.0.1").as_deref(), Some("1.0.0"));
+        assert_eq!(nearest("0.2.0").as_deref(), Some("1.0.0"));
+    }
+}
@@ -0,0 +1,97 @@
+//! Publisher identity pinning: per-namespace patterns the verified identity
+//! of a release's signer must match, e.g. `https://github.com/WebAssembly/*`.
+//!
+//! Identities come from the release's verified OpenPGP signatures (see
+//! [`crate::openpgp`]), the signatures this client verifies itself.
+
+use crate::{Error, PackageRef};
+
+/// Returns Ok if any of the given verified signer identities matches one of
+/// the allowed publisher patterns.
+pub fn check_publishers(
+    package: &PackageRef,
+    publishers: &[String],
+    identities: &[String],
+) -> Result<(), Error> {
+    let allowed = identities.iter().any(|identity| {
+        publishers
+            .iter()
+            .any(|pattern| matches_pattern(pattern, identity))
+    });
+    if allowed {
+        return Ok(());
+    }
+    let signers = if identities.is_empty() {
+        "no signer identity".to_string()
+    } else {
+        format!("signed by {}", identities.join(", "))
+    };
+    Err(Error::SignatureRejected(
+        package.clone(),
+        format!(
+            "{signers}, which doesn't match the allowed publishers for namespace {:?} ({})",
+            package.namespace().to_string(),
+            publishers.join(", ")
+        ),
+    ))
+}
+
+/// Returns true if the identity matches the pattern, in which `*` matches
+/// any sequence of characters. Matching is case-sensitive.
+fn matches_pattern(pattern: &str, identity: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = identity.strip_prefix(first) else {
+        return false;
+    };
+    let mut parts: Vec<_> = parts.collect();
+    let Some(last) = parts.pop() else {
+        // No wildcard
+        return rest.is_empty();
+    };
+    for part in parts {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_pattern() {
+        let pattern = "https://github.com/WebAssembly/*";
+        assert!(matches_pattern(
+            pattern,
+            "https://github.com/WebAssembly/wasi-io"
+        ));
+        assert!(!matches_pattern(
+            pattern,
+            "https://github.com/other/wasi-io"
+        ));
+        assert!(matches_pattern("*@example.com", "releases@example.com"));
+        assert!(!matches_pattern(
+            "*@example.com",
+            "releases@example.com.evil"
+        ));
+        assert!(matches_pattern("a*b*c", "abc"));
+        assert!(!matches_pattern("ab*bc", "abc"));
+        assert!(matches_pattern("exact", "exact"));
+        assert!(!matches_pattern("exact", "exactly"));
+    }
+
+    #[test]
+    fn test_check_publishers() {
+        let package: PackageRef = "wasi:io".parse().unwrap();
+        let publishers = ["*@bytecodealliance.org".to_string()];
+        assert!(
+            check_publishers(&package, &publishers, &["ci@bytecodealliance.org".into()]).is_ok()
+        );
+        assert!(check_publishers(&package, &publishers, &["me@example.com".into()]).is_err());
+        assert!(check_publishers(&package, &publishers, &[]).is_err());
+    }
+}
@@ -0,0 +1,189 @@
+//! GitHub Actions output: workflow command annotations for errors and
+//! warnings, and a markdown job summary of fetched packages.
+
+use std::{
+    fmt::Write as _,
+    io::Write as _,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use anyhow::Context;
+use tracing::{field::Field, Event, Level, Subscriber};
+use tracing_subscriber::{field::Visit, layer::Context as LayerContext, Layer};
+use wasm_pkg_loader::{ContentDigest, PackageRef, Version};
+
+use crate::policy::PolicyViolation;
+
+/// The packages fetched by a command, written as a job summary.
+#[derive(Debug, Default)]
+pub struct JobSummary {
+    fetched: Mutex<Vec<FetchedPackage>>,
+}
+
+#[derive(Debug)]
+struct FetchedPackage {
+    package: PackageRef,
+    version: Version,
+    digest: ContentDigest,
+    output: PathBuf,
+}
+
+impl JobSummary {
+    /// Records a package release written to the given output path.
+    pub fn record(
+        &self,
+        package: &PackageRef,
+        version: &Version,
+        digest: &ContentDigest,
+        output: &Path,
+    ) {
+        self.fetched.lock().unwrap().push(FetchedPackage {
+            package: package.clone(),
+            version: version.clone(),
+            digest: digest.clone(),
+            output: output.to_path_buf(),
+        });
+    }
+
+    /// Appends the summary to the job summary file given by
+    /// `GITHUB_STEP_SUMMARY`, if set.
+    pub fn write(&self, error: Option<&anyhow::Error>) -> anyhow::Result<()> {
+        let Some(path) = std::env::var_os("GITHUB_STEP_SUMMARY") else {
+            tracing::debug!("GITHUB_STEP_SUMMARY not set; not writing job summary");
+            return Ok(());
+        };
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("Failed to open job summary file {path:?}"))?;
+        file.write_all(self.render(error).as_bytes())
+            .with_context(|| format!("Failed to write job summary file {path:?}"))
+    }
+
+    fn render(&self, error: Option<&anyhow::Error>) -> String {
+        let fetched = self.fetched.lock().unwrap();
+        let mut summary = String::from("### wkg\n\n");
+        if let Some(err) = error {
+            writeln!(summary, "**{}:** {err:#}\n", error_title(err)).unwrap();
+        }
+        if fetched.is_empty() {
+            summary.push_str("No packages fetched.\n\n");
+            return summary;
+        }
+        summary.push_str("| Package | Version | Digest | Output |\n");
+        summary.push_str("| --- | --- | --- | --- |\n");
+        for fetched in fetched.iter() {
+            writeln!(
+                summary,
+                "| `{}` | {} | `{}` | `{}` |",
+                fetched.package,
+                fetched.version,
+                fetched.digest,
+                fetched.output.display()
+            )
+            .unwrap();
+        }
+        summary.push('\n');
+        summary
+    }
+}
+
+/// Prints an `::error` workflow command for the given error.
+pub fn annotate_error(err: &anyhow::Error) {
+    println!(
+        "::error title={}::{}",
+        escape_property(error_title(err)),
+        escape_data(&format!("{err:#}"))
+    );
+}
+
+/// Returns an annotation title describing the kind of the given error.
+fn error_title(err: &anyhow::Error) -> &'static str {
+    use wasm_pkg_loader::Error;
+    for cause in err.chain() {
+        if cause.is::<PolicyViolation>() {
+            return "Policy violation";
+        }
+        match cause.downcast_ref::<Error>() {
+            Some(Error::InvalidContent(_) | Error::InvalidContentDigest(_)) => {
+                return "Content verification failed"
+            }
+            Some(Error::VersionYanked(_)) => return "Yanked version",
+            Some(
+                Error::ProvenanceRejected(..)
+                | Error::SignatureRejected(..)
+                | Error::RegistryNotAllowed(..)
+                | Error::RegistryRedirectNotAllowed(..),
+            ) => return "Policy violation",
+            _ => (),
+        }
+    }
+    "wkg failed"
+}
+
+/// A tracing layer printing warnings as `::warning` workflow commands.
+pub struct AnnotationLayer;
+
+impl<S: Subscriber> Layer<S> for AnnotationLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: LayerContext<'_, S>) {
+        if *event.metadata().level() != Level::WARN {
+            return;
+        }
+        let mut visitor = MessageVisitor(String::new());
+        event.record(&mut visitor);
+        println!("::warning::{}", escape_data(&visitor.0));
+    }
+}
+
+struct MessageVisitor(String);
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            self.0 = format!("{value:?}");
+        }
+    }
+}
+
+/// Escapes a workflow command message.
+fn escape_data(data: &str) -> String {
+    data.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Escapes a workflow command property value.
+fn escape_property(value: &str) -> String {
+    escape_data(value).replace(':', "%3A").replace(',', "%2C")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_job_summary() {
+        let summary = JobSummary::default();
+        assert!(summary.render(None).contains("No packages fetched."));
+
+        summary.record(
+            &"wasi:http".parse().unwrap(),
+            &"0.2.0".parse().unwrap(),
+            &format!("sha256:{}", "0".repeat(64)).parse().unwrap(),
+            Path::new("deps/wasi-http.wit"),
+        );
+        let err = anyhow::Error::new(wasm_pkg_loader::Error::VersionYanked(
+            "0.1.0".parse().unwrap(),
+        ))
+        .context("Failed to get");
+        let rendered = summary.render(Some(&err));
+        assert!(rendered.contains("**Yanked version:** Failed to get: version yanked: 0.1.0"));
+        assert!(rendered.contains("| `wasi:http` | 0.2.0 | `sha256:000"));
+        assert!(rendered.contains("| `deps/wasi-http.wit` |"));
+
+        assert_eq!(escape_data("50%\nof it"), "50%25%0Aof it");
+        assert_eq!(escape_property("a: b, c"), "a%3A b%2C c");
+    }
+}
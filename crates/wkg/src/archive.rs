@@ -0,0 +1,109 @@
+//! Gzipped tar archives, which some releases (e.g. WIT source trees) are
+//! packaged as instead of raw Wasm.
+
+use std::path::Path;
+
+use anyhow::Context;
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Returns true if the given content looks like a gzipped (tar) archive.
+pub fn is_archive(content: &[u8]) -> bool {
+    content.starts_with(&GZIP_MAGIC)
+}
+
+/// Unpacks a gzipped tar archive into the given directory, replacing it if
+/// it exists. Nothing is written to `output` unless unpacking succeeds.
+pub fn unpack(content: &[u8], output: &Path) -> anyhow::Result<()> {
+    let parent = match output.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let tmp_dir = tempfile::Builder::new()
+        .prefix(".wkg-unpack")
+        .tempdir_in(parent)
+        .with_context(|| format!("Failed to create directory in {parent:?}"))?;
+
+    // Entries with absolute paths or `..` components are skipped.
+    tar::Archive::new(GzDecoder::new(content))
+        .unpack(tmp_dir.path())
+        .context("Failed to unpack archive")?;
+
+    if output.is_dir() {
+        std::fs::remove_dir_all(output).with_context(|| format!("Failed to remove {output:?}"))?;
+    }
+    std::fs::rename(tmp_dir.path(), output)
+        .with_context(|| format!("Failed to write directory {output:?}"))?;
+    Ok(())
+}
+
+/// Packs the contents of the given directory into a gzipped tar archive.
+/// Entries are sorted, with normalized metadata, so packing the same tree
+/// always produces the same archive.
+pub fn pack(dir: &Path) -> anyhow::Result<Vec<u8>> {
+    let mut builder = tar::Builder::new(GzEncoder::new(vec![], Compression::default()));
+    builder.mode(tar::HeaderMode::Deterministic);
+    builder.follow_symlinks(false);
+    append_dir(&mut builder, dir, Path::new(""))?;
+    let content = builder
+        .into_inner()
+        .and_then(|encoder| encoder.finish())
+        .with_context(|| format!("Failed to pack {dir:?}"))?;
+    Ok(content)
+}
+
+fn append_dir(
+    builder: &mut tar::Builder<GzEncoder<Vec<u8>>>,
+    dir: &Path,
+    prefix: &Path,
+) -> anyhow::Result<()> {
+    let mut entries = std::fs::read_dir(dir)
+        .with_context(|| format!("Failed to read {dir:?}"))?
+        .collect::<Result<Vec<_>, _>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+    for entry in entries {
+        let path = entry.path();
+        let name = prefix.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            builder.append_dir(&name, &path)?;
+            append_dir(builder, &path, &name)?;
+        } else {
+            builder
+                .append_path_with_name(&path, &name)
+                .with_context(|| format!("Failed to pack {path:?}"))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pack_unpack() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("src");
+        std::fs::create_dir_all(src.join("deps/test-dep")).unwrap();
+        std::fs::write(src.join("package.wit"), "package test:pkg;\n").unwrap();
+        std::fs::write(src.join("deps/test-dep/package.wit"), "package test:dep;\n").unwrap();
+
+        let content = pack(&src).unwrap();
+        assert!(is_archive(&content));
+        assert_eq!(pack(&src).unwrap(), content, "packing isn't deterministic");
+
+        let out = dir.path().join("out");
+        unpack(&content, &out).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(out.join("package.wit")).unwrap(),
+            "package test:pkg;\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(out.join("deps/test-dep/package.wit")).unwrap(),
+            "package test:dep;\n"
+        );
+
+        assert!(!is_archive(b"\0asm\x01\0\0\0"));
+    }
+}
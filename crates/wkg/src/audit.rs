@@ -0,0 +1,547 @@
+//! Auditing a project's dependencies against an advisory database of
+//! known-vulnerable versions and revoked releases.
+//!
+//! The database is a JSON document of the form:
+//!
+//! ```json
+//! {
+//!   "advisories": [
+//!     {
+//!       "id": "WKG-2024-0001",
+//!       "package": "example:pkg",
+//!       "kind": "vulnerability",
+//!       "versions": [">=1.0.0, <1.2.3"],
+//!       "summary": "Out-of-bounds read in parser",
+//!       "url": "https://example.com/advisories/WKG-2024-0001"
+//!     },
+//!     {
+//!       "id": "WKG-2024-0002",
+//!       "package": "example:other",
+//!       "kind": "revoked",
+//!       "digests": ["sha256:..."]
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! An advisory with neither `versions` nor `digests` affects every version.
+//!
+//! Packages from namespaces the organization policy doesn't allow are also
+//! reported. Findings can be written as SARIF for code scanning dashboards.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context};
+use clap::Args;
+use semver::VersionReq;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use wasm_pkg_loader::{ContentDigest, PackageRef, Version};
+use wit_component::DecodedWasm;
+
+use crate::{lock::LockFile, manifest::Manifest, GlobalArgs};
+
+#[derive(Args, Debug)]
+pub struct AuditCommand {
+    /// The advisory database to check against; a file path or an http(s)
+    /// URL.
+    #[arg(long, value_name = "PATH_OR_URL", env = "WKG_ADVISORY_DB")]
+    db: String,
+
+    /// Audit the packages in this dependencies directory (as written by
+    /// `wkg sync`) instead of the lock file.
+    #[arg(long)]
+    deps_dir: Option<PathBuf>,
+
+    /// Advisory IDs to ignore.
+    #[arg(long, value_name = "ID")]
+    ignore: Vec<String>,
+
+    /// Also write the findings to this file as a SARIF 2.1.0 log, e.g. for
+    /// GitHub code scanning.
+    #[arg(long, value_name = "FILE")]
+    sarif: Option<PathBuf>,
+}
+
+impl AuditCommand {
+    pub async fn run(self, global: &GlobalArgs) -> anyhow::Result<()> {
+        let db = AdvisoryDb::load(&self.db).await?;
+        let policy = global.load_policy()?;
+        let packages = match &self.deps_dir {
+            Some(deps_dir) => audited_deps(deps_dir).await?,
+            None => {
+                let manifest = Manifest::load_current()?;
+                let lock_path = LockFile::path_for(manifest.root_dir());
+                let lock = LockFile::load(&lock_path)?.with_context(|| {
+                    format!("'{}' not found; run `wkg lock` first", lock_path.display())
+                })?;
+                // Relative to the working directory, as SARIF consumers
+                // expect for repository files
+                let cwd = std::env::current_dir()?;
+                let location = lock_path.strip_prefix(&cwd).unwrap_or(&lock_path);
+                lock.packages
+                    .into_iter()
+                    .map(|locked| AuditedPackage {
+                        source: format!("{}@{}", locked.name, locked.version),
+                        location: location.display().to_string(),
+                        package: Some((locked.name, locked.version)),
+                        digest: locked.digest,
+                    })
+                    .collect()
+            }
+        };
+
+        let mut findings: Vec<Finding> = packages
+            .iter()
+            .flat_map(|audited| {
+                db.advisories
+                    .iter()
+                    .filter(|advisory| !self.ignore.contains(&advisory.id))
+                    .filter(|advisory| advisory.affects(audited))
+                    .map(|advisory| Finding {
+                        source: &audited.source,
+                        location: &audited.location,
+                        issue: Issue::Advisory(advisory),
+                    })
+            })
+            .collect();
+        for audited in &packages {
+            if let Some((package, _)) = &audited.package {
+                if let Err(err) = policy.check_package(package) {
+                    findings.push(Finding {
+                        source: &audited.source,
+                        location: &audited.location,
+                        issue: Issue::PolicyViolation(err.to_string()),
+                    });
+                }
+            }
+        }
+
+        if let Some(path) = &self.sarif {
+            let sarif = serde_json::to_string_pretty(&sarif(&findings))?;
+            std::fs::write(path, sarif).with_context(|| format!("Failed to write {path:?}"))?;
+        }
+        if global.json {
+            println!("{}", serde_json::to_string_pretty(&findings)?);
+        } else {
+            for Finding { source, issue, .. } in &findings {
+                match issue {
+                    Issue::Advisory(advisory) => {
+                        println!("{source}: {} ({})", advisory.id, advisory.kind);
+                        if let Some(summary) = &advisory.summary {
+                            println!("  {summary}");
+                        }
+                        if let Some(url) = &advisory.url {
+                            println!("  {url}");
+                        }
+                    }
+                    Issue::PolicyViolation(message) => {
+                        println!("{source}: policy violation");
+                        println!("  {message}");
+                    }
+                }
+            }
+            println!(
+                "Audited {} packages against {} advisories",
+                packages.len(),
+                db.advisories.len()
+            );
+        }
+        if !findings.is_empty() {
+            bail!(
+                "Found {} advisories or policy violations affecting dependencies",
+                findings.len()
+            );
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct AdvisoryDb {
+    advisories: Vec<Advisory>,
+}
+
+impl AdvisoryDb {
+    async fn load(location: &str) -> anyhow::Result<Self> {
+        let contents = if location.starts_with("https://") || location.starts_with("http://") {
+            reqwest::get(location)
+                .await
+                .and_then(|resp| resp.error_for_status())
+                .with_context(|| format!("Failed to fetch advisory database {location:?}"))?
+                .bytes()
+                .await?
+                .to_vec()
+        } else {
+            std::fs::read(location)
+                .with_context(|| format!("Failed to read advisory database {location:?}"))?
+        };
+        serde_json::from_slice(&contents)
+            .with_context(|| format!("Invalid advisory database {location:?}"))
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct Advisory {
+    id: String,
+    #[serde(with = "serde_display")]
+    package: PackageRef,
+    kind: AdvisoryKind,
+    /// Requirements matching the affected versions.
+    #[serde(default, with = "serde_display::vec")]
+    versions: Vec<VersionReq>,
+    /// Digests of affected content.
+    #[serde(default, with = "serde_display::vec")]
+    digests: Vec<ContentDigest>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    summary: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    url: Option<String>,
+}
+
+impl Advisory {
+    fn affects(&self, audited: &AuditedPackage) -> bool {
+        if let Some(digest) = &audited.digest {
+            if self.digests.contains(digest) {
+                return true;
+            }
+        }
+        let Some((package, version)) = &audited.package else {
+            return false;
+        };
+        if package != &self.package {
+            return false;
+        }
+        if self.versions.is_empty() {
+            return self.digests.is_empty();
+        }
+        self.versions.iter().any(|req| req.matches(version))
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum AdvisoryKind {
+    /// A version with a known vulnerability.
+    Vulnerability,
+    /// A release withdrawn by its publisher.
+    Revoked,
+}
+
+impl std::fmt::Display for AdvisoryKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Vulnerability => "vulnerability",
+            Self::Revoked => "revoked",
+        })
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Finding<'a> {
+    /// The affected package version or dependency file.
+    source: &'a str,
+    /// The file the package was audited in, for SARIF results.
+    #[serde(skip)]
+    location: &'a str,
+    #[serde(flatten)]
+    issue: Issue<'a>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Issue<'a> {
+    Advisory(&'a Advisory),
+    PolicyViolation(String),
+}
+
+/// The SARIF rule ID of policy violation results.
+const POLICY_RULE_ID: &str = "policy-violation";
+
+/// Returns a SARIF 2.1.0 log of the given findings, with a rule for each
+/// advisory found.
+fn sarif(findings: &[Finding]) -> Value {
+    let mut rules: Vec<Value> = vec![];
+    let mut rule_ids: Vec<&str> = vec![];
+    let results: Vec<_> = findings
+        .iter()
+        .map(|finding| {
+            let (rule_id, level, message) = match &finding.issue {
+                Issue::Advisory(advisory) => {
+                    let level = match advisory.kind {
+                        AdvisoryKind::Vulnerability => "error",
+                        AdvisoryKind::Revoked => "warning",
+                    };
+                    let message = match &advisory.summary {
+                        Some(summary) => format!("{}: {summary}", finding.source),
+                        None => format!("{} is affected by {}", finding.source, advisory.id),
+                    };
+                    (advisory.id.as_str(), level, message)
+                }
+                Issue::PolicyViolation(message) => (POLICY_RULE_ID, "error", message.clone()),
+            };
+            if !rule_ids.contains(&rule_id) {
+                rule_ids.push(rule_id);
+                rules.push(sarif_rule(&finding.issue));
+            }
+            json!({
+                "ruleId": rule_id,
+                "level": level,
+                "message": {"text": message},
+                "locations": [{
+                    "physicalLocation": {"artifactLocation": {"uri": finding.location}},
+                }],
+            })
+        })
+        .collect();
+    json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "wkg",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules,
+                },
+            },
+            "results": results,
+        }],
+    })
+}
+
+fn sarif_rule(issue: &Issue) -> Value {
+    match issue {
+        Issue::Advisory(advisory) => {
+            let mut rule = json!({
+                "id": advisory.id,
+                "name": format!("{} {}", advisory.package, advisory.kind),
+                "properties": {"tags": [advisory.kind.to_string()]},
+            });
+            if let Some(summary) = &advisory.summary {
+                rule["shortDescription"] = json!({"text": summary});
+            }
+            if let Some(url) = &advisory.url {
+                rule["helpUri"] = json!(url);
+            }
+            rule
+        }
+        Issue::PolicyViolation(_) => json!({
+            "id": POLICY_RULE_ID,
+            "name": "organization policy violation",
+            "shortDescription": {"text": "Package not allowed by the organization policy"},
+        }),
+    }
+}
+
+/// A package to audit.
+#[derive(Debug)]
+struct AuditedPackage {
+    /// How the package is described in findings.
+    source: String,
+    /// The file the package was found in: the lock file or the dependency
+    /// file itself.
+    location: String,
+    /// The package and version, if known.
+    package: Option<(PackageRef, Version)>,
+    digest: Option<ContentDigest>,
+}
+
+/// Returns the packages in a dependencies directory. WIT packages are
+/// identified by their package declaration; other files can only be matched
+/// by content digest.
+async fn audited_deps(deps_dir: &Path) -> anyhow::Result<Vec<AuditedPackage>> {
+    let mut paths = std::fs::read_dir(deps_dir)
+        .with_context(|| format!("Failed to read {deps_dir:?}"))?
+        .map(|entry| Ok(entry?.path()))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    paths.sort();
+
+    let mut packages = vec![];
+    for path in paths {
+        let audited = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("wit") => {
+                let wit = std::fs::read_to_string(&path)
+                    .with_context(|| format!("Failed to read {path:?}"))?;
+                AuditedPackage {
+                    source: path.display().to_string(),
+                    location: path.display().to_string(),
+                    package: wit_package_decl(&wit),
+                    digest: None,
+                }
+            }
+            Some("wasm") => {
+                let content =
+                    std::fs::read(&path).with_context(|| format!("Failed to read {path:?}"))?;
+                let package = match wit_component::decode(&content) {
+                    Ok(DecodedWasm::WitPackage(resolve, pkg)) => {
+                        let name = &resolve.packages[pkg].name;
+                        name.version.clone().and_then(|version| {
+                            let package =
+                                format!("{}:{}", name.namespace, name.name).parse().ok()?;
+                            Some((package, version))
+                        })
+                    }
+                    _ => None,
+                };
+                AuditedPackage {
+                    source: path.display().to_string(),
+                    location: path.display().to_string(),
+                    package,
+                    digest: Some(ContentDigest::sha256_from_file(&path).await?),
+                }
+            }
+            _ => continue,
+        };
+        if audited.package.is_none() && audited.digest.is_none() {
+            tracing::warn!(?path, "Couldn't identify dependency; skipping");
+            continue;
+        }
+        packages.push(audited);
+    }
+    Ok(packages)
+}
+
+/// Returns the versioned package named by a WIT document's `package`
+/// declaration.
+fn wit_package_decl(wit: &str) -> Option<(PackageRef, Version)> {
+    let decl = wit
+        .lines()
+        .map(str::trim)
+        .find_map(|line| line.strip_prefix("package "))?;
+    let spec = decl.trim_end_matches(|c: char| c == ';' || c == '{' || c.is_whitespace());
+    let (package, version) = spec.split_once('@')?;
+    Some((package.parse().ok()?, version.parse().ok()?))
+}
+
+/// Serializes values through their `Display` and `FromStr` impls.
+mod serde_display {
+    use std::{fmt::Display, str::FromStr};
+
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<T: Display, S: Serializer>(value: &T, ser: S) -> Result<S::Ok, S::Error> {
+        ser.collect_str(value)
+    }
+
+    pub fn deserialize<'de, T, D>(de: D) -> Result<T, D::Error>
+    where
+        T: FromStr,
+        T::Err: Display,
+        D: Deserializer<'de>,
+    {
+        String::deserialize(de)?.parse().map_err(D::Error::custom)
+    }
+
+    pub mod vec {
+        use super::*;
+
+        pub fn serialize<T: Display, S: Serializer>(
+            values: &[T],
+            ser: S,
+        ) -> Result<S::Ok, S::Error> {
+            ser.collect_seq(values.iter().map(ToString::to_string))
+        }
+
+        pub fn deserialize<'de, T, D>(de: D) -> Result<Vec<T>, D::Error>
+        where
+            T: FromStr,
+            T::Err: Display,
+            D: Deserializer<'de>,
+        {
+            Vec::<String>::deserialize(de)?
+                .into_iter()
+                .map(|s| s.parse().map_err(D::Error::custom))
+                .collect()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn audited(spec: &str) -> AuditedPackage {
+        let (package, version) = spec.split_once('@').unwrap();
+        AuditedPackage {
+            source: spec.into(),
+            location: "wkg.lock".into(),
+            package: Some((package.parse().unwrap(), version.parse().unwrap())),
+            digest: None,
+        }
+    }
+
+    #[test]
+    fn test_advisory_affects() {
+        let db: AdvisoryDb = serde_json::from_str(
+            r#"{"advisories": [
+                {"id": "A-1", "package": "test:pkg", "kind": "vulnerability", "versions": [">=1.0.0, <1.2.3"]},
+                {"id": "A-2", "package": "test:all", "kind": "revoked"},
+                {"id": "A-3", "package": "test:dig", "kind": "revoked", "digests": ["sha256:4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b"]}
+            ]}"#,
+        )
+        .unwrap();
+        let [vuln, all, by_digest] = &db.advisories[..] else {
+            panic!("expected 3 advisories");
+        };
+        assert!(vuln.affects(&audited("test:pkg@1.2.0")));
+        assert!(!vuln.affects(&audited("test:pkg@1.2.3")));
+        assert!(!vuln.affects(&audited("test:other@1.0.0")));
+        assert!(all.affects(&audited("test:all@0.1.0")));
+
+        let mut dig = audited("test:dig@1.0.0");
+        assert!(!by_digest.affects(&dig));
+        dig.digest = Some(by_digest.digests[0].clone());
+        assert!(by_digest.affects(&dig));
+    }
+
+    #[test]
+    fn test_wit_package_decl() {
+        let wit = "// comment\npackage wasi:io@0.2.0;\n\ninterface streams {}\n";
+        assert_eq!(
+            wit_package_decl(wit),
+            Some(("wasi:io".parse().unwrap(), "0.2.0".parse().unwrap()))
+        );
+        assert_eq!(wit_package_decl("package wasi:io;\n"), None);
+    }
+
+    #[test]
+    fn test_sarif() {
+        let db: AdvisoryDb = serde_json::from_str(
+            r#"{"advisories": [
+                {"id": "A-1", "package": "test:pkg", "kind": "vulnerability", "summary": "Bad parser"},
+                {"id": "A-2", "package": "test:pkg", "kind": "revoked"}
+            ]}"#,
+        )
+        .unwrap();
+        let finding = |issue| Finding {
+            source: "test:pkg@1.0.0",
+            location: "wkg.lock",
+            issue,
+        };
+        let findings = [
+            finding(Issue::Advisory(&db.advisories[0])),
+            finding(Issue::Advisory(&db.advisories[1])),
+            finding(Issue::Advisory(&db.advisories[0])),
+            finding(Issue::PolicyViolation("not allowed".into())),
+        ];
+        let sarif = sarif(&findings);
+        let run = &sarif["runs"][0];
+        let rule_ids: Vec<_> = run["tool"]["driver"]["rules"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|rule| rule["id"].as_str().unwrap())
+            .collect();
+        assert_eq!(rule_ids, ["A-1", "A-2", POLICY_RULE_ID]);
+        let result = &run["results"][0];
+        assert_eq!(result["level"], "error");
+        assert_eq!(result["message"]["text"], "test:pkg@1.0.0: Bad parser");
+        assert_eq!(
+            result["locations"][0]["physicalLocation"]["artifactLocation"]["uri"],
+            "wkg.lock"
+        );
+        assert_eq!(run["results"][1]["level"], "warning");
+        assert_eq!(run["results"][3]["ruleId"], POLICY_RULE_ID);
+    }
+}
@@ -0,0 +1,133 @@
+//! `--continue-on-error`: commands that fetch many packages can carry on past
+//! a failure to fetch one of them, reporting every failure at the end rather
+//! than stopping at the first.
+
+use std::fmt::Display;
+
+use crate::error_code::ErrorCode;
+
+/// Tracks the failures of items in a batch.
+pub struct Batch {
+    continue_on_error: bool,
+    total: usize,
+    failures: Vec<(String, anyhow::Error)>,
+}
+
+impl Batch {
+    pub fn new(continue_on_error: bool) -> Self {
+        Self {
+            continue_on_error,
+            total: 0,
+            failures: vec![],
+        }
+    }
+
+    /// Handles the result of one item. Returns the error if not continuing
+    /// on errors; otherwise prints and records it and returns `Ok(None)`.
+    pub fn check<T>(
+        &mut self,
+        item: impl Display,
+        result: anyhow::Result<T>,
+    ) -> anyhow::Result<Option<T>> {
+        self.total += 1;
+        match result {
+            Ok(value) => Ok(Some(value)),
+            Err(err) if self.continue_on_error => {
+                eprintln!("Failed to fetch {item}: {err:#}; continuing");
+                self.failures.push((item.to_string(), err));
+                Ok(None)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Returns the number of items that succeeded.
+    pub fn succeeded(&self) -> usize {
+        self.total - self.failures.len()
+    }
+
+    /// Returns an error listing the recorded failures, if any.
+    pub fn finish(self) -> anyhow::Result<()> {
+        if self.failures.is_empty() {
+            return Ok(());
+        }
+        Err(BatchFailed {
+            total: self.total,
+            failures: self.failures,
+        }
+        .into())
+    }
+}
+
+/// The error for a batch in which some items failed.
+#[derive(Debug)]
+pub struct BatchFailed {
+    total: usize,
+    failures: Vec<(String, anyhow::Error)>,
+}
+
+impl BatchFailed {
+    /// Returns the error code shared by all of the failures, or
+    /// [`ErrorCode::Other`] if they differ.
+    pub fn code(&self) -> ErrorCode {
+        let mut codes = self.failures.iter().map(|(_, err)| ErrorCode::of(err));
+        let first = codes.next().unwrap_or(ErrorCode::Other);
+        if codes.all(|code| code == first) {
+            first
+        } else {
+            ErrorCode::Other
+        }
+    }
+}
+
+impl std::fmt::Display for BatchFailed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} of {} packages failed:",
+            self.failures.len(),
+            self.total
+        )?;
+        for (item, err) in &self.failures {
+            write!(f, "\n  {item}: {err:#}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for BatchFailed {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_batch() {
+        let not_found = || {
+            anyhow::Error::new(wasm_pkg_loader::Error::VersionNotFound(
+                "1.0.0".parse().unwrap(),
+            ))
+        };
+
+        let mut batch = Batch::new(false);
+        assert!(batch.check("wasi:io", Err::<(), _>(not_found())).is_err());
+
+        let mut batch = Batch::new(true);
+        assert_eq!(batch.check("wasi:http", Ok(1)).unwrap(), Some(1));
+        assert_eq!(
+            batch.check("wasi:io", Err::<i32, _>(not_found())).unwrap(),
+            None
+        );
+        assert_eq!(
+            batch.check("wasi:cli", Err::<i32, _>(not_found())).unwrap(),
+            None
+        );
+        assert_eq!(batch.succeeded(), 1);
+
+        let err = batch.finish().unwrap_err();
+        assert_eq!(ErrorCode::of(&err), ErrorCode::NotFound);
+        let message = err.to_string();
+        assert!(message.starts_with("2 of 3 packages failed:"));
+        assert!(message.contains("\n  wasi:io: version not found: 1.0.0"));
+    }
+}
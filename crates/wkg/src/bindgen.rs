@@ -0,0 +1,78 @@
+//! `wkg bindgen`: fetching a WIT package and generating guest bindings for
+//! one of its worlds with wit-bindgen.
+
+use std::path::PathBuf;
+
+use anyhow::Context;
+use clap::{Args, ValueEnum};
+use wit_bindgen_core::{Files, WorldGenerator};
+
+use crate::{package_spec::PackageSpec, wit, GlobalArgs, RegistryArgs};
+
+#[derive(Args, Debug)]
+pub struct BindgenCommand {
+    /// The package to generate bindings for, specified as
+    /// <namespace>:<name> plus optional @<version>, e.g. "wasi:http@0.2.0".
+    package_spec: PackageSpec,
+
+    /// The language to generate bindings for.
+    #[arg(long, value_enum)]
+    language: Language,
+
+    /// The world to generate bindings for. May be omitted if the package
+    /// has only one world.
+    #[arg(long)]
+    world: Option<String>,
+
+    /// The directory to write generated files to.
+    #[arg(long, default_value = ".")]
+    out: PathBuf,
+
+    #[command(flatten)]
+    registry: RegistryArgs,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Language {
+    Rust,
+    C,
+}
+
+impl Language {
+    fn generator(self) -> Box<dyn WorldGenerator> {
+        match self {
+            Language::Rust => wit_bindgen_rust::Opts::default().build(),
+            Language::C => wit_bindgen_c::Opts::default().build(),
+        }
+    }
+}
+
+impl BindgenCommand {
+    pub async fn run(self, global: &GlobalArgs) -> anyhow::Result<()> {
+        let (package, version, content) =
+            wit::fetch_package(global, self.package_spec, self.registry).await?;
+        let (resolve, pkg) = wit::decode_package(&content)
+            .with_context(|| format!("Failed to decode {package}@{version}"))?;
+        let world = resolve.select_world(pkg, self.world.as_deref())?;
+
+        let mut files = Files::default();
+        self.language
+            .generator()
+            .generate(&resolve, world, &mut files)
+            .with_context(|| {
+                format!(
+                    "Failed to generate bindings for world {}",
+                    resolve.worlds[world].name
+                )
+            })?;
+
+        std::fs::create_dir_all(&self.out)
+            .with_context(|| format!("Failed to create {:?}", self.out))?;
+        for (name, contents) in files.iter() {
+            let path = self.out.join(name);
+            std::fs::write(&path, contents).with_context(|| format!("Failed to write {path:?}"))?;
+            println!("Wrote '{}'", path.display());
+        }
+        Ok(())
+    }
+}
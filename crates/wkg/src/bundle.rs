@@ -0,0 +1,406 @@
+//! Offline bundles of a project's locked packages, for moving dependencies
+//! onto disconnected networks.
+//!
+//! A bundle is a directory (archive it with any tool to transfer it):
+//!
+//! - `bundle.json`: the bundled packages' names, versions, and digests
+//! - `bundle.json.sig`: a signature over `bundle.json` made with a key from
+//!   `wkg keygen`
+//! - `content/sha256/<hex>`: each package's content, by digest
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, ensure, Context};
+use bytes::Bytes;
+use clap::{Args, Subcommand};
+use futures_util::{stream, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use warg_crypto::signing::{PublicKey, Signature};
+use wasm_pkg_loader::{ContentDigest, PackageRef, Release, Version};
+
+use crate::{
+    keys::KeyStore,
+    lock::LockFile,
+    manifest::Manifest,
+    project::{project_client, ResolveArgs},
+    GlobalArgs,
+};
+
+const BUNDLE_MANIFEST_FILE_NAME: &str = "bundle.json";
+
+const BUNDLE_SIGNATURE_FILE_NAME: &str = "bundle.json.sig";
+
+const BUNDLE_VERSION: u32 = 1;
+
+#[derive(Subcommand, Debug)]
+pub enum BundleCommand {
+    /// Export the project's locked packages to a signed bundle.
+    Export(ExportArgs),
+    /// Verify a bundle and import its packages into the local cache or a
+    /// local registry.
+    Import(ImportArgs),
+}
+
+impl BundleCommand {
+    pub async fn run(self, global: &GlobalArgs) -> anyhow::Result<()> {
+        match self {
+            BundleCommand::Export(args) => args.run(global).await,
+            BundleCommand::Import(args) => args.run(global).await,
+        }
+    }
+}
+
+#[derive(Args, Debug)]
+pub struct ExportArgs {
+    /// The bundle directory to create.
+    #[arg(long, short)]
+    output: PathBuf,
+
+    /// The name of the signing key, as created by `wkg keygen`.
+    #[arg(long, default_value = "default")]
+    key: String,
+
+    /// Don't sign the bundle.
+    #[arg(long, conflicts_with = "key")]
+    unsigned: bool,
+}
+
+impl ExportArgs {
+    async fn run(self, global: &GlobalArgs) -> anyhow::Result<()> {
+        let manifest = Manifest::load_current()?;
+        let lock_path = LockFile::path_for(manifest.root_dir());
+        let lock = LockFile::load(&lock_path)?.with_context(|| {
+            format!("'{}' not found; run `wkg lock` first", lock_path.display())
+        })?;
+        let manifest_path = self.output.join(BUNDLE_MANIFEST_FILE_NAME);
+        ensure!(
+            !manifest_path.exists(),
+            "{:?} already contains a bundle",
+            self.output
+        );
+        // Load the key up front rather than failing after fetching everything
+        let key = if self.unsigned {
+            None
+        } else {
+            Some(KeyStore::default_store()?.load(&self.key)?)
+        };
+
+        let mut client = project_client(global, &manifest, &ResolveArgs::default())?;
+        let mut packages = vec![];
+        for locked in &lock.packages {
+            let (name, version) = (&locked.name, &locked.version);
+            println!("Bundling {name}@{version}...");
+            let release = match &locked.digest {
+                Some(digest) => Release {
+                    version: version.clone(),
+                    content_digest: digest.clone(),
+                },
+                None => client.get_release(name, version).await.with_context(|| {
+                    format!("Failed to get release details for {name}@{version}")
+                })?,
+            };
+            let cache_hit = client.is_content_cached(name, &release);
+            let fetch = global
+                .run_report
+                .start(&client, name, &release, cache_hit)?;
+            let content: Vec<u8> = client
+                .stream_content(name, &release)
+                .await?
+                .map_ok(|chunk| chunk.to_vec())
+                .try_concat()
+                .await
+                .with_context(|| format!("Failed to fetch {name}@{version}"))?;
+            let path = content_path(&self.output, &release.content_digest);
+            std::fs::create_dir_all(path.parent().unwrap())?;
+            let bytes = content.len() as u64;
+            std::fs::write(&path, content).with_context(|| format!("Failed to write {path:?}"))?;
+            global.run_report.record(fetch, bytes, Some(&path));
+            packages.push(BundledPackage {
+                name: name.clone(),
+                version: version.clone(),
+                digest: release.content_digest,
+            });
+        }
+
+        let bundle = BundleManifest {
+            version: BUNDLE_VERSION,
+            packages,
+        };
+        let manifest_bytes = bundle.to_json()?;
+        std::fs::write(&manifest_path, &manifest_bytes)
+            .with_context(|| format!("Failed to write {manifest_path:?}"))?;
+        if let Some(key) = key {
+            let signature = key.sign(&manifest_bytes)?;
+            let signature_path = self.output.join(BUNDLE_SIGNATURE_FILE_NAME);
+            std::fs::write(&signature_path, format!("{signature}\n"))
+                .with_context(|| format!("Failed to write {signature_path:?}"))?;
+            println!(
+                "Signed bundle with key {:?} ({})",
+                self.key,
+                key.public_key().fingerprint()
+            );
+        }
+        println!(
+            "Exported {} packages to '{}'",
+            bundle.packages.len(),
+            self.output.display()
+        );
+        Ok(())
+    }
+}
+
+#[derive(Args, Debug)]
+pub struct ImportArgs {
+    /// The bundle directory to import.
+    bundle: PathBuf,
+
+    /// A public key (or a file containing one, as written by `wkg key
+    /// export`) trusted to sign bundles. May be given multiple times.
+    #[arg(long = "trusted-key", value_name = "KEY_OR_PATH")]
+    trusted_keys: Vec<String>,
+
+    /// Import an unsigned bundle, or one not signed by a trusted key.
+    #[arg(long, conflicts_with = "trusted_keys")]
+    allow_unsigned: bool,
+
+    /// Import into the local registry rooted at this directory instead of
+    /// the local cache.
+    #[arg(long, value_name = "DIR")]
+    local_registry: Option<PathBuf>,
+}
+
+impl ImportArgs {
+    async fn run(self, global: &GlobalArgs) -> anyhow::Result<()> {
+        let manifest_path = self.bundle.join(BUNDLE_MANIFEST_FILE_NAME);
+        let manifest_bytes = std::fs::read(&manifest_path)
+            .with_context(|| format!("Failed to read {manifest_path:?}"))?;
+        if self.allow_unsigned {
+            println!("Skipping bundle signature verification");
+        } else {
+            let trusted_keys = self
+                .trusted_keys
+                .iter()
+                .map(String::as_str)
+                .map(parse_public_key)
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            ensure!(
+                !trusted_keys.is_empty(),
+                "no trusted keys given; pass --trusted-key or --allow-unsigned"
+            );
+            let signer = verify_signature(&self.bundle, &manifest_bytes, &trusted_keys)?;
+            println!("Verified bundle signature from {}", signer.fingerprint());
+        }
+        let bundle = BundleManifest::from_json(&manifest_bytes)
+            .with_context(|| format!("Invalid bundle manifest {manifest_path:?}"))?;
+
+        // Verify everything before importing anything
+        let mut contents = vec![];
+        for package in &bundle.packages {
+            let path = content_path(&self.bundle, &package.digest);
+            let content = Bytes::from(
+                std::fs::read(&path).with_context(|| format!("Failed to read {path:?}"))?,
+            );
+            package
+                .digest
+                .validating_stream(stream::iter([Ok(content.clone())]))
+                .try_for_each(|_| async { Ok(()) })
+                .await
+                .with_context(|| {
+                    format!(
+                        "Content of {}@{} doesn't match its digest",
+                        package.name, package.version
+                    )
+                })?;
+            contents.push(content);
+        }
+
+        let mut client = global.load_config()?.to_client();
+        for (package, content) in bundle.packages.iter().zip(contents) {
+            let BundledPackage {
+                name,
+                version,
+                digest,
+            } = package;
+            match &self.local_registry {
+                Some(root) => {
+                    let path = root
+                        .join(name.namespace().as_ref())
+                        .join(name.name().as_ref())
+                        .join(format!("{version}.wasm"));
+                    std::fs::create_dir_all(path.parent().unwrap())?;
+                    std::fs::write(&path, content)
+                        .with_context(|| format!("Failed to write {path:?}"))?;
+                }
+                None => {
+                    let release = Release {
+                        version: version.clone(),
+                        content_digest: digest.clone(),
+                    };
+                    client
+                        .import_release(name, &release, content)
+                        .await
+                        .with_context(|| format!("Failed to import {name}@{version}"))?;
+                }
+            }
+            println!("Imported {name}@{version}");
+        }
+        println!("Imported {} packages", bundle.packages.len());
+        Ok(())
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct BundleManifest {
+    version: u32,
+    packages: Vec<BundledPackage>,
+}
+
+#[derive(Debug, PartialEq)]
+struct BundledPackage {
+    name: PackageRef,
+    version: Version,
+    digest: ContentDigest,
+}
+
+impl BundleManifest {
+    fn to_json(&self) -> anyhow::Result<Vec<u8>> {
+        let json = JsonBundleManifest {
+            version: self.version,
+            packages: self
+                .packages
+                .iter()
+                .map(|package| JsonBundledPackage {
+                    name: package.name.to_string(),
+                    version: package.version.to_string(),
+                    digest: package.digest.to_string(),
+                })
+                .collect(),
+        };
+        let mut bytes = serde_json::to_vec_pretty(&json)?;
+        bytes.push(b'\n');
+        Ok(bytes)
+    }
+
+    fn from_json(bytes: &[u8]) -> anyhow::Result<Self> {
+        let JsonBundleManifest { version, packages } = serde_json::from_slice(bytes)?;
+        ensure!(
+            version == BUNDLE_VERSION,
+            "unsupported bundle version {version}"
+        );
+        let packages = packages
+            .into_iter()
+            .map(|package| {
+                Ok(BundledPackage {
+                    name: package.name.parse()?,
+                    version: package.version.parse()?,
+                    digest: package.digest.parse()?,
+                })
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Ok(Self { version, packages })
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+struct JsonBundleManifest {
+    version: u32,
+    packages: Vec<JsonBundledPackage>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct JsonBundledPackage {
+    name: String,
+    version: String,
+    digest: String,
+}
+
+fn content_path(bundle: &Path, digest: &ContentDigest) -> PathBuf {
+    match digest {
+        ContentDigest::Sha256 { hex } => bundle.join("content").join("sha256").join(hex),
+    }
+}
+
+/// Parses a public key, or reads one from the file at the given path.
+fn parse_public_key(key_or_path: &str) -> anyhow::Result<PublicKey> {
+    if let Ok(key) = key_or_path.parse() {
+        return Ok(key);
+    }
+    let contents = std::fs::read_to_string(key_or_path)
+        .with_context(|| format!("{key_or_path:?} is neither a public key nor a readable file"))?;
+    contents
+        .trim()
+        .parse()
+        .with_context(|| format!("Invalid public key in {key_or_path:?}"))
+}
+
+/// Verifies the bundle's signature over `manifest_bytes`, returning the
+/// trusted key that made it.
+fn verify_signature<'a>(
+    bundle: &Path,
+    manifest_bytes: &[u8],
+    trusted_keys: &'a [PublicKey],
+) -> anyhow::Result<&'a PublicKey> {
+    let signature_path = bundle.join(BUNDLE_SIGNATURE_FILE_NAME);
+    let signature: Signature = match std::fs::read_to_string(&signature_path) {
+        Ok(contents) => contents
+            .trim()
+            .parse()
+            .with_context(|| format!("Invalid signature in {signature_path:?}"))?,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            bail!("Bundle is unsigned; pass --allow-unsigned to import it anyway")
+        }
+        Err(err) => return Err(err).with_context(|| format!("Failed to read {signature_path:?}")),
+    };
+    trusted_keys
+        .iter()
+        .find(|key| key.verify(manifest_bytes, &signature).is_ok())
+        .context("Bundle is not signed by a trusted key")
+}
+
+#[cfg(test)]
+mod tests {
+    use p256::ecdsa::SigningKey;
+    use warg_crypto::signing::PrivateKey;
+
+    use super::*;
+
+    #[test]
+    fn test_manifest_roundtrip() {
+        let manifest = BundleManifest {
+            version: BUNDLE_VERSION,
+            packages: vec![BundledPackage {
+                name: "test:pkg".parse().unwrap(),
+                version: "1.0.0".parse().unwrap(),
+                digest: "sha256:4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b"
+                    .parse()
+                    .unwrap(),
+            }],
+        };
+        let json = manifest.to_json().unwrap();
+        assert_eq!(BundleManifest::from_json(&json).unwrap(), manifest);
+    }
+
+    #[test]
+    fn test_verify_signature() {
+        let dir = tempfile::tempdir().unwrap();
+        let key = |seed: u8| PrivateKey::from(SigningKey::from_slice(&[seed; 32]).unwrap());
+        let (signer, other) = (key(1), key(2));
+        let manifest = b"{}\n";
+        let trusted = [other.public_key(), signer.public_key()];
+
+        assert!(verify_signature(dir.path(), manifest, &trusted).is_err());
+
+        let signature = signer.sign(manifest).unwrap();
+        std::fs::write(
+            dir.path().join(BUNDLE_SIGNATURE_FILE_NAME),
+            format!("{signature}\n"),
+        )
+        .unwrap();
+        assert_eq!(
+            verify_signature(dir.path(), manifest, &trusted).unwrap(),
+            &signer.public_key()
+        );
+        assert!(verify_signature(dir.path(), b"tampered", &trusted).is_err());
+        assert!(verify_signature(dir.path(), manifest, &trusted[..1]).is_err());
+    }
+}
@@ -0,0 +1,109 @@
+//! `wkg compose`: composing registry-sourced components with wac.
+
+use std::path::PathBuf;
+
+use anyhow::{ensure, Context};
+use clap::Args;
+use wac_graph::{types::Package, CompositionGraph, EncodeOptions, PackageId};
+
+use crate::{package_spec::PackageSpec, wit, GlobalArgs, RegistryArgs};
+
+#[derive(Args, Debug)]
+pub struct ComposeCommand {
+    /// The component whose imports are satisfied by the plugs, specified as
+    /// <namespace>:<name> plus optional @<version>.
+    socket: PackageSpec,
+
+    /// A component whose exports satisfy matching imports of the socket.
+    /// May be repeated; later plugs take precedence.
+    #[arg(long, required = true, value_name = "PACKAGE_SPEC")]
+    plug: Vec<PackageSpec>,
+
+    /// The path to write the composed component to.
+    #[arg(long, short)]
+    output: PathBuf,
+
+    /// Overwrite any existing output file.
+    #[arg(long)]
+    overwrite: bool,
+
+    #[command(flatten)]
+    registry: RegistryArgs,
+}
+
+impl ComposeCommand {
+    pub async fn run(self, global: &GlobalArgs) -> anyhow::Result<()> {
+        ensure!(
+            self.overwrite || !self.output.exists(),
+            "{:?} already exists; you can use '--overwrite' to overwrite it",
+            self.output
+        );
+
+        let mut graph = CompositionGraph::new();
+        let socket = self
+            .register(global, &mut graph, self.socket.clone())
+            .await?;
+        let socket_instance = graph.instantiate(socket);
+
+        let mut plugged = 0;
+        for spec in self.plug.clone() {
+            let plug = self.register(global, &mut graph, spec).await?;
+            let plug_instance = graph.instantiate(plug);
+            let plug_ty = graph[plug].ty();
+            let socket_ty = graph[socket].ty();
+            let names: Vec<String> = graph.types()[plug_ty]
+                .exports
+                .keys()
+                .filter(|name| graph.types()[socket_ty].imports.contains_key(*name))
+                .cloned()
+                .collect();
+            for name in names {
+                let export = graph.alias_instance_export(plug_instance, &name)?;
+                graph.set_instantiation_argument(socket_instance, &name, export)?;
+                tracing::debug!(name, "plugged socket import");
+                plugged += 1;
+            }
+        }
+        ensure!(plugged > 0, "no plug exports match any socket imports");
+
+        let socket_ty = graph[socket].ty();
+        let exports: Vec<String> = graph.types()[socket_ty].exports.keys().cloned().collect();
+        for name in exports {
+            let export = graph.alias_instance_export(socket_instance, &name)?;
+            graph.export(export, &name)?;
+        }
+
+        let composed = graph
+            .encode(EncodeOptions::default())
+            .context("Failed to encode composed component")?;
+        std::fs::write(&self.output, composed)
+            .with_context(|| format!("Failed to write {:?}", self.output))?;
+        println!(
+            "Plugged {plugged} imports; wrote '{}'",
+            self.output.display()
+        );
+        Ok(())
+    }
+
+    /// Fetches the given component and registers it with the graph.
+    async fn register(
+        &self,
+        global: &GlobalArgs,
+        graph: &mut CompositionGraph,
+        spec: PackageSpec,
+    ) -> anyhow::Result<PackageId> {
+        let registry = RegistryArgs {
+            domain: self.registry.domain.clone(),
+        };
+        let (package, version, content) = wit::fetch_package(global, spec, registry).await?;
+        println!("Fetched {package}@{version}");
+        let loaded = Package::from_bytes(
+            &package.to_string(),
+            Some(&version),
+            content,
+            graph.types_mut(),
+        )
+        .with_context(|| format!("Failed to load {package}@{version}"))?;
+        Ok(graph.register_package(loaded)?)
+    }
+}
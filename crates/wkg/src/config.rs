@@ -0,0 +1,254 @@
+use std::{collections::HashMap, io::Write, path::Path};
+
+use anyhow::{bail, ensure, Context};
+use clap::{Args, Subcommand};
+use wasm_pkg_loader::{BasicCredentials, ClientConfig, RegistryCredentials};
+
+use crate::GlobalArgs;
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigCommand {
+    /// List the effective configuration. Credentials are never printed.
+    List,
+    /// List the effective configuration along with the file, environment
+    /// variable, or flag that supplied each setting.
+    Sources,
+    /// Edit the config file (or the `--config` file) in $VISUAL or $EDITOR.
+    /// The edited config is validated before it is saved.
+    Edit,
+    /// Move the config, cache, and data directories from their legacy
+    /// (platform default) locations to those given by `XDG_CONFIG_HOME`,
+    /// `XDG_CACHE_HOME`, and `XDG_DATA_HOME`.
+    Migrate,
+}
+
+impl ConfigCommand {
+    pub async fn run(self, global: &GlobalArgs) -> anyhow::Result<()> {
+        match self {
+            ConfigCommand::List => {
+                let config = global.load_config()?;
+                if let Some(profile) = &global.profile {
+                    println!("# profile: {profile}");
+                }
+                for (key, value) in config.entries() {
+                    println!("{key} = {value}");
+                }
+                Ok(())
+            }
+            ConfigCommand::Sources => {
+                let entries = attribute(&global.load_config_layers()?);
+                if global.json {
+                    let entries: Vec<_> = entries
+                        .iter()
+                        .map(|(key, value, source)| {
+                            serde_json::json!({"key": key, "value": value, "source": source})
+                        })
+                        .collect();
+                    println!("{}", serde_json::Value::from(entries));
+                } else {
+                    for (key, value, source) in entries {
+                        println!("{key} = {value}  # {source}");
+                    }
+                }
+                Ok(())
+            }
+            ConfigCommand::Edit => {
+                let path = match &global.config_file {
+                    Some(path) => std::env::current_dir()?.join(path),
+                    None => ClientConfig::default_config_path()
+                        .context("Failed to determine config file path")?,
+                };
+                edit_config(&path)
+            }
+            ConfigCommand::Migrate => {
+                let migrated = ClientConfig::migrate_default_dirs()
+                    .context("Failed to migrate directories")?;
+                if migrated.is_empty() {
+                    println!("Nothing to migrate");
+                }
+                for (from, to) in migrated {
+                    println!("Moved {from:?} to {to:?}");
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Attributes each setting of the last of the given cumulative config layers
+/// to the layer that last changed it, returning `(key, value, source)`.
+fn attribute(layers: &[(String, ClientConfig)]) -> Vec<(String, String, String)> {
+    let mut sources = HashMap::new();
+    let mut previous = HashMap::new();
+    let mut entries = Vec::new();
+    for (source, config) in layers {
+        entries = config.entries();
+        for (key, value) in &entries {
+            if previous.get(key) != Some(value) {
+                sources.insert(key.clone(), source.clone());
+            }
+        }
+        previous = entries.iter().cloned().collect();
+    }
+    entries
+        .into_iter()
+        .map(|(key, value)| {
+            let source = sources.remove(&key).unwrap_or_default();
+            (key, value, source)
+        })
+        .collect()
+}
+
+fn edit_config(path: &Path) -> anyhow::Result<()> {
+    let dir = path.parent().context("Invalid config file path")?;
+    std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {dir:?}"))?;
+    let original = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(err) => return Err(err).with_context(|| format!("Failed to read {path:?}")),
+    };
+
+    // Edit a copy in the same directory so relative includes and override
+    // paths are validated as they will be resolved.
+    let mut tmp = tempfile::Builder::new()
+        .prefix(".config-edit-")
+        .suffix(".toml")
+        .tempfile_in(dir)?;
+    tmp.write_all(original.as_bytes())?;
+    tmp.flush()?;
+    loop {
+        run_editor(tmp.path())?;
+        match ClientConfig::from_file(tmp.path()) {
+            Ok(_) => break,
+            Err(err) => {
+                eprintln!("Invalid config: {err}");
+                if !confirm("Edit again?")? {
+                    bail!("Config not saved; {path:?} is unchanged");
+                }
+            }
+        }
+    }
+    if std::fs::read_to_string(tmp.path())? == original {
+        println!("No changes made");
+        return Ok(());
+    }
+    tmp.persist(path)
+        .with_context(|| format!("Failed to save {path:?}"))?;
+    println!("Saved '{}'", path.display());
+    Ok(())
+}
+
+fn run_editor(path: &Path) -> anyhow::Result<()> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| {
+            if cfg!(windows) {
+                "notepad".into()
+            } else {
+                "vi".into()
+            }
+        });
+    // Allow editors given with arguments, e.g. "code --wait"
+    let mut args = editor.split_whitespace();
+    let program = args.next().context("Empty editor command")?;
+    let status = std::process::Command::new(program)
+        .args(args)
+        .arg(path)
+        .status()
+        .with_context(|| format!("Failed to run editor {editor:?}"))?;
+    ensure!(status.success(), "Editor {editor:?} exited with {status}");
+    Ok(())
+}
+
+fn confirm(prompt: &str) -> anyhow::Result<bool> {
+    eprint!("{prompt} [Y/n] ");
+    std::io::stderr().flush()?;
+    let mut answer = String::new();
+    if std::io::stdin().read_line(&mut answer)? == 0 {
+        return Ok(false);
+    }
+    Ok(!answer.trim().eq_ignore_ascii_case("n"))
+}
+
+#[derive(Args, Debug)]
+pub struct LoginCommand {
+    /// The registry domain (or alias) to store credentials for.
+    registry: String,
+
+    /// Username for basic (OCI) credentials. If not given, the secret is
+    /// stored as an auth token (Warg).
+    #[arg(long)]
+    username: Option<String>,
+}
+
+impl LoginCommand {
+    pub async fn run(self, global: &GlobalArgs) -> anyhow::Result<()> {
+        let path = ClientConfig::default_credentials_path()
+            .context("Failed to determine credentials file path")?;
+
+        eprintln!("Enter password or token for {}:", self.registry);
+        let mut secret = String::new();
+        std::io::stdin()
+            .read_line(&mut secret)
+            .context("Failed to read secret from stdin")?;
+        let secret = secret.trim_end_matches(['\r', '\n']).to_string();
+        ensure!(!secret.is_empty(), "No password or token given");
+
+        let credentials = match self.username {
+            Some(username) => RegistryCredentials {
+                basic: Some(BasicCredentials {
+                    username,
+                    password: secret.into(),
+                }),
+                auth_token: None,
+            },
+            None => RegistryCredentials {
+                basic: None,
+                auth_token: Some(secret.into()),
+            },
+        };
+        let config = global.load_config()?;
+        let registry = config.resolve_registry_alias(&self.registry);
+        credentials.store(&path, global.profile.as_deref(), registry)?;
+        println!("Stored credentials for {registry} in '{}'", path.display());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_attribute() {
+        let mut config = ClientConfig::default();
+        config.set_default_registry("bytecodealliance.org");
+        let mut layers = vec![("built-in default".to_string(), config.clone())];
+        config
+            .set_default_registry("example.com")
+            .set_namespace_registry("wasi", "wasi.dev");
+        layers.push(("config.toml".to_string(), config.clone()));
+        config.set_namespace_registry("wasi", "mirror.example.com");
+        layers.push(("profile \"ci\"".to_string(), config.clone()));
+        config.set_offline(true);
+        layers.push(("--frozen".to_string(), config));
+
+        let entries = attribute(&layers);
+        let entries: Vec<_> = entries
+            .iter()
+            .map(|(key, value, source)| (key.as_str(), value.as_str(), source.as_str()))
+            .collect();
+        assert_eq!(
+            entries,
+            [
+                ("default_registry", "\"example.com\"", "config.toml"),
+                (
+                    "namespace.wasi.registry",
+                    "\"mirror.example.com\"",
+                    "profile \"ci\""
+                ),
+                ("offline", "true", "--frozen"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,151 @@
+//! `wkg copy`: copies a package release from one registry to another, e.g.
+//! to promote an artifact from a staging registry to production.
+
+use anyhow::{bail, Context};
+use clap::Args;
+use futures_util::TryStreamExt;
+use wasm_pkg_loader::{Client, PackageRef, Release};
+
+use crate::{
+    archive,
+    package_spec::{suggest_not_found, PackageSpec},
+    policy::OrgPolicy,
+    quarantine, GlobalArgs,
+};
+
+#[derive(Args, Debug)]
+pub struct CopyCommand {
+    /// The package to copy, specified as <namespace>:<name> plus optional
+    /// @<version>. Without a version, the latest release is copied.
+    package_spec: PackageSpec,
+
+    /// The registry domain (or alias) to copy from. Defaults to the
+    /// package's configured registry.
+    #[arg(long, value_name = "DOMAIN")]
+    from: Option<String>,
+
+    /// The registry domain (or alias) to copy to.
+    #[arg(long, value_name = "DOMAIN")]
+    to: String,
+
+    /// Skip checking that the content (unless an archive) is valid Wasm
+    /// before publishing it.
+    #[arg(long)]
+    no_validate: bool,
+}
+
+impl CopyCommand {
+    pub async fn run(self, global: &GlobalArgs) -> anyhow::Result<()> {
+        let (package, version) = self.package_spec.into_versioned()?;
+        let policy = global.load_policy()?;
+        policy.check_package(&package)?;
+        let mut source = registry_client(global, &package, self.from.as_deref())?;
+        let mut dest = registry_client(global, &package, Some(&self.to))?;
+
+        let version = match version {
+            Some(version) => version,
+            None => {
+                let result = source.latest_version(&package).await;
+                suggest_not_found(result, &mut source, &package, None)
+                    .await
+                    .context("Failed to select latest version")?
+            }
+        };
+        let result = source.get_release(&package, &version).await;
+        let release = suggest_not_found(result, &mut source, &package, Some(&version))
+            .await
+            .context("Failed to get release details")?;
+
+        copy_release(
+            &policy,
+            &mut source,
+            &mut dest,
+            &package,
+            &release,
+            &self.to,
+            !self.no_validate,
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+/// Copies a release from the source client's registry to the destination
+/// client's `to`, unless it already has identical content. The content is
+/// checked against the org policy and, if `validate`, as Wasm (unless an
+/// archive). Returns the release in the destination.
+pub async fn copy_release(
+    policy: &OrgPolicy,
+    source: &mut Client,
+    dest: &mut Client,
+    package: &PackageRef,
+    release: &Release,
+    to: &str,
+    validate: bool,
+) -> anyhow::Result<Release> {
+    let version = &release.version;
+    match dest.get_release(package, version).await {
+        Ok(existing) if existing.content_digest == release.content_digest => {
+            println!(
+                "{package}@{version} is already in {to} ({})",
+                existing.content_digest
+            );
+            return Ok(existing);
+        }
+        Ok(existing) => bail!(
+            "{package}@{version} is already in {to} with different content ({}, not {})",
+            existing.content_digest,
+            release.content_digest
+        ),
+        Err(err) if err.is_not_found() => (),
+        Err(err) => {
+            return Err(err).with_context(|| format!("Failed to check {to}"));
+        }
+    }
+
+    println!("Fetching {package}@{version}...");
+    // Content is verified against the release's digest as it's streamed
+    let content: Vec<u8> = source
+        .stream_content(package, release)
+        .await?
+        .map_ok(|chunk| chunk.to_vec())
+        .try_concat()
+        .await
+        .with_context(|| format!("Failed to fetch {package}@{version}"))?;
+    policy.check_content(package, version, &content)?;
+    if validate && !archive::is_archive(&content) {
+        quarantine::validate_wasm(&content)
+            .with_context(|| format!("Failed to verify {package}@{version}"))?;
+    }
+
+    println!("Publishing {package}@{version} to {to}...");
+    let published = dest
+        .publish(package, version, content.into())
+        .await
+        .with_context(|| format!("Failed to publish {package}@{version} to {to}"))?;
+    if published.content_digest == release.content_digest {
+        println!("Copied {package}@{version} ({})", published.content_digest);
+    } else {
+        // Content is copied byte for byte, but some registries wrap it
+        // differently, changing the digest they report
+        println!(
+            "Copied {package}@{version}; its digest changed from {} to {}",
+            release.content_digest, published.content_digest
+        );
+    }
+    Ok(published)
+}
+
+/// Returns a client fetching the given package from the given registry, if
+/// set, or else its configured one.
+pub fn registry_client(
+    global: &GlobalArgs,
+    package: &PackageRef,
+    registry: Option<&str>,
+) -> anyhow::Result<Client> {
+    let mut config = global.load_config()?;
+    if let Some(registry) = registry {
+        config.set_package_registry(package.clone(), registry);
+    }
+    Ok(config.to_client())
+}
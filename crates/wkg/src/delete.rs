@@ -0,0 +1,95 @@
+//! `wkg delete`: permanently deletes releases from a registry, for registry
+//! administrators (e.g. to remove a release that leaked a secret). Prefer
+//! `wkg deprecate`, which leaves existing lock files working.
+//!
+//! Registries decide who may delete; OCI registries typically require a
+//! credential with delete permission on the repository.
+
+use std::io::{IsTerminal, Write};
+
+use anyhow::{bail, Context};
+use clap::Args;
+
+use crate::{package_spec::PackageSpec, GlobalArgs, RegistryArgs};
+
+#[derive(Args, Debug)]
+pub struct DeleteCommand {
+    /// The package to delete, as <namespace>:<name>@<version> for a single
+    /// release or <namespace>:<name> for every release.
+    package_spec: PackageSpec,
+
+    /// Don't ask for confirmation, e.g. in automation.
+    #[arg(long, short)]
+    yes: bool,
+
+    #[command(flatten)]
+    registry: RegistryArgs,
+}
+
+impl DeleteCommand {
+    pub async fn run(self, global: &GlobalArgs) -> anyhow::Result<()> {
+        let (package, version) = self.package_spec.into_versioned()?;
+        let mut client = {
+            let mut config = global.load_config()?;
+            if let Some(registry) = self.registry.domain {
+                config.set_namespace_registry(package.namespace().to_string(), registry);
+            }
+            config.to_client()
+        };
+        let versions = match version {
+            Some(version) => vec![version],
+            None => {
+                let mut versions: Vec<_> = client
+                    .list_all_versions(&package)
+                    .await
+                    .with_context(|| format!("Failed to list versions of {package}"))?
+                    .into_iter()
+                    .map(|info| info.version)
+                    .collect();
+                versions.sort();
+                versions
+            }
+        };
+        if versions.is_empty() {
+            bail!("{package} has no releases to delete");
+        }
+        let registry = client
+            .package_registry(&package)?
+            .map_or_else(|| "(local override)".to_string(), ToOwned::to_owned);
+
+        if !self.yes {
+            let listed = versions
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ");
+            let prompt = format!(
+                "Permanently delete {package} {listed} from {registry}? This can't be undone."
+            );
+            if !confirm(&prompt)? {
+                bail!("Deletion cancelled");
+            }
+        }
+        for version in &versions {
+            client
+                .delete(&package, version)
+                .await
+                .with_context(|| format!("Failed to delete {package}@{version} from {registry}"))?;
+            println!("Deleted {package}@{version}");
+        }
+        Ok(())
+    }
+}
+
+/// Asks for confirmation, defaulting to no. Fails if stdin isn't a terminal,
+/// so automation must pass `--yes`.
+fn confirm(prompt: &str) -> anyhow::Result<bool> {
+    if !std::io::stdin().is_terminal() {
+        bail!("Refusing to delete without confirmation; pass '--yes' to confirm");
+    }
+    eprint!("{prompt} [y/N] ");
+    std::io::stderr().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
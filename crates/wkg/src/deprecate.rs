@@ -0,0 +1,161 @@
+//! `wkg deprecate`: attaches deprecation notices to releases, which `wkg get`
+//! and `wkg sync` print as warnings when fetching them, as does dependency
+//! resolution when selecting them from registries that list notices.
+
+use anyhow::{ensure, Context};
+use clap::Args;
+use wasm_pkg_loader::{Client, Deprecation, PackageRef, Release};
+
+use crate::{package_spec::PackageSpec, GlobalArgs, RegistryArgs};
+
+#[derive(Args, Debug)]
+pub struct DeprecateCommand {
+    /// The package to deprecate, as <namespace>:<name>@<version> for a
+    /// single release or <namespace>:<name> for every release.
+    package_spec: PackageSpec,
+
+    /// The notice shown to users fetching the package.
+    #[arg(long, short)]
+    message: String,
+
+    /// A package (or version of it) to suggest using instead.
+    #[arg(long, value_name = "PACKAGE_SPEC")]
+    replacement: Option<PackageSpec>,
+
+    /// The date after which the release will no longer be supported, as
+    /// YYYY-MM-DD.
+    #[arg(long, value_name = "DATE", value_parser = parse_date)]
+    end_of_life: Option<String>,
+
+    #[command(flatten)]
+    registry: RegistryArgs,
+}
+
+impl DeprecateCommand {
+    pub async fn run(self, global: &GlobalArgs) -> anyhow::Result<()> {
+        let (package, version) = self.package_spec.into_versioned()?;
+        let mut client = {
+            let mut config = global.load_config()?;
+            if let Some(registry) = self.registry.domain {
+                config.set_namespace_registry(package.namespace().to_string(), registry);
+            }
+            config.to_client()
+        };
+        let deprecation = Deprecation {
+            message: self.message,
+            replacement: self.replacement.as_ref().map(ToString::to_string),
+            package: version.is_none(),
+            end_of_life: self.end_of_life,
+        };
+
+        let versions = match version {
+            Some(version) => vec![version],
+            None => client
+                .list_all_versions(&package)
+                .await
+                .with_context(|| format!("Failed to list versions of {package}"))?
+                .into_iter()
+                .map(|info| info.version)
+                .collect(),
+        };
+        for version in &versions {
+            let release = client
+                .get_release(&package, version)
+                .await
+                .with_context(|| {
+                    format!("Failed to get release details for {package}@{version}")
+                })?;
+            client
+                .deprecate(&package, &release, &deprecation)
+                .await
+                .with_context(|| format!("Failed to deprecate {package}@{version}"))?;
+            println!("Deprecated {package}@{version}");
+        }
+        if deprecation.package {
+            println!(
+                "Deprecated {} releases of {package}; deprecate releases published later separately",
+                versions.len()
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Warns if the given release has a deprecation notice. Failing to look it
+/// up (e.g. offline) doesn't fail the fetch.
+pub async fn warn_if_deprecated(client: &mut Client, package: &PackageRef, release: &Release) {
+    let version = &release.version;
+    match client.deprecation(package, release).await {
+        Ok(Some(deprecation)) => tracing::warn!("{}", warning(package, release, &deprecation)),
+        Ok(None) => (),
+        Err(err) => {
+            tracing::debug!("Failed to check whether {package}@{version} is deprecated: {err}")
+        }
+    }
+}
+
+/// Checks that a date is given as YYYY-MM-DD.
+fn parse_date(date: &str) -> anyhow::Result<String> {
+    let valid = match date.split('-').collect::<Vec<_>>()[..] {
+        [year, month, day] => {
+            year.len() == 4
+                && month.len() == 2
+                && day.len() == 2
+                && date.bytes().all(|b| b.is_ascii_digit() || b == b'-')
+                && (1..=12).contains(&month.parse().unwrap_or(0))
+                && (1..=31).contains(&day.parse().unwrap_or(0))
+        }
+        _ => false,
+    };
+    ensure!(valid, "expected a date as YYYY-MM-DD, e.g. 2025-06-30");
+    Ok(date.to_string())
+}
+
+fn warning(package: &PackageRef, release: &Release, deprecation: &Deprecation) -> String {
+    if deprecation.package {
+        format!("{package} is deprecated: {deprecation}")
+    } else {
+        format!("{package}@{} is deprecated: {deprecation}", release.version)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_warning() {
+        let package: PackageRef = "my-org:gateway".parse().unwrap();
+        let release = Release {
+            version: "1.4.2".parse().unwrap(),
+            content_digest:
+                "sha256:4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b"
+                    .parse()
+                    .unwrap(),
+        };
+        let mut deprecation = Deprecation {
+            message: "Unmaintained".into(),
+            replacement: Some("my-org:gateway2".into()),
+            package: true,
+            end_of_life: None,
+        };
+        assert_eq!(
+            warning(&package, &release, &deprecation),
+            "my-org:gateway is deprecated: Unmaintained (use my-org:gateway2 instead)"
+        );
+        deprecation.package = false;
+        deprecation.replacement = None;
+        assert_eq!(
+            warning(&package, &release, &deprecation),
+            "my-org:gateway@1.4.2 is deprecated: Unmaintained"
+        );
+    }
+
+    #[test]
+    fn test_parse_date() {
+        assert_eq!(parse_date("2025-06-30").unwrap(), "2025-06-30");
+        assert!(parse_date("2025-6-30").is_err());
+        assert!(parse_date("2025-13-01").is_err());
+        assert!(parse_date("30/06/2025").is_err());
+    }
+}
@@ -0,0 +1,367 @@
+//! `wkg doc`: Markdown reference documentation for a WIT package.
+
+use std::{
+    fmt::{self, Write as _},
+    path::PathBuf,
+};
+
+use anyhow::Context;
+use clap::Args;
+use wit_parser::{
+    Docs, Function, FunctionKind, InterfaceId, PackageId, Resolve, Type, TypeDefKind, TypeId,
+    TypeOwner, WorldItem,
+};
+
+use crate::{package_spec::PackageSpec, wit, GlobalArgs, RegistryArgs};
+
+#[derive(Args, Debug)]
+pub struct DocCommand {
+    /// The package to document, specified as <namespace>:<name> plus
+    /// optional @<version>, e.g. "wasi:cli" or "wasi:http@0.2.0".
+    package_spec: PackageSpec,
+
+    /// Output path. A single page is written to stdout by default. With
+    /// `--split` this is the directory to write and is required.
+    #[arg(long, short)]
+    output: Option<PathBuf>,
+
+    /// Write an `index.md` for the package and its worlds plus one
+    /// `<interface>.md` per interface, instead of a single page.
+    #[arg(long, requires = "output")]
+    split: bool,
+
+    #[command(flatten)]
+    registry: RegistryArgs,
+}
+
+impl DocCommand {
+    pub async fn run(self, global: &GlobalArgs) -> anyhow::Result<()> {
+        let (package, version, content) =
+            wit::fetch_package(global, self.package_spec, self.registry).await?;
+        let (resolve, pkg) = wit::decode_package(&content)
+            .with_context(|| format!("Failed to decode {package}@{version}"))?;
+        let docs = Markdown::new(&resolve, pkg);
+
+        match (self.output, self.split) {
+            (Some(dir), true) => {
+                std::fs::create_dir_all(&dir)
+                    .with_context(|| format!("Failed to create {dir:?}"))?;
+                for (file_name, page) in docs.pages() {
+                    let path = dir.join(file_name);
+                    std::fs::write(&path, page)
+                        .with_context(|| format!("Failed to write {path:?}"))?;
+                }
+                println!("Wrote documentation to '{}'", dir.display());
+            }
+            (Some(path), false) => {
+                std::fs::write(&path, docs.single_page())
+                    .with_context(|| format!("Failed to write {path:?}"))?;
+                println!("Wrote '{}'", path.display());
+            }
+            (None, _) => print!("{}", docs.single_page()),
+        }
+        Ok(())
+    }
+}
+
+/// Renders a package's worlds and interfaces, with their doc comments, as
+/// Markdown.
+pub struct Markdown<'a> {
+    resolve: &'a Resolve,
+    pkg: PackageId,
+}
+
+impl<'a> Markdown<'a> {
+    pub fn new(resolve: &'a Resolve, pkg: PackageId) -> Self {
+        Self { resolve, pkg }
+    }
+
+    /// Returns the whole package's documentation as one page.
+    pub fn single_page(&self) -> String {
+        render(|out| {
+            self.package(out, false)?;
+            for (name, &id) in &self.resolve.packages[self.pkg].interfaces {
+                write!(out, "## Interface `{name}`\n\n")?;
+                self.interface(out, id, "###")?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Returns `(file name, contents)` pairs: `index.md` for the package and
+    /// its worlds, plus `<interface>.md` for each interface.
+    pub fn pages(&self) -> Vec<(String, String)> {
+        let mut pages = vec![(
+            "index.md".to_string(),
+            render(|out| self.package(out, true)),
+        )];
+        for (name, &id) in &self.resolve.packages[self.pkg].interfaces {
+            let page = render(|out| {
+                let id_name = self.resolve.id_of(id).unwrap_or_else(|| name.clone());
+                write!(out, "# Interface `{id_name}`\n\n")?;
+                self.interface(out, id, "##")
+            });
+            pages.push((format!("{name}.md"), page));
+        }
+        pages
+    }
+
+    /// Renders the package heading, docs, and worlds. With `index`, also
+    /// links to each interface's page.
+    fn package(&self, out: &mut String, index: bool) -> fmt::Result {
+        let resolve = self.resolve;
+        let package = &resolve.packages[self.pkg];
+        write!(out, "# Package `{}`\n\n", package.name)?;
+        write_docs(out, &package.docs)?;
+
+        if index && !package.interfaces.is_empty() {
+            writeln!(out, "## Interfaces\n")?;
+            for name in package.interfaces.keys() {
+                writeln!(out, "- [`{name}`]({name}.md)")?;
+            }
+            writeln!(out)?;
+        }
+
+        for (name, &id) in &package.worlds {
+            let world = &resolve.worlds[id];
+            write!(out, "## World `{name}`\n\n")?;
+            write_docs(out, &world.docs)?;
+            for (heading, items) in [("Imports", &world.imports), ("Exports", &world.exports)] {
+                if items.is_empty() {
+                    continue;
+                }
+                write!(out, "### {heading}\n\n")?;
+                for (key, item) in items {
+                    let name = resolve.name_world_key(key);
+                    match item {
+                        WorldItem::Interface(_) => writeln!(out, "- interface `{name}`")?,
+                        WorldItem::Function(func) => {
+                            writeln!(out, "- `{name}: func{}`", wit::signature(resolve, func))?
+                        }
+                        WorldItem::Type(_) => writeln!(out, "- type `{name}`")?,
+                    }
+                }
+                writeln!(out)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Renders an interface's docs, types, and functions, using the given
+    /// heading prefix for its sections.
+    fn interface(&self, out: &mut String, id: InterfaceId, heading: &str) -> fmt::Result {
+        let interface = &self.resolve.interfaces[id];
+        write_docs(out, &interface.docs)?;
+
+        if !interface.types.is_empty() {
+            write!(out, "{heading} Types\n\n")?;
+            for (name, &type_id) in &interface.types {
+                self.type_def(out, name, type_id, heading)?;
+            }
+        }
+
+        let functions: Vec<_> = interface
+            .functions
+            .values()
+            .filter(|func| func.kind == FunctionKind::Freestanding)
+            .collect();
+        if !functions.is_empty() {
+            write!(out, "{heading} Functions\n\n")?;
+            for func in functions {
+                self.function(out, func, heading)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn type_def(&self, out: &mut String, name: &str, id: TypeId, heading: &str) -> fmt::Result {
+        let resolve = self.resolve;
+        let def = &resolve.types[id];
+        let members: Vec<(String, &Docs)> = match &def.kind {
+            TypeDefKind::Record(record) => {
+                write!(out, "{heading}# record `{name}`\n\n")?;
+                record
+                    .fields
+                    .iter()
+                    .map(|field| {
+                        let ty = wit::type_name(resolve, &field.ty);
+                        (format!("{}: {ty}", field.name), &field.docs)
+                    })
+                    .collect()
+            }
+            TypeDefKind::Variant(variant) => {
+                write!(out, "{heading}# variant `{name}`\n\n")?;
+                variant
+                    .cases
+                    .iter()
+                    .map(|case| match &case.ty {
+                        Some(ty) => {
+                            let ty = wit::type_name(resolve, ty);
+                            (format!("{}({ty})", case.name), &case.docs)
+                        }
+                        None => (case.name.clone(), &case.docs),
+                    })
+                    .collect()
+            }
+            TypeDefKind::Enum(enum_) => {
+                write!(out, "{heading}# enum `{name}`\n\n")?;
+                enum_
+                    .cases
+                    .iter()
+                    .map(|case| (case.name.clone(), &case.docs))
+                    .collect()
+            }
+            TypeDefKind::Flags(flags) => {
+                write!(out, "{heading}# flags `{name}`\n\n")?;
+                flags
+                    .flags
+                    .iter()
+                    .map(|flag| (flag.name.clone(), &flag.docs))
+                    .collect()
+            }
+            TypeDefKind::Resource => {
+                write!(out, "{heading}# resource `{name}`\n\n")?;
+                vec![]
+            }
+            TypeDefKind::Type(Type::Id(target)) if resolve.types[*target].owner != def.owner => {
+                let target = &resolve.types[*target];
+                write!(out, "{heading}# type `{name}`\n\n")?;
+                if let TypeOwner::Interface(owner) = target.owner {
+                    let from = resolve.id_of(owner).unwrap_or_default();
+                    let target_name = target.name.as_deref().unwrap_or(name);
+                    write!(out, "Used from `{from}.{{{target_name}}}`.\n\n")?;
+                }
+                vec![]
+            }
+            kind => {
+                write!(out, "{heading}# type `{name}`\n\n")?;
+                write!(
+                    out,
+                    "`type {name} = {}`\n\n",
+                    wit::kind_syntax(resolve, kind)
+                )?;
+                vec![]
+            }
+        };
+        write_docs(out, &def.docs)?;
+
+        for (member, docs) in &members {
+            match &docs.contents {
+                Some(contents) => writeln!(out, "- `{member}`: {}", contents.replace('\n', " "))?,
+                None => writeln!(out, "- `{member}`")?,
+            }
+        }
+        if !members.is_empty() {
+            writeln!(out)?;
+        }
+
+        if let (TypeDefKind::Resource, TypeOwner::Interface(owner)) = (&def.kind, def.owner) {
+            let methods =
+                resolve.interfaces[owner]
+                    .functions
+                    .values()
+                    .filter(|func| match func.kind {
+                        FunctionKind::Method(resource)
+                        | FunctionKind::Static(resource)
+                        | FunctionKind::Constructor(resource) => resource == id,
+                        FunctionKind::Freestanding => false,
+                    });
+            for func in methods {
+                self.function(out, func, &format!("{heading}#"))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn function(&self, out: &mut String, func: &Function, heading: &str) -> fmt::Result {
+        let signature = wit::signature(self.resolve, func);
+        let name = func.item_name();
+        match func.kind {
+            FunctionKind::Constructor(_) => write!(out, "{heading}# `constructor{signature}`\n\n")?,
+            FunctionKind::Static(_) => {
+                write!(out, "{heading}# `{name}: static func{signature}`\n\n")?
+            }
+            _ => write!(out, "{heading}# `{name}: func{signature}`\n\n")?,
+        }
+        write_docs(out, &func.docs)
+    }
+}
+
+fn render(f: impl FnOnce(&mut String) -> fmt::Result) -> String {
+    let mut out = String::new();
+    f(&mut out).expect("writing to a String can't fail");
+    out
+}
+
+fn write_docs(out: &mut String, docs: &Docs) -> fmt::Result {
+    match &docs.contents {
+        Some(contents) => write!(out, "{contents}\n\n"),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use wit_parser::UnresolvedPackage;
+
+    use super::*;
+
+    #[test]
+    fn test_markdown() {
+        let mut resolve = Resolve::default();
+        let unresolved = UnresolvedPackage::parse(
+            Path::new("test.wit"),
+            r#"
+            /// A test package.
+            package test:pkg;
+
+            /// Key-value storage.
+            interface store {
+                /// A storage error.
+                enum error {
+                    /// The key wasn't found.
+                    not-found,
+                    other,
+                }
+                type key = list<u8>;
+                resource bucket {
+                    /// Gets a value.
+                    get: func(key: key) -> result<string, error>;
+                }
+                /// Opens a bucket.
+                open: func(name: string) -> bucket;
+            }
+
+            world app {
+                import store;
+            }
+            "#,
+        )
+        .unwrap();
+        let pkg = resolve.push(unresolved).unwrap();
+        let docs = Markdown::new(&resolve, pkg);
+
+        let page = docs.single_page();
+        for expected in [
+            "# Package `test:pkg`\n\nA test package.\n\n",
+            "## World `app`\n\n### Imports\n\n- interface `test:pkg/store`\n",
+            "## Interface `store`\n\nKey-value storage.\n\n### Types\n\n",
+            "#### enum `error`\n\nA storage error.\n\n- `not-found`: The key wasn't found.\n- `other`\n",
+            "`type key = list<u8>`",
+            "#### resource `bucket`\n\n##### `get: func(key: key) -> result<string, error>`\n\nGets a value.\n",
+            "### Functions\n\n#### `open: func(name: string) -> bucket`\n\nOpens a bucket.\n",
+        ] {
+            assert!(page.contains(expected), "{expected:?} not in {page}");
+        }
+
+        let pages = docs.pages();
+        let names: Vec<_> = pages.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["index.md", "store.md"]);
+        assert!(pages[0].1.contains("- [`store`](store.md)"));
+        assert!(pages[1]
+            .1
+            .starts_with("# Interface `test:pkg/store`\n\nKey-value storage.\n\n## Types\n\n"));
+    }
+}
@@ -0,0 +1,169 @@
+//! Stable error codes, reported as the process exit code and in `--json`
+//! error output so wrappers can branch on the kind of failure rather than
+//! matching error messages.
+
+use std::process::ExitCode;
+
+use wasm_pkg_loader::Error;
+
+use crate::{batch::BatchFailed, policy::PolicyViolation, quarantine::ValidationFailed};
+
+/// The exit codes, as listed in `--help`.
+pub const EXIT_CODES_HELP: &str = "\
+Exit codes:
+  1  Other failure
+  2  Invalid arguments
+  4  Package or version not found (not-found)
+  5  Registry authentication failed (auth)
+  6  Content failed verification (integrity)
+  7  Registry unreachable, or offline (network)
+  8  Not allowed by policy (policy)
+  9  Dependencies can't be resolved together (conflict)";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorCode {
+    /// Any failure not covered by a more specific code.
+    Other,
+    /// A package or version doesn't exist.
+    NotFound,
+    /// A registry rejected or couldn't get credentials.
+    Auth,
+    /// Content or an attestation failed verification, e.g. by not matching
+    /// its digest or failing Wasm validation.
+    Integrity,
+    /// A registry couldn't be reached, including in offline mode.
+    Network,
+    /// A package, version, or registry isn't allowed by policy.
+    Policy,
+    /// The requested dependencies can't be resolved together.
+    Conflict,
+}
+
+impl ErrorCode {
+    /// Returns the code for the given error, determined by the first error
+    /// in its chain with a specific code.
+    pub fn of(err: &anyhow::Error) -> Self {
+        if let Some(failed) = err.downcast_ref::<BatchFailed>() {
+            return failed.code();
+        }
+        if err.downcast_ref::<PolicyViolation>().is_some() {
+            return Self::Policy;
+        }
+        if err.downcast_ref::<ValidationFailed>().is_some() {
+            return Self::Integrity;
+        }
+        err.chain()
+            .filter_map(|cause| cause.downcast_ref::<Error>())
+            .find_map(Self::of_loader_error)
+            .unwrap_or(Self::Other)
+    }
+
+    fn of_loader_error(err: &Error) -> Option<Self> {
+        if err.is_not_found() {
+            return Some(Self::NotFound);
+        }
+        if err.is_unauthorized() {
+            return Some(Self::Auth);
+        }
+        if err.is_network() {
+            return Some(Self::Network);
+        }
+        match err {
+            Error::InvalidContent(_) | Error::InvalidContentDigest(_) => Some(Self::Integrity),
+            Error::ProvenanceRejected(..)
+            | Error::SignatureRejected(..)
+            | Error::RegistryNotAllowed(..)
+            | Error::RegistryRedirectNotAllowed(..)
+            | Error::VersionYanked(_) => Some(Self::Policy),
+            Error::DependencyConflict(_) => Some(Self::Conflict),
+            _ => None,
+        }
+    }
+
+    /// Returns the stable name of this code, as used in JSON output.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Other => "other",
+            Self::NotFound => "not-found",
+            Self::Auth => "auth",
+            Self::Integrity => "integrity",
+            Self::Network => "network",
+            Self::Policy => "policy",
+            Self::Conflict => "conflict",
+        }
+    }
+
+    /// Returns the process exit code for this code. Exit code 2 is used by
+    /// the argument parser for usage errors.
+    pub fn exit_code(self) -> u8 {
+        match self {
+            Self::Other => 1,
+            Self::NotFound => 4,
+            Self::Auth => 5,
+            Self::Integrity => 6,
+            Self::Network => 7,
+            Self::Policy => 8,
+            Self::Conflict => 9,
+        }
+    }
+}
+
+/// Returns true if the given error may not recur if retried, e.g. a dropped
+/// connection.
+pub fn is_transient(err: &anyhow::Error) -> bool {
+    err.chain()
+        .filter_map(|cause| cause.downcast_ref::<Error>())
+        .any(Error::is_transient)
+}
+
+/// Prints the given error to stderr, as JSON if `json` is set, and returns
+/// the exit code for it.
+pub fn report(err: &anyhow::Error, json: bool) -> ExitCode {
+    let code = ErrorCode::of(err);
+    if json {
+        let error = serde_json::json!({
+            "error": {
+                "code": code.name(),
+                "exit_code": code.exit_code(),
+                "message": err.to_string(),
+                "causes": err.chain().skip(1).map(ToString::to_string).collect::<Vec<_>>(),
+            }
+        });
+        eprintln!("{error}");
+    } else {
+        eprintln!("Error: {err:?}");
+    }
+    ExitCode::from(code.exit_code())
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Context;
+
+    use super::*;
+
+    #[test]
+    fn test_error_code() {
+        let not_found = Err::<(), _>(Error::VersionNotFound("1.0.0".parse().unwrap()))
+            .context("Failed to get release details")
+            .unwrap_err();
+        assert_eq!(ErrorCode::of(&not_found), ErrorCode::NotFound);
+        assert_eq!(ErrorCode::NotFound.exit_code(), 4);
+
+        let integrity = Err::<(), _>(Error::InvalidContent("digest mismatch".into()))
+            .context("Failed to fetch")
+            .unwrap_err();
+        assert_eq!(ErrorCode::of(&integrity), ErrorCode::Integrity);
+
+        let invalid = Err::<(), _>(anyhow::anyhow!("bad section"))
+            .context(ValidationFailed("wasi:io@0.2.0 failed validation".into()))
+            .context("Failed to get wasi:io")
+            .unwrap_err();
+        assert_eq!(ErrorCode::of(&invalid), ErrorCode::Integrity);
+
+        assert_eq!(
+            ErrorCode::of(&anyhow::anyhow!("something else")),
+            ErrorCode::Other
+        );
+    }
+}
@@ -0,0 +1,95 @@
+//! `wkg hash`: computes the content digest a registry would record for a
+//! local file, and compares it against an expected digest or a release's.
+
+use std::path::PathBuf;
+
+use anyhow::{bail, Context};
+use clap::Args;
+use wasm_pkg_loader::ContentDigest;
+
+use crate::{
+    package_spec::{suggest_not_found, PackageSpec},
+    publish,
+    quarantine::ValidationFailed,
+    GlobalArgs, RegistryArgs,
+};
+
+#[derive(Args, Debug)]
+pub struct HashCommand {
+    /// The file to hash: a `.wasm` or `.tar.gz` file as-is, or a WIT source
+    /// directory or file, encoded as `wkg publish` would encode it.
+    path: PathBuf,
+
+    /// Fail unless the digest is this one.
+    #[arg(long, value_name = "DIGEST", conflicts_with = "package")]
+    expect: Option<ContentDigest>,
+
+    /// Fail unless the digest is that of this release in its registry, as
+    /// <namespace>:<name>@<version> (or <namespace>:<name> for the latest
+    /// release).
+    #[arg(long, value_name = "PACKAGE_SPEC")]
+    package: Option<PackageSpec>,
+
+    #[command(flatten)]
+    registry: RegistryArgs,
+}
+
+impl HashCommand {
+    pub async fn run(self, global: &GlobalArgs) -> anyhow::Result<()> {
+        let (content, _) = publish::load_content(&self.path)?;
+        let digest = ContentDigest::sha256(&content);
+
+        let expected = match (self.expect, self.package) {
+            (Some(expected), _) => Some((expected.to_string(), expected)),
+            (None, Some(spec)) => {
+                let (package, version) = spec.into_versioned()?;
+                let mut client = {
+                    let mut config = global.load_config()?;
+                    if let Some(registry) = self.registry.domain {
+                        config.set_namespace_registry(package.namespace().to_string(), registry);
+                    }
+                    config.to_client()
+                };
+                let version = match version {
+                    Some(version) => version,
+                    None => {
+                        let result = client.latest_version(&package).await;
+                        suggest_not_found(result, &mut client, &package, None)
+                            .await
+                            .context("Failed to select latest version")?
+                    }
+                };
+                let result = client.get_release(&package, &version).await;
+                let release = suggest_not_found(result, &mut client, &package, Some(&version))
+                    .await
+                    .context("Failed to get release details")?;
+                Some((format!("{package}@{version}"), release.content_digest))
+            }
+            (None, None) => None,
+        };
+        let matches = expected
+            .as_ref()
+            .map(|(_, expected_digest)| *expected_digest == digest);
+
+        if global.json {
+            let json = serde_json::json!({
+                "path": self.path.display().to_string(),
+                "digest": digest.to_string(),
+                "expected": expected.as_ref().map(|(_, digest)| digest.to_string()),
+                "matches": matches,
+            });
+            println!("{}", serde_json::to_string_pretty(&json)?);
+        } else {
+            println!("{digest}  {}", self.path.display());
+        }
+        match (expected, matches) {
+            (Some((name, expected_digest)), Some(false)) => bail!(ValidationFailed(format!(
+                "{:?} has digest {digest}, not {expected_digest} ({name})",
+                self.path
+            ))),
+            (Some((name, _)), _) if !global.json => println!("Matches {name}"),
+            _ => (),
+        }
+        Ok(())
+    }
+}
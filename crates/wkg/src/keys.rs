@@ -0,0 +1,272 @@
+//! Signing key management. Key pairs are stored as `<name>.key` files in
+//! the `keys` directory under the config dir (e.g.
+//! `~/.config/wasm-pkg/keys`). Rotated keys are kept under `keys/retired`
+//! so their public keys can still be exported to verify older signatures.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, ensure, Context};
+use clap::{Args, Subcommand};
+use warg_crypto::signing::{PrivateKey, PublicKey};
+use wasm_pkg_loader::ClientConfig;
+
+use crate::{
+    table::{Cell, Color, Table},
+    GlobalArgs,
+};
+
+const DEFAULT_KEY_NAME: &str = "default";
+
+const KEY_EXTENSION: &str = "key";
+
+#[derive(Args, Debug)]
+pub struct KeygenCommand {
+    /// The name of the key pair.
+    #[arg(long, default_value = DEFAULT_KEY_NAME)]
+    name: String,
+
+    /// Replace an existing key pair with the same name. Prefer `wkg key
+    /// rotate`, which keeps the old public key.
+    #[arg(long)]
+    force: bool,
+}
+
+impl KeygenCommand {
+    pub async fn run(self, _global: &GlobalArgs) -> anyhow::Result<()> {
+        let store = KeyStore::default_store()?;
+        let public_key = store.generate(&self.name, self.force)?;
+        println!(
+            "Generated key pair {:?} with fingerprint {}",
+            self.name,
+            public_key.fingerprint()
+        );
+        println!("Public key: {public_key}");
+        Ok(())
+    }
+}
+
+#[derive(Subcommand, Debug)]
+pub enum KeyCommand {
+    /// List signing keys, including retired ones.
+    List,
+    /// Replace a key pair with a newly generated one, retiring the old one.
+    Rotate {
+        /// The name of the key pair.
+        #[arg(long, default_value = DEFAULT_KEY_NAME)]
+        name: String,
+    },
+    /// Print a public key, for distribution to consumers.
+    Export {
+        /// The name of the key pair.
+        #[arg(long, default_value = DEFAULT_KEY_NAME)]
+        name: String,
+
+        /// Write the public key to this path instead of stdout.
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+}
+
+impl KeyCommand {
+    pub async fn run(self, _global: &GlobalArgs) -> anyhow::Result<()> {
+        let store = KeyStore::default_store()?;
+        match self {
+            KeyCommand::List => {
+                let mut table = Table::new(&["name", "fingerprint", "status", "public key"]);
+                for key in store.list()? {
+                    let status = match key.retired {
+                        Some(secs) => Cell::colored(format!("retired {secs}"), Some(Color::Dim)),
+                        None => Cell::colored("active", Some(Color::Green)),
+                    };
+                    table.push(vec![
+                        Cell::new(&key.name),
+                        Cell::new(key.public_key.fingerprint()),
+                        status,
+                        Cell::new(&key.public_key),
+                    ]);
+                }
+                table.print();
+            }
+            KeyCommand::Rotate { name } => {
+                let public_key = store.rotate(&name)?;
+                println!(
+                    "Rotated key pair {name:?}; new fingerprint {}",
+                    public_key.fingerprint()
+                );
+                println!("Public key: {public_key}");
+            }
+            KeyCommand::Export { name, output } => {
+                let public_key = store.load(&name)?.public_key();
+                match output {
+                    Some(path) => std::fs::write(&path, format!("{public_key}\n"))
+                        .with_context(|| format!("Failed to write {path:?}"))?,
+                    None => println!("{public_key}"),
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A signing key listed by [`KeyStore::list`].
+pub struct KeyInfo {
+    pub name: String,
+    pub public_key: PublicKey,
+    /// When the key was retired, in seconds since the Unix epoch.
+    pub retired: Option<u64>,
+}
+
+/// A directory of signing key pairs.
+pub struct KeyStore {
+    dir: PathBuf,
+}
+
+impl KeyStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Returns the key store in the default config directory.
+    pub fn default_store() -> anyhow::Result<Self> {
+        let config_dir = ClientConfig::default_config_path()
+            .as_deref()
+            .and_then(Path::parent)
+            .map(Path::to_path_buf)
+            .context("Couldn't determine config directory")?;
+        Ok(Self::new(config_dir.join("keys")))
+    }
+
+    fn key_path(&self, name: &str) -> anyhow::Result<PathBuf> {
+        ensure!(
+            !name.is_empty()
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'),
+            "invalid key name {name:?}; may only contain letters, numbers, '-', and '_'"
+        );
+        Ok(self.dir.join(format!("{name}.{KEY_EXTENSION}")))
+    }
+
+    /// Generates and stores a new key pair, returning its public key.
+    pub fn generate(&self, name: &str, overwrite: bool) -> anyhow::Result<PublicKey> {
+        let path = self.key_path(name)?;
+        if path.exists() && !overwrite {
+            bail!("key pair {name:?} already exists; use `wkg key rotate` to replace it");
+        }
+        let key = PrivateKey::from(p256::ecdsa::SigningKey::random(&mut rand_core::OsRng));
+        std::fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create {:?}", self.dir))?;
+        write_private_file(&path, key.encode().as_bytes())
+            .with_context(|| format!("Failed to write {path:?}"))?;
+        Ok(key.public_key())
+    }
+
+    /// Loads the named private key.
+    pub fn load(&self, name: &str) -> anyhow::Result<PrivateKey> {
+        let path = self.key_path(name)?;
+        read_key(&path).with_context(|| {
+            format!("Failed to load key pair {name:?}; run `wkg keygen --name {name}` to create it")
+        })
+    }
+
+    /// Retires the named key pair and generates a replacement, returning
+    /// the new public key.
+    pub fn rotate(&self, name: &str) -> anyhow::Result<PublicKey> {
+        let path = self.key_path(name)?;
+        ensure!(path.exists(), "key pair {name:?} doesn't exist");
+        let secs = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let retired_dir = self.dir.join("retired");
+        std::fs::create_dir_all(&retired_dir)
+            .with_context(|| format!("Failed to create {retired_dir:?}"))?;
+        let retired_path = retired_dir.join(format!("{name}.{secs}.{KEY_EXTENSION}"));
+        std::fs::rename(&path, &retired_path)
+            .with_context(|| format!("Failed to move {path:?} to {retired_path:?}"))?;
+        self.generate(name, false)
+    }
+
+    /// Returns all stored keys, active ones first, sorted by name.
+    pub fn list(&self) -> anyhow::Result<Vec<KeyInfo>> {
+        let mut keys = vec![];
+        for (dir, is_retired) in [(self.dir.clone(), false), (self.dir.join("retired"), true)] {
+            let entries = match std::fs::read_dir(&dir) {
+                Ok(entries) => entries,
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(err) => return Err(err).with_context(|| format!("Failed to read {dir:?}")),
+            };
+            let mut dir_keys = vec![];
+            for entry in entries {
+                let path = entry?.path();
+                if path.extension().and_then(|ext| ext.to_str()) != Some(KEY_EXTENSION) {
+                    continue;
+                }
+                let Some(stem) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                    continue;
+                };
+                let (name, retired) = if is_retired {
+                    let Some((name, secs)) = stem.rsplit_once('.') else {
+                        continue;
+                    };
+                    (name.to_string(), secs.parse().ok())
+                } else {
+                    (stem.to_string(), None)
+                };
+                let public_key = read_key(&path)
+                    .with_context(|| format!("Failed to load key {path:?}"))?
+                    .public_key();
+                dir_keys.push(KeyInfo {
+                    name,
+                    public_key,
+                    retired,
+                });
+            }
+            dir_keys.sort_by(|a, b| (&a.name, a.retired).cmp(&(&b.name, b.retired)));
+            keys.extend(dir_keys);
+        }
+        Ok(keys)
+    }
+}
+
+fn read_key(path: &Path) -> anyhow::Result<PrivateKey> {
+    let encoded = std::fs::read_to_string(path)?;
+    Ok(PrivateKey::decode(encoded.trim().to_string())?)
+}
+
+fn write_private_file(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(path)?;
+    file.write_all(contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = KeyStore::new(dir.path());
+        assert!(store.load("test").is_err());
+        assert!(store.generate("../test", false).is_err());
+
+        let first = store.generate("test", false).unwrap();
+        assert!(store.generate("test", false).is_err());
+        assert_eq!(store.load("test").unwrap().public_key(), first);
+
+        let second = store.rotate("test").unwrap();
+        assert_ne!(first, second);
+        let keys = store.list().unwrap();
+        assert_eq!(keys.len(), 2);
+        assert_eq!(keys[0].public_key, second);
+        assert!(keys[0].retired.is_none());
+        assert_eq!(keys[1].public_key, first);
+        assert!(keys[1].retired.is_some());
+    }
+}
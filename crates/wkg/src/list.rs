@@ -0,0 +1,46 @@
+//! `wkg list`: lists the packages a registry has in a namespace, for
+//! discovery and for mirroring tools that need the full set.
+
+use anyhow::Context;
+use clap::Args;
+
+use crate::{GlobalArgs, RegistryArgs};
+
+#[derive(Args, Debug)]
+pub struct ListCommand {
+    /// The namespace to list packages in, e.g. "wasi".
+    #[arg(long)]
+    namespace: String,
+
+    #[command(flatten)]
+    registry: RegistryArgs,
+}
+
+impl ListCommand {
+    pub async fn run(self, global: &GlobalArgs) -> anyhow::Result<()> {
+        let mut client = {
+            let mut config = global.load_config()?;
+            if let Some(registry) = self.registry.domain {
+                config.set_namespace_registry(self.namespace.clone(), registry);
+            }
+            config.to_client()
+        };
+        let namespace = &self.namespace;
+        let packages = client
+            .list_packages(namespace)
+            .await
+            .with_context(|| format!("Failed to list packages in namespace {namespace:?}"))?
+            .with_context(|| {
+                format!("The registry for namespace {namespace:?} doesn't support listing packages")
+            })?;
+        if global.json {
+            let packages: Vec<_> = packages.iter().map(ToString::to_string).collect();
+            println!("{}", serde_json::to_string_pretty(&packages)?);
+        } else {
+            for package in &packages {
+                println!("{package}");
+            }
+        }
+        Ok(())
+    }
+}
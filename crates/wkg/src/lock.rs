@@ -0,0 +1,220 @@
+//! The `wkg.lock` lock file, recording the exact version and content digest
+//! selected for each manifest dependency and each of their transitive
+//! dependencies.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use wasm_pkg_loader::{ContentDigest, PackageRef, Version};
+
+pub const LOCK_FILE_NAME: &str = "wkg.lock";
+
+const LOCK_FILE_VERSION: u32 = 1;
+
+const LOCK_FILE_HEADER: &str =
+    "# This file is automatically generated by wkg.\n# It is not intended for manual editing.\n";
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LockFile {
+    /// Locked packages, sorted by name.
+    pub packages: Vec<LockedPackage>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LockedPackage {
+    pub name: PackageRef,
+    pub version: Version,
+    /// The registry the package was resolved from, if overridden by the
+    /// manifest.
+    pub registry: Option<String>,
+    /// The digest of the package's content. Fetched content must match it,
+    /// guarding against a registry serving different content for a locked
+    /// version. Lock files written before digests were recorded lack it.
+    pub digest: Option<ContentDigest>,
+}
+
+impl LockFile {
+    /// Returns the lock file path for the given manifest directory.
+    pub fn path_for(root_dir: &Path) -> PathBuf {
+        root_dir.join(LOCK_FILE_NAME)
+    }
+
+    /// Loads the lock file at the given path, returning Ok(None) if it
+    /// doesn't exist.
+    pub fn load(path: &Path) -> anyhow::Result<Option<Self>> {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err).with_context(|| format!("Failed to read {path:?}")),
+        };
+        let lock =
+            Self::from_toml(&contents).with_context(|| format!("Invalid lock file {path:?}"))?;
+        Ok(Some(lock))
+    }
+
+    fn from_toml(contents: &str) -> anyhow::Result<Self> {
+        let TomlLockFile { version, package } = toml::from_str(contents)?;
+        anyhow::ensure!(
+            version == LOCK_FILE_VERSION,
+            "unsupported lock file version {version}"
+        );
+        let mut packages = package
+            .into_iter()
+            .map(|pkg| {
+                Ok(LockedPackage {
+                    name: pkg.name.parse()?,
+                    version: pkg.version.parse()?,
+                    registry: pkg.registry,
+                    digest: pkg.digest.as_deref().map(str::parse).transpose()?,
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        packages.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(Self { packages })
+    }
+
+    pub fn to_toml(&self) -> anyhow::Result<String> {
+        let toml_lock = TomlLockFile {
+            version: LOCK_FILE_VERSION,
+            package: self
+                .packages
+                .iter()
+                .map(|pkg| TomlLockedPackage {
+                    name: pkg.name.to_string(),
+                    version: pkg.version.to_string(),
+                    registry: pkg.registry.clone(),
+                    digest: pkg.digest.as_ref().map(ToString::to_string),
+                })
+                .collect(),
+        };
+        Ok(format!(
+            "{LOCK_FILE_HEADER}{}",
+            toml::to_string(&toml_lock)?
+        ))
+    }
+
+    /// Writes the lock file to the given path.
+    pub fn store(&self, path: &Path) -> anyhow::Result<()> {
+        std::fs::write(path, self.to_toml()?).with_context(|| format!("Failed to write {path:?}"))
+    }
+
+    /// Returns the locked entry for the given package, if any.
+    pub fn get(&self, name: &PackageRef) -> Option<&LockedPackage> {
+        self.packages.iter().find(|pkg| &pkg.name == name)
+    }
+
+    /// Returns the differences from this lock file to `other`, as a line
+    /// per removed (`-`) or added (`+`) entry, with a changed entry
+    /// appearing as both.
+    pub fn diff(&self, other: &LockFile) -> Vec<String> {
+        let mut names: Vec<&PackageRef> = self
+            .packages
+            .iter()
+            .chain(&other.packages)
+            .map(|pkg| &pkg.name)
+            .collect();
+        names.sort();
+        names.dedup();
+        let mut lines = vec![];
+        for name in names {
+            let (old, new) = (self.get(name), other.get(name));
+            if old == new {
+                continue;
+            }
+            lines.extend(old.map(|pkg| format!("- {pkg}")));
+            lines.extend(new.map(|pkg| format!("+ {pkg}")));
+        }
+        lines
+    }
+}
+
+impl std::fmt::Display for LockedPackage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}@{}", self.name, self.version)?;
+        if let Some(registry) = &self.registry {
+            write!(f, " from {registry}")?;
+        }
+        match &self.digest {
+            Some(digest) => write!(f, " ({digest})"),
+            None => f.write_str(" (no digest)"),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct TomlLockFile {
+    version: u32,
+    #[serde(default)]
+    package: Vec<TomlLockedPackage>,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct TomlLockedPackage {
+    name: String,
+    version: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    registry: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    digest: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip() {
+        let lock = LockFile {
+            packages: vec![
+                LockedPackage {
+                    name: "wasi:http".parse().unwrap(),
+                    version: "0.2.0".parse().unwrap(),
+                    registry: Some("wasi.dev".into()),
+                    digest: Some(
+                        "sha256:0000000000000000000000000000000000000000000000000000000000000000"
+                            .parse()
+                            .unwrap(),
+                    ),
+                },
+                LockedPackage {
+                    name: "wasi:io".parse().unwrap(),
+                    version: "0.2.1".parse().unwrap(),
+                    registry: None,
+                    digest: None,
+                },
+            ],
+        };
+        let toml = lock.to_toml().unwrap();
+        assert!(toml.starts_with(LOCK_FILE_HEADER));
+        assert_eq!(LockFile::from_toml(&toml).unwrap(), lock);
+    }
+
+    #[test]
+    fn test_diff() {
+        let locked = |name: &str, version: &str| LockedPackage {
+            name: name.parse().unwrap(),
+            version: version.parse().unwrap(),
+            registry: None,
+            digest: None,
+        };
+        let old = LockFile {
+            packages: vec![locked("wasi:cli", "0.2.0"), locked("wasi:io", "0.2.0")],
+        };
+        let new = LockFile {
+            packages: vec![locked("wasi:http", "0.2.0"), locked("wasi:io", "0.2.1")],
+        };
+        assert!(old.diff(&old).is_empty());
+        assert_eq!(
+            old.diff(&new),
+            [
+                "- wasi:cli@0.2.0 (no digest)",
+                "+ wasi:http@0.2.0 (no digest)",
+                "- wasi:io@0.2.0 (no digest)",
+                "+ wasi:io@0.2.1 (no digest)",
+            ]
+        );
+    }
+}
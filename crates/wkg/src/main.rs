@@ -1,26 +1,293 @@
+mod actions;
+mod archive;
+mod audit;
+mod batch;
+mod bindgen;
+mod bundle;
+mod compose;
+mod config;
+mod copy;
+mod delete;
+mod deprecate;
+mod doc;
+mod error_code;
+mod hash;
+mod keys;
+mod list;
+mod lock;
+mod manifest;
+mod metadata;
+mod module;
+mod outdated;
+mod output;
 mod package_spec;
+mod policy;
+mod prefetch;
+mod project;
+mod project_metadata;
+mod promote;
+mod publish;
+mod quarantine;
+mod report;
+mod sbom;
+mod semver_check;
+mod show;
+mod spool;
+mod strip;
+mod table;
+mod tag;
+mod watch;
+mod why;
+mod wit;
 
-use std::{io::Seek, path::PathBuf};
+use std::{
+    borrow::Cow,
+    ffi::OsStr,
+    path::{Path, PathBuf},
+    process::ExitCode,
+    time::Duration,
+};
 
-use anyhow::{ensure, Context};
+use anyhow::{bail, ensure, Context};
+use audit::AuditCommand;
+use batch::Batch;
+use bindgen::BindgenCommand;
+use bundle::BundleCommand;
 use clap::{Args, Parser, Subcommand, ValueEnum};
+use compose::ComposeCommand;
+use config::{ConfigCommand, LoginCommand};
+use copy::CopyCommand;
+use delete::DeleteCommand;
+use deprecate::DeprecateCommand;
+use doc::DocCommand;
 use futures_util::TryStreamExt;
-use package_spec::PackageSpec;
-use tokio::io::AsyncWriteExt;
+use hash::HashCommand;
+use keys::{KeyCommand, KeygenCommand};
+use list::ListCommand;
+use metadata::PackageMetadata;
+use outdated::OutdatedCommand;
+use output::NameTemplate;
+use package_spec::{suggest_not_found, PackageSpec, SpecArg};
+use policy::OrgPolicy;
+use prefetch::PrefetchCommand;
+use project::{AddCommand, LockCommand, ManifestCommand, SyncCommand, TreeCommand, UpdateCommand};
+use project_metadata::MetadataCommand;
+use promote::PromoteCommand;
+use publish::PublishCommand;
+use quarantine::{Quarantine, StreamingValidator, ValidationFailed};
+use sbom::SbomCommand;
+use semver_check::SemverCheckCommand;
+use show::ShowCommand;
+use spool::Spool;
+use tag::TagCommand;
 use tracing::level_filters::LevelFilter;
-use wasm_pkg_loader::ClientConfig;
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use wasm_pkg_loader::{ClientConfig, ContentDigest, PackageRef, Release};
+use watch::WatchCommand;
+use why::WhyCommand;
+use wit::Selection;
 use wit_component::DecodedWasm;
 
 #[derive(Parser, Debug)]
-#[command(version)]
+#[command(version, after_help = error_code::EXIT_CODES_HELP)]
 struct Cli {
+    #[command(flatten)]
+    global: GlobalArgs,
+
     #[command(subcommand)]
     command: Commands,
 }
 
+#[derive(Args, Debug)]
+struct GlobalArgs {
+    /// Use this config file instead of the default config and credentials
+    /// files.
+    #[arg(long = "config", global = true, env = "WKG_CONFIG_FILE")]
+    config_file: Option<PathBuf>,
+
+    /// The config profile to use, as defined by a `[profile.<name>]` config
+    /// section.
+    #[arg(long, global = true, env = "WKG_PROFILE")]
+    profile: Option<String>,
+
+    /// Fail if the lock file (wkg.lock) is missing or needs to be updated.
+    #[arg(long, global = true)]
+    locked: bool,
+
+    /// Like --locked, but also fail if any registry would need to be
+    /// accessed; only cached metadata and content are used.
+    #[arg(long, global = true)]
+    frozen: bool,
+
+    /// Bypass the local cache entirely: fetch all metadata and content from
+    /// registries, and cache nothing.
+    #[arg(long, global = true, conflicts_with_all = ["frozen", "refresh"])]
+    no_cache: bool,
+
+    /// Refetch cached metadata (version lists and release details) from
+    /// registries, still reusing cached content that matches it.
+    #[arg(long, global = true, conflicts_with = "frozen")]
+    refresh: bool,
+
+    /// Fail to fetch any content that can't be verified against a content
+    /// digest obtained before downloading it.
+    #[arg(long, global = true)]
+    require_digest: bool,
+
+    /// Fail to fetch any package without a provenance attestation accepted
+    /// by the configured provenance policy.
+    #[arg(long, global = true)]
+    require_provenance: bool,
+
+    /// The organization policy file to enforce. Defaults to
+    /// `wkg-policy.toml` in the config directory, if present.
+    #[arg(long, global = true, env = "WKG_POLICY_FILE")]
+    policy_file: Option<PathBuf>,
+
+    /// Download content into this directory, only moving it to its output
+    /// path once it has passed all verification, including Wasm
+    /// validation.
+    #[arg(long, global = true, env = "WKG_QUARANTINE_DIR")]
+    quarantine_dir: Option<PathBuf>,
+
+    /// Print errors and warnings as GitHub Actions workflow annotations
+    /// and write a job summary of fetched packages to the file given by
+    /// `GITHUB_STEP_SUMMARY`.
+    #[arg(long, global = true, env = "WKG_GITHUB_ACTIONS")]
+    github_actions: bool,
+
+    /// Print output as JSON where supported, and errors as a JSON object
+    /// with a stable `code` naming the failure's exit code.
+    #[arg(long, global = true)]
+    json: bool,
+
+    /// Write a JSON report of the packages fetched (with their digests,
+    /// source registries, cache hits, sizes, and timings) to this file once
+    /// the command finishes, whether or not it succeeds.
+    #[arg(long, global = true, value_name = "FILE")]
+    report: Option<PathBuf>,
+
+    /// When fetching many packages (`get` with specs from stdin,
+    /// `prefetch`, and `sync`), carry on past packages that fail, listing
+    /// the failures at the end, rather than stopping at the first.
+    #[arg(long, global = true)]
+    continue_on_error: bool,
+
+    #[arg(skip)]
+    job_summary: actions::JobSummary,
+
+    #[arg(skip)]
+    run_report: report::RunReport,
+}
+
+/// Names where a global option's value came from: its environment variable
+/// if that holds the value, or else its flag.
+fn option_source(env: &str, flag: &str, value: &OsStr) -> String {
+    if std::env::var_os(env).is_some_and(|env_value| env_value == value) {
+        format!("env {env}")
+    } else {
+        flag.to_string()
+    }
+}
+
+impl GlobalArgs {
+    /// Returns the effective client config: built-in defaults merged with
+    /// the `--config` file (or else the default config and credentials
+    /// files) and the selected profile.
+    fn load_config(&self) -> anyhow::Result<ClientConfig> {
+        let (_, config) = self
+            .load_config_layers()?
+            .pop()
+            .expect("built-in defaults are always loaded");
+        Ok(config)
+    }
+
+    /// Loads the config as [`Self::load_config`] does, returning the config
+    /// as it stands after each layer is applied, along with where that
+    /// layer came from (a file, environment variable, or flag).
+    fn load_config_layers(&self) -> anyhow::Result<Vec<(String, ClientConfig)>> {
+        let mut layers = Vec::new();
+        let mut config = ClientConfig::default();
+        config.set_default_registry("bytecodealliance.org");
+        layers.push(("built-in default".to_string(), config.clone()));
+        match &self.config_file {
+            Some(path) => {
+                let file_config = ClientConfig::from_file(path)
+                    .with_context(|| format!("Failed to load config file {path:?}"))?;
+                config.merge_config(file_config);
+                let source = option_source("WKG_CONFIG_FILE", "--config", path.as_os_str());
+                layers.push((format!("{} ({source})", path.display()), config.clone()));
+            }
+            None => {
+                if let Some(file_config) = ClientConfig::from_default_file()? {
+                    config.merge_config(file_config);
+                    let files: Vec<_> = [
+                        ClientConfig::default_config_path(),
+                        ClientConfig::default_credentials_path(),
+                    ]
+                    .into_iter()
+                    .flatten()
+                    .filter(|path| path.exists())
+                    .map(|path| path.display().to_string())
+                    .collect();
+                    layers.push((files.join(" or "), config.clone()));
+                }
+            }
+        }
+        if let Some(profile) = &self.profile {
+            config.apply_profile(profile)?;
+            let source = option_source("WKG_PROFILE", "--profile", profile.as_ref());
+            layers.push((format!("profile {profile:?} ({source})"), config.clone()));
+        }
+        self.load_policy()?.apply_to_config(&mut config);
+        layers.push(("organization policy".to_string(), config.clone()));
+        if self.frozen {
+            config.set_offline(true);
+            layers.push(("--frozen".to_string(), config.clone()));
+        }
+        if self.no_cache {
+            config.set_no_cache(true);
+            layers.push(("--no-cache".to_string(), config.clone()));
+        }
+        if self.refresh {
+            config.set_refresh(true);
+            layers.push(("--refresh".to_string(), config.clone()));
+        }
+        if self.require_digest {
+            config.set_require_digest(true);
+            layers.push(("--require-digest".to_string(), config.clone()));
+        }
+        if self.require_provenance {
+            let mut policy = config.provenance_policy().clone();
+            policy.require = true;
+            config.set_provenance_policy(policy);
+            layers.push(("--require-provenance".to_string(), config.clone()));
+        }
+        Ok(layers)
+    }
+
+    /// Returns the organization policy to enforce.
+    fn load_policy(&self) -> anyhow::Result<OrgPolicy> {
+        OrgPolicy::load(self.policy_file.as_deref())
+    }
+
+    /// Returns the quarantine for fetched content, if enabled.
+    fn quarantine(&self) -> anyhow::Result<Option<Quarantine>> {
+        self.quarantine_dir
+            .as_ref()
+            .map(Quarantine::open)
+            .transpose()
+    }
+
+    /// Returns true if the lock file must not be changed.
+    fn locked(&self) -> bool {
+        self.locked || self.frozen
+    }
+}
+
 #[derive(Args, Debug)]
 struct RegistryArgs {
-    /// The registry domain to use. Overrides configuration file(s).
+    /// The registry domain (or alias) to use. Overrides configuration file(s).
     #[arg(long = "registry", value_name = "DOMAIN")]
     domain: Option<String>,
 }
@@ -29,16 +296,139 @@ struct RegistryArgs {
 enum Commands {
     /// Get a package.
     Get(GetCommand),
+    /// Show a package release's details and provenance attestations.
+    Inspect(InspectCommand),
+    /// List the packages a registry has in a namespace.
+    List(ListCommand),
+    /// Compute the content digest a registry would record for a local file,
+    /// optionally comparing it against a release's.
+    Hash(HashCommand),
+    /// Publish a package from a WIT source directory or Wasm file.
+    Publish(PublishCommand),
+    /// Copy a package release from one registry to another.
+    Copy(CopyCommand),
+    /// Set or show channel tags (e.g. "stable") pointing at releases.
+    #[command(subcommand)]
+    Tag(TagCommand),
+    /// Verify a release and promote it to another channel tag and/or
+    /// registry, writing a signed promotion record.
+    Promote(PromoteCommand),
+    /// Attach a deprecation notice to a release or a whole package.
+    Deprecate(DeprecateCommand),
+    /// Permanently delete a release or a whole package from a registry
+    /// (for registry administrators).
+    Delete(DeleteCommand),
+    /// Summarize the worlds, interfaces, and dependencies of a WIT package.
+    Show(ShowCommand),
+    /// Generate Markdown reference documentation for a WIT package.
+    Doc(DocCommand),
+    /// Compare two versions of a WIT package and check that the new version
+    /// number reflects the breaking changes and additions between them.
+    SemverCheck(SemverCheckCommand),
+    /// Generate guest bindings for a world of a WIT package.
+    Bindgen(BindgenCommand),
+    /// Compose registry components by plugging their exports into another
+    /// component's imports.
+    Compose(ComposeCommand),
+    /// Inspect or edit configuration.
+    #[command(subcommand)]
+    Config(ConfigCommand),
+    /// Store registry credentials in the credentials file.
+    Login(LoginCommand),
+    /// Generate a signing key pair.
+    Keygen(KeygenCommand),
+    /// List, rotate, or export signing keys.
+    #[command(subcommand)]
+    Key(KeyCommand),
+    /// Generate the project manifest (wkg.toml).
+    #[command(subcommand)]
+    Manifest(ManifestCommand),
+    /// Add a dependency to the project manifest (wkg.toml).
+    Add(AddCommand),
+    /// Resolve the project's dependencies and write the lock file (wkg.lock).
+    Lock(LockCommand),
+    /// Update locked dependencies to newer (or given) versions.
+    Update(UpdateCommand),
+    /// Lock and fetch the project's dependencies.
+    Sync(SyncCommand),
+    /// Download packages into the local cache without writing any outputs,
+    /// e.g. to warm CI images or developer machines.
+    Prefetch(PrefetchCommand),
+    /// Show the project's dependency tree.
+    Tree(TreeCommand),
+    /// List the newer versions available for the project's locked
+    /// dependencies.
+    Outdated(OutdatedCommand),
+    /// Poll packages for new releases, reporting them or running a hook.
+    Watch(WatchCommand),
+    /// Explain why a package is among the project's dependencies.
+    Why(WhyCommand),
+    /// Print a JSON description of the project and its resolved
+    /// dependencies, for IDEs and build tools.
+    Metadata(MetadataCommand),
+    /// Generate a software bill of materials (SBOM) for the project.
+    Sbom(SbomCommand),
+    /// Check the project's dependencies against an advisory database.
+    Audit(AuditCommand),
+    /// Export or import offline bundles of the project's dependencies.
+    #[command(subcommand)]
+    Bundle(BundleCommand),
 }
 
 #[derive(Args, Debug)]
 struct GetCommand {
     /// Output path. If this ends with a '/', a filename based on the package
     /// name, version, and format will be appended, e.g.
-    /// `name-space_name@1.0.0.wasm``.
+    /// `name-space_name@1.0.0.wasm``. With `--format wit-dir` this is the
+    /// directory to write.
     #[arg(long, short, default_value = "./")]
     output: PathBuf,
 
+    /// The filename to append to an output path ending with '/', using the
+    /// placeholders `{namespace}`, `{name}`, `{version}`, and `{ext}`, e.g.
+    /// "{name}-{version}.{ext}". Defaults to the config file's
+    /// `output.name_template`, or "{namespace}_{name}@{version}.{ext}".
+    #[arg(long)]
+    name_template: Option<NameTemplate>,
+
+    /// Unpack a gzipped tar archive release (e.g. a WIT source tree) into
+    /// the output directory instead of writing the archive.
+    #[arg(long, conflicts_with_all = ["format", "world", "interface"])]
+    unpack: bool,
+
+    /// Strip volatile custom sections (e.g. "producers") from Wasm output,
+    /// so content fetched and re-published stays byte-identical.
+    #[arg(long, conflicts_with = "unpack")]
+    strip: bool,
+
+    /// Skip validating fetched Wasm content before writing it. By default,
+    /// corrupt or truncated content is rejected.
+    #[arg(long)]
+    no_validate: bool,
+
+    /// The number of times to retry a download interrupted by a network or
+    /// server error. Downloads in parallel ranges resume from the failed
+    /// range; others restart. Defaults to the config file's
+    /// `download.retries`, or 3.
+    #[arg(long, value_name = "N")]
+    retries: Option<u32>,
+
+    /// Don't retry interrupted downloads.
+    #[arg(long, conflicts_with = "retries")]
+    no_retry: bool,
+
+    /// Write each WIT package encoded in the content, including those
+    /// referenced by a component, to its own `.wit` file in the output
+    /// directory.
+    #[arg(long, conflicts_with_all = ["unpack", "world", "interface"])]
+    split: bool,
+
+    /// Also get this WIT package and merge it, with its dependencies, into
+    /// the output, which must be a WIT directory. Merged packages are
+    /// written under `deps/`. May be repeated.
+    #[arg(long, value_name = "PACKAGE_SPEC", conflicts_with_all = ["unpack", "split"])]
+    merge: Vec<PackageSpec>,
+
     /// Output format. The default of "auto" detects the format based on the
     /// output filename or package contents.
     #[arg(long, value_enum, default_value = "auto")]
@@ -48,61 +438,310 @@ struct GetCommand {
     #[arg(long)]
     overwrite: bool,
 
+    /// If the output file already holds the release, skip fetching it and
+    /// succeed; otherwise overwrite it. Wasm (or archive) output is compared
+    /// by content digest and WIT output by the package version it declares.
+    #[arg(long, conflicts_with_all = ["unpack", "split", "merge"])]
+    if_newer: bool,
+
+    /// Only output the named world of a WIT package, along with the
+    /// interfaces it uses.
+    #[arg(long, conflicts_with = "interface")]
+    world: Option<String>,
+
+    /// Only output the named interface of a WIT package, along with the
+    /// interfaces whose types it uses.
+    #[arg(long)]
+    interface: Option<String>,
+
+    #[command(flatten)]
+    wit_style: wit::WitStyleArgs,
+
     /// The package to get, specified as <namespace>:<name> plus optional
+    /// @<version>, e.g. "wasi:cli" or "wasi:http@0.2.0". A channel tag or a
+    /// content digest (e.g. "wasi:http@sha256:<hex>") may be given instead
+    /// of a version to pin the exact content. Use "-" to read
+    /// specs from stdin, one per line (blank lines and lines starting with
+    /// '#' are ignored), with an output directory ending with '/'.
+    package_spec: SpecArg,
+
+    #[command(flatten)]
+    registry: RegistryArgs,
+}
+
+#[derive(Args, Debug)]
+struct InspectCommand {
+    /// The package to inspect, specified as <namespace>:<name> plus optional
     /// @<version>, e.g. "wasi:cli" or "wasi:http@0.2.0".
     package_spec: PackageSpec,
 
+    /// Also fetch the content and, for components and core modules, list
+    /// its imports and exports.
+    #[arg(long)]
+    io: bool,
+
+    /// Also fetch the content and show the package metadata embedded in it
+    /// by `wkg publish --embed-metadata`, if any.
+    #[arg(long)]
+    metadata: bool,
+
     #[command(flatten)]
     registry: RegistryArgs,
 }
 
+impl InspectCommand {
+    pub async fn run(self, global: &GlobalArgs) -> anyhow::Result<()> {
+        let (package, version) = self.package_spec.into_versioned()?;
+
+        let config = {
+            let mut config = global.load_config()?;
+            if let Some(registry) = self.registry.domain {
+                config.set_namespace_registry(package.namespace().to_string(), registry);
+            }
+            config
+        };
+        let policy = config.provenance_policy().clone();
+        let mut client = config.to_client();
+
+        let version = match version {
+            Some(ver) => ver,
+            None => {
+                let result = client.latest_version(&package).await;
+                suggest_not_found(result, &mut client, &package, None)
+                    .await
+                    .context("Failed to select latest version")?
+            }
+        };
+        let result = client.get_release(&package, &version).await;
+        let release = suggest_not_found(result, &mut client, &package, Some(&version))
+            .await
+            .context("Failed to get release details")?;
+        let provenance = client
+            .provenance(&package, &release)
+            .await
+            .context("Failed to fetch provenance attestations")?;
+
+        println!("{package}@{version}");
+        match client.package_registry(&package)? {
+            Some(registry) => println!("  registry: {registry}"),
+            None => println!("  registry: (local override)"),
+        }
+        println!("  digest: {}", release.content_digest);
+        match client.deprecation(&package, &release).await {
+            Ok(Some(deprecation)) => println!("  deprecated: {deprecation}"),
+            Ok(None) => (),
+            Err(err) => println!("  deprecated: {err}"),
+        }
+        if provenance.is_empty() {
+            println!("  provenance: none found");
+        } else {
+            println!("  provenance:");
+            for attestation in &provenance {
+                println!("    {attestation}");
+            }
+        }
+        if policy.require {
+            match policy.check(&package, &provenance) {
+                Ok(()) => println!("  provenance policy: satisfied"),
+                Err(err) => println!("  provenance policy: {err}"),
+            }
+        }
+
+        if !self.io && !self.metadata {
+            return Ok(());
+        }
+        let content: Vec<u8> = client
+            .stream_content(&package, &release)
+            .await?
+            .map_ok(|chunk| chunk.to_vec())
+            .try_concat()
+            .await?;
+        if self.metadata {
+            match PackageMetadata::read(&content) {
+                Ok(Some(metadata)) => println!("  metadata: {metadata}"),
+                Ok(None) => println!("  metadata: none embedded"),
+                Err(err) => println!("  metadata: {err:#}"),
+            }
+        }
+        if self.io {
+            if module::is_module(&content) {
+                println!("  content: core Wasm module");
+                let (imports, exports) = module::imports_exports(&content)?;
+                for (heading, items) in [("imports", imports), ("exports", exports)] {
+                    println!("  {heading}:");
+                    if items.is_empty() {
+                        println!("    none");
+                    }
+                    for item in items {
+                        println!("    {item}");
+                    }
+                }
+                return Ok(());
+            }
+            match wit_component::decode(&content) {
+                Ok(DecodedWasm::Component(resolve, world)) => {
+                    let world = &resolve.worlds[world];
+                    for (heading, items) in
+                        [("imports", &world.imports), ("exports", &world.exports)]
+                    {
+                        println!("  {heading}:");
+                        if items.is_empty() {
+                            println!("    none");
+                        }
+                        for (key, item) in items {
+                            println!("    {}", wit::world_item_label(&resolve, key, item));
+                        }
+                    }
+                }
+                Ok(DecodedWasm::WitPackage(..)) => {
+                    println!("  content: WIT package; see `wkg show` for its contents")
+                }
+                Err(err) => println!("  content: failed to decode: {err:#}"),
+            }
+        }
+        Ok(())
+    }
+}
+
 #[derive(ValueEnum, Clone, Debug, PartialEq)]
 enum Format {
     Auto,
     Wasm,
     Wit,
+    /// WebAssembly text format, disassembled from the package content.
+    Wat,
+    /// A WIT directory: `package.wit` plus `deps/<namespace>-<name>/` for
+    /// each referenced package.
+    WitDir,
+    /// A JSON description of a WIT package's worlds, interfaces, functions,
+    /// and types.
+    Json,
 }
 
 impl GetCommand {
-    pub async fn run(self) -> anyhow::Result<()> {
-        let PackageSpec { package, version } = self.package_spec;
+    pub async fn run(self, global: &GlobalArgs) -> anyhow::Result<()> {
+        let specs = match &self.package_spec {
+            SpecArg::Spec(spec) => return self.get(global, spec.clone()).await,
+            SpecArg::Stdin => package_spec::read_stdin()?,
+        };
+        ensure!(
+            self.output.as_os_str().to_string_lossy().ends_with('/'),
+            "Reading package specs from stdin requires an output directory ending with '/'"
+        );
+        let mut batch = Batch::new(global.continue_on_error);
+        for spec in &specs {
+            let result = self.get(global, spec.clone()).await;
+            batch.check(spec, result)?;
+        }
+        println!("Got {} packages", batch.succeeded());
+        batch.finish()
+    }
 
-        let mut client = {
-            let mut config = ClientConfig::default();
-            config.set_default_registry("bytecodealliance.org");
-            if let Some(file_config) = ClientConfig::from_default_file()? {
-                config.merge_config(file_config);
-            }
-            if let Some(registry) = self.registry.domain {
+    async fn get(&self, global: &GlobalArgs, spec: PackageSpec) -> anyhow::Result<()> {
+        let PackageSpec {
+            package,
+            version,
+            tag,
+            digest,
+        } = spec;
+        let policy = global.load_policy()?;
+        policy.check_package(&package)?;
+
+        let registry_domain = self.registry.domain.clone();
+        let (mut client, name_template, retries, wit_style) = {
+            let mut config = global.load_config()?;
+            if let Some(registry) = registry_domain.clone() {
                 let namespace = package.namespace().to_string();
                 tracing::debug!(namespace, registry, "overriding namespace registry");
                 config.set_namespace_registry(namespace, registry);
             }
-            config.to_client()
+            let retries = match (self.retries, self.no_retry) {
+                (_, true) => Some(0),
+                (retries, false) => retries,
+            };
+            if let Some(retries) = retries {
+                let mut download_config = config.download_config().clone();
+                download_config.retries = retries;
+                config.set_download_config(download_config);
+            }
+            let name_template = match (&self.name_template, config.output_name_template()) {
+                (Some(template), _) => template.clone(),
+                (None, Some(template)) => template
+                    .parse()
+                    .context("Invalid output.name_template in config")?,
+                (None, None) => NameTemplate::default(),
+            };
+            let retries = config.download_config().retries;
+            let wit_style = wit::WitStyle::new(&self.wit_style, config.wit_output())?;
+            (config.to_client(), name_template, retries, wit_style)
         };
 
-        let version = match version {
-            Some(ver) => ver,
-            None => {
+        let pinned = match &digest {
+            Some(digest) => {
+                let result = client.get_release_by_digest(&package, digest).await;
+                let release = suggest_not_found(result, &mut client, &package, None)
+                    .await
+                    .with_context(|| format!("Failed to find {package}@{digest}"))?;
+                println!("Resolved {package}@{digest} to {}", release.version);
+                Some(release)
+            }
+            None => None,
+        };
+        let version = match (version, &tag, &pinned) {
+            (_, _, Some(release)) => release.version.clone(),
+            (Some(ver), _, None) => ver,
+            (None, Some(tag), None) => {
+                let version = client
+                    .resolve_tag(&package, tag)
+                    .await
+                    .with_context(|| format!("Failed to resolve {package}@{tag}"))?;
+                println!("Resolved {package}@{tag} to {version}");
+                version
+            }
+            (None, None, None) => {
                 println!("No version specified; fetching version list...");
-                let versions = client.list_all_versions(&package).await?;
-                tracing::trace!(?versions);
-                versions
-                    .into_iter()
-                    .filter_map(|vi| (!vi.yanked).then_some(vi.version))
-                    .max()
-                    .context("No releases found")?
+                let result = client.latest_version(&package).await;
+                suggest_not_found(result, &mut client, &package, None)
+                    .await
+                    .context("Failed to select latest version")?
             }
         };
 
         println!("Getting {package}@{version}...");
-        let release = client
-            .get_release(&package, &version)
-            .await
-            .context("Failed to get release details")?;
+        let release = match pinned {
+            Some(release) => release,
+            None => {
+                let result = client.get_release(&package, &version).await;
+                suggest_not_found(result, &mut client, &package, Some(&version))
+                    .await
+                    .context("Failed to get release details")?
+            }
+        };
         tracing::debug!(?release);
+        deprecate::warn_if_deprecated(&mut client, &package, &release).await;
 
         let output_trailing_slash = self.output.as_os_str().to_string_lossy().ends_with('/');
+        if self.if_newer {
+            // The output's extension depends on the content, so check each
+            // file name it may have been written with
+            let candidates = if output_trailing_slash {
+                ["wasm", "tar.gz", "wit"]
+                    .into_iter()
+                    .map(|ext| {
+                        self.output
+                            .join(name_template.render(&package, &version, Some(ext)))
+                    })
+                    .collect()
+            } else {
+                vec![self.output.clone()]
+            };
+            for path in &candidates {
+                if output_matches(path, &package, &release).await? {
+                    println!("{package}@{version} is up to date ('{}')", path.display());
+                    return Ok(());
+                }
+            }
+        }
         let parent_dir = if output_trailing_slash {
             self.output.as_path()
         } else {
@@ -111,23 +750,115 @@ impl GetCommand {
                 .context("Failed to resolve output parent dir")?
         };
 
-        let (tmp_file, tmp_path) =
-            tempfile::NamedTempFile::with_prefix_in(".wkg-get", parent_dir)?.into_parts();
-        tracing::debug!(?tmp_path);
-
-        let mut content_stream = client.stream_content(&package, &release).await?;
-
-        let mut file = tokio::fs::File::from_std(tmp_file);
-        while let Some(chunk) = content_stream.try_next().await? {
-            file.write_all(&chunk).await?;
+        let quarantine = global.quarantine()?;
+        let staging_dir = match &quarantine {
+            Some(quarantine) => quarantine.dir(),
+            None => parent_dir,
+        };
+        let validate = !self.no_validate || quarantine.is_some();
+        let validation_failed = || {
+            ValidationFailed(format!("{package}@{version} failed validation; it may be corrupt or truncated (use '--no-validate' to skip this check)"))
+        };
+        // Content is validated as it's spooled (digests are checked as it's
+        // streamed) rather than by reading it back afterwards
+        let mut validator = validate.then(StreamingValidator::default);
+        let mut spool = Spool::new(staging_dir);
+        let cached_path = client.cached_content_file(&package, &release).await?;
+        let fetch = global
+            .run_report
+            .start(&client, &package, &release, cached_path.is_some())?;
+        let spooled = match cached_path {
+            Some(path) => {
+                policy.check_size(&package, std::fs::metadata(&path)?.len())?;
+                let spooled = spool.copy_from(&path)?;
+                if let Some(validator) = &mut validator {
+                    validator
+                        .update(spooled.content())
+                        .with_context(validation_failed)?;
+                }
+                spooled
+            }
+            None => {
+                let mut attempt = 0;
+                // The number of bytes spooled so far, and whether any were
+                // fetched by resuming an interrupted download
+                let mut size = 0;
+                let mut resumed = false;
+                let spooled = loop {
+                    let result: anyhow::Result<()> = async {
+                        let resume = match size {
+                            0 => None,
+                            offset => client.resume_content(&package, &release, offset).await?,
+                        };
+                        let mut content_stream = match resume {
+                            Some(stream) => {
+                                resumed = true;
+                                stream
+                            }
+                            None => {
+                                if size > 0 {
+                                    // Restart with any partial content discarded
+                                    size = 0;
+                                    resumed = false;
+                                    spool = Spool::new(staging_dir);
+                                    validator = validate.then(StreamingValidator::default);
+                                }
+                                client.stream_content(&package, &release).await?
+                            }
+                        };
+                        while let Some(chunk) = content_stream.try_next().await? {
+                            policy.check_size(&package, size + chunk.len() as u64)?;
+                            if let Some(validator) = &mut validator {
+                                validator.update(&chunk).with_context(validation_failed)?;
+                            }
+                            spool.write(&chunk).await?;
+                            size += chunk.len() as u64;
+                        }
+                        Ok(())
+                    }
+                    .await;
+                    match result {
+                        Ok(()) => break spool.finish().await?,
+                        Err(err) if attempt < retries && error_code::is_transient(&err) => {
+                            attempt += 1;
+                            println!(
+                                "Download interrupted ({err:#}); retrying ({attempt}/{retries})..."
+                            );
+                            tokio::time::sleep(Duration::from_secs(1 << attempt.min(5))).await;
+                        }
+                        Err(err) => return Err(err),
+                    }
+                };
+                // Resumed content bypasses the streamed digest check
+                if resumed {
+                    let digest = ContentDigest::sha256(spooled.content());
+                    if digest != release.content_digest {
+                        return Err(wasm_pkg_loader::Error::InvalidContent(format!(
+                            "expected digest {}, got {digest} after resuming download",
+                            release.content_digest
+                        ))
+                        .into());
+                    }
+                }
+                spooled
+            }
+        };
+        if let Some(validator) = validator {
+            validator.finish().with_context(validation_failed)?;
         }
+        if policy.checks_content() {
+            policy.check_content(&package, &version, spooled.content())?;
+        }
+        let content_len = spooled.content().len() as u64;
 
-        let mut format = self.format;
+        let mut format = self.format.clone();
         if let (Format::Auto, Some(ext)) = (&format, self.output.extension()) {
             tracing::debug!("Inferring output format from file extension {ext:?}");
             format = match ext.to_string_lossy().as_ref() {
                 "wasm" => Format::Wasm,
                 "wit" => Format::Wit,
+                "wat" => Format::Wat,
+                "json" => Format::Json,
                 _ => {
                     println!(
                         "Couldn't infer output format from file name {:?}",
@@ -138,20 +869,96 @@ impl GetCommand {
             }
         }
 
-        let wit = if format == Format::Wasm {
+        let selection = match (&self.world, &self.interface) {
+            (Some(world), _) => Some(Selection::World(world.clone())),
+            (None, Some(interface)) => Some(Selection::Interface(interface.clone())),
+            (None, None) => None,
+        };
+        ensure!(
+            selection.is_none() || !matches!(format, Format::Wasm | Format::Wat),
+            "'--world' and '--interface' require WIT output"
+        );
+        ensure!(
+            !self.split || matches!(format, Format::Auto | Format::Wit),
+            "'--split' requires WIT output"
+        );
+        ensure!(
+            self.merge.is_empty() || format == Format::WitDir,
+            "'--merge' requires '--format wit-dir'"
+        );
+
+        let is_module = module::is_module(spooled.content());
+        let is_archive = archive::is_archive(spooled.content());
+
+        if self.unpack {
+            ensure!(
+                is_archive,
+                "{package}@{version} is not a tar/gzip archive; '--unpack' requires one"
+            );
+            let output_path = if output_trailing_slash {
+                self.output
+                    .join(name_template.render(&package, &version, None))
+            } else {
+                self.output.clone()
+            };
+            ensure!(
+                self.overwrite || !output_path.exists(),
+                "{output_path:?} already exists; you can use '--overwrite' to overwrite it"
+            );
+            archive::unpack(spooled.content(), &output_path)?;
+            println!("Unpacked to '{}'", output_path.display());
+            global
+                .run_report
+                .record(fetch, content_len, Some(&output_path));
+            return Ok(());
+        }
+
+        let wat = if format == Format::Wat {
+            let mut content = Cow::Borrowed(spooled.content());
+            if self.strip {
+                content = strip::strip_volatile_sections(&content)?.into();
+            }
+            Some(wasmprinter::print_bytes(content).context("Failed to print WAT")?)
+        } else {
+            None
+        };
+
+        let mut decoded = if matches!(format, Format::Wasm | Format::Wat) {
+            None
+        } else if is_module {
+            if matches!(format, Format::Wit | Format::WitDir | Format::Json) || selection.is_some()
+            {
+                bail!("{package}@{version} is a core Wasm module, not a WIT package");
+            }
+            println!("{package}@{version} is a core Wasm module; writing it as-is");
+            None
+        } else if is_archive {
+            if matches!(format, Format::Wit | Format::WitDir | Format::Json) || selection.is_some()
+            {
+                bail!("{package}@{version} is a tar/gzip archive, not a WIT package");
+            }
+            println!("{package}@{version} is a tar/gzip archive; writing it as-is (use '--unpack' to extract it)");
             None
         } else {
-            let mut file = file.into_std().await;
-            file.rewind()?;
-            match wit_component::decode_reader(&mut file) {
+            match spooled.decode() {
                 Ok(DecodedWasm::WitPackage(resolve, pkg)) => {
                     tracing::debug!(?pkg, "decoded WIT package");
-                    Some(wit_component::WitPrinter::default().print(&resolve, pkg)?)
+                    Some((resolve, pkg))
+                }
+                Ok(DecodedWasm::Component(resolve, world)) if self.split => {
+                    tracing::debug!(?world, "decoded component");
+                    let pkg = resolve.worlds[world]
+                        .package
+                        .context("Component world has no package")?;
+                    Some((resolve, pkg))
                 }
                 Ok(_) => None,
                 Err(err) => {
                     tracing::debug!(?err);
-                    if format == Format::Wit {
+                    if matches!(format, Format::Wit | Format::WitDir | Format::Json)
+                        || selection.is_some()
+                        || self.split
+                    {
                         return Err(err);
                     }
                     println!("Failed to detect package content type: {err:#}");
@@ -159,50 +966,203 @@ impl GetCommand {
                 }
             }
         };
+        ensure!(
+            !matches!(format, Format::WitDir | Format::Json) || decoded.is_some(),
+            "{package}@{version} is not a WIT package, as required by the output format"
+        );
+        ensure!(
+            !self.split || decoded.is_some(),
+            "{package}@{version} doesn't encode any WIT packages to split"
+        );
+        if let Some(selection) = &selection {
+            let Some((resolve, pkg)) = &mut decoded else {
+                bail!("{package}@{version} is not a WIT package; '--world' and '--interface' require one");
+            };
+            wit::select(resolve, *pkg, selection)?;
+        }
+        if let Some((resolve, _)) = &mut decoded {
+            for spec in &self.merge {
+                let registry = RegistryArgs {
+                    domain: registry_domain.clone(),
+                };
+                let (name, version, content) =
+                    wit::fetch_package(global, spec.clone(), registry).await?;
+                let (other, _) = wit::decode_package(&content)
+                    .with_context(|| format!("Failed to decode {name}@{version}"))?;
+                resolve
+                    .merge(other)
+                    .with_context(|| format!("Failed to merge {name}@{version}"))?;
+                println!("Merged {name}@{version}");
+            }
+        }
 
         let output_path = if output_trailing_slash {
-            let ext = if wit.is_some() { "wit" } else { "wasm" };
-            self.output.join(format!(
-                "{namespace}_{name}@{version}.{ext}",
-                namespace = package.namespace(),
-                name = package.name(),
-            ))
-        } else {
+            let ext = match (&format, &decoded) {
+                (Format::WitDir, _) => None,
+                _ if self.split => None,
+                (Format::Wat, _) => Some("wat"),
+                (Format::Json, _) => Some("json"),
+                (_, Some(_)) => Some("wit"),
+                (_, None) if is_archive => Some("tar.gz"),
+                (_, None) => Some("wasm"),
+            };
             self.output
+                .join(name_template.render(&package, &version, ext))
+        } else {
+            self.output.clone()
         };
         ensure!(
-            self.overwrite || !output_path.exists(),
+            self.overwrite || self.if_newer || !output_path.exists(),
             "{output_path:?} already exists; you can use '--overwrite' to overwrite it"
         );
 
-        if let Some(wit) = wit {
-            std::fs::write(&output_path, wit)
-                .with_context(|| format!("Failed to write WIT to {output_path:?}"))?
-        } else {
-            tmp_path
-                .persist(&output_path)
-                .with_context(|| format!("Failed to persist WASM to {output_path:?}"))?
+        let wit_style = wit_style.with_source(client.package_registry(&package)?);
+        match (decoded, wat) {
+            (Some((resolve, _)), _) if self.split => {
+                wit::write_split(&resolve, &output_path, &wit_style)?
+            }
+            (Some((resolve, pkg)), _) if format == Format::WitDir => {
+                wit::write_wit_dir(&resolve, pkg, &output_path, &wit_style)?
+            }
+            (Some((resolve, pkg)), _) if format == Format::Json => {
+                let json = serde_json::to_string_pretty(&wit::package_json(&resolve, pkg))?;
+                project::write_output(quarantine.as_ref(), &output_path, json.as_bytes())
+                    .context("Failed to write JSON")?
+            }
+            (Some((resolve, pkg)), _) => {
+                let wit = wit_style.print(&resolve, pkg)?;
+                project::write_output(quarantine.as_ref(), &output_path, wit.as_bytes())
+                    .context("Failed to write WIT")?
+            }
+            (None, Some(wat)) => {
+                project::write_output(quarantine.as_ref(), &output_path, wat.as_bytes())
+                    .context("Failed to write WAT")?
+            }
+            (None, None) if self.strip => {
+                let stripped = strip::strip_volatile_sections(spooled.content())?;
+                project::write_output(quarantine.as_ref(), &output_path, &stripped)
+                    .context("Failed to write WASM")?
+            }
+            (None, None) => spooled
+                .persist(quarantine.as_ref(), &output_path)
+                .with_context(|| format!("Failed to persist WASM to {output_path:?}"))?,
         }
         println!("Wrote '{}'", output_path.display());
+        if let Some(tag) = &tag {
+            // Channels move; the version and digest identify what was fetched
+            println!(
+                "{package}@{tag} was {package}@{version} ({})",
+                release.content_digest
+            );
+        }
+        global
+            .job_summary
+            .record(&package, &version, &release.content_digest, &output_path);
+        global
+            .run_report
+            .record(fetch, content_len, Some(&output_path));
 
         Ok(())
     }
 }
 
+/// Returns whether the file at `path` already holds the given release.
+async fn output_matches(
+    path: &Path,
+    package: &PackageRef,
+    release: &Release,
+) -> anyhow::Result<bool> {
+    if !path.is_file() {
+        return Ok(false);
+    }
+    if path.extension().is_some_and(|ext| ext == "wit") {
+        let source =
+            std::fs::read_to_string(path).with_context(|| format!("Failed to read {path:?}"))?;
+        return Ok(wit::declares_package(
+            path,
+            &source,
+            package,
+            &release.version,
+        ));
+    }
+    let digest = ContentDigest::sha256_from_file(path)
+        .await
+        .with_context(|| format!("Failed to read {path:?}"))?;
+    Ok(digest == release.content_digest)
+}
+
 #[tokio::main]
-async fn main() -> anyhow::Result<()> {
-    tracing_subscriber::fmt()
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    let subscriber = tracing_subscriber::fmt()
         .with_env_filter(
             tracing_subscriber::EnvFilter::builder()
                 .with_default_directive(LevelFilter::WARN.into())
                 .from_env_lossy(),
         )
-        .init();
-
-    let cli = Cli::parse();
+        .finish();
+    if cli.global.github_actions {
+        subscriber.with(actions::AnnotationLayer).init();
+    } else {
+        subscriber.init();
+    }
     tracing::debug!(?cli);
 
-    match cli.command {
-        Commands::Get(cmd) => cmd.run().await,
+    let mut result = match cli.command {
+        Commands::Get(cmd) => cmd.run(&cli.global).await,
+        Commands::Inspect(cmd) => cmd.run(&cli.global).await,
+        Commands::List(cmd) => cmd.run(&cli.global).await,
+        Commands::Hash(cmd) => cmd.run(&cli.global).await,
+        Commands::Publish(cmd) => cmd.run(&cli.global).await,
+        Commands::Copy(cmd) => cmd.run(&cli.global).await,
+        Commands::Tag(cmd) => cmd.run(&cli.global).await,
+        Commands::Promote(cmd) => cmd.run(&cli.global).await,
+        Commands::Deprecate(cmd) => cmd.run(&cli.global).await,
+        Commands::Delete(cmd) => cmd.run(&cli.global).await,
+        Commands::Show(cmd) => cmd.run(&cli.global).await,
+        Commands::Doc(cmd) => cmd.run(&cli.global).await,
+        Commands::SemverCheck(cmd) => cmd.run(&cli.global).await,
+        Commands::Bindgen(cmd) => cmd.run(&cli.global).await,
+        Commands::Compose(cmd) => cmd.run(&cli.global).await,
+        Commands::Config(cmd) => cmd.run(&cli.global).await,
+        Commands::Login(cmd) => cmd.run(&cli.global).await,
+        Commands::Keygen(cmd) => cmd.run(&cli.global).await,
+        Commands::Key(cmd) => cmd.run(&cli.global).await,
+        Commands::Manifest(cmd) => cmd.run(&cli.global).await,
+        Commands::Add(cmd) => cmd.run(&cli.global).await,
+        Commands::Lock(cmd) => cmd.run(&cli.global).await,
+        Commands::Update(cmd) => cmd.run(&cli.global).await,
+        Commands::Sync(cmd) => cmd.run(&cli.global).await,
+        Commands::Prefetch(cmd) => cmd.run(&cli.global).await,
+        Commands::Tree(cmd) => cmd.run(&cli.global).await,
+        Commands::Outdated(cmd) => cmd.run(&cli.global).await,
+        Commands::Watch(cmd) => cmd.run(&cli.global).await,
+        Commands::Why(cmd) => cmd.run(&cli.global).await,
+        Commands::Metadata(cmd) => cmd.run(&cli.global).await,
+        Commands::Sbom(cmd) => cmd.run(&cli.global).await,
+        Commands::Audit(cmd) => cmd.run(&cli.global).await,
+        Commands::Bundle(cmd) => cmd.run(&cli.global).await,
+    };
+    if cli.global.github_actions {
+        if let Err(err) = &result {
+            actions::annotate_error(err);
+        }
+        result = cli
+            .global
+            .job_summary
+            .write(result.as_ref().err())
+            .and(result);
+    }
+    if let Some(path) = &cli.global.report {
+        result = cli
+            .global
+            .run_report
+            .write(path, result.as_ref().err())
+            .and(result);
+    }
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => error_code::report(&err, cli.global.json),
     }
 }
@@ -0,0 +1,346 @@
+//! The `wkg.toml` project manifest, e.g.:
+//!
+//! ```toml
+//! [package]
+//! name = "my:app"
+//! version = "0.1.0"
+//!
+//! [dependencies]
+//! "wasi:io" = "0.2.0"
+//! "wasi:http" = { version = "^0.2", registry = "wasi.dev" }
+//! ```
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use semver::VersionReq;
+use serde::Deserialize;
+use wasm_pkg_loader::{PackageRef, Version};
+use wit_component::DecodedWasm;
+use wit_parser::{PackageName, UnresolvedPackage};
+
+pub const MANIFEST_FILE_NAME: &str = "wkg.toml";
+
+#[derive(Clone, Debug)]
+pub struct Manifest {
+    /// The path of the manifest file.
+    pub path: PathBuf,
+    /// The project's own package identity, if declared.
+    pub package: Option<ManifestPackage>,
+    pub dependencies: BTreeMap<PackageRef, Dependency>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ManifestPackage {
+    pub name: PackageRef,
+    pub version: Option<Version>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Dependency {
+    /// The semver requirement the dependency's version must match.
+    pub version: VersionReq,
+    /// The registry to fetch the dependency from, overriding configuration.
+    pub registry: Option<String>,
+}
+
+impl Manifest {
+    /// Returns the path of the nearest manifest in `dir` or its ancestors.
+    pub fn find(dir: &Path) -> Option<PathBuf> {
+        dir.ancestors()
+            .map(|dir| dir.join(MANIFEST_FILE_NAME))
+            .find(|path| path.is_file())
+    }
+
+    /// Loads the nearest manifest to the current directory.
+    pub fn load_current() -> anyhow::Result<Self> {
+        let cwd = std::env::current_dir()?;
+        let path = Self::find(&cwd).with_context(|| {
+            format!("Couldn't find {MANIFEST_FILE_NAME} in {cwd:?} or any parent directory")
+        })?;
+        Self::load(path)
+    }
+
+    pub fn load(path: impl Into<PathBuf>) -> anyhow::Result<Self> {
+        let path = path.into();
+        tracing::debug!(?path, "loading manifest");
+        let contents = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read manifest {path:?}"))?;
+        Self::from_toml(path.clone(), &contents)
+            .with_context(|| format!("Invalid manifest {path:?}"))
+    }
+
+    fn from_toml(path: PathBuf, contents: &str) -> anyhow::Result<Self> {
+        let TomlManifest {
+            package,
+            dependencies,
+        } = toml::from_str(contents)?;
+        let package = package
+            .map(|TomlPackage { name, version }| {
+                anyhow::Ok(ManifestPackage {
+                    name: name.parse().context("invalid package name")?,
+                    version: version
+                        .map(|ver| ver.parse())
+                        .transpose()
+                        .context("invalid package version")?,
+                })
+            })
+            .transpose()?;
+        let dependencies = dependencies
+            .into_iter()
+            .map(|(name, dep)| {
+                let package: PackageRef = name
+                    .parse()
+                    .with_context(|| format!("invalid dependency name {name:?}"))?;
+                let (version, registry) = match dep {
+                    TomlDependency::Version(version) => (version, None),
+                    TomlDependency::Detailed { version, registry } => (version, registry),
+                };
+                let version = version
+                    .parse()
+                    .with_context(|| format!("invalid version requirement for {package}"))?;
+                Ok((package, Dependency { version, registry }))
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Ok(Self {
+            path,
+            package,
+            dependencies,
+        })
+    }
+
+    /// Returns the directory containing the manifest.
+    pub fn root_dir(&self) -> &Path {
+        self.path.parent().unwrap_or(Path::new("."))
+    }
+
+    /// Adds or replaces a dependency in the manifest file, preserving the
+    /// rest of its formatting.
+    pub fn add_dependency(
+        &mut self,
+        package: PackageRef,
+        dependency: Dependency,
+    ) -> anyhow::Result<()> {
+        let contents = std::fs::read_to_string(&self.path)?;
+        let mut doc: toml_edit::DocumentMut = contents.parse()?;
+        let deps = doc
+            .entry("dependencies")
+            .or_insert_with(toml_edit::table)
+            .as_table_like_mut()
+            .context("'dependencies' must be a table")?;
+        deps.insert(&package.to_string(), dependency.to_toml());
+        std::fs::write(&self.path, doc.to_string())?;
+        self.dependencies.insert(package, dependency);
+        Ok(())
+    }
+
+    /// Returns a manifest at `path` for the WIT package in `wit_dir`, with a
+    /// dependency on each foreign package it references. Dependencies
+    /// require the version vendored in `wit_dir/deps`, if any, or else the
+    /// referenced version.
+    pub fn from_wit_dir(path: PathBuf, wit_dir: &Path) -> anyhow::Result<Self> {
+        let unresolved = UnresolvedPackage::parse_path(wit_dir)
+            .with_context(|| format!("Failed to parse WIT package in {wit_dir:?}"))?;
+        let package = ManifestPackage {
+            name: package_ref(&unresolved.name)?,
+            version: unresolved.name.version.clone(),
+        };
+        let vendored = vendored_versions(&wit_dir.join("deps"))?;
+        let mut dependencies = BTreeMap::new();
+        for (package, req) in wasm_pkg_loader::wit_path_dependencies(wit_dir)? {
+            let version = match vendored.get(&package) {
+                Some(version) if req.matches(version) => VersionReq::parse(&version.to_string())?,
+                Some(version) => {
+                    tracing::warn!(
+                        "Vendored {package}@{version} doesn't match the referenced {req}; requiring {req}"
+                    );
+                    req
+                }
+                None => req,
+            };
+            let dependency = Dependency {
+                version,
+                registry: None,
+            };
+            dependencies.insert(package, dependency);
+        }
+        Ok(Self {
+            path,
+            package: Some(package),
+            dependencies,
+        })
+    }
+
+    /// Serializes the manifest as TOML.
+    pub fn to_toml(&self) -> String {
+        let mut doc = toml_edit::DocumentMut::new();
+        if let Some(package) = &self.package {
+            let mut table = toml_edit::Table::new();
+            table.insert("name", toml_edit::value(package.name.to_string()));
+            if let Some(version) = &package.version {
+                table.insert("version", toml_edit::value(version.to_string()));
+            }
+            doc.insert("package", toml_edit::Item::Table(table));
+        }
+        let mut deps = toml_edit::Table::new();
+        for (package, dependency) in &self.dependencies {
+            deps.insert(&package.to_string(), dependency.to_toml());
+        }
+        doc.insert("dependencies", toml_edit::Item::Table(deps));
+        doc.to_string()
+    }
+}
+
+impl Dependency {
+    fn to_toml(&self) -> toml_edit::Item {
+        let version = self.version.to_string();
+        match &self.registry {
+            None => toml_edit::value(version),
+            Some(registry) => {
+                let mut table = toml_edit::InlineTable::new();
+                table.insert("version", version.into());
+                table.insert("registry", registry.as_str().into());
+                toml_edit::value(table)
+            }
+        }
+    }
+}
+
+fn package_ref(name: &PackageName) -> anyhow::Result<PackageRef> {
+    format!("{}:{}", name.namespace, name.name)
+        .parse()
+        .with_context(|| format!("Invalid package name {name}"))
+}
+
+/// Returns the version of each package vendored in the given `deps`
+/// directory, as WIT files or directories or as binary WIT packages.
+/// Unversioned packages are skipped.
+fn vendored_versions(deps_dir: &Path) -> anyhow::Result<HashMap<PackageRef, Version>> {
+    let mut versions = HashMap::new();
+    let entries = match std::fs::read_dir(deps_dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(versions),
+        Err(err) => return Err(err).with_context(|| format!("Failed to read {deps_dir:?}")),
+    };
+    for entry in entries {
+        let path = entry?.path();
+        let name = if path.is_dir() || path.extension().is_some_and(|ext| ext == "wit") {
+            UnresolvedPackage::parse_path(&path).map(|pkg| pkg.name)
+        } else if path.extension().is_some_and(|ext| ext == "wasm") {
+            let content =
+                std::fs::read(&path).with_context(|| format!("Failed to read {path:?}"))?;
+            match wit_component::decode(&content) {
+                Ok(DecodedWasm::WitPackage(resolve, pkg)) => Ok(resolve.packages[pkg].name.clone()),
+                _ => continue,
+            }
+        } else {
+            continue;
+        };
+        let name = match name {
+            Ok(name) => name,
+            Err(err) => {
+                tracing::warn!("Skipping vendored dependency {path:?}: {err:#}");
+                continue;
+            }
+        };
+        if let Some(version) = name.version.clone() {
+            versions.insert(package_ref(&name)?, version);
+        }
+    }
+    Ok(versions)
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TomlManifest {
+    package: Option<TomlPackage>,
+    #[serde(default)]
+    dependencies: BTreeMap<String, TomlDependency>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TomlPackage {
+    name: String,
+    version: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TomlDependency {
+    Version(String),
+    Detailed {
+        version: String,
+        registry: Option<String>,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_manifest() {
+        let manifest = Manifest::from_toml(
+            "wkg.toml".into(),
+            r#"
+            [package]
+            name = "my:app"
+            version = "0.1.0"
+
+            [dependencies]
+            "wasi:io" = "0.2.0"
+            "wasi:http" = { version = "^0.2", registry = "wasi.dev" }
+            "#,
+        )
+        .unwrap();
+        let package = manifest.package.unwrap();
+        assert_eq!(package.name.to_string(), "my:app");
+        assert_eq!(package.version.unwrap().to_string(), "0.1.0");
+
+        let io = &manifest.dependencies[&"wasi:io".parse::<PackageRef>().unwrap()];
+        assert!(io.version.matches(&"0.2.1".parse().unwrap()));
+        assert_eq!(io.registry, None);
+
+        let http = &manifest.dependencies[&"wasi:http".parse::<PackageRef>().unwrap()];
+        assert!(http.version.matches(&"0.2.3".parse().unwrap()));
+        assert_eq!(http.registry.as_deref(), Some("wasi.dev"));
+    }
+
+    #[test]
+    fn test_from_wit_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let wit_dir = dir.path().join("wit");
+        std::fs::create_dir_all(wit_dir.join("deps")).unwrap();
+        std::fs::write(
+            wit_dir.join("world.wit"),
+            "package my:app@0.1.0;\n\nworld app {\n  import wasi:io/streams@0.2.0;\n  import wasi:cli/stdout@0.2.0;\n}\n",
+        )
+        .unwrap();
+        std::fs::write(
+            wit_dir.join("deps/wasi-io.wit"),
+            "package wasi:io@0.2.1;\n\ninterface streams {}\n",
+        )
+        .unwrap();
+
+        let path = dir.path().join(MANIFEST_FILE_NAME);
+        let manifest = Manifest::from_wit_dir(path.clone(), &wit_dir).unwrap();
+        let package = manifest.package.as_ref().unwrap();
+        assert_eq!(package.name.to_string(), "my:app");
+        assert_eq!(package.version.as_ref().unwrap().to_string(), "0.1.0");
+        let requirement = |name: &str| {
+            manifest.dependencies[&name.parse::<PackageRef>().unwrap()]
+                .version
+                .to_string()
+        };
+        assert_eq!(requirement("wasi:io"), "^0.2.1");
+        assert_eq!(requirement("wasi:cli"), "^0.2.0");
+
+        let reloaded = Manifest::from_toml(path, &manifest.to_toml()).unwrap();
+        assert_eq!(reloaded.dependencies.len(), 2);
+        assert_eq!(reloaded.package.unwrap().name, package.name);
+    }
+}
@@ -0,0 +1,113 @@
+//! Package metadata embedded in published content as a custom section, so
+//! artifacts stay self-describing outside the registry.
+
+use std::fmt;
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use wasm_encoder::{CustomSection, Section};
+use wasmparser::{Parser, Payload};
+
+use crate::strip;
+
+/// The name of the custom section holding [`PackageMetadata`] as JSON.
+pub const SECTION_NAME: &str = "wkg-metadata";
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PackageMetadata {
+    /// The package name, e.g. "wasi:http".
+    pub package: String,
+    /// The package version.
+    pub version: String,
+    /// The source repository the package was built from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// Free-form build details, e.g. a CI run or commit.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub build: Option<String>,
+}
+
+impl PackageMetadata {
+    /// Returns the given module or component with this metadata embedded,
+    /// replacing any metadata it already had.
+    pub fn embed(&self, content: &[u8]) -> anyhow::Result<Vec<u8>> {
+        let mut output = strip::filter_custom_sections(content, |name, depth| {
+            depth > 0 || name != SECTION_NAME
+        })?;
+        CustomSection {
+            name: SECTION_NAME.into(),
+            data: serde_json::to_vec(self)?.into(),
+        }
+        .append_to(&mut output);
+        Ok(output)
+    }
+
+    /// Returns the metadata embedded in the given module or component, if
+    /// any.
+    pub fn read(content: &[u8]) -> anyhow::Result<Option<Self>> {
+        let mut depth = 0;
+        for payload in Parser::new(0).parse_all(content) {
+            match payload.context("Invalid Wasm content")? {
+                Payload::ModuleSection { .. } | Payload::ComponentSection { .. } => depth += 1,
+                Payload::End(_) if depth > 0 => depth -= 1,
+                Payload::CustomSection(section) if depth == 0 && section.name() == SECTION_NAME => {
+                    let metadata = serde_json::from_slice(section.data())
+                        .context("Invalid embedded package metadata")?;
+                    return Ok(Some(metadata));
+                }
+                _ => (),
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl fmt::Display for PackageMetadata {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}@{}", self.package, self.version)?;
+        if let Some(source) = &self.source {
+            write!(f, " from {source}")?;
+        }
+        if let Some(build) = &self.build {
+            write!(f, " ({build})")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_embed_read() {
+        let module = [0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00];
+        assert_eq!(PackageMetadata::read(&module).unwrap(), None);
+
+        let mut metadata = PackageMetadata {
+            package: "test:pkg".into(),
+            version: "1.0.0".into(),
+            source: Some("https://example.com/test/pkg".into()),
+            build: None,
+        };
+        let embedded = metadata.embed(&module).unwrap();
+        assert_eq!(
+            PackageMetadata::read(&embedded).unwrap(),
+            Some(metadata.clone())
+        );
+        assert_eq!(
+            metadata.to_string(),
+            "test:pkg@1.0.0 from https://example.com/test/pkg"
+        );
+
+        // Embedding again replaces the existing section
+        metadata.build = Some("ci run 42".into());
+        let reembedded = metadata.embed(&embedded).unwrap();
+        assert_eq!(PackageMetadata::read(&reembedded).unwrap(), Some(metadata));
+        let sections = Parser::new(0)
+            .parse_all(&reembedded)
+            .filter(|payload| matches!(payload, Ok(Payload::CustomSection(_))))
+            .count();
+        assert_eq!(sections, 1);
+    }
+}
@@ -0,0 +1,79 @@
+//! Core Wasm modules, which some registries store alongside components.
+
+use anyhow::Context;
+use wasmparser::{ExternalKind, Parser, Payload, TypeRef};
+
+/// Returns true if the given content is a core Wasm module rather than a
+/// component (or WIT package).
+pub fn is_module(content: &[u8]) -> bool {
+    Parser::is_core_wasm(content)
+}
+
+/// Returns labels for a core module's imports and exports, e.g.
+/// `func env.log` and `memory memory`.
+pub fn imports_exports(content: &[u8]) -> anyhow::Result<(Vec<String>, Vec<String>)> {
+    let mut imports = vec![];
+    let mut exports = vec![];
+    for payload in Parser::new(0).parse_all(content) {
+        match payload.context("Invalid core Wasm module")? {
+            Payload::ImportSection(reader) => {
+                for import in reader {
+                    let import = import?;
+                    let kind = match import.ty {
+                        TypeRef::Func(_) => "func",
+                        TypeRef::Table(_) => "table",
+                        TypeRef::Memory(_) => "memory",
+                        TypeRef::Global(_) => "global",
+                        TypeRef::Tag(_) => "tag",
+                    };
+                    imports.push(format!("{kind} {}.{}", import.module, import.name));
+                }
+            }
+            Payload::ExportSection(reader) => {
+                for export in reader {
+                    let export = export?;
+                    let kind = match export.kind {
+                        ExternalKind::Func => "func",
+                        ExternalKind::Table => "table",
+                        ExternalKind::Memory => "memory",
+                        ExternalKind::Global => "global",
+                        ExternalKind::Tag => "tag",
+                    };
+                    exports.push(format!("{kind} {}", export.name));
+                }
+            }
+            _ => (),
+        }
+    }
+    Ok((imports, exports))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_imports_exports() {
+        #[rustfmt::skip]
+        let module = [
+            // Header
+            0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00,
+            // Type section: (func)
+            0x01, 0x04, 0x01, 0x60, 0x00, 0x00,
+            // Import section: (import "env" "log" (func (type 0)))
+            0x02, 0x0b, 0x01, 0x03, b'e', b'n', b'v', 0x03, b'l', b'o', b'g', 0x00, 0x00,
+            // Memory section: (memory 1)
+            0x05, 0x03, 0x01, 0x00, 0x01,
+            // Export section: (export "memory" (memory 0))
+            0x07, 0x0a, 0x01, 0x06, b'm', b'e', b'm', b'o', b'r', b'y', 0x02, 0x00,
+        ];
+        assert!(is_module(&module));
+
+        let (imports, exports) = imports_exports(&module).unwrap();
+        assert_eq!(imports, ["func env.log"]);
+        assert_eq!(exports, ["memory memory"]);
+
+        let component = [0x00, 0x61, 0x73, 0x6d, 0x0d, 0x00, 0x01, 0x00];
+        assert!(!is_module(&component));
+    }
+}
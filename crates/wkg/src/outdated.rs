@@ -0,0 +1,163 @@
+//! `wkg outdated`: compares a project's locked dependency versions against
+//! the versions available from their registries.
+
+use std::io::IsTerminal;
+
+use anyhow::Context;
+use clap::Args;
+use semver::VersionReq;
+use wasm_pkg_loader::{PackageRef, Version};
+
+use crate::{
+    lock::LockFile,
+    manifest::Manifest,
+    project::{project_client, ResolveArgs},
+    table::{Cell, Color, Table},
+    GlobalArgs,
+};
+
+#[derive(Args, Debug)]
+pub struct OutdatedCommand {
+    /// Only list dependencies with newer versions available.
+    #[arg(long)]
+    only_outdated: bool,
+}
+
+#[derive(Debug)]
+struct OutdatedPackage {
+    package: PackageRef,
+    /// The locked version.
+    current: Version,
+    /// The manifest's requirement for a direct dependency, or the versions
+    /// compatible with the locked version for a transitive one.
+    requirement: VersionReq,
+    /// Whether the package is a direct dependency in the manifest.
+    direct: bool,
+    /// The newest version satisfying `requirement`, if any.
+    compatible: Option<Version>,
+    /// The newest version overall, if any.
+    latest: Option<Version>,
+}
+
+impl OutdatedPackage {
+    fn is_outdated(&self) -> bool {
+        [&self.compatible, &self.latest]
+            .into_iter()
+            .flatten()
+            .any(|version| *version > self.current)
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "package": self.package.to_string(),
+            "current": self.current.to_string(),
+            "requirement": self.requirement.to_string(),
+            "direct": self.direct,
+            "compatible": self.compatible.as_ref().map(ToString::to_string),
+            "latest": self.latest.as_ref().map(ToString::to_string),
+        })
+    }
+}
+
+impl OutdatedCommand {
+    pub async fn run(self, global: &GlobalArgs) -> anyhow::Result<()> {
+        let manifest = Manifest::load_current()?;
+        let lock_path = LockFile::path_for(manifest.root_dir());
+        let lock = LockFile::load(&lock_path)?.with_context(|| {
+            format!("'{}' not found; run `wkg lock` first", lock_path.display())
+        })?;
+        let mut client = project_client(global, &manifest, &ResolveArgs::default())?;
+
+        let mut packages = vec![];
+        for locked in &lock.packages {
+            let (name, version) = (&locked.name, &locked.version);
+            let dependency = manifest.dependencies.get(name);
+            let requirement = match dependency {
+                Some(dependency) => dependency.version.clone(),
+                None => VersionReq::parse(&format!("^{version}"))?,
+            };
+            let versions = client
+                .list_all_versions(name)
+                .await
+                .with_context(|| format!("Failed to list versions of {name}"))?;
+            // Versions excluded by policy (e.g. yanked ones) aren't offered
+            // as upgrades
+            let policy = client.version_policy(name);
+            let newest = |req: &VersionReq| {
+                policy
+                    .resolve(name, versions.clone(), std::slice::from_ref(req))
+                    .ok()
+            };
+            packages.push(OutdatedPackage {
+                package: name.clone(),
+                current: version.clone(),
+                compatible: newest(&requirement),
+                latest: newest(&VersionReq::STAR),
+                requirement,
+                direct: dependency.is_some(),
+            });
+        }
+        let outdated = packages
+            .iter()
+            .filter(|package| package.is_outdated())
+            .count();
+        if self.only_outdated {
+            packages.retain(OutdatedPackage::is_outdated);
+        }
+
+        if global.json {
+            let json: Vec<_> = packages.iter().map(OutdatedPackage::to_json).collect();
+            println!("{}", serde_json::to_string_pretty(&json)?);
+            return Ok(());
+        }
+        let mut table = Table::new(&["package", "current", "requirement", "compatible", "latest"]);
+        let newer = |version: &Option<Version>, current: &Version| match version {
+            Some(version) if version > current => Cell::colored(version, Some(Color::Yellow)),
+            Some(version) => Cell::colored(version, Some(Color::Dim)),
+            None => Cell::colored("-", Some(Color::Dim)),
+        };
+        for package in &packages {
+            let requirement = if package.direct {
+                package.requirement.to_string()
+            } else {
+                format!("{} (transitive)", package.requirement)
+            };
+            table.push(vec![
+                Cell::new(&package.package),
+                Cell::new(&package.current),
+                Cell::new(requirement),
+                newer(&package.compatible, &package.current),
+                newer(&package.latest, &package.current),
+            ]);
+        }
+        table.print();
+        if std::io::stdout().is_terminal() {
+            println!(
+                "{outdated} of {} dependencies have newer versions",
+                lock.packages.len()
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_outdated() {
+        let mut package = OutdatedPackage {
+            package: "wasi:http".parse().unwrap(),
+            current: "0.2.0".parse().unwrap(),
+            requirement: "^0.2.0".parse().unwrap(),
+            direct: true,
+            compatible: Some("0.2.0".parse().unwrap()),
+            latest: None,
+        };
+        assert!(!package.is_outdated());
+        package.latest = Some("0.3.0".parse().unwrap());
+        assert!(package.is_outdated());
+        assert_eq!(package.to_json()["latest"], "0.3.0");
+    }
+}
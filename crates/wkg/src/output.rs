@@ -0,0 +1,98 @@
+//! Output file naming for fetched packages.
+
+use std::str::FromStr;
+
+use anyhow::{bail, Context};
+use wasm_pkg_loader::{PackageRef, Version};
+
+/// A file name template, e.g. `{namespace}_{name}@{version}.{ext}`.
+///
+/// Templates may reference `{namespace}`, `{name}`, `{version}`, and
+/// `{ext}`. For outputs without an extension, such as WIT directories,
+/// `{ext}` is empty and a `.` immediately before it is dropped.
+#[derive(Clone, Debug)]
+pub struct NameTemplate(String);
+
+const PLACEHOLDERS: &[&str] = &["namespace", "name", "version", "ext"];
+
+impl NameTemplate {
+    /// Returns the file name for the given package release and extension.
+    pub fn render(&self, package: &PackageRef, version: &Version, ext: Option<&str>) -> String {
+        let template = match ext {
+            Some(_) => self.0.clone(),
+            None => self.0.replace(".{ext}", ""),
+        };
+        template
+            .replace("{namespace}", package.namespace().as_ref())
+            .replace("{name}", package.name().as_ref())
+            .replace("{version}", &version.to_string())
+            .replace("{ext}", ext.unwrap_or_default())
+    }
+}
+
+impl Default for NameTemplate {
+    fn default() -> Self {
+        Self("{namespace}_{name}@{version}.{ext}".into())
+    }
+}
+
+impl FromStr for NameTemplate {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut rest = s;
+        while let Some(start) = rest.find('{') {
+            let end = rest[start..]
+                .find('}')
+                .with_context(|| format!("unclosed '{{' in name template {s:?}"))?;
+            let placeholder = &rest[start + 1..start + end];
+            if !PLACEHOLDERS.contains(&placeholder) {
+                bail!(
+                    "unknown placeholder {{{placeholder}}} in name template {s:?}; expected one of {}",
+                    PLACEHOLDERS
+                        .iter()
+                        .map(|name| format!("{{{name}}}"))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                );
+            }
+            rest = &rest[start + end + 1..];
+        }
+        if !s.contains("{name}") {
+            bail!("name template {s:?} must contain '{{name}}'");
+        }
+        if s.contains(['/', '\\']) {
+            bail!("name template {s:?} must be a file name, not a path");
+        }
+        Ok(Self(s.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_name_template() {
+        let package: PackageRef = "wasi:http".parse().unwrap();
+        let version: Version = "0.2.0".parse().unwrap();
+
+        let default = NameTemplate::default();
+        assert_eq!(
+            default.render(&package, &version, Some("wasm")),
+            "wasi_http@0.2.0.wasm"
+        );
+        assert_eq!(default.render(&package, &version, None), "wasi_http@0.2.0");
+
+        let custom: NameTemplate = "{name}-{version}.{ext}".parse().unwrap();
+        assert_eq!(
+            custom.render(&package, &version, Some("wit")),
+            "http-0.2.0.wit"
+        );
+
+        assert!("{name}-{vers}.{ext}".parse::<NameTemplate>().is_err());
+        assert!("{name".parse::<NameTemplate>().is_err());
+        assert!("{version}.{ext}".parse::<NameTemplate>().is_err());
+        assert!("{namespace}/{name}.{ext}".parse::<NameTemplate>().is_err());
+    }
+}
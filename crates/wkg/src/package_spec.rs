@@ -1,12 +1,55 @@
-use std::str::FromStr;
+use std::{io::Read, path::Path, str::FromStr};
 
-use wasm_pkg_loader::{PackageRef, Version};
+use anyhow::{bail, Context};
+use wasm_pkg_loader::{is_valid_tag, Client, ContentDigest, Error, PackageRef, Version};
 
 // TODO: move to some library crate
 #[derive(Clone, Debug)]
 pub struct PackageSpec {
     pub package: PackageRef,
     pub version: Option<Version>,
+    /// A channel tag (e.g. "stable") given instead of a version.
+    pub tag: Option<String>,
+    /// A content digest (e.g. "sha256:...") given instead of a version.
+    pub digest: Option<ContentDigest>,
+}
+
+impl PackageSpec {
+    /// Returns the package and version, for commands that don't resolve
+    /// channel tags or content digests.
+    pub fn into_versioned(self) -> anyhow::Result<(PackageRef, Option<Version>)> {
+        if let Some(tag) = &self.tag {
+            bail!(
+                "{}@{tag}: channel tags are only supported by `wkg get`; specify a version",
+                self.package
+            );
+        }
+        if let Some(digest) = &self.digest {
+            bail!(
+                "{}@{digest}: content digests are only supported by `wkg get`; specify a version",
+                self.package
+            );
+        }
+        Ok((self.package, self.version))
+    }
+}
+
+/// A package spec argument, or "-" to read specs from stdin.
+#[derive(Clone, Debug)]
+pub enum SpecArg {
+    Spec(PackageSpec),
+    Stdin,
+}
+
+impl FromStr for SpecArg {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "-" => Ok(Self::Stdin),
+            spec => Ok(Self::Spec(spec.parse()?)),
+        }
+    }
 }
 
 impl FromStr for PackageSpec {
@@ -17,9 +60,136 @@ impl FromStr for PackageSpec {
             .split_once('@')
             .map(|(pkg, ver)| (pkg, Some(ver)))
             .unwrap_or((s, None));
-        Ok(Self {
-            package: package.parse()?,
-            version: version.map(|ver| ver.parse()).transpose()?,
+        let package = package.parse()?;
+        match version {
+            Some(digest) if digest.starts_with("sha256:") => Ok(Self {
+                package,
+                version: None,
+                tag: None,
+                digest: Some(digest.parse()?),
+            }),
+            // Versions start with a digit, tags with a letter
+            Some(tag) if is_valid_tag(tag) => Ok(Self {
+                package,
+                version: None,
+                tag: Some(tag.to_string()),
+                digest: None,
+            }),
+            version => Ok(Self {
+                package,
+                version: version.map(|ver| ver.parse()).transpose()?,
+                tag: None,
+                digest: None,
+            }),
+        }
+    }
+}
+
+impl std::fmt::Display for PackageSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.package)?;
+        if let Some(version) = &self.version {
+            write!(f, "@{version}")?;
+        } else if let Some(tag) = &self.tag {
+            write!(f, "@{tag}")?;
+        } else if let Some(digest) = &self.digest {
+            write!(f, "@{digest}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Reads package specs, one per line, from the given file, or from stdin if
+/// the path is "-".
+pub fn read_spec_file(path: &Path) -> anyhow::Result<Vec<PackageSpec>> {
+    if path == Path::new("-") {
+        return read_stdin();
+    }
+    let contents =
+        std::fs::read_to_string(path).with_context(|| format!("Failed to read {path:?}"))?;
+    parse_specs(&contents).with_context(|| format!("Invalid package spec file {path:?}"))
+}
+
+/// Reads package specs, one per line, from stdin.
+pub fn read_stdin() -> anyhow::Result<Vec<PackageSpec>> {
+    let mut contents = String::new();
+    std::io::stdin()
+        .read_to_string(&mut contents)
+        .context("Failed to read package specs from stdin")?;
+    parse_specs(&contents).context("Invalid package specs on stdin")
+}
+
+/// Parses package specs, one per line. Blank lines and lines starting with
+/// '#' are ignored.
+fn parse_specs(contents: &str) -> anyhow::Result<Vec<PackageSpec>> {
+    contents
+        .lines()
+        .enumerate()
+        .map(|(idx, line)| (idx, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(idx, line)| {
+            line.parse()
+                .with_context(|| format!("line {}: invalid package spec {line:?}", idx + 1))
         })
+        .collect()
+}
+
+/// Adds a "did you mean" suggestion to an error from fetching the given
+/// package (or version of it) if it wasn't found.
+pub async fn suggest_not_found<T>(
+    result: Result<T, Error>,
+    client: &mut Client,
+    package: &PackageRef,
+    version: Option<&Version>,
+) -> anyhow::Result<T> {
+    let err = match result {
+        Err(err) if err.is_not_found() => err,
+        result => return Ok(result?),
+    };
+    let suggestion = match client.suggest(package, version).await {
+        Ok(Some(suggestion)) => suggestion,
+        Ok(None) => return Err(err.into()),
+        Err(suggest_err) => {
+            tracing::debug!("Failed to find suggestions for {package}: {suggest_err}");
+            return Err(err.into());
+        }
+    };
+    let spec = match version {
+        Some(version) => format!("{package}@{version}"),
+        None => package.to_string(),
+    };
+    Err(err).with_context(|| format!("{spec} not found; {suggestion}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_specs() {
+        let specs = parse_specs("# deps\nwasi:http@0.2.0\n\n  wasi:io\n").unwrap();
+        assert_eq!(specs.len(), 2);
+        assert_eq!(specs[0].package.to_string(), "wasi:http");
+        assert_eq!(specs[0].version, Some("0.2.0".parse().unwrap()));
+        assert_eq!(specs[1].version, None);
+
+        let spec: PackageSpec = "wasi:http@stable".parse().unwrap();
+        assert_eq!(spec.tag.as_deref(), Some("stable"));
+        assert_eq!(spec.to_string(), "wasi:http@stable");
+        assert!(spec.into_versioned().is_err());
+        assert!("wasi:http@1.x".parse::<PackageSpec>().is_err());
+
+        let digest = format!("sha256:{}", "a".repeat(64));
+        let spec: PackageSpec = format!("wasi:http@{digest}").parse().unwrap();
+        assert_eq!(spec.digest, Some(digest.parse().unwrap()));
+        assert_eq!(spec.to_string(), format!("wasi:http@{digest}"));
+        assert!(spec.into_versioned().is_err());
+        assert!("wasi:http@sha256:xyz".parse::<PackageSpec>().is_err());
+
+        let err = parse_specs("wasi:http\nnot a spec\n").unwrap_err();
+        assert!(format!("{err:#}").starts_with("line 2:"));
+
+        assert!(matches!("-".parse::<SpecArg>(), Ok(SpecArg::Stdin)));
+        assert!(matches!("wasi:io".parse::<SpecArg>(), Ok(SpecArg::Spec(_))));
     }
 }
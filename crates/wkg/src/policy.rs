@@ -0,0 +1,265 @@
+//! Organization-level policy (`wkg-policy.toml`), giving platform teams a
+//! single enforcement point for the packages developers may fetch, e.g.:
+//!
+//! ```toml
+//! max_artifact_size = 10485760
+//! allowed_licenses = ["Apache-2.0", "MIT"]
+//! allowed_namespaces = ["wasi", "my-org"]
+//! require_provenance = true
+//! provenance_builders = ["https://github.com/actions/runner"]
+//! ```
+//!
+//! The policy is loaded from `--policy-file` (or `WKG_POLICY_FILE`), falling
+//! back to `wkg-policy.toml` in the config directory (e.g.
+//! `~/.config/wasm-pkg/wkg-policy.toml`).
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context};
+use serde::Deserialize;
+use wasm_pkg_loader::{ClientConfig, PackageRef, ProvenancePolicy, Version};
+
+pub const POLICY_FILE_NAME: &str = "wkg-policy.toml";
+
+/// The custom section holding package metadata such as the license.
+const REGISTRY_METADATA_SECTION: &str = "registry-metadata";
+
+/// The error returned for packages or content not allowed by policy.
+#[derive(Debug)]
+pub struct PolicyViolation(String);
+
+impl std::fmt::Display for PolicyViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for PolicyViolation {}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OrgPolicy {
+    /// The maximum size, in bytes, of fetched package content.
+    max_artifact_size: Option<u64>,
+    /// SPDX license identifiers packages may be licensed under.
+    allowed_licenses: Option<Vec<String>>,
+    /// Namespaces packages may be fetched from.
+    allowed_namespaces: Option<Vec<String>>,
+    /// Whether packages must have a provenance attestation.
+    #[serde(default)]
+    require_provenance: bool,
+    /// If non-empty, builder IDs accepted in provenance attestations.
+    #[serde(default)]
+    provenance_builders: Vec<String>,
+}
+
+impl OrgPolicy {
+    /// Loads the policy file at the given path or, if None, the default
+    /// policy file if it exists. Returns an empty policy if there is no
+    /// policy file.
+    pub fn load(path: Option<&Path>) -> anyhow::Result<Self> {
+        let path = match path {
+            Some(path) => path.to_path_buf(),
+            None => match Self::default_path() {
+                Some(path) if path.exists() => path,
+                _ => return Ok(Self::default()),
+            },
+        };
+        let contents =
+            std::fs::read_to_string(&path).with_context(|| format!("Failed to read {path:?}"))?;
+        toml::from_str(&contents).with_context(|| format!("Invalid policy file {path:?}"))
+    }
+
+    /// Returns the default policy file path, e.g.
+    /// `~/.config/wasm-pkg/wkg-policy.toml`.
+    pub fn default_path() -> Option<PathBuf> {
+        Some(
+            ClientConfig::default_config_path()?
+                .parent()?
+                .join(POLICY_FILE_NAME),
+        )
+    }
+
+    /// Applies the parts of this policy enforced by the client itself.
+    pub fn apply_to_config(&self, config: &mut ClientConfig) {
+        if self.require_provenance {
+            config.set_provenance_policy(ProvenancePolicy {
+                require: true,
+                builder_ids: self.provenance_builders.clone(),
+                ..Default::default()
+            });
+        }
+    }
+
+    /// Fails if the given package may not be fetched at all.
+    pub fn check_package(&self, package: &PackageRef) -> anyhow::Result<()> {
+        if let Some(allowed) = &self.allowed_namespaces {
+            let namespace = package.namespace().to_string();
+            if !allowed.contains(&namespace) {
+                bail!(PolicyViolation(format!(
+                    "{package} is not allowed by policy: namespace {namespace:?} is not allowed"
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Fails if content of the given size is too large.
+    pub fn check_size(&self, package: &PackageRef, size: u64) -> anyhow::Result<()> {
+        if let Some(max) = self.max_artifact_size {
+            if size > max {
+                bail!(PolicyViolation(format!("{package} is not allowed by policy: content exceeds the maximum artifact size of {max} bytes")));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns true if [`Self::check_content`] needs the full content.
+    pub fn checks_content(&self) -> bool {
+        self.allowed_licenses.is_some()
+    }
+
+    /// Fails if the given package content doesn't satisfy this policy.
+    pub fn check_content(
+        &self,
+        package: &PackageRef,
+        version: &Version,
+        content: &[u8],
+    ) -> anyhow::Result<()> {
+        self.check_size(package, content.len() as u64)?;
+        if let Some(allowed) = &self.allowed_licenses {
+            let Some(license) = registry_metadata_license(content) else {
+                bail!(PolicyViolation(format!(
+                    "{package}@{version} is not allowed by policy: no license metadata found"
+                )));
+            };
+            if !license_allowed(&license, allowed) {
+                bail!(PolicyViolation(format!("{package}@{version} is not allowed by policy: license {license:?} is not allowed")));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns true if the given SPDX license expression is satisfied by the
+/// allowed licenses: at least one `OR` alternative must consist only of
+/// allowed licenses. Expressions with parentheses are treated
+/// conservatively, requiring every license they mention to be allowed.
+fn license_allowed(expression: &str, allowed: &[String]) -> bool {
+    if expression.contains('(') {
+        return expression
+            .replace(['(', ')'], " ")
+            .split(" AND ")
+            .flat_map(|term| term.split(" OR "))
+            .all(|term| license_term_allowed(term, allowed));
+    }
+    expression.split(" OR ").any(|alternative| {
+        alternative
+            .split(" AND ")
+            .all(|term| license_term_allowed(term, allowed))
+    })
+}
+
+fn license_term_allowed(term: &str, allowed: &[String]) -> bool {
+    // Exceptions (e.g. "Apache-2.0 WITH LLVM-exception") only grant rights
+    let id = term.split(" WITH ").next().unwrap_or_default().trim();
+    allowed.iter().any(|allowed| allowed == id)
+}
+
+/// Returns the license from the given Wasm's top-level `registry-metadata`
+/// custom section, if present.
+fn registry_metadata_license(wasm: &[u8]) -> Option<String> {
+    #[derive(Deserialize)]
+    struct RegistryMetadata {
+        license: Option<String>,
+    }
+
+    let mut rest = wasm.strip_prefix(b"\0asm")?.get(4..)?;
+    while let Some((&id, tail)) = rest.split_first() {
+        let (size, tail) = read_leb128_u32(tail)?;
+        let (payload, tail) = split_at(tail, size)?;
+        rest = tail;
+        if id != 0 {
+            continue;
+        }
+        let (name_len, payload) = read_leb128_u32(payload)?;
+        let (name, data) = split_at(payload, name_len)?;
+        if name == REGISTRY_METADATA_SECTION.as_bytes() {
+            let metadata: RegistryMetadata = serde_json::from_slice(data).ok()?;
+            return metadata.license;
+        }
+    }
+    None
+}
+
+fn split_at(bytes: &[u8], mid: u32) -> Option<(&[u8], &[u8])> {
+    let mid = mid as usize;
+    (mid <= bytes.len()).then(|| bytes.split_at(mid))
+}
+
+fn read_leb128_u32(bytes: &[u8]) -> Option<(u32, &[u8])> {
+    let mut value = 0u32;
+    for (i, &byte) in bytes.iter().enumerate().take(5) {
+        value |= u32::from(byte & 0x7f) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Some((value, &bytes[i + 1..]));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn module_with_custom_section(name: &str, data: &[u8]) -> Vec<u8> {
+        let mut payload = vec![name.len() as u8];
+        payload.extend(name.as_bytes());
+        payload.extend(data);
+        let mut wasm = b"\0asm\x01\0\0\0".to_vec();
+        wasm.push(0);
+        wasm.push(payload.len() as u8);
+        wasm.extend(payload);
+        wasm
+    }
+
+    #[test]
+    fn test_registry_metadata_license() {
+        let wasm = module_with_custom_section(REGISTRY_METADATA_SECTION, br#"{"license":"MIT"}"#);
+        assert_eq!(registry_metadata_license(&wasm).as_deref(), Some("MIT"));
+        let wasm = module_with_custom_section("name", b"\0");
+        assert_eq!(registry_metadata_license(&wasm), None);
+    }
+
+    #[test]
+    fn test_license_allowed() {
+        let allowed = ["Apache-2.0".to_string(), "MIT".to_string()];
+        assert!(license_allowed("MIT", &allowed));
+        assert!(license_allowed("GPL-3.0 OR MIT", &allowed));
+        assert!(license_allowed("Apache-2.0 WITH LLVM-exception", &allowed));
+        assert!(!license_allowed("MIT AND GPL-3.0", &allowed));
+        assert!(!license_allowed(
+            "(MIT OR GPL-3.0) AND Apache-2.0",
+            &allowed
+        ));
+    }
+
+    #[test]
+    fn test_policy() {
+        let policy: OrgPolicy = toml::from_str(
+            r#"
+            max_artifact_size = 16
+            allowed_licenses = ["MIT"]
+            allowed_namespaces = ["wasi"]
+            "#,
+        )
+        .unwrap();
+        let package: PackageRef = "wasi:io".parse().unwrap();
+        let version: Version = "0.2.0".parse().unwrap();
+        policy.check_package(&package).unwrap();
+        assert!(policy.check_package(&"other:pkg".parse().unwrap()).is_err());
+        assert!(policy.check_size(&package, 17).is_err());
+        // No license metadata
+        assert!(policy.check_content(&package, &version, b"\0asm").is_err());
+    }
+}
@@ -0,0 +1,177 @@
+//! `wkg prefetch`: downloads packages into the local cache without writing
+//! any outputs, e.g. to bake dependencies into CI images or warm developer
+//! machines before going offline.
+
+use std::path::PathBuf;
+
+use anyhow::{ensure, Context};
+use clap::Args;
+use futures_util::TryStreamExt;
+use wasm_pkg_loader::{Client, PackageRef, Release};
+
+use crate::{
+    batch::Batch,
+    lock::LockFile,
+    manifest::Manifest,
+    package_spec::{self, suggest_not_found, PackageSpec},
+    project::{project_client, resolve_lock, ResolveArgs},
+    GlobalArgs,
+};
+
+#[derive(Args, Debug)]
+pub struct PrefetchCommand {
+    /// Packages to prefetch, specified as <namespace>:<name> plus optional
+    /// @<version>. Without a version, the latest release is fetched.
+    package_specs: Vec<PackageSpec>,
+
+    /// Read package specs from a file, one per line. Blank lines and lines
+    /// starting with '#' are ignored. Use "-" to read from stdin. May be
+    /// given more than once.
+    #[arg(long, short = 'f', value_name = "FILE")]
+    from_file: Vec<PathBuf>,
+
+    /// Prefetch the current project's dependencies: the versions in its
+    /// lock file if present, or as resolved from its manifest otherwise (no
+    /// lock file is written). This is the default if no packages are given.
+    #[arg(long)]
+    project: bool,
+}
+
+impl PrefetchCommand {
+    pub async fn run(self, global: &GlobalArgs) -> anyhow::Result<()> {
+        let mut specs = self.package_specs;
+        for path in &self.from_file {
+            specs.extend(package_spec::read_spec_file(path)?);
+        }
+        let policy = global.load_policy()?;
+        let mut stats = PrefetchStats::default();
+        let mut batch = Batch::new(global.continue_on_error);
+
+        if self.project || specs.is_empty() {
+            let manifest = Manifest::load_current()?;
+            let resolve_args = ResolveArgs::default();
+            let mut client = project_client(global, &manifest, &resolve_args)?;
+            let lock_path = LockFile::path_for(manifest.root_dir());
+            let lock = match LockFile::load(&lock_path)? {
+                Some(lock) => lock,
+                None => {
+                    println!(
+                        "'{}' not found; resolving dependencies...",
+                        lock_path.display()
+                    );
+                    resolve_lock(&mut client, &manifest, None, &resolve_args).await?
+                }
+            };
+            for locked in &lock.packages {
+                let (name, version) = (&locked.name, &locked.version);
+                policy.check_package(name)?;
+                let result = async {
+                    // Fetch by the locked digest so the cached content is
+                    // verified against it
+                    let release = match &locked.digest {
+                        Some(digest) => Release {
+                            version: version.clone(),
+                            content_digest: digest.clone(),
+                        },
+                        None => client.get_release(name, version).await.with_context(|| {
+                            format!("Failed to get release details for {name}@{version}")
+                        })?,
+                    };
+                    prefetch(&mut client, name, &release).await
+                }
+                .await;
+                if let Some(fetched) = batch.check(format_args!("{name}@{version}"), result)? {
+                    stats.record(fetched);
+                }
+            }
+        }
+
+        if !specs.is_empty() {
+            let mut client = global.load_config()?.to_client();
+            for spec in specs {
+                policy.check_package(&spec.package)?;
+                let result = prefetch_spec(&mut client, spec.clone()).await;
+                if let Some(fetched) = batch.check(spec, result)? {
+                    stats.record(fetched);
+                }
+            }
+        }
+
+        println!(
+            "Prefetched {} packages ({} bytes); {} already cached",
+            stats.fetched, stats.bytes, stats.cached
+        );
+        batch.finish()
+    }
+}
+
+/// Resolves a package spec's release and prefetches it.
+async fn prefetch_spec(client: &mut Client, spec: PackageSpec) -> anyhow::Result<Option<u64>> {
+    let (package, version) = spec.into_versioned()?;
+    let version = match version {
+        Some(version) => version,
+        None => {
+            let result = client.latest_version(&package).await;
+            suggest_not_found(result, client, &package, None)
+                .await
+                .with_context(|| format!("Failed to select latest version of {package}"))?
+        }
+    };
+    let result = client.get_release(&package, &version).await;
+    let release = suggest_not_found(result, client, &package, Some(&version))
+        .await
+        .with_context(|| format!("Failed to get release details for {package}@{version}"))?;
+    prefetch(client, &package, &release).await
+}
+
+#[derive(Default)]
+struct PrefetchStats {
+    fetched: usize,
+    cached: usize,
+    bytes: u64,
+}
+
+impl PrefetchStats {
+    fn record(&mut self, fetched: Option<u64>) {
+        match fetched {
+            Some(bytes) => {
+                self.fetched += 1;
+                self.bytes += bytes;
+            }
+            None => self.cached += 1,
+        }
+    }
+}
+
+/// Downloads a release's content into the cache, returning its size, or None
+/// if it was already cached.
+async fn prefetch(
+    client: &mut Client,
+    package: &PackageRef,
+    release: &Release,
+) -> anyhow::Result<Option<u64>> {
+    let version = &release.version;
+    if client
+        .cached_content_file(package, release)
+        .await?
+        .is_some()
+    {
+        println!("{package}@{version} is already cached");
+        return Ok(None);
+    }
+    println!("Fetching {package}@{version}...");
+    let size = client
+        .stream_content(package, release)
+        .await?
+        .try_fold(
+            0,
+            |size, chunk| async move { Ok(size + chunk.len() as u64) },
+        )
+        .await
+        .with_context(|| format!("Failed to fetch {package}@{version}"))?;
+    ensure!(
+        client.cached_content_file(package, release).await?.is_some(),
+        "{package}@{version} wasn't cached; check that the cache is enabled and its content policy allows storing it"
+    );
+    Ok(Some(size))
+}
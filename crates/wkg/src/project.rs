@@ -0,0 +1,727 @@
+//! Project-centric commands operating on a `wkg.toml` manifest and its
+//! `wkg.lock` lock file.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, ensure, Context};
+use clap::{Args, Subcommand, ValueEnum};
+use futures_util::TryStreamExt;
+use semver::VersionReq;
+use wasm_pkg_loader::{
+    Client, DependencyGraph, Error, PackageRef, Release, ResolutionStrategy, ResolveOptions,
+    Resolver, Version,
+};
+use wit_component::DecodedWasm;
+
+use crate::{
+    batch::Batch,
+    deprecate,
+    lock::{LockFile, LockedPackage},
+    manifest::{Dependency, Manifest, MANIFEST_FILE_NAME},
+    package_spec::{suggest_not_found, PackageSpec},
+    policy::OrgPolicy,
+    quarantine::{self, Quarantine},
+    table::{color_enabled, Color},
+    wit::{WitStyle, WitStyleArgs},
+    GlobalArgs,
+};
+
+/// Options controlling how the manifest's dependencies are resolved.
+#[derive(Args, Debug, Default)]
+pub struct ResolveArgs {
+    /// Select the lowest version satisfying each requirement instead of the
+    /// highest, ignoring versions in the existing lock file. Useful for
+    /// verifying that declared minimum versions are correct.
+    #[arg(long)]
+    minimal_versions: bool,
+
+    /// Allow selecting yanked versions, including keeping yanked versions
+    /// already in the lock file. By default a yanked locked version is
+    /// replaced by another version satisfying the same requirements.
+    #[arg(long)]
+    allow_yanked: bool,
+}
+
+/// Returns a client for the given manifest, honoring any per-dependency
+/// registry overrides.
+pub(crate) fn project_client(
+    global: &GlobalArgs,
+    manifest: &Manifest,
+    resolve_args: &ResolveArgs,
+) -> anyhow::Result<Client> {
+    let mut config = global.load_config()?;
+    for (package, dependency) in &manifest.dependencies {
+        if let Some(registry) = &dependency.registry {
+            config.set_package_registry(package.clone(), registry);
+        }
+    }
+    if resolve_args.minimal_versions {
+        config.set_resolution_strategy(ResolutionStrategy::Minimal);
+    }
+    Ok(config.to_client())
+}
+
+/// Returns the dependency graph of the project's locked versions, failing if
+/// the lock file is missing or out of date.
+pub(crate) async fn locked_graph(
+    global: &GlobalArgs,
+    manifest: &Manifest,
+) -> anyhow::Result<DependencyGraph> {
+    let lock_path = LockFile::path_for(manifest.root_dir());
+    let lock = LockFile::load(&lock_path)?
+        .with_context(|| format!("'{}' not found; run `wkg lock` first", lock_path.display()))?;
+    let graph = resolve_graph(global, manifest, Some(&lock)).await?;
+    ensure!(
+        lock_matches(&lock, &graph),
+        "'{}' is out of date; run `wkg lock` first",
+        lock_path.display()
+    );
+    Ok(graph)
+}
+
+/// Resolves the dependency graph of the manifest's dependencies, preferring
+/// the versions in the given lock file.
+pub(crate) async fn resolve_graph(
+    global: &GlobalArgs,
+    manifest: &Manifest,
+    lock: Option<&LockFile>,
+) -> anyhow::Result<DependencyGraph> {
+    let mut client = project_client(global, manifest, &ResolveArgs::default())?;
+    let options = ResolveOptions {
+        preferred: lock
+            .iter()
+            .flat_map(|lock| &lock.packages)
+            .map(|locked| (locked.name.clone(), locked.version.clone()))
+            .collect(),
+        ..Default::default()
+    };
+    let roots = manifest
+        .dependencies
+        .iter()
+        .map(|(package, dependency)| (package.clone(), dependency.version.clone()));
+    client
+        .resolve_graph(roots, &options)
+        .await
+        .context("Failed to resolve dependencies")
+}
+
+/// Returns true if the lock file records exactly the given graph's package
+/// versions (and digests, where recorded).
+pub(crate) fn lock_matches(lock: &LockFile, graph: &DependencyGraph) -> bool {
+    graph.nodes().len() == lock.packages.len()
+        && graph.nodes().iter().all(|node| {
+            lock.get(&node.package).is_some_and(|locked| {
+                locked.version == node.version
+                    && locked
+                        .digest
+                        .as_ref()
+                        .is_none_or(|d| d == &node.content_digest)
+            })
+        })
+}
+
+/// Resolves the manifest's dependencies and their transitive dependencies,
+/// keeping versions from the existing lock file where they still satisfy
+/// every requirement.
+pub(crate) async fn resolve_lock(
+    client: &mut Client,
+    manifest: &Manifest,
+    existing: Option<&LockFile>,
+    resolve_args: &ResolveArgs,
+) -> anyhow::Result<LockFile> {
+    let mut resolver = Resolver::new(client);
+    resolver.allow_yanked(resolve_args.allow_yanked);
+    for locked in existing.iter().flat_map(|lock| &lock.packages) {
+        let registry = manifest
+            .dependencies
+            .get(&locked.name)
+            .and_then(|dep| dep.registry.clone());
+        if locked.registry == registry {
+            resolver.prefer_version(locked.name.clone(), locked.version.clone());
+        }
+    }
+    let roots = manifest
+        .dependencies
+        .iter()
+        .map(|(package, dependency)| (package.clone(), dependency.version.clone()));
+    let resolved = match resolver.resolve(roots).await {
+        Ok(resolved) => resolved,
+        Err(err @ Error::VersionYanked(_)) => {
+            return Err(err).context(
+                "Failed to resolve dependencies; pass --allow-yanked to select yanked versions",
+            )
+        }
+        Err(err) => return Err(err).context("Failed to resolve dependencies"),
+    };
+
+    let mut packages = vec![];
+    for resolved in resolved.iter() {
+        let name = &resolved.package;
+        packages.push(LockedPackage {
+            name: name.clone(),
+            version: resolved.version.clone(),
+            registry: manifest
+                .dependencies
+                .get(name)
+                .and_then(|dep| dep.registry.clone()),
+            digest: Some(resolved.release.content_digest.clone()),
+        });
+    }
+    Ok(LockFile { packages })
+}
+
+/// Resolves and writes the manifest's lock file if it has changed.
+async fn update_lock(
+    client: &mut Client,
+    manifest: &Manifest,
+    global: &GlobalArgs,
+    resolve_args: &ResolveArgs,
+) -> anyhow::Result<LockFile> {
+    let path = LockFile::path_for(manifest.root_dir());
+    let existing = LockFile::load(&path)?;
+    let lock = resolve_lock(client, manifest, existing.as_ref(), resolve_args).await?;
+    store_lock(&path, existing.as_ref(), &lock, global, false)?;
+    Ok(lock)
+}
+
+/// Prints the changes from `existing` to `lock` and, unless `dry_run` is
+/// set, writes `lock` to `path` if anything changed. Fails if the content
+/// digest of a package version locked in `existing` has changed.
+fn store_lock(
+    path: &Path,
+    existing: Option<&LockFile>,
+    lock: &LockFile,
+    global: &GlobalArgs,
+    dry_run: bool,
+) -> anyhow::Result<()> {
+    if existing == Some(lock) {
+        return Ok(());
+    }
+    check_digests(existing, lock)?;
+    if global.locked() {
+        let flag = if global.frozen {
+            "--frozen"
+        } else {
+            "--locked"
+        };
+        bail!(
+            "'{}' needs to be updated but {flag} was passed",
+            path.display()
+        );
+    }
+    print_lock_changes(existing, lock);
+    if dry_run {
+        println!("Not writing '{}' (dry run)", path.display());
+    } else {
+        lock.store(path)?;
+        println!("Wrote '{}'", path.display());
+    }
+    Ok(())
+}
+
+/// Checks that every package version kept from `existing` has the same
+/// content digest in `lock`.
+fn check_digests(existing: Option<&LockFile>, lock: &LockFile) -> anyhow::Result<()> {
+    for old in existing.iter().flat_map(|lock| &lock.packages) {
+        let Some(new) = lock.get(&old.name) else {
+            continue;
+        };
+        if old.version != new.version || old.registry != new.registry {
+            continue;
+        }
+        if let (Some(old_digest), Some(new_digest)) = (&old.digest, &new.digest) {
+            ensure!(
+                old_digest == new_digest,
+                "Content of locked package {}@{} has changed: locked digest is {old_digest} but the registry returned {new_digest}",
+                old.name,
+                old.version
+            );
+        }
+    }
+    Ok(())
+}
+
+fn print_lock_changes(existing: Option<&LockFile>, lock: &LockFile) {
+    for locked in &lock.packages {
+        let name = &locked.name;
+        match existing.and_then(|lock| lock.get(name)) {
+            None => println!("Locking {name}@{}", locked.version),
+            Some(old) if old.version != locked.version => {
+                println!("Updating {name}@{} -> {}", old.version, locked.version)
+            }
+            Some(_) => (),
+        }
+    }
+    for old in existing.iter().flat_map(|lock| &lock.packages) {
+        if lock.get(&old.name).is_none() {
+            println!("Removing {}@{}", old.name, old.version);
+        }
+    }
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ManifestCommand {
+    /// Generate a manifest (wkg.toml) for an existing WIT directory, with a
+    /// dependency on each package it references at the version vendored in
+    /// its `deps` directory, if any.
+    Generate {
+        /// The WIT directory.
+        #[arg(long, default_value = "wit")]
+        wit_dir: PathBuf,
+
+        /// Overwrite an existing manifest.
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+impl ManifestCommand {
+    pub async fn run(self, _global: &GlobalArgs) -> anyhow::Result<()> {
+        match self {
+            ManifestCommand::Generate { wit_dir, force } => {
+                let path = std::env::current_dir()?.join(MANIFEST_FILE_NAME);
+                ensure!(
+                    force || !path.exists(),
+                    "'{}' already exists; pass '--force' to overwrite it",
+                    path.display()
+                );
+                let manifest = Manifest::from_wit_dir(path.clone(), &wit_dir)?;
+                for (package, dependency) in &manifest.dependencies {
+                    println!("Adding {package} {}", dependency.version);
+                }
+                std::fs::write(&path, manifest.to_toml())
+                    .with_context(|| format!("Failed to write {path:?}"))?;
+                println!("Wrote '{}'", path.display());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Args, Debug)]
+pub struct AddCommand {
+    /// The package to add, specified as <namespace>:<name> plus optional
+    /// @<version>. Without a version, the latest release is used. The
+    /// dependency requires a semver-compatible version, e.g. "^0.2.0".
+    package_spec: PackageSpec,
+
+    /// The registry to fetch this dependency from, overriding configuration.
+    #[arg(long, value_name = "DOMAIN")]
+    registry: Option<String>,
+}
+
+impl AddCommand {
+    pub async fn run(self, global: &GlobalArgs) -> anyhow::Result<()> {
+        let cwd = std::env::current_dir()?;
+        let path = match Manifest::find(&cwd) {
+            Some(path) => path,
+            None => {
+                let path = cwd.join(MANIFEST_FILE_NAME);
+                println!("Creating '{}'", path.display());
+                std::fs::write(&path, "")?;
+                path
+            }
+        };
+        let mut manifest = Manifest::load(path)?;
+
+        let (package, version) = self.package_spec.into_versioned()?;
+        let version = match version {
+            Some(version) => version,
+            None => {
+                let mut config = global.load_config()?;
+                if let Some(registry) = &self.registry {
+                    config.set_package_registry(package.clone(), registry);
+                }
+                let mut client = config.to_client();
+                let result = client.latest_version(&package).await;
+                suggest_not_found(result, &mut client, &package, None)
+                    .await
+                    .with_context(|| format!("Failed to resolve latest version of {package}"))?
+            }
+        };
+        let dependency = Dependency {
+            version: VersionReq::parse(&version.to_string())?,
+            registry: self.registry,
+        };
+        println!("Adding {package} {}", dependency.version);
+        manifest.add_dependency(package, dependency)?;
+        Ok(())
+    }
+}
+
+#[derive(Args, Debug)]
+pub struct LockCommand {
+    /// Check that the lock file is up to date with the manifest, printing
+    /// what would change and failing if not, without writing it.
+    #[arg(long)]
+    check: bool,
+
+    #[command(flatten)]
+    resolve_args: ResolveArgs,
+}
+
+impl LockCommand {
+    pub async fn run(self, global: &GlobalArgs) -> anyhow::Result<()> {
+        let manifest = Manifest::load_current()?;
+        let mut client = project_client(global, &manifest, &self.resolve_args)?;
+        if self.check {
+            return check_lock(&mut client, &manifest, &self.resolve_args).await;
+        }
+        update_lock(&mut client, &manifest, global, &self.resolve_args).await?;
+        Ok(())
+    }
+}
+
+/// Re-resolves the manifest's dependencies and fails, printing the
+/// differences, if the lock file doesn't match the result.
+async fn check_lock(
+    client: &mut Client,
+    manifest: &Manifest,
+    resolve_args: &ResolveArgs,
+) -> anyhow::Result<()> {
+    let path = LockFile::path_for(manifest.root_dir());
+    let existing = LockFile::load(&path)?
+        .with_context(|| format!("'{}' not found; run `wkg lock`", path.display()))?;
+    let lock = resolve_lock(client, manifest, Some(&existing), resolve_args).await?;
+    check_digests(Some(&existing), &lock)?;
+    let diff = existing.diff(&lock);
+    if diff.is_empty() {
+        println!("'{}' is up to date", path.display());
+        return Ok(());
+    }
+    println!("--- {} (current)", path.display());
+    println!("+++ {} (resolved)", path.display());
+    for line in &diff {
+        println!("{line}");
+    }
+    bail!("'{}' is out of date; run `wkg lock`", path.display())
+}
+
+#[derive(Args, Debug)]
+pub struct UpdateCommand {
+    /// The locked packages to update. All packages are updated if none are
+    /// given.
+    packages: Vec<PackageRef>,
+
+    /// Update the single given package to exactly this version.
+    #[arg(long, value_name = "VERSION", requires = "packages")]
+    precise: Option<Version>,
+
+    /// Print the changes that would be made without writing the lock file.
+    #[arg(long)]
+    dry_run: bool,
+
+    #[command(flatten)]
+    resolve_args: ResolveArgs,
+}
+
+impl UpdateCommand {
+    pub async fn run(self, global: &GlobalArgs) -> anyhow::Result<()> {
+        let manifest = Manifest::load_current()?;
+        let path = LockFile::path_for(manifest.root_dir());
+        let existing = LockFile::load(&path)?;
+
+        // Versions left in this lock are kept if they still satisfy every
+        // requirement; the rest are re-resolved.
+        let mut preferred = existing.clone().unwrap_or_default();
+        if self.packages.is_empty() {
+            preferred.packages.clear();
+        }
+        for package in &self.packages {
+            ensure!(
+                preferred.get(package).is_some(),
+                "Package {package} is not in '{}'",
+                path.display()
+            );
+        }
+        match &self.precise {
+            Some(version) => {
+                ensure!(
+                    self.packages.len() == 1,
+                    "--precise requires exactly one package"
+                );
+                let locked = preferred
+                    .packages
+                    .iter_mut()
+                    .find(|locked| locked.name == self.packages[0])
+                    .unwrap();
+                locked.version = version.clone();
+                locked.digest = None;
+            }
+            None => preferred
+                .packages
+                .retain(|locked| !self.packages.contains(&locked.name)),
+        }
+
+        let mut client = project_client(global, &manifest, &self.resolve_args)?;
+        let lock =
+            resolve_lock(&mut client, &manifest, Some(&preferred), &self.resolve_args).await?;
+        if let Some(version) = &self.precise {
+            let package = &self.packages[0];
+            let resolved = lock.get(package).map(|locked| &locked.version);
+            ensure!(
+                resolved == Some(version),
+                "Cannot update {package} to {version}; it doesn't satisfy the requirements on {package}"
+            );
+        }
+        store_lock(&path, existing.as_ref(), &lock, global, self.dry_run)
+    }
+}
+
+#[derive(Args, Debug)]
+pub struct SyncCommand {
+    /// The directory to write dependencies to, relative to the manifest.
+    #[arg(long, default_value = "wit/deps")]
+    deps_dir: PathBuf,
+
+    /// How to lay out dependencies in the deps directory.
+    #[arg(long, value_enum, default_value = "flat")]
+    layout: DepsLayout,
+
+    #[command(flatten)]
+    wit_style: WitStyleArgs,
+
+    #[command(flatten)]
+    resolve_args: ResolveArgs,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum DepsLayout {
+    /// One `<namespace>-<name>.wit` (or `.wasm`) file per dependency.
+    Flat,
+    /// One `<namespace>-<name>/package.wit` directory per WIT dependency,
+    /// as cargo-component and wit-bindgen expect under `wit/deps`.
+    CargoComponent,
+}
+
+impl SyncCommand {
+    pub async fn run(self, global: &GlobalArgs) -> anyhow::Result<()> {
+        let manifest = Manifest::load_current()?;
+        let mut client = project_client(global, &manifest, &self.resolve_args)?;
+        let lock = update_lock(&mut client, &manifest, global, &self.resolve_args).await?;
+
+        let policy = global.load_policy()?;
+        let quarantine = global.quarantine()?;
+        for locked in &lock.packages {
+            policy.check_package(&locked.name)?;
+        }
+        let wit_style = WitStyle::new(&self.wit_style, global.load_config()?.wit_output())?;
+
+        let deps_dir = manifest.root_dir().join(&self.deps_dir);
+        std::fs::create_dir_all(&deps_dir)
+            .with_context(|| format!("Failed to create {deps_dir:?}"))?;
+        let output = SyncOutput {
+            deps_dir: &deps_dir,
+            quarantine: quarantine.as_ref(),
+            wit_style: &wit_style,
+        };
+        let mut batch = Batch::new(global.continue_on_error);
+        for locked in &lock.packages {
+            let result = self
+                .sync_package(global, &mut client, &policy, &output, locked)
+                .await;
+            batch.check(format_args!("{}@{}", locked.name, locked.version), result)?;
+        }
+        println!(
+            "Synced {} dependencies to '{}'",
+            batch.succeeded(),
+            deps_dir.display()
+        );
+        batch.finish()
+    }
+
+    async fn sync_package(
+        &self,
+        global: &GlobalArgs,
+        client: &mut Client,
+        policy: &OrgPolicy,
+        output: &SyncOutput<'_>,
+        locked: &LockedPackage,
+    ) -> anyhow::Result<()> {
+        let SyncOutput {
+            deps_dir,
+            quarantine,
+            wit_style,
+        } = *output;
+        let LockedPackage {
+            name,
+            version,
+            digest,
+            ..
+        } = locked;
+        println!("Fetching {name}@{version}...");
+        // Fetch by the locked digest so the content is verified against it
+        let release = match digest {
+            Some(digest) => Release {
+                version: version.clone(),
+                content_digest: digest.clone(),
+            },
+            None => client
+                .get_release(name, version)
+                .await
+                .with_context(|| format!("Failed to get release details for {name}@{version}"))?,
+        };
+        deprecate::warn_if_deprecated(client, name, &release).await;
+        let cache_hit = client.is_content_cached(name, &release);
+        let fetch = global.run_report.start(client, name, &release, cache_hit)?;
+        let content: Vec<u8> = client
+            .stream_content(name, &release)
+            .await?
+            .map_ok(|chunk| chunk.to_vec())
+            .try_concat()
+            .await?;
+        policy.check_content(name, version, &content)?;
+        if quarantine.is_some() {
+            quarantine::validate_wasm(&content)
+                .with_context(|| format!("Failed to verify {name}@{version}"))?;
+        }
+
+        // Each package is written once, however many dependents share
+        // it; WIT references resolve by package name within the deps dir.
+        let file_stem = format!("{}-{}", name.namespace(), name.name());
+        let wit_file = deps_dir.join(format!("{file_stem}.wit"));
+        let wasm_file = deps_dir.join(format!("{file_stem}.wasm"));
+        let wit_dir = deps_dir.join(&file_stem);
+        let output_path = match wit_component::decode(&content) {
+            Ok(DecodedWasm::WitPackage(resolve, pkg)) => {
+                let wit = wit_style
+                    .clone()
+                    .with_source(client.package_registry(name)?)
+                    .print(&resolve, pkg)?;
+                let path = match self.layout {
+                    DepsLayout::Flat => wit_file.clone(),
+                    DepsLayout::CargoComponent => {
+                        std::fs::create_dir_all(&wit_dir)
+                            .with_context(|| format!("Failed to create {wit_dir:?}"))?;
+                        wit_dir.join("package.wit")
+                    }
+                };
+                write_output(quarantine, &path, wit.as_bytes())?;
+                path
+            }
+            _ => {
+                write_output(quarantine, &wasm_file, &content)?;
+                wasm_file.clone()
+            }
+        };
+        // Remove copies left by an earlier sync in another format or
+        // layout
+        for stale_path in [&wit_file, &wasm_file, &wit_dir] {
+            if output_path.starts_with(stale_path) {
+                continue;
+            }
+            let removed = if stale_path.is_dir() {
+                std::fs::remove_dir_all(stale_path)
+            } else {
+                std::fs::remove_file(stale_path)
+            };
+            match removed {
+                Ok(()) => tracing::debug!(?stale_path, "removed stale dependency"),
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => (),
+                Err(err) => {
+                    return Err(err).with_context(|| format!("Failed to remove {stale_path:?}"))
+                }
+            }
+        }
+        tracing::debug!(?output_path, "wrote dependency");
+        global
+            .job_summary
+            .record(name, version, &release.content_digest, &output_path);
+        global
+            .run_report
+            .record(fetch, content.len() as u64, Some(&output_path));
+        Ok(())
+    }
+}
+
+/// Where and how `sync` writes dependencies.
+struct SyncOutput<'a> {
+    deps_dir: &'a Path,
+    quarantine: Option<&'a Quarantine>,
+    wit_style: &'a WitStyle,
+}
+
+/// Writes fetched content, staging it through the quarantine if enabled so
+/// it appears at `path` only once complete.
+pub fn write_output(
+    quarantine: Option<&Quarantine>,
+    path: &Path,
+    contents: &[u8],
+) -> anyhow::Result<()> {
+    match quarantine {
+        Some(quarantine) => quarantine::promote(quarantine.stage(contents)?, path),
+        None => std::fs::write(path, contents).with_context(|| format!("Failed to write {path:?}")),
+    }
+}
+
+#[derive(Args, Debug)]
+pub struct TreeCommand {
+    /// The output format. `dot` and `mermaid` render the full locked
+    /// dependency graph, including indirect dependencies.
+    #[arg(long, value_enum, default_value_t = TreeFormat::Text)]
+    format: TreeFormat,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum TreeFormat {
+    /// The manifest's dependencies and their locked versions
+    Text,
+    /// A Graphviz DOT digraph
+    Dot,
+    /// A Mermaid flowchart
+    Mermaid,
+}
+
+impl TreeCommand {
+    pub async fn run(self, global: &GlobalArgs) -> anyhow::Result<()> {
+        let manifest = Manifest::load_current()?;
+        if self.format != TreeFormat::Text {
+            let graph = locked_graph(global, &manifest).await?;
+            let project = match &manifest.package {
+                Some(package) => package.name.to_string(),
+                None => manifest.path.display().to_string(),
+            };
+            let output = match self.format {
+                TreeFormat::Dot => graph.to_dot(Some(&project)),
+                _ => graph.to_mermaid(Some(&project)),
+            };
+            print!("{output}");
+            return Ok(());
+        }
+        let lock = LockFile::load(&LockFile::path_for(manifest.root_dir()))?;
+
+        match &manifest.package {
+            Some(package) => match &package.version {
+                Some(version) => println!("{}@{version}", package.name),
+                None => println!("{}", package.name),
+            },
+            None => println!("{}", manifest.path.display()),
+        }
+        let color = color_enabled();
+        let count = manifest.dependencies.len();
+        for (idx, (package, dependency)) in manifest.dependencies.iter().enumerate() {
+            let branch = if idx + 1 == count {
+                "└──"
+            } else {
+                "├──"
+            };
+            let locked = lock
+                .as_ref()
+                .and_then(|lock| lock.get(package))
+                .filter(|locked| dependency.version.matches(&locked.version));
+            match locked {
+                Some(locked) => println!(
+                    "{branch} {package} {} -> {}",
+                    dependency.version,
+                    Color::Green.paint(&locked.version, color)
+                ),
+                None => println!(
+                    "{branch} {package} {} {}",
+                    dependency.version,
+                    Color::Yellow.paint("(not locked)", color)
+                ),
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,178 @@
+//! `wkg metadata`: a JSON description of the current project, its resolved
+//! dependency graph, and where its files live, as a stable integration point
+//! for IDEs and build systems (like `cargo metadata`).
+//!
+//! The document's `format_version` is incremented on incompatible changes.
+
+use std::collections::BTreeSet;
+
+use clap::Args;
+use serde_json::{json, Value};
+use wasm_pkg_loader::{ClientConfig, DependencyGraph};
+
+use crate::{
+    lock::LockFile,
+    manifest::Manifest,
+    project::{lock_matches, resolve_graph},
+    GlobalArgs,
+};
+
+const FORMAT_VERSION: u32 = 1;
+
+#[derive(Args, Debug)]
+pub struct MetadataCommand {
+    /// Don't resolve the dependency graph, which may contact registries;
+    /// `resolve` and the lock file's `up_to_date` are null.
+    #[arg(long)]
+    no_resolve: bool,
+}
+
+impl MetadataCommand {
+    pub async fn run(self, global: &GlobalArgs) -> anyhow::Result<()> {
+        let manifest = Manifest::load_current()?;
+        let lock_path = LockFile::path_for(manifest.root_dir());
+        let lock = LockFile::load(&lock_path)?;
+        let config = global.load_config()?;
+        let graph = if self.no_resolve {
+            None
+        } else {
+            Some(resolve_graph(global, &manifest, lock.as_ref()).await?)
+        };
+
+        let metadata = json!({
+            "format_version": FORMAT_VERSION,
+            "root_dir": manifest.root_dir(),
+            "manifest": manifest_json(&manifest),
+            "lock_file": {
+                "path": lock_path,
+                "exists": lock.is_some(),
+                "up_to_date": graph.as_ref().map(|graph| {
+                    lock.as_ref().is_some_and(|lock| lock_matches(lock, graph))
+                }),
+            },
+            "resolve": graph.as_ref().map(graph_json),
+            "registries": registries_json(&config, &manifest, graph.as_ref()),
+            "paths": {
+                "config_file": global
+                    .config_file
+                    .clone()
+                    .or_else(ClientConfig::default_config_path),
+                "cache_dir": config
+                    .cache_dir()
+                    .map(ToOwned::to_owned)
+                    .or_else(ClientConfig::default_cache_dir),
+            },
+        });
+        println!("{}", serde_json::to_string_pretty(&metadata)?);
+        Ok(())
+    }
+}
+
+fn manifest_json(manifest: &Manifest) -> Value {
+    json!({
+        "path": manifest.path,
+        "package": manifest.package.as_ref().map(|package| json!({
+            "name": package.name.to_string(),
+            "version": package.version.as_ref().map(ToString::to_string),
+        })),
+        "dependencies": manifest
+            .dependencies
+            .iter()
+            .map(|(package, dependency)| json!({
+                "package": package.to_string(),
+                "requirement": dependency.version.to_string(),
+                "registry": dependency.registry,
+            }))
+            .collect::<Vec<_>>(),
+    })
+}
+
+fn graph_json(graph: &DependencyGraph) -> Value {
+    let node_id = |index: usize| {
+        let node = &graph.nodes()[index];
+        format!("{}@{}", node.package, node.version)
+    };
+    json!({
+        "roots": graph.roots().iter().map(|&index| node_id(index)).collect::<Vec<_>>(),
+        "packages": graph
+            .nodes()
+            .iter()
+            .enumerate()
+            .map(|(index, node)| json!({
+                "id": node_id(index),
+                "package": node.package.to_string(),
+                "version": node.version.to_string(),
+                "digest": node.content_digest.to_string(),
+                "registry": node.registry,
+                "dependencies": graph
+                    .dependencies(index)
+                    .map(|edge| json!({
+                        "id": node_id(edge.to),
+                        "requirement": edge.req.to_string(),
+                    }))
+                    .collect::<Vec<_>>(),
+            }))
+            .collect::<Vec<_>>(),
+    })
+}
+
+/// Describes the registries the project's dependencies come from, with the
+/// type of each configured one; others are discovered from the registry.
+fn registries_json(
+    config: &ClientConfig,
+    manifest: &Manifest,
+    graph: Option<&DependencyGraph>,
+) -> Value {
+    let registries: BTreeSet<&str> = manifest
+        .dependencies
+        .values()
+        .filter_map(|dependency| dependency.registry.as_deref())
+        .chain(
+            graph
+                .into_iter()
+                .flat_map(|graph| graph.nodes())
+                .filter_map(|node| node.registry.as_deref()),
+        )
+        .collect();
+    registries
+        .into_iter()
+        .map(|registry| {
+            let registry_type = config
+                .registry_configs()
+                .find(|(name, _)| *name == registry)
+                .map(|(_, registry_config)| registry_config.type_name());
+            json!({ "name": registry, "type": registry_type })
+        })
+        .collect::<Vec<_>>()
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::Dependency;
+
+    #[test]
+    fn test_manifest_json() {
+        let mut manifest = Manifest {
+            path: "wkg.toml".into(),
+            package: None,
+            dependencies: Default::default(),
+        };
+        manifest.dependencies.insert(
+            "wasi:http".parse().unwrap(),
+            Dependency {
+                version: "^0.2".parse().unwrap(),
+                registry: Some("wasi.dev".into()),
+            },
+        );
+
+        let json = manifest_json(&manifest);
+        assert_eq!(json["package"], Value::Null);
+        assert_eq!(json["dependencies"][0]["package"], "wasi:http");
+        assert_eq!(json["dependencies"][0]["requirement"], "^0.2");
+
+        let registries = registries_json(&ClientConfig::default(), &manifest, None);
+        assert_eq!(registries, json!([{ "name": "wasi.dev", "type": null }]));
+    }
+}
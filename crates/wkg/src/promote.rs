@@ -0,0 +1,315 @@
+//! `wkg promote`: promotes a release between channels and/or registries in
+//! a release pipeline, e.g. from `canary` to `stable` or from a staging
+//! registry to production.
+//!
+//! The release is verified in its source first (its digest, provenance,
+//! and the org policy), then copied to the target registry if given, and
+//! only then is the target channel tag moved, so consumers of the channel
+//! never see a release that isn't fully in place. A record of the promotion
+//! is written, signed with a key from `wkg keygen`.
+
+use std::{
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{bail, ensure, Context};
+use clap::Args;
+use futures_util::TryStreamExt;
+use serde_json::json;
+use warg_crypto::signing::PrivateKey;
+use wasm_pkg_loader::{ContentDigest, PackageRef, ProvenancePolicy, Release};
+
+use crate::{
+    archive,
+    copy::{copy_release, registry_client},
+    keys::KeyStore,
+    package_spec::{suggest_not_found, PackageSpec},
+    quarantine, GlobalArgs,
+};
+
+const RECORD_VERSION: u32 = 1;
+
+#[derive(Args, Debug)]
+pub struct PromoteCommand {
+    /// The release to promote, as <namespace>:<name>@<version> or
+    /// <namespace>:<name>@<tag> for the release a channel tag points at.
+    package_spec: PackageSpec,
+
+    /// The registry domain (or alias) to promote from. Defaults to the
+    /// package's configured registry.
+    #[arg(long, value_name = "DOMAIN")]
+    from: Option<String>,
+
+    /// The registry domain (or alias) to copy the release to. Without this,
+    /// the release stays in its source registry.
+    #[arg(long, value_name = "DOMAIN", required_unless_present = "to_tag")]
+    to: Option<String>,
+
+    /// The channel tag (e.g. "stable") to point at the release in the
+    /// target registry.
+    #[arg(long, value_name = "TAG")]
+    to_tag: Option<String>,
+
+    /// The expected content digest of the release; promotion fails if the
+    /// source's differs.
+    #[arg(long, value_name = "DIGEST")]
+    digest: Option<ContentDigest>,
+
+    /// Require a provenance attestation for the release, from one of the
+    /// configured provenance builders if any.
+    #[arg(long)]
+    require_provenance: bool,
+
+    /// Skip checking that the content (unless an archive) is valid Wasm.
+    #[arg(long)]
+    no_validate: bool,
+
+    /// The path to write the promotion record to. Its signature is written
+    /// alongside, with a `.sig` extension appended.
+    #[arg(long, value_name = "PATH")]
+    record: PathBuf,
+
+    /// The name of the key to sign the record with, as created by `wkg
+    /// keygen`.
+    #[arg(long, default_value = "default")]
+    key: String,
+
+    /// Don't sign the promotion record.
+    #[arg(long, conflicts_with = "key")]
+    unsigned: bool,
+}
+
+impl PromoteCommand {
+    pub async fn run(self, global: &GlobalArgs) -> anyhow::Result<()> {
+        let PackageSpec {
+            package,
+            version,
+            tag,
+            digest,
+        } = self.package_spec;
+        if let Some(digest) = digest {
+            bail!("{package}@{digest}: content digests are only supported by `wkg get`; specify a version or tag");
+        }
+        let policy = global.load_policy()?;
+        policy.check_package(&package)?;
+        // Load the key up front rather than failing after promoting
+        let key = if self.unsigned {
+            None
+        } else {
+            Some(KeyStore::default_store()?.load(&self.key)?)
+        };
+        let mut source = registry_client(global, &package, self.from.as_deref())?;
+
+        let version = match (version, &tag) {
+            (Some(version), _) => version,
+            (None, Some(tag)) => {
+                let version = source
+                    .resolve_tag(&package, tag)
+                    .await
+                    .with_context(|| format!("Failed to resolve {package}@{tag}"))?;
+                println!("Resolved {package}@{tag} to {version}");
+                version
+            }
+            (None, None) => {
+                bail!("specify the release to promote, e.g. {package}@1.0.0 or {package}@canary")
+            }
+        };
+        let result = source.get_release(&package, &version).await;
+        let release = suggest_not_found(result, &mut source, &package, Some(&version))
+            .await
+            .context("Failed to get release details")?;
+        if let Some(expected) = &self.digest {
+            ensure!(
+                release.content_digest == *expected,
+                "{package}@{version} has digest {}, not the expected {expected}",
+                release.content_digest
+            );
+        }
+
+        println!("Verifying {package}@{version}...");
+        let provenance = source
+            .provenance(&package, &release)
+            .await
+            .with_context(|| format!("Failed to get provenance for {package}@{version}"))?;
+        if self.require_provenance {
+            ProvenancePolicy {
+                require: true,
+                ..global.load_config()?.provenance_policy().clone()
+            }
+            .check(&package, &provenance)?;
+        }
+        // Content is verified against the release's digest as it's streamed
+        let content: Vec<u8> = source
+            .stream_content(&package, &release)
+            .await?
+            .map_ok(|chunk| chunk.to_vec())
+            .try_concat()
+            .await
+            .with_context(|| format!("Failed to fetch {package}@{version}"))?;
+        policy.check_content(&package, &version, &content)?;
+        if !self.no_validate && !archive::is_archive(&content) {
+            quarantine::validate_wasm(&content)
+                .with_context(|| format!("Failed to verify {package}@{version}"))?;
+        }
+
+        let (mut target, promoted) = match &self.to {
+            Some(to) => {
+                let mut dest = registry_client(global, &package, Some(to))?;
+                let promoted = copy_release(
+                    &policy,
+                    &mut source,
+                    &mut dest,
+                    &package,
+                    &release,
+                    to,
+                    !self.no_validate,
+                )
+                .await?;
+                (dest, promoted)
+            }
+            None => (source, release.clone()),
+        };
+        if let Some(to_tag) = &self.to_tag {
+            target
+                .set_tag(&package, to_tag, &version)
+                .await
+                .with_context(|| format!("Failed to tag {package}@{version} as {to_tag:?}"))?;
+            println!("Tagged {package}@{version} as {to_tag:?}");
+        }
+
+        let record = PromotionRecord {
+            package: &package,
+            release: &release,
+            promoted: &promoted,
+            from: (self.from.as_deref(), tag.as_deref()),
+            to: (
+                self.to.as_deref().or(self.from.as_deref()),
+                self.to_tag.as_deref(),
+            ),
+            builder_ids: provenance
+                .iter()
+                .filter_map(|provenance| provenance.builder_id.as_deref())
+                .collect(),
+            promoted_at: SystemTime::now(),
+        };
+        write_record(&self.record, &record.to_json()?, key.as_ref())?;
+        println!(
+            "Promoted {package}@{version}; wrote promotion record '{}'",
+            self.record.display()
+        );
+        Ok(())
+    }
+}
+
+/// A record of a promotion. Registries are `None` for the package's
+/// configured registry.
+struct PromotionRecord<'a> {
+    package: &'a PackageRef,
+    /// The release in the source registry.
+    release: &'a Release,
+    /// The release in the target registry.
+    promoted: &'a Release,
+    /// The source registry and channel tag, if any.
+    from: (Option<&'a str>, Option<&'a str>),
+    /// The target registry and channel tag, if any.
+    to: (Option<&'a str>, Option<&'a str>),
+    /// The builders named by the release's provenance attestations.
+    builder_ids: Vec<&'a str>,
+    promoted_at: SystemTime,
+}
+
+impl PromotionRecord<'_> {
+    fn to_json(&self) -> anyhow::Result<Vec<u8>> {
+        let secs = self
+            .promoted_at
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let record = json!({
+            "version": RECORD_VERSION,
+            "package": self.package.to_string(),
+            "release": self.release.version.to_string(),
+            "from": {
+                "registry": self.from.0,
+                "tag": self.from.1,
+                "digest": self.release.content_digest.to_string(),
+            },
+            "to": {
+                "registry": self.to.0,
+                "tag": self.to.1,
+                "digest": self.promoted.content_digest.to_string(),
+            },
+            "provenance_builders": self.builder_ids,
+            "promoted_at": secs,
+        });
+        let mut json = serde_json::to_vec_pretty(&record)?;
+        json.push(b'\n');
+        Ok(json)
+    }
+}
+
+/// Writes the record to `path`, and its signature, if signed, to `path`
+/// with `.sig` appended.
+fn write_record(path: &Path, record: &[u8], key: Option<&PrivateKey>) -> anyhow::Result<()> {
+    std::fs::write(path, record).with_context(|| format!("Failed to write {path:?}"))?;
+    if let Some(key) = key {
+        let signature = key.sign(record)?;
+        let mut signature_path = path.as_os_str().to_owned();
+        signature_path.push(".sig");
+        let signature_path = PathBuf::from(signature_path);
+        std::fs::write(&signature_path, format!("{signature}\n"))
+            .with_context(|| format!("Failed to write {signature_path:?}"))?;
+        println!(
+            "Signed promotion record with key {}",
+            key.public_key().fingerprint()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use p256::ecdsa::SigningKey;
+    use warg_crypto::signing::Signature;
+
+    use super::*;
+
+    #[test]
+    fn test_write_record() {
+        let package: PackageRef = "my-org:gateway".parse().unwrap();
+        let release = Release {
+            version: "1.4.2".parse().unwrap(),
+            content_digest:
+                "sha256:4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b"
+                    .parse()
+                    .unwrap(),
+        };
+        let record = PromotionRecord {
+            package: &package,
+            release: &release,
+            promoted: &release,
+            from: (Some("staging.example.com"), Some("canary")),
+            to: (Some("example.com"), Some("stable")),
+            builder_ids: vec![],
+            promoted_at: UNIX_EPOCH,
+        };
+        let json = record.to_json().unwrap();
+        let value: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(value["release"], "1.4.2");
+        assert_eq!(value["from"]["tag"], "canary");
+        assert_eq!(value["to"]["registry"], "example.com");
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("promotion.json");
+        let key = PrivateKey::from(SigningKey::from_slice(&[1; 32]).unwrap());
+        write_record(&path, &json, Some(&key)).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), json);
+        let signature: Signature = std::fs::read_to_string(dir.path().join("promotion.json.sig"))
+            .unwrap()
+            .trim()
+            .parse()
+            .unwrap();
+        assert!(key.public_key().verify(&json, &signature).is_ok());
+    }
+}
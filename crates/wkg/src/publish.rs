@@ -0,0 +1,213 @@
+//! `wkg publish`: publishing packages, including encoding WIT source
+//! directories into binary WIT packages.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context};
+use clap::Args;
+use wasm_pkg_loader::{PackageRef, Version};
+use wit_component::DecodedWasm;
+use wit_parser::{PackageId, Resolve};
+
+use crate::{
+    archive, metadata::PackageMetadata, package_spec::PackageSpec, GlobalArgs, RegistryArgs,
+};
+
+#[derive(Args, Debug)]
+pub struct PublishCommand {
+    /// The content to publish: a WIT source directory or file, which is
+    /// encoded as a binary WIT package, or a `.wasm` or `.tar.gz` file.
+    path: PathBuf,
+
+    /// Publish the directory as a gzipped tar archive of its files rather
+    /// than encoding it as a binary WIT package.
+    #[arg(long)]
+    archive: bool,
+
+    /// Embed the package name and version in the content as a custom
+    /// section, which `wkg inspect --metadata` reads back.
+    #[arg(long, conflicts_with = "archive")]
+    embed_metadata: bool,
+
+    /// The source repository to record in embedded metadata.
+    #[arg(long, requires = "embed_metadata")]
+    source_repo: Option<String>,
+
+    /// Build details (e.g. a CI run or commit) to record in embedded
+    /// metadata.
+    #[arg(long, requires = "embed_metadata")]
+    build_info: Option<String>,
+
+    /// The package and version to publish as, e.g. "my-ns:pkg@1.0.0".
+    /// Defaults to the package declared by WIT content.
+    #[arg(long)]
+    package: Option<PackageSpec>,
+
+    /// Write the content (e.g. the encoded WIT package) to this path instead
+    /// of publishing it.
+    #[arg(long, short)]
+    output: Option<PathBuf>,
+
+    /// Sign the published release with GnuPG and attach the OpenPGP
+    /// signature, using the given key ID (or else gpg's default key).
+    #[arg(
+        long,
+        value_name = "KEY_ID",
+        num_args = 0..=1,
+        default_missing_value = "",
+        conflicts_with = "output"
+    )]
+    gpg_sign: Option<String>,
+
+    #[command(flatten)]
+    registry: RegistryArgs,
+}
+
+impl PublishCommand {
+    pub async fn run(self, global: &GlobalArgs) -> anyhow::Result<()> {
+        let (mut content, declared) = if self.archive {
+            pack_archive(&self.path)?
+        } else {
+            load_content(&self.path)?
+        };
+        let (package, version) = match (self.package, declared) {
+            (Some(spec), declared) => {
+                let (package, version) = spec.into_versioned()?;
+                let version = version.or(declared.and_then(|(_, version)| version));
+                (package, version)
+            }
+            (None, Some((package, version))) => (package, version),
+            (None, None) => bail!(
+                "{:?} doesn't declare a package; specify one with '--package'",
+                self.path
+            ),
+        };
+        let Some(version) = version else {
+            bail!("no version given for {package}; declare one in WIT (e.g. `package {package}@1.0.0;`) or specify one with '--package'");
+        };
+
+        if self.embed_metadata {
+            let metadata = PackageMetadata {
+                package: package.to_string(),
+                version: version.to_string(),
+                source: self.source_repo,
+                build: self.build_info,
+            };
+            content = metadata
+                .embed(&content)
+                .context("Failed to embed package metadata")?;
+        }
+
+        if let Some(path) = self.output {
+            std::fs::write(&path, &content).with_context(|| format!("Failed to write {path:?}"))?;
+            println!("Wrote {package}@{version} to '{}'", path.display());
+            return Ok(());
+        }
+
+        let mut client = {
+            let mut config = global.load_config()?;
+            if let Some(registry) = self.registry.domain {
+                config.set_namespace_registry(package.namespace().to_string(), registry);
+            }
+            config.to_client()
+        };
+        println!("Publishing {package}@{version}...");
+        let release = client
+            .publish(&package, &version, content.into())
+            .await
+            .with_context(|| format!("Failed to publish {package}@{version}"))?;
+        println!("Published {package}@{version} ({})", release.content_digest);
+        if let Some(key) = &self.gpg_sign {
+            let key = Some(key.as_str()).filter(|key| !key.is_empty());
+            client
+                .sign_openpgp(&package, &release, key)
+                .await
+                .with_context(|| format!("Failed to sign {package}@{version}"))?;
+            println!("Signed {package}@{version} with OpenPGP");
+        }
+        Ok(())
+    }
+}
+
+/// The package name and version (if any) declared by content.
+pub(crate) type Declared = Option<(PackageRef, Option<Version>)>;
+
+/// Loads the content to publish from the given path, encoding WIT sources,
+/// along with the package it declares, if any.
+pub(crate) fn load_content(path: &Path) -> anyhow::Result<(Vec<u8>, Declared)> {
+    if path.is_dir() || path.extension().is_some_and(|ext| ext == "wit") {
+        let mut resolve = Resolve::default();
+        let pkg = if path.is_dir() {
+            resolve.push_dir(path)?.0
+        } else {
+            resolve.push_file(path)?
+        };
+        let content = wit_component::encode(Some(true), &resolve, pkg)
+            .with_context(|| format!("Failed to encode WIT package from {path:?}"))?;
+        let declared = declared_package(&resolve, pkg)?;
+        return Ok((content, Some(declared)));
+    }
+
+    let content = std::fs::read(path).with_context(|| format!("Failed to read {path:?}"))?;
+    let declared = match wit_component::decode(&content) {
+        Ok(DecodedWasm::WitPackage(resolve, pkg)) => Some(declared_package(&resolve, pkg)?),
+        _ => None,
+    };
+    Ok((content, declared))
+}
+
+/// Packs the given directory as a gzipped tar archive, along with the
+/// package declared by any WIT sources it contains.
+fn pack_archive(path: &Path) -> anyhow::Result<(Vec<u8>, Declared)> {
+    if !path.is_dir() {
+        bail!("{path:?} is not a directory; '--archive' requires one");
+    }
+    let content = archive::pack(path)?;
+    let mut resolve = Resolve::default();
+    let declared = match resolve.push_dir(path) {
+        Ok((pkg, _)) => Some(declared_package(&resolve, pkg)?),
+        Err(err) => {
+            tracing::debug!(?err, "no WIT package declared");
+            None
+        }
+    };
+    Ok((content, declared))
+}
+
+fn declared_package(
+    resolve: &Resolve,
+    pkg: PackageId,
+) -> anyhow::Result<(PackageRef, Option<Version>)> {
+    let name = &resolve.packages[pkg].name;
+    let package = format!("{}:{}", name.namespace, name.name)
+        .parse()
+        .with_context(|| format!("Invalid package name {name}"))?;
+    Ok((package, name.version.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_content() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("api.wit"),
+            "package test:pkg@1.2.3;\n\ninterface api {\n  get: func() -> u32;\n}\n",
+        )
+        .unwrap();
+
+        let (content, declared) = load_content(dir.path()).unwrap();
+        let (package, version) = declared.unwrap();
+        assert_eq!(package.to_string(), "test:pkg");
+        assert_eq!(version.unwrap().to_string(), "1.2.3");
+
+        // Encoded content declares the same package
+        let encoded = dir.path().join("pkg.wasm");
+        std::fs::write(&encoded, &content).unwrap();
+        let (reloaded, declared) = load_content(&encoded).unwrap();
+        assert_eq!(reloaded, content);
+        assert_eq!(declared.unwrap().0, package);
+    }
+}
@@ -0,0 +1,218 @@
+//! Quarantine (staging) of fetched content. With `--quarantine-dir`, content
+//! is downloaded into the quarantine directory and only promoted to its
+//! output path once every verification step (digest, provenance, policy,
+//! and Wasm validation) has passed, so builds never see partially-verified
+//! artifacts.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use tempfile::{NamedTempFile, TempPath};
+use wasmparser::{
+    Chunk, FuncValidatorAllocations, Parser, Payload, ValidPayload, Validator, WasmFeatures,
+};
+
+use crate::archive;
+
+pub struct Quarantine {
+    dir: PathBuf,
+}
+
+impl Quarantine {
+    /// Opens the quarantine directory at the given path, creating it if
+    /// needed.
+    pub fn open(dir: impl Into<PathBuf>) -> anyhow::Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create quarantine dir {dir:?}"))?;
+        Ok(Self { dir })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Writes the given contents to a new file in quarantine.
+    pub fn stage(&self, contents: &[u8]) -> anyhow::Result<TempPath> {
+        let file = NamedTempFile::with_prefix_in(".wkg-quarantine", &self.dir)
+            .with_context(|| format!("Failed to create file in {:?}", self.dir))?;
+        std::fs::write(file.path(), contents)
+            .with_context(|| format!("Failed to write {:?}", file.path()))?;
+        Ok(file.into_temp_path())
+    }
+}
+
+/// The error context added to content that fails Wasm validation.
+#[derive(Debug)]
+pub struct ValidationFailed(pub String);
+
+impl std::fmt::Display for ValidationFailed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Fails if the given content isn't a valid Wasm module or component.
+pub fn validate_wasm(content: &[u8]) -> anyhow::Result<()> {
+    Validator::new_with_features(WasmFeatures::all())
+        .validate_all(content)
+        .context(ValidationFailed("Invalid Wasm content".into()))?;
+    Ok(())
+}
+
+/// Validates Wasm content incrementally as it's downloaded, so it needn't be
+/// read again to validate it. Archives aren't Wasm and aren't validated;
+/// their contents are checked on unpacking.
+pub struct StreamingValidator {
+    validator: Validator,
+    /// The parsers of the (possibly nested) modules and components being
+    /// parsed, innermost last.
+    parsers: Vec<Parser>,
+    /// Content received but not yet parsed.
+    buffer: Vec<u8>,
+    allocs: FuncValidatorAllocations,
+    started: bool,
+    done: bool,
+}
+
+impl Default for StreamingValidator {
+    fn default() -> Self {
+        Self {
+            validator: Validator::new_with_features(WasmFeatures::all()),
+            parsers: vec![Parser::new(0)],
+            buffer: vec![],
+            allocs: Default::default(),
+            started: false,
+            done: false,
+        }
+    }
+}
+
+impl StreamingValidator {
+    /// Validates as much of the content as possible with the given chunk.
+    pub fn update(&mut self, chunk: &[u8]) -> anyhow::Result<()> {
+        if self.done {
+            return Ok(());
+        }
+        self.buffer.extend_from_slice(chunk);
+        self.parse(false).context("Invalid Wasm content")
+    }
+
+    /// Fails unless the content received was complete, valid Wasm.
+    pub fn finish(mut self) -> anyhow::Result<()> {
+        self.parse(true).context("Invalid Wasm content")
+    }
+
+    fn parse(&mut self, eof: bool) -> anyhow::Result<()> {
+        if !self.started {
+            if self.buffer.len() < 8 && !eof {
+                return Ok(());
+            }
+            self.started = true;
+            if archive::is_archive(&self.buffer) {
+                tracing::debug!("Content is an archive; not validating it as Wasm");
+                self.done = true;
+                self.buffer = vec![];
+                return Ok(());
+            }
+        }
+        let Self {
+            validator,
+            parsers,
+            buffer,
+            allocs,
+            done,
+            ..
+        } = self;
+        let mut data: &[u8] = buffer;
+        while !*done {
+            let parser = parsers
+                .last_mut()
+                .expect("parsers can't be empty before the end");
+            let (payload, consumed) = match parser.parse(data, eof)? {
+                Chunk::NeedMoreData(_) => break,
+                Chunk::Parsed { payload, consumed } => (payload, consumed),
+            };
+            data = &data[consumed..];
+            match &payload {
+                Payload::ModuleSection { parser, .. }
+                | Payload::ComponentSection { parser, .. } => parsers.push(parser.clone()),
+                Payload::End(_) => {
+                    parsers.pop();
+                    *done = parsers.is_empty();
+                }
+                _ => (),
+            }
+            // Function bodies are validated as they arrive rather than after
+            // the whole module, as `Validator::validate_all` does
+            if let ValidPayload::Func(func, body) = validator.payload(&payload)? {
+                let mut func = func.into_validator(std::mem::take(allocs));
+                func.validate(&body)?;
+                *allocs = func.into_allocations();
+            }
+        }
+        let consumed = buffer.len() - data.len();
+        buffer.drain(..consumed);
+        Ok(())
+    }
+}
+
+/// Moves a staged file to the given output path. Nothing appears at the
+/// output path until its contents are complete, even if the staged file is
+/// on another filesystem.
+pub fn promote(staged: TempPath, output: &Path) -> anyhow::Result<()> {
+    let staged = match staged.persist(output) {
+        Ok(()) => return Ok(()),
+        Err(err) => {
+            // Most likely a rename across filesystems; copy next to the
+            // output and rename from there instead.
+            tracing::debug!(?err, "Failed to move staged file; copying");
+            err.path
+        }
+    };
+    let parent = match output.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let copy = NamedTempFile::with_prefix_in(".wkg-promote", parent)
+        .with_context(|| format!("Failed to create file in {parent:?}"))?
+        .into_temp_path();
+    std::fs::copy(&staged, &copy).with_context(|| format!("Failed to copy {staged:?}"))?;
+    copy.persist(output)
+        .with_context(|| format!("Failed to write {output:?}"))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stage_and_promote() {
+        let quarantine_dir = tempfile::tempdir().unwrap();
+        let output_dir = tempfile::tempdir().unwrap();
+        let quarantine = Quarantine::open(quarantine_dir.path().join("q")).unwrap();
+
+        let empty_module = b"\0asm\x01\0\0\0";
+        validate_wasm(empty_module).unwrap();
+        assert!(validate_wasm(b"not wasm").is_err());
+
+        let mut validator = StreamingValidator::default();
+        for byte in empty_module {
+            validator.update(&[*byte]).unwrap();
+        }
+        validator.finish().unwrap();
+        let mut validator = StreamingValidator::default();
+        validator.update(&empty_module[..6]).unwrap();
+        assert!(validator.finish().is_err());
+        let mut validator = StreamingValidator::default();
+        validator.update(b"\x1f\x8b not wasm").unwrap();
+        validator.finish().unwrap();
+
+        let staged = quarantine.stage(empty_module).unwrap();
+        let output = output_dir.path().join("out.wasm");
+        promote(staged, &output).unwrap();
+        assert_eq!(std::fs::read(&output).unwrap(), empty_module);
+        assert_eq!(std::fs::read_dir(quarantine.dir()).unwrap().count(), 0);
+    }
+}
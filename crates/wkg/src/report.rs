@@ -0,0 +1,151 @@
+//! The `--report` run report: a JSON file listing each package a command
+//! fetched, with where it came from and how long it took, e.g. to keep as a
+//! CI artifact.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use anyhow::Context;
+use serde::Serialize;
+use wasm_pkg_loader::{Client, PackageRef, Release};
+
+use crate::error_code::ErrorCode;
+
+/// The packages fetched by a command, written as a run report.
+#[derive(Debug)]
+pub struct RunReport {
+    started: Instant,
+    packages: Mutex<Vec<ReportedPackage>>,
+}
+
+impl Default for RunReport {
+    fn default() -> Self {
+        Self {
+            started: Instant::now(),
+            packages: Default::default(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct ReportedPackage {
+    package: String,
+    version: String,
+    digest: String,
+    /// None if the package is overridden by a local path.
+    registry: Option<String>,
+    /// Whether the content was read from the local cache rather than
+    /// downloaded.
+    cache_hit: bool,
+    bytes: u64,
+    duration_ms: u64,
+    output: Option<PathBuf>,
+}
+
+/// A fetch in progress, started by [`RunReport::start`].
+pub struct Fetch {
+    package: ReportedPackage,
+    started: Instant,
+}
+
+impl RunReport {
+    /// Starts timing the fetch of the given release, expected to be read
+    /// from the local cache if `cache_hit` is set.
+    pub fn start(
+        &self,
+        client: &Client,
+        package: &PackageRef,
+        release: &Release,
+        cache_hit: bool,
+    ) -> anyhow::Result<Fetch> {
+        Ok(Fetch {
+            package: ReportedPackage {
+                package: package.to_string(),
+                version: release.version.to_string(),
+                digest: release.content_digest.to_string(),
+                registry: client.package_registry(package)?.map(ToString::to_string),
+                cache_hit,
+                bytes: 0,
+                duration_ms: 0,
+                output: None,
+            },
+            started: Instant::now(),
+        })
+    }
+
+    /// Records a finished fetch of `bytes` of content, written to the given
+    /// output path.
+    pub fn record(&self, fetch: Fetch, bytes: u64, output: Option<&Path>) {
+        let mut package = fetch.package;
+        package.bytes = bytes;
+        package.duration_ms = duration_ms(fetch.started.elapsed());
+        package.output = output.map(Path::to_path_buf);
+        self.packages.lock().unwrap().push(package);
+    }
+
+    /// Writes the report to the given path.
+    pub fn write(&self, path: &Path, error: Option<&anyhow::Error>) -> anyhow::Result<()> {
+        let json = serde_json::to_string_pretty(&self.render(error))?;
+        std::fs::write(path, json + "\n")
+            .with_context(|| format!("Failed to write report {path:?}"))
+    }
+
+    fn render(&self, error: Option<&anyhow::Error>) -> serde_json::Value {
+        let packages = self.packages.lock().unwrap();
+        serde_json::json!({
+            "success": error.is_none(),
+            "error": error.map(|err| serde_json::json!({
+                "code": ErrorCode::of(err).name(),
+                "message": format!("{err:#}"),
+            })),
+            "duration_ms": duration_ms(self.started.elapsed()),
+            "bytes": packages.iter().map(|package| package.bytes).sum::<u64>(),
+            "packages": *packages,
+        })
+    }
+}
+
+fn duration_ms(duration: Duration) -> u64 {
+    duration.as_millis().try_into().unwrap_or(u64::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let report = RunReport::default();
+        let fetch = Fetch {
+            package: ReportedPackage {
+                package: "wasi:http".into(),
+                version: "0.2.0".into(),
+                digest: format!("sha256:{}", "0".repeat(64)),
+                registry: Some("wasi.dev".into()),
+                cache_hit: true,
+                bytes: 0,
+                duration_ms: 0,
+                output: None,
+            },
+            started: Instant::now(),
+        };
+        report.record(fetch, 1024, Some(Path::new("deps/wasi-http.wit")));
+
+        let err = anyhow::Error::new(wasm_pkg_loader::Error::VersionNotFound(
+            "0.1.0".parse().unwrap(),
+        ))
+        .context("Failed to get");
+        let rendered = report.render(Some(&err));
+        assert_eq!(rendered["success"], false);
+        assert_eq!(rendered["error"]["code"], "not-found");
+        assert_eq!(rendered["bytes"], 1024);
+        let package = &rendered["packages"][0];
+        assert_eq!(package["package"], "wasi:http");
+        assert_eq!(package["registry"], "wasi.dev");
+        assert_eq!(package["cache_hit"], true);
+        assert_eq!(package["output"], "deps/wasi-http.wit");
+    }
+}
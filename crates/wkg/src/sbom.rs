@@ -0,0 +1,292 @@
+//! Software bill of materials (SBOM) generation for a project's locked
+//! dependency graph, in CycloneDX or SPDX JSON format.
+
+use std::{
+    io::Write,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Context;
+use clap::{Args, ValueEnum};
+use serde_json::{json, Value};
+use wasm_pkg_loader::{ContentDigest, DependencyGraph, GraphNode};
+
+use crate::{manifest::Manifest, project::locked_graph, GlobalArgs};
+
+#[derive(Args, Debug)]
+pub struct SbomCommand {
+    /// The SBOM format.
+    #[arg(long, value_enum, default_value = "cyclonedx")]
+    format: SbomFormat,
+
+    /// Output path. Defaults to stdout.
+    #[arg(long, short)]
+    output: Option<PathBuf>,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum SbomFormat {
+    /// CycloneDX 1.5 JSON
+    Cyclonedx,
+    /// SPDX 2.3 JSON
+    Spdx,
+}
+
+impl SbomCommand {
+    pub async fn run(self, global: &GlobalArgs) -> anyhow::Result<()> {
+        let manifest = Manifest::load_current()?;
+        let graph = locked_graph(global, &manifest).await?;
+
+        let root = RootComponent::new(&manifest);
+        let sbom = match self.format {
+            SbomFormat::Cyclonedx => cyclonedx(&root, &graph),
+            SbomFormat::Spdx => spdx(&root, &graph, SystemTime::now()),
+        };
+        let mut json = serde_json::to_string_pretty(&sbom)?;
+        json.push('\n');
+        match &self.output {
+            Some(path) => {
+                std::fs::write(path, json).with_context(|| format!("Failed to write {path:?}"))?
+            }
+            None => std::io::stdout().write_all(json.as_bytes())?,
+        }
+        Ok(())
+    }
+}
+
+/// The project described by an SBOM.
+struct RootComponent {
+    name: String,
+    version: Option<String>,
+}
+
+impl RootComponent {
+    fn new(manifest: &Manifest) -> Self {
+        match &manifest.package {
+            Some(package) => Self {
+                name: package.name.to_string(),
+                version: package.version.as_ref().map(ToString::to_string),
+            },
+            None => Self {
+                name: manifest
+                    .root_dir()
+                    .canonicalize()
+                    .ok()
+                    .and_then(|dir| Some(dir.file_name()?.to_string_lossy().into_owned()))
+                    .unwrap_or_else(|| "unnamed".into()),
+                version: None,
+            },
+        }
+    }
+}
+
+const ROOT_REF: &str = "root";
+
+fn node_ref(node: &GraphNode) -> String {
+    format!("{}@{}", node.package, node.version)
+}
+
+fn sha256_hex(digest: &ContentDigest) -> &str {
+    match digest {
+        ContentDigest::Sha256 { hex } => hex,
+    }
+}
+
+/// Returns the refs of the given node's dependencies, or the roots'.
+fn depends_on(graph: &DependencyGraph, index: Option<usize>) -> Vec<String> {
+    let targets: Vec<usize> = match index {
+        Some(index) => graph.dependencies(index).map(|edge| edge.to).collect(),
+        None => graph.roots().to_vec(),
+    };
+    targets
+        .into_iter()
+        .map(|to| node_ref(&graph.nodes()[to]))
+        .collect()
+}
+
+fn cyclonedx(root: &RootComponent, graph: &DependencyGraph) -> Value {
+    let components: Vec<_> = graph
+        .nodes()
+        .iter()
+        .map(|node| {
+            let mut component = json!({
+                "type": "library",
+                "bom-ref": node_ref(node),
+                "group": node.package.namespace().to_string(),
+                "name": node.package.name().to_string(),
+                "version": node.version.to_string(),
+                "hashes": [{
+                    "alg": "SHA-256",
+                    "content": sha256_hex(&node.content_digest),
+                }],
+            });
+            if let Some(registry) = &node.registry {
+                component["properties"] = json!([{
+                    "name": "wasm-pkg:registry",
+                    "value": registry,
+                }]);
+            }
+            component
+        })
+        .collect();
+    let mut dependencies = vec![json!({
+        "ref": ROOT_REF,
+        "dependsOn": depends_on(graph, None),
+    })];
+    dependencies.extend((0..graph.nodes().len()).map(|index| {
+        json!({
+            "ref": node_ref(&graph.nodes()[index]),
+            "dependsOn": depends_on(graph, Some(index)),
+        })
+    }));
+    let mut root_component = json!({
+        "type": "application",
+        "bom-ref": ROOT_REF,
+        "name": root.name,
+    });
+    if let Some(version) = &root.version {
+        root_component["version"] = json!(version);
+    }
+    json!({
+        "bomFormat": "CycloneDX",
+        "specVersion": "1.5",
+        "version": 1,
+        "metadata": {
+            "tools": {
+                "components": [{
+                    "type": "application",
+                    "name": "wkg",
+                    "version": env!("CARGO_PKG_VERSION"),
+                }],
+            },
+            "component": root_component,
+        },
+        "components": components,
+        "dependencies": dependencies,
+    })
+}
+
+/// Returns an SPDX element ID; these may only contain letters, numbers,
+/// `.`, and `-`.
+fn spdx_id(name: &str) -> String {
+    let sanitized: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    format!("SPDXRef-Package-{sanitized}")
+}
+
+fn spdx(root: &RootComponent, graph: &DependencyGraph, now: SystemTime) -> Value {
+    let root_id = "SPDXRef-Root".to_string();
+    let node_id = |index: usize| spdx_id(&node_ref(&graph.nodes()[index]));
+
+    let mut root_package = json!({
+        "SPDXID": root_id,
+        "name": root.name,
+        "downloadLocation": "NOASSERTION",
+        "filesAnalyzed": false,
+    });
+    if let Some(version) = &root.version {
+        root_package["versionInfo"] = json!(version);
+    }
+    let mut packages = vec![root_package];
+    packages.extend(graph.nodes().iter().enumerate().map(|(index, node)| {
+        json!({
+            "SPDXID": node_id(index),
+            "name": node.package.to_string(),
+            "versionInfo": node.version.to_string(),
+            "downloadLocation": node.registry.as_deref().unwrap_or("NOASSERTION"),
+            "filesAnalyzed": false,
+            "checksums": [{
+                "algorithm": "SHA256",
+                "checksumValue": sha256_hex(&node.content_digest),
+            }],
+        })
+    }));
+
+    let relationship = |from: &str, kind: &str, to: &str| {
+        json!({
+            "spdxElementId": from,
+            "relationshipType": kind,
+            "relatedSpdxElement": to,
+        })
+    };
+    let mut relationships = vec![relationship("SPDXRef-DOCUMENT", "DESCRIBES", &root_id)];
+    relationships.extend(
+        graph
+            .roots()
+            .iter()
+            .map(|&to| relationship(&root_id, "DEPENDS_ON", &node_id(to))),
+    );
+    relationships.extend(
+        graph
+            .edges()
+            .iter()
+            .map(|edge| relationship(&node_id(edge.from), "DEPENDS_ON", &node_id(edge.to))),
+    );
+
+    let secs = now.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    let namespace = format!(
+        "https://spdx.org/spdxdocs/{}-{secs}",
+        root.name.replace([':', '@'], "-")
+    );
+    json!({
+        "spdxVersion": "SPDX-2.3",
+        "dataLicense": "CC0-1.0",
+        "SPDXID": "SPDXRef-DOCUMENT",
+        "name": root.name,
+        "documentNamespace": namespace,
+        "creationInfo": {
+            "created": rfc3339(secs),
+            "creators": [format!("Tool: wkg-{}", env!("CARGO_PKG_VERSION"))],
+        },
+        "packages": packages,
+        "relationships": relationships,
+    })
+}
+
+/// Formats a Unix timestamp as an RFC 3339 UTC date-time.
+fn rfc3339(secs: u64) -> String {
+    let (days, secs_of_day) = (secs / 86400, secs % 86400);
+    // Civil date from days since the epoch; see
+    // https://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        secs_of_day / 3600,
+        secs_of_day / 60 % 60,
+        secs_of_day % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rfc3339() {
+        assert_eq!(rfc3339(0), "1970-01-01T00:00:00Z");
+        assert_eq!(rfc3339(951_782_400), "2000-02-29T00:00:00Z");
+        assert_eq!(rfc3339(1_718_454_645), "2024-06-15T12:30:45Z");
+    }
+
+    #[test]
+    fn test_spdx_id() {
+        assert_eq!(spdx_id("wasi:io@0.2.0"), "SPDXRef-Package-wasi-io-0.2.0");
+    }
+}
@@ -0,0 +1,350 @@
+//! `wkg semver-check`: compares two versions of a WIT package structurally
+//! and checks that the new version's number reflects the detected changes.
+
+use std::{collections::BTreeMap, fmt, path::Path};
+
+use anyhow::{bail, Context};
+use clap::Args;
+use serde_json::Value;
+use wasm_pkg_loader::Version;
+
+use crate::{package_spec::PackageSpec, publish, wit, GlobalArgs, RegistryArgs};
+
+#[derive(Args, Debug)]
+pub struct SemverCheckCommand {
+    /// The baseline version: a WIT source directory or file, an encoded WIT
+    /// package, or a registry package as <namespace>:<name>@<version>.
+    old: String,
+
+    /// The new version, in the same forms as the baseline.
+    new: String,
+
+    /// The version the new package will be published as. Defaults to the
+    /// version it declares (or was fetched as).
+    #[arg(long, value_name = "VERSION")]
+    version: Option<Version>,
+
+    #[command(flatten)]
+    registry: RegistryArgs,
+}
+
+/// How much of a version bump a set of changes requires.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    /// Nothing changed.
+    None,
+    /// Only documentation changed.
+    Patch,
+    /// Items were added.
+    Minor,
+    /// Items were removed or changed incompatibly.
+    Major,
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Level::None => "none",
+            Level::Patch => "patch",
+            Level::Minor => "minor",
+            Level::Major => "major",
+        })
+    }
+}
+
+/// A change to an item of a WIT package, e.g. `function api.get`.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Change {
+    Added(String),
+    Removed(String),
+    Changed(String),
+    Documented(String),
+}
+
+impl Change {
+    pub fn level(&self) -> Level {
+        match self {
+            Change::Removed(_) | Change::Changed(_) => Level::Major,
+            Change::Added(_) => Level::Minor,
+            Change::Documented(_) => Level::Patch,
+        }
+    }
+
+    fn kind(&self) -> &'static str {
+        match self {
+            Change::Added(_) => "added",
+            Change::Removed(_) => "removed",
+            Change::Changed(_) => "changed",
+            Change::Documented(_) => "documented",
+        }
+    }
+
+    fn item(&self) -> &str {
+        match self {
+            Change::Added(item)
+            | Change::Removed(item)
+            | Change::Changed(item)
+            | Change::Documented(item) => item,
+        }
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self {
+            Change::Added(_) => "Added",
+            Change::Removed(_) => "Removed",
+            Change::Changed(_) => "Changed",
+            Change::Documented(_) => "Docs changed",
+        };
+        write!(f, "{kind}: {}", self.item())
+    }
+}
+
+impl SemverCheckCommand {
+    pub async fn run(self, global: &GlobalArgs) -> anyhow::Result<()> {
+        let (old, old_version) = self.load(global, &self.old).await?;
+        let (new, new_version) = self.load(global, &self.new).await?;
+        let old_version =
+            old_version.with_context(|| format!("{} doesn't declare a version", self.old))?;
+        let new_version =
+            self.version.clone().or(new_version).with_context(|| {
+                format!("{} doesn't declare a version; pass --version", self.new)
+            })?;
+
+        let changes = compare(&old, &new);
+        let level = changes
+            .iter()
+            .map(Change::level)
+            .max()
+            .unwrap_or(Level::None);
+        let required = required_version(&old_version, level);
+        let sufficient = (new_version.major, new_version.minor, new_version.patch)
+            >= (required.major, required.minor, required.patch);
+
+        if global.json {
+            let changes: Vec<_> = changes
+                .iter()
+                .map(|change| {
+                    serde_json::json!({
+                        "change": change.kind(),
+                        "item": change.item(),
+                        "level": change.level().to_string(),
+                    })
+                })
+                .collect();
+            let json = serde_json::json!({
+                "old": old_version.to_string(),
+                "new": new_version.to_string(),
+                "level": level.to_string(),
+                "required": required.to_string(),
+                "sufficient": sufficient,
+                "changes": changes,
+            });
+            println!("{json}");
+        } else {
+            for change in &changes {
+                println!("{change}");
+            }
+            println!(
+                "Detected {level} changes; {old_version} -> {new_version} requires at least {required}"
+            );
+        }
+        if !sufficient {
+            bail!(
+                "{new_version} is too low for {level} changes from {old_version}; it must be at least {required}"
+            );
+        }
+        Ok(())
+    }
+
+    /// Loads a package from a local path or the registry, returning its
+    /// JSON description and version.
+    async fn load(
+        &self,
+        global: &GlobalArgs,
+        source: &str,
+    ) -> anyhow::Result<(Value, Option<Version>)> {
+        let path = Path::new(source);
+        let (content, fetched) = if path.exists() {
+            (publish::load_content(path)?.0, None)
+        } else {
+            let spec: PackageSpec = source
+                .parse()
+                .with_context(|| format!("{source} is neither a path nor a package spec"))?;
+            let registry = RegistryArgs {
+                domain: self.registry.domain.clone(),
+            };
+            let (_, version, content) = wit::fetch_package(global, spec, registry).await?;
+            (content, Some(version))
+        };
+        let (resolve, pkg) =
+            wit::decode_package(&content).with_context(|| format!("Failed to decode {source}"))?;
+        let version = resolve.packages[pkg].name.version.clone().or(fetched);
+        Ok((wit::package_json(&resolve, pkg), version))
+    }
+}
+
+/// Compares the JSON descriptions (see [`wit::package_json`]) of two versions
+/// of a package, returning the changes in item order.
+pub fn compare(old: &Value, new: &Value) -> Vec<Change> {
+    let old = surface(old);
+    let new = surface(new);
+    let mut changes = Vec::new();
+    for (item, old_value) in &old {
+        match new.get(item) {
+            None => changes.push(Change::Removed(item.clone())),
+            Some(new_value) if without_docs(old_value) != without_docs(new_value) => {
+                changes.push(Change::Changed(item.clone()))
+            }
+            Some(new_value) if old_value != new_value => {
+                changes.push(Change::Documented(item.clone()))
+            }
+            Some(_) => {}
+        }
+    }
+    for item in new.keys().filter(|item| !old.contains_key(*item)) {
+        changes.push(Change::Added(item.clone()));
+    }
+    changes
+}
+
+/// Flattens a package description into its individually versioned items,
+/// keyed by e.g. `interface api`, `type api.id`, or `world app import api`.
+fn surface(package: &Value) -> BTreeMap<String, Value> {
+    let mut items = BTreeMap::new();
+    let entries = |value: &Value, key: &str| value[key].as_array().cloned().unwrap_or_default();
+    for interface in entries(package, "interfaces") {
+        let name = interface["name"].as_str().unwrap_or_default();
+        items.insert(
+            format!("interface {name}"),
+            serde_json::json!({"docs": interface["docs"]}),
+        );
+        for ty in entries(&interface, "types") {
+            items.insert(
+                format!("type {name}.{}", ty["name"].as_str().unwrap_or_default()),
+                unversioned_use(ty),
+            );
+        }
+        for func in entries(&interface, "functions") {
+            items.insert(format!("function {name}.{}", function_name(&func)), func);
+        }
+    }
+    for world in entries(package, "worlds") {
+        let name = world["name"].as_str().unwrap_or_default();
+        items.insert(
+            format!("world {name}"),
+            serde_json::json!({"docs": world["docs"]}),
+        );
+        for direction in ["import", "export"] {
+            for item in entries(&world, &format!("{direction}s")) {
+                // Interfaces of other packages are named with their version
+                let item_name = function_name(&item);
+                let item_name = item_name.split('@').next().unwrap_or_default();
+                items.insert(
+                    format!("world {name} {direction} {item_name}"),
+                    unversioned_use(item),
+                );
+            }
+        }
+    }
+    items
+}
+
+fn function_name(func: &Value) -> String {
+    let name = func["name"].as_str().unwrap_or_default();
+    match func["resource"].as_str() {
+        Some(resource) => format!("{resource}.{name}"),
+        None => name.to_string(),
+    }
+}
+
+/// Drops the version from the interface a type is `use`d from, so that
+/// updating a dependency within its compatible range isn't a change.
+fn unversioned_use(mut ty: Value) -> Value {
+    if let Some(from) = ty["from"].as_str() {
+        let unversioned = from.split('@').next().unwrap_or(from).to_string();
+        ty["from"] = unversioned.into();
+    }
+    ty
+}
+
+fn without_docs(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .filter(|(key, _)| *key != "docs")
+                .map(|(key, value)| (key.clone(), without_docs(value)))
+                .collect(),
+        ),
+        Value::Array(values) => Value::Array(values.iter().map(without_docs).collect()),
+        value => value.clone(),
+    }
+}
+
+/// Returns the lowest version a release with changes of the given level may
+/// follow `old` as. As with Cargo, the leftmost non-zero component is treated
+/// as the major version, so breaking changes to 0.2.0 require 0.3.0.
+pub fn required_version(old: &Version, level: Level) -> Version {
+    let (major, minor, patch) = (old.major, old.minor, old.patch);
+    let (major, minor, patch) = match level {
+        Level::None => (major, minor, patch),
+        Level::Patch => (major, minor, patch + 1),
+        Level::Minor if major > 0 => (major, minor + 1, 0),
+        Level::Minor => (0, minor, patch + 1),
+        Level::Major if major > 0 => (major + 1, 0, 0),
+        Level::Major if minor > 0 => (0, minor + 1, 0),
+        Level::Major => (0, 0, patch + 1),
+    };
+    Version::new(major, minor, patch)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use wit_parser::{Resolve, UnresolvedPackage};
+
+    fn describe(source: &str) -> Value {
+        let mut resolve = Resolve::default();
+        let unresolved = UnresolvedPackage::parse(Path::new("test.wit"), source).unwrap();
+        let pkg = resolve.push(unresolved).unwrap();
+        wit::package_json(&resolve, pkg)
+    }
+
+    #[test]
+    fn test_compare() {
+        let old = describe(
+            "package test:pkg@1.0.0;
+            interface api { get: func(id: u32) -> string; /// Puts.\nput: func(); }
+            interface old {}",
+        );
+        let new = describe(
+            "package test:pkg@1.1.0;
+            interface api { get: func(id: u64) -> string; /// Stores.\nput: func(); del: func(); }",
+        );
+        assert_eq!(
+            compare(&old, &new),
+            [
+                Change::Changed("function api.get".into()),
+                Change::Documented("function api.put".into()),
+                Change::Removed("interface old".into()),
+                Change::Added("function api.del".into()),
+            ]
+        );
+        assert!(compare(&old, &old).is_empty());
+    }
+
+    #[test]
+    fn test_required_version() {
+        let required =
+            |old: &str, level| required_version(&old.parse().unwrap(), level).to_string();
+        assert_eq!(required("1.2.3", Level::Major), "2.0.0");
+        assert_eq!(required("1.2.3", Level::Minor), "1.3.0");
+        assert_eq!(required("1.2.3", Level::Patch), "1.2.4");
+        assert_eq!(required("1.2.3", Level::None), "1.2.3");
+        assert_eq!(required("0.2.3", Level::Major), "0.3.0");
+        assert_eq!(required("0.2.3", Level::Minor), "0.2.4");
+        assert_eq!(required("0.0.3", Level::Major), "0.0.4");
+    }
+}
@@ -0,0 +1,256 @@
+//! `wkg show`: a summary of what a WIT package exposes.
+
+use std::fmt;
+
+use anyhow::Context;
+use clap::{Args, ValueEnum};
+use wit_parser::{
+    FunctionKind, InterfaceId, PackageId, Resolve, Type, TypeDefKind, TypeOwner, WorldItem,
+};
+
+use crate::{package_spec::PackageSpec, wit, GlobalArgs, RegistryArgs};
+
+#[derive(Args, Debug)]
+pub struct ShowCommand {
+    /// The package to show, specified as <namespace>:<name> plus optional
+    /// @<version>, e.g. "wasi:cli" or "wasi:http@0.2.0".
+    package_spec: PackageSpec,
+
+    /// Output format.
+    #[arg(long, value_enum, default_value = "text")]
+    format: ShowFormat,
+
+    #[command(flatten)]
+    registry: RegistryArgs,
+}
+
+impl ShowCommand {
+    pub async fn run(self, global: &GlobalArgs) -> anyhow::Result<()> {
+        let (package, version, content) =
+            wit::fetch_package(global, self.package_spec, self.registry).await?;
+        let (resolve, pkg) = wit::decode_package(&content)
+            .with_context(|| format!("Failed to decode {package}@{version}"))?;
+        match self.format {
+            ShowFormat::Text => print!("{}", Summary::new(&resolve, pkg)),
+            ShowFormat::Json => println!(
+                "{}",
+                serde_json::to_string_pretty(&wit::package_json(&resolve, pkg))?
+            ),
+        }
+        Ok(())
+    }
+}
+
+#[derive(ValueEnum, Clone, Debug)]
+enum ShowFormat {
+    /// A human-readable summary.
+    Text,
+    /// A JSON description; see `wkg get --format json`.
+    Json,
+}
+
+/// A human-readable overview of a package's worlds, interfaces, and
+/// foreign dependencies.
+pub struct Summary<'a> {
+    resolve: &'a Resolve,
+    pkg: PackageId,
+}
+
+impl<'a> Summary<'a> {
+    pub fn new(resolve: &'a Resolve, pkg: PackageId) -> Self {
+        Self { resolve, pkg }
+    }
+
+    fn fmt_world_item(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        direction: &str,
+        name: &str,
+        item: &WorldItem,
+    ) -> fmt::Result {
+        let resolve = self.resolve;
+        match item {
+            WorldItem::Interface(_) => writeln!(f, "    {direction} {name}"),
+            WorldItem::Function(func) => writeln!(
+                f,
+                "    {direction} func {name}{}",
+                wit::signature(resolve, func)
+            ),
+            WorldItem::Type(_) => writeln!(f, "    type {name}"),
+        }
+    }
+
+    fn fmt_interface(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        name: &str,
+        id: InterfaceId,
+    ) -> fmt::Result {
+        let resolve = self.resolve;
+        let interface = &resolve.interfaces[id];
+        writeln!(f, "  {name}")?;
+        for (type_name, &type_id) in &interface.types {
+            let def = &resolve.types[type_id];
+            match &def.kind {
+                TypeDefKind::Type(Type::Id(target))
+                    if resolve.types[*target].owner != def.owner =>
+                {
+                    let target = &resolve.types[*target];
+                    match target.owner {
+                        TypeOwner::Interface(owner) => writeln!(
+                            f,
+                            "    use {}.{{{}}}",
+                            resolve.id_of(owner).unwrap_or_default(),
+                            target.name.as_deref().unwrap_or(type_name)
+                        )?,
+                        _ => writeln!(f, "    type {type_name}")?,
+                    }
+                }
+                TypeDefKind::Resource => {
+                    writeln!(f, "    resource {type_name}")?;
+                    for func in interface.functions.values() {
+                        let (FunctionKind::Method(owner)
+                        | FunctionKind::Static(owner)
+                        | FunctionKind::Constructor(owner)) = func.kind
+                        else {
+                            continue;
+                        };
+                        if owner != type_id {
+                            continue;
+                        }
+                        let signature = wit::signature(resolve, func);
+                        match func.kind {
+                            FunctionKind::Constructor(_) => {
+                                writeln!(f, "      constructor{signature}")?
+                            }
+                            FunctionKind::Static(_) => {
+                                writeln!(f, "      static {}{signature}", func.item_name())?
+                            }
+                            _ => writeln!(f, "      method {}{signature}", func.item_name())?,
+                        }
+                    }
+                }
+                kind => writeln!(f, "    {} {type_name}", kind_name(kind))?,
+            }
+        }
+        for func in interface.functions.values() {
+            if func.kind == FunctionKind::Freestanding {
+                writeln!(f, "    func {}{}", func.name, wit::signature(resolve, func))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Summary<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let resolve = self.resolve;
+        let package = &resolve.packages[self.pkg];
+        writeln!(f, "package {}", package.name)?;
+        if let Some(docs) = &package.docs.contents {
+            for line in docs.lines() {
+                writeln!(f, "  {line}")?;
+            }
+        }
+
+        if !package.worlds.is_empty() {
+            writeln!(f, "\nworlds:")?;
+            for (name, &id) in &package.worlds {
+                let world = &resolve.worlds[id];
+                writeln!(f, "  {name}")?;
+                for (key, item) in &world.imports {
+                    self.fmt_world_item(f, "import", &resolve.name_world_key(key), item)?;
+                }
+                for (key, item) in &world.exports {
+                    self.fmt_world_item(f, "export", &resolve.name_world_key(key), item)?;
+                }
+            }
+        }
+
+        if !package.interfaces.is_empty() {
+            writeln!(f, "\ninterfaces:")?;
+            for (name, &id) in &package.interfaces {
+                self.fmt_interface(f, name, id)?;
+            }
+        }
+
+        let dependencies: Vec<_> = resolve
+            .packages
+            .iter()
+            .filter(|(id, _)| *id != self.pkg)
+            .collect();
+        if !dependencies.is_empty() {
+            writeln!(f, "\ndependencies:")?;
+            for (_, dependency) in dependencies {
+                let interfaces: Vec<_> = dependency.interfaces.keys().map(String::as_str).collect();
+                writeln!(f, "  {} ({})", dependency.name, interfaces.join(", "))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn kind_name(kind: &TypeDefKind) -> &'static str {
+    match kind {
+        TypeDefKind::Record(_) => "record",
+        TypeDefKind::Resource => "resource",
+        TypeDefKind::Flags(_) => "flags",
+        TypeDefKind::Variant(_) => "variant",
+        TypeDefKind::Enum(_) => "enum",
+        _ => "type",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use wit_parser::UnresolvedPackage;
+
+    use super::*;
+
+    #[test]
+    fn test_summary() {
+        let mut resolve = Resolve::default();
+        let mut push = |path: &str, contents: &str| {
+            let unresolved = UnresolvedPackage::parse(Path::new(path), contents).unwrap();
+            resolve.push(unresolved).unwrap()
+        };
+        push(
+            "dep.wit",
+            "package test:dep@1.0.0; interface types { type id = u32; }",
+        );
+        let pkg = push(
+            "pkg.wit",
+            r#"
+            package test:pkg;
+            interface api {
+                use test:dep/types@1.0.0.{id};
+                record entry { key: string }
+                resource store {
+                    constructor();
+                    get: func(key: id) -> option<entry>;
+                }
+                open: func(name: string) -> result<store, string>;
+            }
+            world app {
+                import api;
+                export run: func();
+            }
+            "#,
+        );
+
+        let summary = Summary::new(&resolve, pkg).to_string();
+        for expected in [
+            "package test:pkg\n",
+            "    import test:pkg/api\n    export func run()\n",
+            "    use test:dep/types@1.0.0.{id}\n",
+            "    record entry\n",
+            "    resource store\n      constructor()\n      method get(key: id) -> option<entry>\n",
+            "    func open(name: string) -> result<store, string>\n",
+            "  test:dep@1.0.0 (types)\n",
+        ] {
+            assert!(summary.contains(expected), "{expected:?} not in {summary}");
+        }
+    }
+}
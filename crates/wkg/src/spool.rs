@@ -0,0 +1,169 @@
+//! Spooling of downloaded content. Small content is buffered in memory and
+//! written to its output in one go; content larger than the threshold is
+//! written to a temp file as it's downloaded and memory-mapped for
+//! inspection and decoding.
+
+use std::path::Path;
+
+use anyhow::Context;
+use memmap2::Mmap;
+use tempfile::{NamedTempFile, TempPath};
+use tokio::io::AsyncWriteExt;
+use wit_component::DecodedWasm;
+
+use crate::{project, quarantine, quarantine::Quarantine};
+
+/// The size, in bytes, of the largest content buffered in memory.
+pub const MEMORY_THRESHOLD: usize = 16 * 1024 * 1024;
+
+pub struct Spool<'a> {
+    staging_dir: &'a Path,
+    threshold: usize,
+    buffer: Vec<u8>,
+    file: Option<(tokio::fs::File, TempPath)>,
+}
+
+impl<'a> Spool<'a> {
+    /// Creates a spool, with any temp file created in the given directory.
+    pub fn new(staging_dir: &'a Path) -> Self {
+        Self::with_threshold(staging_dir, MEMORY_THRESHOLD)
+    }
+
+    fn with_threshold(staging_dir: &'a Path, threshold: usize) -> Self {
+        Self {
+            staging_dir,
+            threshold,
+            buffer: vec![],
+            file: None,
+        }
+    }
+
+    pub async fn write(&mut self, chunk: &[u8]) -> anyhow::Result<()> {
+        if self.file.is_none() && self.buffer.len() + chunk.len() <= self.threshold {
+            self.buffer.extend_from_slice(chunk);
+            return Ok(());
+        }
+        let file = match &mut self.file {
+            Some((file, _)) => file,
+            None => {
+                let (file, path) = temp_file(self.staging_dir)?.into_parts();
+                tracing::debug!(?path, "Spooling content to file");
+                let mut file = tokio::fs::File::from_std(file);
+                file.write_all(&std::mem::take(&mut self.buffer)).await?;
+                &mut self.file.insert((file, path)).0
+            }
+        };
+        file.write_all(chunk).await?;
+        Ok(())
+    }
+
+    pub async fn finish(self) -> anyhow::Result<Spooled> {
+        match self.file {
+            None => Ok(Spooled::Memory(self.buffer)),
+            Some((mut file, path)) => {
+                file.flush().await?;
+                Spooled::map(&file.into_std().await, path)
+            }
+        }
+    }
+
+    /// Spools content from an existing file, e.g. a cache entry. Content
+    /// over the threshold is copied by the OS (which may share the file's
+    /// blocks rather than copying them) instead of through memory.
+    pub fn copy_from(self, source: &Path) -> anyhow::Result<Spooled> {
+        let len = std::fs::metadata(source)
+            .with_context(|| format!("Failed to read metadata of {source:?}"))?
+            .len();
+        if len <= self.threshold as u64 {
+            let content =
+                std::fs::read(source).with_context(|| format!("Failed to read {source:?}"))?;
+            return Ok(Spooled::Memory(content));
+        }
+        let (file, path) = temp_file(self.staging_dir)?.into_parts();
+        drop(file);
+        tracing::debug!(?path, "Copying {source:?} to spool file");
+        std::fs::copy(source, &path)
+            .with_context(|| format!("Failed to copy {source:?} to {path:?}"))?;
+        let file = std::fs::File::open(&path)?;
+        Spooled::map(&file, path)
+    }
+}
+
+fn temp_file(dir: &Path) -> anyhow::Result<NamedTempFile> {
+    NamedTempFile::with_prefix_in(".wkg-get", dir)
+        .with_context(|| format!("Failed to create file in {dir:?}"))
+}
+
+/// Content spooled by a [`Spool`].
+pub enum Spooled {
+    Memory(Vec<u8>),
+    File { mmap: Mmap, path: TempPath },
+}
+
+impl Spooled {
+    fn map(file: &std::fs::File, path: TempPath) -> anyhow::Result<Self> {
+        // SAFETY: the file is a private temp file that nothing else writes
+        // to while it's mapped.
+        let mmap = unsafe { Mmap::map(file) }.with_context(|| format!("Failed to map {path:?}"))?;
+        Ok(Self::File { mmap, path })
+    }
+
+    pub fn content(&self) -> &[u8] {
+        match self {
+            Self::Memory(content) => content,
+            Self::File { mmap, .. } => mmap,
+        }
+    }
+
+    pub fn decode(&self) -> anyhow::Result<DecodedWasm> {
+        wit_component::decode(self.content())
+    }
+
+    /// Writes the content to the given output path, through quarantine if
+    /// given. Spooled files are moved into place rather than copied.
+    pub fn persist(self, quarantine: Option<&Quarantine>, output: &Path) -> anyhow::Result<()> {
+        match self {
+            Self::Memory(content) => project::write_output(quarantine, output, &content),
+            Self::File { mmap, path } => {
+                // Unmap first; mapped files can't be renamed on Windows
+                drop(mmap);
+                quarantine::promote(path, output)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_spool_threshold() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let mut spool = Spool::with_threshold(dir.path(), 8);
+        spool.write(b"abcd").await.unwrap();
+        spool.write(b"efgh").await.unwrap();
+        let spooled = spool.finish().await.unwrap();
+        assert!(matches!(spooled, Spooled::Memory(_)));
+        assert_eq!(spooled.content(), b"abcdefgh");
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+
+        let mut spool = Spool::with_threshold(dir.path(), 8);
+        spool.write(b"abcd").await.unwrap();
+        spool.write(b"efghi").await.unwrap();
+        let spooled = spool.finish().await.unwrap();
+        assert!(matches!(spooled, Spooled::File { .. }));
+        assert_eq!(spooled.content(), b"abcdefghi");
+
+        let output = dir.path().join("out.wasm");
+        spooled.persist(None, &output).unwrap();
+        assert_eq!(std::fs::read(&output).unwrap(), b"abcdefghi");
+
+        let spooled = Spool::with_threshold(dir.path(), 8)
+            .copy_from(&output)
+            .unwrap();
+        assert!(matches!(spooled, Spooled::File { .. }));
+        assert_eq!(spooled.content(), b"abcdefghi");
+    }
+}
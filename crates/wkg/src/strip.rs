@@ -0,0 +1,94 @@
+//! Stripping volatile custom sections, so that content fetched and
+//! re-published (e.g. by mirroring pipelines) stays byte-identical.
+
+use anyhow::Context;
+use wasm_encoder::{ComponentSectionId, Encode, RawSection, Section};
+use wasmparser::{Encoding, Parser, Payload};
+
+/// Custom sections recording details of the build that produced the content
+/// rather than the content itself.
+const VOLATILE_SECTIONS: &[&str] = &["producers", "build_id"];
+
+/// Returns the given module or component without volatile custom sections,
+/// including those of any nested modules and components.
+pub fn strip_volatile_sections(content: &[u8]) -> anyhow::Result<Vec<u8>> {
+    filter_custom_sections(content, |name, _| {
+        let volatile = VOLATILE_SECTIONS.contains(&name);
+        if volatile {
+            tracing::debug!(name, "stripping custom section");
+        }
+        !volatile
+    })
+}
+
+/// Returns the given module or component with only the custom sections for
+/// which `keep` returns true, given each section's name and nesting depth
+/// (0 for top-level sections).
+pub fn filter_custom_sections(
+    content: &[u8],
+    keep: impl Fn(&str, usize) -> bool,
+) -> anyhow::Result<Vec<u8>> {
+    let mut output = vec![];
+    let mut stack = vec![];
+    for payload in Parser::new(0).parse_all(content) {
+        let payload = payload.context("Invalid Wasm content")?;
+        match &payload {
+            Payload::Version { encoding, .. } => {
+                output.extend_from_slice(match encoding {
+                    Encoding::Module => &wasm_encoder::Module::HEADER,
+                    Encoding::Component => &wasm_encoder::Component::HEADER,
+                });
+                continue;
+            }
+            Payload::ModuleSection { .. } | Payload::ComponentSection { .. } => {
+                stack.push(std::mem::take(&mut output));
+                continue;
+            }
+            Payload::End(_) => {
+                let Some(mut parent) = stack.pop() else {
+                    break;
+                };
+                let id = if output.starts_with(&wasm_encoder::Component::HEADER) {
+                    ComponentSectionId::Component
+                } else {
+                    ComponentSectionId::CoreModule
+                };
+                parent.push(id as u8);
+                output.encode(&mut parent);
+                output = parent;
+                continue;
+            }
+            Payload::CustomSection(section) if !keep(section.name(), stack.len()) => continue,
+            _ => (),
+        }
+        if let Some((id, range)) = payload.as_section() {
+            RawSection {
+                id,
+                data: &content[range],
+            }
+            .append_to(&mut output);
+        }
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_volatile_sections() {
+        let header = [0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00];
+        // (custom "keep" "x")
+        let keep = [0x00, 0x06, 0x04, b'k', b'e', b'e', b'p', b'x'];
+        // (custom "producers" with no fields)
+        let producers = [
+            0x00, 0x0b, 0x09, b'p', b'r', b'o', b'd', b'u', b'c', b'e', b'r', b's', 0x00,
+        ];
+        let module = [&header[..], &producers, &keep].concat();
+
+        let stripped = strip_volatile_sections(&module).unwrap();
+        assert_eq!(stripped, [&header[..], &keep].concat());
+        assert_eq!(strip_volatile_sections(&stripped).unwrap(), stripped);
+    }
+}
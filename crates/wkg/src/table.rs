@@ -0,0 +1,156 @@
+//! Table output for listing commands. On a terminal, rows are aligned under
+//! a header and notable entries (e.g. yanked or outdated versions) are
+//! colored unless `NO_COLOR` is set. When piped, rows are written as plain
+//! tab-separated text for scripts.
+
+use std::{fmt::Display, io::IsTerminal};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Color {
+    Green,
+    Yellow,
+    Dim,
+}
+
+impl Color {
+    fn code(self) -> &'static str {
+        match self {
+            Self::Green => "32",
+            Self::Yellow => "33",
+            Self::Dim => "2",
+        }
+    }
+
+    /// Returns the text wrapped in this color's escape codes if `enabled`.
+    pub fn paint(self, text: impl Display, enabled: bool) -> String {
+        if enabled {
+            format!("\x1b[{}m{text}\x1b[0m", self.code())
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+/// Returns true if stdout is a terminal and color isn't disabled with
+/// `NO_COLOR` (see <https://no-color.org>).
+pub fn color_enabled() -> bool {
+    std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
+}
+
+/// A table cell: text with an optional color.
+pub struct Cell {
+    text: String,
+    color: Option<Color>,
+}
+
+impl Cell {
+    pub fn new(text: impl Display) -> Self {
+        Self {
+            text: text.to_string(),
+            color: None,
+        }
+    }
+
+    pub fn colored(text: impl Display, color: Option<Color>) -> Self {
+        Self {
+            text: text.to_string(),
+            color,
+        }
+    }
+}
+
+pub struct Table {
+    header: Vec<&'static str>,
+    rows: Vec<Vec<Cell>>,
+}
+
+impl Table {
+    pub fn new(header: &[&'static str]) -> Self {
+        Self {
+            header: header.to_vec(),
+            rows: vec![],
+        }
+    }
+
+    pub fn push(&mut self, row: Vec<Cell>) {
+        self.rows.push(row);
+    }
+
+    /// Prints the table to stdout, formatted for a terminal if it is one.
+    pub fn print(&self) {
+        let aligned = std::io::stdout().is_terminal();
+        print!("{}", self.render(aligned, aligned && color_enabled()));
+    }
+
+    fn render(&self, aligned: bool, color: bool) -> String {
+        let mut out = String::new();
+        if !aligned {
+            for row in &self.rows {
+                let texts: Vec<&str> = row.iter().map(|cell| cell.text.as_str()).collect();
+                out.push_str(&texts.join("\t"));
+                out.push('\n');
+            }
+            return out;
+        }
+        let mut widths: Vec<usize> = self.header.iter().map(|h| h.chars().count()).collect();
+        for row in &self.rows {
+            for (idx, cell) in row.iter().enumerate() {
+                let width = cell.text.chars().count();
+                match widths.get_mut(idx) {
+                    Some(max) => *max = (*max).max(width),
+                    None => widths.push(width),
+                }
+            }
+        }
+        let header: Vec<Cell> = self
+            .header
+            .iter()
+            .map(|h| Cell::new(h.to_uppercase()))
+            .collect();
+        for row in std::iter::once(&header).chain(&self.rows) {
+            let mut line = String::new();
+            for (idx, cell) in row.iter().enumerate() {
+                if idx > 0 {
+                    line.push_str("  ");
+                }
+                let text = match cell.color {
+                    Some(cell_color) => cell_color.paint(&cell.text, color),
+                    None => cell.text.clone(),
+                };
+                line.push_str(&text);
+                line.push_str(&" ".repeat(widths[idx] - cell.text.chars().count()));
+            }
+            out.push_str(line.trim_end());
+            out.push('\n');
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let mut table = Table::new(&["name", "version"]);
+        table.push(vec![Cell::new("wasi:http"), Cell::new("0.2.0")]);
+        table.push(vec![
+            Cell::new("wasi:io"),
+            Cell::colored("0.1.0", Some(Color::Yellow)),
+        ]);
+
+        assert_eq!(
+            table.render(true, false),
+            "NAME       VERSION\nwasi:http  0.2.0\nwasi:io    0.1.0\n"
+        );
+        assert_eq!(
+            table.render(true, true).lines().nth(2),
+            Some("wasi:io    \x1b[33m0.1.0\x1b[0m")
+        );
+        assert_eq!(
+            table.render(false, false),
+            "wasi:http\t0.2.0\nwasi:io\t0.1.0\n"
+        );
+    }
+}
@@ -0,0 +1,101 @@
+//! `wkg tag`: manages channel tags (e.g. `stable` or `canary`) pointing at
+//! package releases, on registries with mutable tags. Packages can be
+//! fetched by channel with `wkg get <package>@<tag>`.
+
+use anyhow::Context;
+use clap::Subcommand;
+use wasm_pkg_loader::{Client, PackageRef, Version};
+
+use crate::{package_spec::suggest_not_found, GlobalArgs, RegistryArgs};
+
+#[derive(Subcommand, Debug)]
+pub enum TagCommand {
+    /// Point a channel tag at a release, replacing any previous target.
+    Set {
+        /// The package, as <namespace>:<name>.
+        package: PackageRef,
+        /// The channel tag, e.g. "stable".
+        tag: String,
+        /// The version to tag.
+        version: Version,
+        #[command(flatten)]
+        registry: RegistryArgs,
+    },
+    /// Show the release a channel tag points at.
+    Show {
+        /// The package, as <namespace>:<name>.
+        package: PackageRef,
+        /// The channel tag, e.g. "stable".
+        tag: String,
+        #[command(flatten)]
+        registry: RegistryArgs,
+    },
+}
+
+impl TagCommand {
+    pub async fn run(self, global: &GlobalArgs) -> anyhow::Result<()> {
+        match self {
+            TagCommand::Set {
+                package,
+                tag,
+                version,
+                registry,
+            } => {
+                let mut client = tag_client(global, &package, registry)?;
+                // Checked first for a clearer error than the registry's
+                let result = client.get_release(&package, &version).await;
+                let release = suggest_not_found(result, &mut client, &package, Some(&version))
+                    .await
+                    .context("Failed to get release details")?;
+                client
+                    .set_tag(&package, &tag, &version)
+                    .await
+                    .with_context(|| format!("Failed to tag {package}@{version} as {tag:?}"))?;
+                println!(
+                    "Tagged {package}@{version} as {tag:?} ({})",
+                    release.content_digest
+                );
+            }
+            TagCommand::Show {
+                package,
+                tag,
+                registry,
+            } => {
+                let mut client = tag_client(global, &package, registry)?;
+                let version = client
+                    .resolve_tag(&package, &tag)
+                    .await
+                    .with_context(|| format!("Failed to resolve {package}@{tag}"))?;
+                let release = client
+                    .get_release(&package, &version)
+                    .await
+                    .context("Failed to get release details")?;
+                if global.json {
+                    let json = serde_json::json!({
+                        "package": package.to_string(),
+                        "tag": tag,
+                        "version": version.to_string(),
+                        "digest": release.content_digest.to_string(),
+                    });
+                    println!("{}", serde_json::to_string_pretty(&json)?);
+                } else {
+                    println!("{package}@{tag}: {version} ({})", release.content_digest);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn tag_client(
+    global: &GlobalArgs,
+    package: &PackageRef,
+    registry: RegistryArgs,
+) -> anyhow::Result<Client> {
+    global.load_policy()?.check_package(package)?;
+    let mut config = global.load_config()?;
+    if let Some(registry) = registry.domain {
+        config.set_namespace_registry(package.namespace().to_string(), registry);
+    }
+    Ok(config.to_client())
+}
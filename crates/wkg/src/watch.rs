@@ -0,0 +1,233 @@
+//! `wkg watch`: polls packages for new releases and reports them, to
+//! automate workflows like rebuilding when a new `wasi:http` is released.
+//!
+//! The first poll of a package only records its existing versions; versions
+//! seen in later polls are reported. With `--state`, known versions persist
+//! across runs, so `--once` can be run periodically (e.g. from cron or CI).
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::Write,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::{bail, Context};
+use clap::Args;
+use serde::{Deserialize, Serialize};
+use wasm_pkg_loader::{PackageRef, Version, VersionInfo};
+
+use crate::{manifest::Manifest, GlobalArgs};
+
+#[derive(Args, Debug)]
+pub struct WatchCommand {
+    /// The packages to watch, as <namespace>:<name>. Defaults to the
+    /// current project's dependencies.
+    packages: Vec<PackageRef>,
+
+    /// Poll once and exit, rather than polling until interrupted.
+    #[arg(long, requires = "state")]
+    once: bool,
+
+    /// The number of seconds between polls.
+    #[arg(long, value_name = "SECONDS", default_value_t = 300)]
+    interval: u64,
+
+    /// A file to persist known versions in between runs.
+    #[arg(long, value_name = "PATH")]
+    state: Option<PathBuf>,
+
+    /// A command to run for each new release, with the release in the
+    /// `WKG_PACKAGE` and `WKG_VERSION` environment variables.
+    #[arg(long, value_name = "COMMAND")]
+    exec: Option<String>,
+}
+
+impl WatchCommand {
+    pub async fn run(self, global: &GlobalArgs) -> anyhow::Result<()> {
+        let packages = if self.packages.is_empty() {
+            let manifest = Manifest::load_current()
+                .context("No packages given and no project to watch the dependencies of")?;
+            manifest.dependencies.into_keys().collect()
+        } else {
+            self.packages.clone()
+        };
+        if packages.is_empty() {
+            bail!("No packages to watch");
+        }
+        let mut client = {
+            let mut config = global.load_config()?;
+            // Each poll must see the registry's current versions
+            config.set_refresh(true);
+            config.to_client()
+        };
+        let mut state = match &self.state {
+            Some(path) => WatchState::load(path)?,
+            None => WatchState::default(),
+        };
+        if !self.once {
+            eprintln!(
+                "Watching {} packages every {}s",
+                packages.len(),
+                self.interval
+            );
+        }
+
+        loop {
+            for package in &packages {
+                match client.list_all_versions(package).await {
+                    Ok(versions) => {
+                        for version in state.update(package, &versions) {
+                            self.report(global, package, &version)?;
+                        }
+                    }
+                    Err(err) if self.once => {
+                        return Err(err)
+                            .with_context(|| format!("Failed to list versions of {package}"));
+                    }
+                    // Keep watching through transient registry failures
+                    Err(err) => tracing::warn!("Failed to list versions of {package}: {err}"),
+                }
+            }
+            if let Some(path) = &self.state {
+                state.save(path)?;
+            }
+            if self.once {
+                return Ok(());
+            }
+            tokio::time::sleep(Duration::from_secs(self.interval)).await;
+        }
+    }
+
+    fn report(
+        &self,
+        global: &GlobalArgs,
+        package: &PackageRef,
+        version: &Version,
+    ) -> anyhow::Result<()> {
+        if global.json {
+            let json = serde_json::json!({
+                "package": package.to_string(),
+                "version": version.to_string(),
+            });
+            println!("{json}");
+        } else {
+            println!("New release: {package}@{version}");
+        }
+        std::io::stdout().flush()?;
+        if let Some(exec) = &self.exec {
+            // A failing hook shouldn't stop the watch
+            if let Err(err) = run_hook(exec, package, version) {
+                tracing::warn!("Hook for {package}@{version} failed: {err:#}");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Runs the hook command, given with any arguments (e.g. "notify.sh
+/// --channel releases").
+fn run_hook(command: &str, package: &PackageRef, version: &Version) -> anyhow::Result<()> {
+    let mut args = command.split_whitespace();
+    let program = args.next().context("Empty hook command")?;
+    let status = std::process::Command::new(program)
+        .args(args)
+        .env("WKG_PACKAGE", package.to_string())
+        .env("WKG_VERSION", version.to_string())
+        .status()
+        .with_context(|| format!("Failed to run {command:?}"))?;
+    anyhow::ensure!(status.success(), "{command:?} exited with {status}");
+    Ok(())
+}
+
+/// The versions known for each watched package.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct WatchState {
+    packages: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl WatchState {
+    fn load(path: &Path) -> anyhow::Result<Self> {
+        match std::fs::read(path) {
+            Ok(contents) => serde_json::from_slice(&contents)
+                .with_context(|| format!("Invalid watch state in {path:?}")),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err).with_context(|| format!("Failed to read {path:?}")),
+        }
+    }
+
+    fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        // Written atomically so an interrupted run can't corrupt it
+        let mut tmp = tempfile::NamedTempFile::new_in(dir)?;
+        serde_json::to_writer_pretty(&mut tmp, self)?;
+        tmp.persist(path)
+            .with_context(|| format!("Failed to write {path:?}"))?;
+        Ok(())
+    }
+
+    /// Records the package's current versions, returning the non-yanked
+    /// ones not seen before, oldest first. Nothing is returned for a
+    /// package seen for the first time.
+    fn update(&mut self, package: &PackageRef, versions: &[VersionInfo]) -> Vec<Version> {
+        let current = versions.iter().map(|info| info.version.to_string());
+        let Some(known) = self.packages.get_mut(&package.to_string()) else {
+            self.packages.insert(package.to_string(), current.collect());
+            return vec![];
+        };
+        let mut new: Vec<Version> = versions
+            .iter()
+            .filter(|info| !info.yanked && !known.contains(&info.version.to_string()))
+            .map(|info| info.version.clone())
+            .collect();
+        new.sort();
+        known.extend(current);
+        new
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_update() {
+        let package: PackageRef = "wasi:http".parse().unwrap();
+        let versions = |versions: &[(&str, bool)]| -> Vec<VersionInfo> {
+            versions
+                .iter()
+                .map(|(version, yanked)| VersionInfo {
+                    version: version.parse().unwrap(),
+                    yanked: *yanked,
+                    deprecation: None,
+                })
+                .collect()
+        };
+        let mut state = WatchState::default();
+        assert!(state
+            .update(&package, &versions(&[("0.2.0", false)]))
+            .is_empty());
+        let new = state.update(
+            &package,
+            &versions(&[
+                ("0.2.2", false),
+                ("0.2.0", false),
+                ("0.2.1", false),
+                ("0.3.0", true),
+            ]),
+        );
+        let expected: Vec<Version> = vec!["0.2.1".parse().unwrap(), "0.2.2".parse().unwrap()];
+        assert_eq!(new, expected);
+        assert!(state
+            .update(&package, &versions(&[("0.2.2", false)]))
+            .is_empty());
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("watch.json");
+        state.save(&path).unwrap();
+        assert_eq!(WatchState::load(&path).unwrap().packages, state.packages);
+    }
+}
@@ -0,0 +1,85 @@
+//! `wkg why`: explains why a package is in a project's dependency graph.
+
+use anyhow::Context;
+use clap::Args;
+use wasm_pkg_loader::{DependencyGraph, PackageRef};
+
+use crate::{
+    manifest::{Manifest, MANIFEST_FILE_NAME},
+    project::locked_graph,
+    GlobalArgs,
+};
+
+/// The maximum number of dependency paths shown.
+const MAX_PATHS: usize = 20;
+
+#[derive(Args, Debug)]
+pub struct WhyCommand {
+    /// The package to explain, as <namespace>:<name>.
+    package: PackageRef,
+}
+
+impl WhyCommand {
+    pub async fn run(self, global: &GlobalArgs) -> anyhow::Result<()> {
+        let manifest = Manifest::load_current()?;
+        let graph = locked_graph(global, &manifest).await?;
+        let index = graph
+            .find(&self.package)
+            .with_context(|| format!("{} is not a dependency of this project", self.package))?;
+        let node = &graph.nodes()[index];
+
+        // The requirements constraining the version: the manifest's, for a
+        // direct dependency, and those of each package referencing it
+        let mut requirements = vec![];
+        if let Some(dependency) = manifest.dependencies.get(&self.package) {
+            requirements.push((
+                MANIFEST_FILE_NAME.to_string(),
+                dependency.version.to_string(),
+            ));
+        }
+        for edge in graph.dependents(index) {
+            requirements.push((node_name(&graph, edge.from), edge.req.to_string()));
+        }
+        let paths: Vec<Vec<String>> = graph
+            .paths_to(index, MAX_PATHS)
+            .iter()
+            .map(|path| path.iter().map(|&index| node_name(&graph, index)).collect())
+            .collect();
+
+        if global.json {
+            let json = serde_json::json!({
+                "package": node.package.to_string(),
+                "version": node.version.to_string(),
+                "registry": node.registry,
+                "requirements": requirements
+                    .iter()
+                    .map(|(from, req)| serde_json::json!({ "from": from, "requirement": req }))
+                    .collect::<Vec<_>>(),
+                "paths": paths,
+            });
+            println!("{}", serde_json::to_string_pretty(&json)?);
+            return Ok(());
+        }
+        match &node.registry {
+            Some(registry) => println!("{} from {registry}", node_name(&graph, index)),
+            None => println!("{} from a local path override", node_name(&graph, index)),
+        }
+        println!("\nRequired by:");
+        for (from, req) in &requirements {
+            println!("  {from}: {req}");
+        }
+        println!("\nIncluded through:");
+        for path in &paths {
+            println!("  {}", path.join(" -> "));
+        }
+        if paths.len() == MAX_PATHS {
+            println!("  (showing the first {MAX_PATHS} paths)");
+        }
+        Ok(())
+    }
+}
+
+fn node_name(graph: &DependencyGraph, index: usize) -> String {
+    let node = &graph.nodes()[index];
+    format!("{}@{}", node.package, node.version)
+}
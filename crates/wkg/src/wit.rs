@@ -0,0 +1,789 @@
+//! Fetching, decoding, and output of WIT packages.
+
+use std::{collections::HashSet, path::Path};
+
+use anyhow::{anyhow, bail, Context};
+use clap::{Args, ValueEnum};
+use futures_util::TryStreamExt;
+use serde_json::{json, Value};
+use wasm_pkg_loader::{PackageRef, Version, WitOutputConfig};
+use wit_component::{DecodedWasm, WitPrinter};
+use wit_parser::{
+    Function, FunctionKind, Handle, InterfaceId, PackageId, Resolve, Results, Type, TypeDefKind,
+    TypeId, TypeOwner, UnresolvedPackage, WorldItem, WorldKey,
+};
+
+use crate::{package_spec::PackageSpec, GlobalArgs, RegistryArgs};
+
+/// Fetches the content of the given package release (or the latest release
+/// if no version is given), using the cache if possible and enforcing the
+/// organization policy.
+pub async fn fetch_package(
+    global: &GlobalArgs,
+    spec: PackageSpec,
+    registry: RegistryArgs,
+) -> anyhow::Result<(PackageRef, Version, Vec<u8>)> {
+    let (package, version) = spec.into_versioned()?;
+    let policy = global.load_policy()?;
+    policy.check_package(&package)?;
+
+    let mut client = {
+        let mut config = global.load_config()?;
+        if let Some(registry) = registry.domain {
+            config.set_namespace_registry(package.namespace().to_string(), registry);
+        }
+        config.to_client()
+    };
+    let version = match version {
+        Some(ver) => ver,
+        None => client
+            .latest_version(&package)
+            .await
+            .context("Failed to select latest version")?,
+    };
+    let release = client
+        .get_release(&package, &version)
+        .await
+        .context("Failed to get release details")?;
+    let content: Vec<u8> = client
+        .stream_content(&package, &release)
+        .await?
+        .map_ok(|chunk| chunk.to_vec())
+        .try_concat()
+        .await?;
+    policy.check_content(&package, &version, &content)?;
+    Ok((package, version, content))
+}
+
+/// Decodes the given content as a WIT package.
+pub fn decode_package(content: &[u8]) -> anyhow::Result<(Resolve, PackageId)> {
+    if crate::module::is_module(content) {
+        bail!("content is a core Wasm module, not a WIT package");
+    }
+    match wit_component::decode(content)? {
+        DecodedWasm::WitPackage(resolve, pkg) => Ok((resolve, pkg)),
+        DecodedWasm::Component(..) => bail!("content is a component, not a WIT package"),
+    }
+}
+
+/// Returns the WIT syntax for the given type, e.g. `list<u8>`.
+pub fn type_name(resolve: &Resolve, ty: &Type) -> String {
+    let id = match ty {
+        Type::Bool => return "bool".into(),
+        Type::U8 => return "u8".into(),
+        Type::U16 => return "u16".into(),
+        Type::U32 => return "u32".into(),
+        Type::U64 => return "u64".into(),
+        Type::S8 => return "s8".into(),
+        Type::S16 => return "s16".into(),
+        Type::S32 => return "s32".into(),
+        Type::S64 => return "s64".into(),
+        Type::F32 => return "f32".into(),
+        Type::F64 => return "f64".into(),
+        Type::Char => return "char".into(),
+        Type::String => return "string".into(),
+        Type::Id(id) => *id,
+    };
+    let def = &resolve.types[id];
+    match &def.name {
+        Some(name) => name.clone(),
+        None => kind_syntax(resolve, &def.kind),
+    }
+}
+
+/// Returns the WIT syntax for an anonymous type definition, e.g. the
+/// `list<u8>` of `type bytes = list<u8>`.
+pub fn kind_syntax(resolve: &Resolve, kind: &TypeDefKind) -> String {
+    let name = |ty: &Type| type_name(resolve, ty);
+    match kind {
+        TypeDefKind::Handle(Handle::Own(id)) => name(&Type::Id(*id)),
+        TypeDefKind::Handle(Handle::Borrow(id)) => format!("borrow<{}>", name(&Type::Id(*id))),
+        TypeDefKind::Tuple(tuple) => {
+            let types: Vec<_> = tuple.types.iter().map(name).collect();
+            format!("tuple<{}>", types.join(", "))
+        }
+        TypeDefKind::Option(ty) => format!("option<{}>", name(ty)),
+        TypeDefKind::Result(result) => match (&result.ok, &result.err) {
+            (None, None) => "result".into(),
+            (Some(ok), None) => format!("result<{}>", name(ok)),
+            (None, Some(err)) => format!("result<_, {}>", name(err)),
+            (Some(ok), Some(err)) => format!("result<{}, {}>", name(ok), name(err)),
+        },
+        TypeDefKind::List(ty) => format!("list<{}>", name(ty)),
+        TypeDefKind::Future(None) => "future".into(),
+        TypeDefKind::Future(Some(ty)) => format!("future<{}>", name(ty)),
+        TypeDefKind::Stream(stream) => match (&stream.element, &stream.end) {
+            (None, None) => "stream".into(),
+            (Some(element), None) => format!("stream<{}>", name(element)),
+            (None, Some(end)) => format!("stream<_, {}>", name(end)),
+            (Some(element), Some(end)) => format!("stream<{}, {}>", name(element), name(end)),
+        },
+        TypeDefKind::Type(ty) => name(ty),
+        // Other kinds are always named
+        TypeDefKind::Record(_)
+        | TypeDefKind::Resource
+        | TypeDefKind::Flags(_)
+        | TypeDefKind::Variant(_)
+        | TypeDefKind::Enum(_)
+        | TypeDefKind::Unknown => "_".into(),
+    }
+}
+
+/// Returns the WIT syntax for the given function's parameters and results,
+/// e.g. `(name: string) -> u32`. As in WIT, the `self` parameter of
+/// methods and the result of constructors are omitted.
+pub fn signature(resolve: &Resolve, func: &Function) -> String {
+    let skip = usize::from(matches!(func.kind, FunctionKind::Method(_)));
+    let params: Vec<_> = func
+        .params
+        .iter()
+        .skip(skip)
+        .map(|(name, ty)| format!("{name}: {}", type_name(resolve, ty)))
+        .collect();
+    let mut signature = format!("({})", params.join(", "));
+    match &func.results {
+        _ if matches!(func.kind, FunctionKind::Constructor(_)) => (),
+        Results::Named(results) if results.is_empty() => (),
+        Results::Named(results) => {
+            let results: Vec<_> = results
+                .iter()
+                .map(|(name, ty)| format!("{name}: {}", type_name(resolve, ty)))
+                .collect();
+            signature.push_str(&format!(" -> ({})", results.join(", ")));
+        }
+        Results::Anon(ty) => {
+            signature.push_str(" -> ");
+            signature.push_str(&type_name(resolve, ty));
+        }
+    }
+    signature
+}
+
+/// Returns a short description of a world import or export, e.g.
+/// `interface wasi:io/streams@0.2.0` or `func run() -> result`.
+pub fn world_item_label(resolve: &Resolve, key: &WorldKey, item: &WorldItem) -> String {
+    let name = resolve.name_world_key(key);
+    match item {
+        WorldItem::Interface(_) => format!("interface {name}"),
+        WorldItem::Function(func) => format!("func {name}{}", signature(resolve, func)),
+        WorldItem::Type(_) => format!("type {name}"),
+    }
+}
+
+/// Returns a JSON description of the given package's worlds, interfaces,
+/// and foreign dependencies, with types in WIT syntax (see [`type_name`]):
+///
+/// ```json
+/// {
+///   "package": "wasi:cli@0.2.0",
+///   "docs": null,
+///   "worlds": [{"name": "...", "docs": null, "imports": [...], "exports": [...]}],
+///   "interfaces": [{"name": "...", "docs": null, "types": [...], "functions": [...]}],
+///   "dependencies": [{"package": "wasi:io@0.2.0", "interfaces": ["streams"]}]
+/// }
+/// ```
+pub fn package_json(resolve: &Resolve, pkg: PackageId) -> Value {
+    let package = &resolve.packages[pkg];
+    let worlds: Vec<_> = package
+        .worlds
+        .iter()
+        .map(|(name, &id)| {
+            let world = &resolve.worlds[id];
+            json!({
+                "name": name,
+                "docs": world.docs.contents,
+                "imports": world_items_json(resolve, &world.imports),
+                "exports": world_items_json(resolve, &world.exports),
+            })
+        })
+        .collect();
+    let interfaces: Vec<_> = package
+        .interfaces
+        .iter()
+        .map(|(name, &id)| {
+            let interface = &resolve.interfaces[id];
+            let types: Vec<_> = interface
+                .types
+                .iter()
+                .map(|(name, &id)| type_json(resolve, name, id))
+                .collect();
+            let functions: Vec<_> = interface
+                .functions
+                .values()
+                .map(|func| function_json(resolve, func))
+                .collect();
+            json!({
+                "name": name,
+                "docs": interface.docs.contents,
+                "types": types,
+                "functions": functions,
+            })
+        })
+        .collect();
+    let dependencies: Vec<_> = resolve
+        .packages
+        .iter()
+        .filter(|(id, dependency)| *id != pkg && !dependency.interfaces.is_empty())
+        .map(|(_, dependency)| {
+            json!({
+                "package": dependency.name.to_string(),
+                "interfaces": dependency.interfaces.keys().collect::<Vec<_>>(),
+            })
+        })
+        .collect();
+    json!({
+        "package": package.name.to_string(),
+        "docs": package.docs.contents,
+        "worlds": worlds,
+        "interfaces": interfaces,
+        "dependencies": dependencies,
+    })
+}
+
+fn world_items_json<'a>(
+    resolve: &Resolve,
+    items: impl IntoIterator<Item = (&'a WorldKey, &'a WorldItem)>,
+) -> Vec<Value> {
+    items
+        .into_iter()
+        .map(|(key, item)| {
+            let name = resolve.name_world_key(key);
+            match item {
+                WorldItem::Interface(_) => json!({"kind": "interface", "name": name}),
+                WorldItem::Function(func) => function_json(resolve, func),
+                WorldItem::Type(id) => type_json(resolve, &name, *id),
+            }
+        })
+        .collect()
+}
+
+fn type_json(resolve: &Resolve, name: &str, id: TypeId) -> Value {
+    let def = &resolve.types[id];
+    let ty = |ty: &Type| type_name(resolve, ty);
+    let mut value = match &def.kind {
+        TypeDefKind::Record(record) => {
+            let fields: Vec<_> = record
+                .fields
+                .iter()
+                .map(|field| {
+                    json!({"name": field.name, "type": ty(&field.ty), "docs": field.docs.contents})
+                })
+                .collect();
+            json!({"kind": "record", "fields": fields})
+        }
+        TypeDefKind::Variant(variant) => {
+            let cases: Vec<_> = variant
+                .cases
+                .iter()
+                .map(|case| {
+                    json!({
+                        "name": case.name,
+                        "type": case.ty.as_ref().map(ty),
+                        "docs": case.docs.contents,
+                    })
+                })
+                .collect();
+            json!({"kind": "variant", "cases": cases})
+        }
+        TypeDefKind::Enum(enum_) => {
+            let cases: Vec<_> = enum_
+                .cases
+                .iter()
+                .map(|case| json!({"name": case.name, "docs": case.docs.contents}))
+                .collect();
+            json!({"kind": "enum", "cases": cases})
+        }
+        TypeDefKind::Flags(flags) => {
+            let flags: Vec<_> = flags
+                .flags
+                .iter()
+                .map(|flag| json!({"name": flag.name, "docs": flag.docs.contents}))
+                .collect();
+            json!({"kind": "flags", "flags": flags})
+        }
+        TypeDefKind::Resource => json!({"kind": "resource"}),
+        TypeDefKind::Type(Type::Id(target)) if resolve.types[*target].owner != def.owner => {
+            // A type `use`d from another interface
+            let from = match resolve.types[*target].owner {
+                TypeOwner::Interface(owner) => resolve.id_of(owner),
+                _ => None,
+            };
+            json!({"kind": "use", "type": ty(&Type::Id(*target)), "from": from})
+        }
+        kind => json!({"kind": "alias", "type": kind_syntax(resolve, kind)}),
+    };
+    value["name"] = name.into();
+    value["docs"] = def.docs.contents.clone().into();
+    value
+}
+
+fn function_json(resolve: &Resolve, func: &Function) -> Value {
+    let ty = |ty: &Type| type_name(resolve, ty);
+    let (kind, resource) = match func.kind {
+        FunctionKind::Freestanding => ("function", None),
+        FunctionKind::Method(id) => ("method", Some(id)),
+        FunctionKind::Static(id) => ("static", Some(id)),
+        FunctionKind::Constructor(id) => ("constructor", Some(id)),
+    };
+    let params: Vec<_> = func
+        .params
+        .iter()
+        .map(|(name, param)| json!({"name": name, "type": ty(param)}))
+        .collect();
+    let results: Vec<_> = match &func.results {
+        Results::Named(results) => results
+            .iter()
+            .map(|(name, result)| json!({"name": name, "type": ty(result)}))
+            .collect(),
+        Results::Anon(result) => vec![json!({"name": null, "type": ty(result)})],
+    };
+    json!({
+        "kind": kind,
+        "name": func.item_name(),
+        "resource": resource.map(|id| ty(&Type::Id(id))),
+        "params": params,
+        "results": results,
+        "signature": signature(resolve, func),
+        "docs": func.docs.contents,
+    })
+}
+
+/// An item of a WIT package to select with [`select`].
+#[derive(Debug)]
+pub enum Selection {
+    World(String),
+    Interface(String),
+}
+
+/// Removes everything from the given package except the selected item and
+/// the interfaces it (transitively) depends on, including interfaces of
+/// foreign packages. Foreign packages left without any interfaces can be
+/// skipped on output.
+pub fn select(resolve: &mut Resolve, pkg: PackageId, selection: &Selection) -> anyhow::Result<()> {
+    let package = &resolve.packages[pkg];
+    let mut queue: Vec<InterfaceId> = vec![];
+    let world_name = match selection {
+        Selection::World(name) => {
+            let Some(&id) = package.worlds.get(name) else {
+                bail!("package {} has no world {name:?}", package.name);
+            };
+            let world = &resolve.worlds[id];
+            for item in world.imports.values().chain(world.exports.values()) {
+                match item {
+                    WorldItem::Interface(id) => queue.push(*id),
+                    WorldItem::Type(id) => queue.extend(alias_owner(resolve, *id)),
+                    WorldItem::Function(_) => (),
+                }
+            }
+            Some(name)
+        }
+        Selection::Interface(name) => {
+            let Some(&id) = package.interfaces.get(name) else {
+                bail!("package {} has no interface {name:?}", package.name);
+            };
+            queue.push(id);
+            None
+        }
+    };
+
+    let mut needed = HashSet::new();
+    while let Some(id) = queue.pop() {
+        if needed.insert(id) {
+            let types = resolve.interfaces[id].types.values();
+            queue.extend(types.filter_map(|ty| alias_owner(resolve, *ty)));
+        }
+    }
+    for (id, package) in resolve.packages.iter_mut() {
+        package
+            .interfaces
+            .retain(|_, interface| needed.contains(interface));
+        package
+            .worlds
+            .retain(|name, _| id == pkg && Some(name) == world_name);
+    }
+    Ok(())
+}
+
+/// Returns the interface owning the type aliased by the given type, e.g.
+/// the interface a type is `use`d from.
+fn alias_owner(resolve: &Resolve, id: TypeId) -> Option<InterfaceId> {
+    let TypeDefKind::Type(Type::Id(target)) = resolve.types[id].kind else {
+        return None;
+    };
+    match resolve.types[target].owner {
+        TypeOwner::Interface(owner) => Some(owner),
+        _ => None,
+    }
+}
+
+/// Returns whether the given WIT source declares exactly the given package
+/// version.
+pub fn declares_package(
+    path: &Path,
+    source: &str,
+    package: &PackageRef,
+    version: &Version,
+) -> bool {
+    let Ok(unresolved) = UnresolvedPackage::parse(path, source) else {
+        return false;
+    };
+    let name = &unresolved.name;
+    name.namespace == package.namespace().as_ref()
+        && name.name == package.name().as_ref()
+        && name.version.as_ref() == Some(version)
+}
+
+/// Options for printing WIT output. Each defaults to the config file's
+/// `output.wit` settings.
+#[derive(Args, Debug, Default)]
+pub struct WitStyleArgs {
+    /// Omit doc comments from WIT output.
+    #[arg(long)]
+    no_docs: bool,
+
+    /// How to order interfaces and worlds in WIT output.
+    #[arg(long, value_enum, value_name = "ORDER")]
+    wit_order: Option<WitOrder>,
+
+    /// Start WIT output with a `// from registry <registry>` comment naming
+    /// the registry it was fetched from.
+    #[arg(long)]
+    source_comments: bool,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq)]
+pub enum WitOrder {
+    /// The order the package declares them in.
+    #[default]
+    Declaration,
+    /// Sorted by name.
+    Alphabetical,
+}
+
+/// How WIT output is printed.
+#[derive(Clone, Debug)]
+pub struct WitStyle {
+    docs: bool,
+    order: WitOrder,
+    source_comments: bool,
+    /// The registry the printed packages came from, if known.
+    source: Option<String>,
+}
+
+impl Default for WitStyle {
+    fn default() -> Self {
+        Self {
+            docs: true,
+            order: WitOrder::default(),
+            source_comments: false,
+            source: None,
+        }
+    }
+}
+
+impl WitStyle {
+    /// Returns the style given by the flags, falling back to the config.
+    pub fn new(args: &WitStyleArgs, config: &WitOutputConfig) -> anyhow::Result<Self> {
+        let order = match (args.wit_order, &config.order) {
+            (Some(order), _) => order,
+            (None, Some(order)) => WitOrder::from_str(order, false)
+                .map_err(|err| anyhow!("Invalid output.wit.order in config: {err}"))?,
+            (None, None) => WitOrder::default(),
+        };
+        Ok(Self {
+            docs: !args.no_docs && config.docs.unwrap_or(true),
+            order,
+            source_comments: args.source_comments || config.source_comments.unwrap_or(false),
+            source: None,
+        })
+    }
+
+    /// Sets the registry the printed packages came from, for source
+    /// comments.
+    pub fn with_source(mut self, registry: Option<&str>) -> Self {
+        self.source = registry.map(ToOwned::to_owned);
+        self
+    }
+
+    /// Prints the given package as WIT.
+    pub fn print(&self, resolve: &Resolve, pkg: PackageId) -> anyhow::Result<String> {
+        let mut printer = WitPrinter::default();
+        printer.emit_docs(self.docs);
+        let wit = match self.order {
+            WitOrder::Declaration => printer.print(resolve, pkg)?,
+            WitOrder::Alphabetical => {
+                let mut sorted = resolve.clone();
+                let package = &mut sorted.packages[pkg];
+                package.interfaces.sort_keys();
+                package.worlds.sort_keys();
+                printer.print(&sorted, pkg)?
+            }
+        };
+        match &self.source {
+            Some(registry) if self.source_comments => {
+                Ok(format!("// from registry {registry}\n{wit}"))
+            }
+            _ => Ok(wit),
+        }
+    }
+}
+
+/// Writes the given package as a WIT directory at `output`: the package
+/// itself in `package.wit` and each package it references in
+/// `deps/<namespace>-<name>/package.wit`, the layout expected by
+/// `wit_parser::Resolve::push_dir` and other WIT tooling.
+///
+/// The directory is assembled next to `output` and moved into place once
+/// complete. An existing directory at `output` is replaced.
+pub fn write_wit_dir(
+    resolve: &Resolve,
+    pkg: PackageId,
+    output: &Path,
+    style: &WitStyle,
+) -> anyhow::Result<()> {
+    let parent = match output.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let tmp_dir = tempfile::Builder::new()
+        .prefix(".wkg-wit-dir")
+        .tempdir_in(parent)
+        .with_context(|| format!("Failed to create directory in {parent:?}"))?;
+
+    write_package(style, resolve, pkg, tmp_dir.path())?;
+    for (id, package) in resolve.packages.iter() {
+        if id == pkg || (package.interfaces.is_empty() && package.worlds.is_empty()) {
+            continue;
+        }
+        let dep_dir = tmp_dir
+            .path()
+            .join("deps")
+            .join(format!("{}-{}", package.name.namespace, package.name.name));
+        write_package(style, resolve, id, &dep_dir)?;
+    }
+
+    if output.is_dir() {
+        std::fs::remove_dir_all(output).with_context(|| format!("Failed to remove {output:?}"))?;
+    }
+    std::fs::rename(tmp_dir.path(), output)
+        .with_context(|| format!("Failed to write WIT directory {output:?}"))?;
+    Ok(())
+}
+
+fn write_package(
+    style: &WitStyle,
+    resolve: &Resolve,
+    pkg: PackageId,
+    dir: &Path,
+) -> anyhow::Result<()> {
+    let wit = style.print(resolve, pkg)?;
+    std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {dir:?}"))?;
+    let path = dir.join("package.wit");
+    std::fs::write(&path, wit).with_context(|| format!("Failed to write {path:?}"))
+}
+
+/// Returns `(file name, WIT)` pairs for each package in the given resolve
+/// that defines any interfaces or worlds, e.g. `wasi-io@0.2.0.wit`.
+pub fn split_packages(
+    resolve: &Resolve,
+    style: &WitStyle,
+) -> anyhow::Result<Vec<(String, String)>> {
+    let mut files = vec![];
+    for (id, package) in resolve.packages.iter() {
+        if package.interfaces.is_empty() && package.worlds.is_empty() {
+            continue;
+        }
+        let name = &package.name;
+        let file_name = match &name.version {
+            Some(version) => format!("{}-{}@{version}.wit", name.namespace, name.name),
+            None => format!("{}-{}.wit", name.namespace, name.name),
+        };
+        files.push((file_name, style.print(resolve, id)?));
+    }
+    Ok(files)
+}
+
+/// Writes each package in the given resolve to its own `.wit` file in the
+/// given directory; see [`split_packages`].
+pub fn write_split(resolve: &Resolve, output: &Path, style: &WitStyle) -> anyhow::Result<()> {
+    std::fs::create_dir_all(output).with_context(|| format!("Failed to create {output:?}"))?;
+    for (file_name, wit) in split_packages(resolve, style)? {
+        let path = output.join(file_name);
+        std::fs::write(&path, wit).with_context(|| format!("Failed to write {path:?}"))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parses the given WIT sources in order, returning the last package.
+    fn parse(sources: &[&str]) -> (Resolve, PackageId) {
+        let mut resolve = Resolve::default();
+        let mut pkg = None;
+        for source in sources {
+            let unresolved = UnresolvedPackage::parse(Path::new("test.wit"), source).unwrap();
+            pkg = Some(resolve.push(unresolved).unwrap());
+        }
+        (resolve, pkg.unwrap())
+    }
+
+    #[test]
+    fn test_write_wit_dir() {
+        let (resolve, pkg) = parse(&[
+            "package test:dep; interface types { type id = u32; }",
+            "package test:pkg; interface api { use test:dep/types.{id}; get: func() -> id; }",
+        ]);
+
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("test_pkg");
+        let style = WitStyle::default();
+        write_wit_dir(&resolve, pkg, &output, &style).unwrap();
+        // Overwrites an existing directory
+        write_wit_dir(&resolve, pkg, &output, &style).unwrap();
+
+        let mut roundtrip = Resolve::default();
+        let (pkg, _) = roundtrip.push_dir(&output).unwrap();
+        assert_eq!(roundtrip.packages[pkg].name.to_string(), "test:pkg");
+        assert!(output.join("deps/test-dep/package.wit").is_file());
+    }
+
+    #[test]
+    fn test_split_packages() {
+        let (resolve, _) = parse(&[
+            "package test:empty@1.0.0;",
+            "package test:dep@1.0.0; interface types { type id = u32; }",
+            "package test:pkg; interface api { use test:dep/types@1.0.0.{id}; get: func() -> id; }",
+        ]);
+        let files = split_packages(&resolve, &WitStyle::default()).unwrap();
+        let names: Vec<_> = files.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["test-dep@1.0.0.wit", "test-pkg.wit"]);
+        assert!(files[0].1.contains("package test:dep@1.0.0;"));
+        assert!(files[1].1.contains("package test:pkg;"));
+    }
+
+    #[test]
+    fn test_declares_package() {
+        let package: PackageRef = "wasi:http".parse().unwrap();
+        let version: Version = "0.2.0".parse().unwrap();
+        let path = Path::new("wasi-http.wit");
+        let declares = |source| declares_package(path, source, &package, &version);
+        assert!(declares(
+            "// from registry wasi.dev\npackage wasi:http@0.2.0;"
+        ));
+        assert!(!declares("package wasi:http@0.2.1;"));
+        assert!(!declares("package wasi:http;"));
+        assert!(!declares("package wasi:io@0.2.0;"));
+        assert!(!declares("not wit"));
+    }
+
+    #[test]
+    fn test_wit_style() {
+        let (resolve, pkg) =
+            parse(&["package test:pkg; /// Reads.\ninterface reader {} interface api {}"]);
+        let wit = WitStyle::default().print(&resolve, pkg).unwrap();
+        assert!(wit.find("reader").unwrap() < wit.find("api").unwrap());
+        assert!(wit.contains("/// Reads."));
+
+        let args = WitStyleArgs {
+            no_docs: true,
+            ..Default::default()
+        };
+        let config = WitOutputConfig {
+            order: Some("alphabetical".into()),
+            source_comments: Some(true),
+            ..Default::default()
+        };
+        let style = WitStyle::new(&args, &config)
+            .unwrap()
+            .with_source(Some("example.com"));
+        let wit = style.print(&resolve, pkg).unwrap();
+        assert!(wit.starts_with("// from registry example.com\n"));
+        assert!(wit.find("api").unwrap() < wit.find("reader").unwrap());
+        assert!(!wit.contains("/// Reads."));
+
+        let config = WitOutputConfig {
+            order: Some("random".into()),
+            ..Default::default()
+        };
+        assert!(WitStyle::new(&WitStyleArgs::default(), &config).is_err());
+    }
+
+    #[test]
+    fn test_select() {
+        let sources = [
+            "package test:dep; interface types { type id = u32; } interface other {}",
+            r#"
+            package test:pkg;
+            interface types { use test:dep/types.{id}; record entry { id: id } }
+            interface api { use types.{entry}; get: func() -> entry; }
+            interface unused {}
+            world app { import api; }
+            world other { import unused; }
+            "#,
+        ];
+        let names = |resolve: &Resolve, pkg: PackageId| {
+            let package = &resolve.packages[pkg];
+            let mut names: Vec<_> = package
+                .interfaces
+                .keys()
+                .chain(package.worlds.keys())
+                .cloned()
+                .collect();
+            names.sort();
+            names
+        };
+
+        let (mut resolve, pkg) = parse(&sources);
+        select(&mut resolve, pkg, &Selection::World("app".into())).unwrap();
+        assert_eq!(names(&resolve, pkg), ["api", "app", "types"]);
+        let (dep, _) = resolve.packages.iter().find(|(id, _)| *id != pkg).unwrap();
+        assert_eq!(names(&resolve, dep), ["types"]);
+        // The selection still prints as valid WIT
+        WitPrinter::default().print(&resolve, pkg).unwrap();
+
+        let (mut resolve, pkg) = parse(&sources);
+        select(&mut resolve, pkg, &Selection::Interface("types".into())).unwrap();
+        assert_eq!(names(&resolve, pkg), ["types"]);
+
+        let (mut resolve, pkg) = parse(&sources);
+        assert!(select(&mut resolve, pkg, &Selection::World("missing".into())).is_err());
+    }
+
+    #[test]
+    fn test_package_json() {
+        let (resolve, pkg) = parse(&[
+            "package test:dep@1.0.0; interface types { type id = u32; }",
+            r#"
+            package test:pkg;
+            interface api {
+                use test:dep/types@1.0.0.{id};
+                type bytes = list<u8>;
+                /// A stored entry.
+                record entry { key: id, value: bytes }
+                resource store {
+                    get: func(key: id) -> option<entry>;
+                }
+            }
+            "#,
+        ]);
+        let json = package_json(&resolve, pkg);
+        assert_eq!(json["package"], "test:pkg");
+        assert_eq!(json["dependencies"][0]["package"], "test:dep@1.0.0");
+        let api = &json["interfaces"][0];
+        assert_eq!(api["name"], "api");
+        assert_eq!(
+            api["types"][0],
+            json!({"kind": "use", "name": "id", "type": "id", "from": "test:dep/types@1.0.0", "docs": null})
+        );
+        assert_eq!(api["types"][1]["type"], "list<u8>");
+        assert_eq!(api["types"][2]["docs"], "A stored entry.");
+        assert_eq!(api["types"][2]["fields"][1]["type"], "bytes");
+        let get = &api["functions"][0];
+        assert_eq!(get["kind"], "method");
+        assert_eq!(get["name"], "get");
+        assert_eq!(get["resource"], "store");
+        assert_eq!(get["signature"], "(key: id) -> option<entry>");
+    }
+}